@@ -0,0 +1,261 @@
+use crate::{BlockDevError, BlockDevices};
+
+/// The version byte that starts every binary snapshot.
+///
+/// Bump it whenever a change to the data model would make older readers
+/// misinterpret newer snapshots, or the other way round.
+const BINARY_FORMAT_VERSION: u8 = 1;
+
+impl BlockDevices {
+    /// Encodes this snapshot, including its [`meta`](BlockDevices::meta) and
+    /// [`warnings`](BlockDevices::warnings), in a compact binary form for archival.
+    ///
+    /// The encoding is a version byte followed by the snapshot as CBOR
+    /// (RFC 8949), which keeps the field names and so stays readable by
+    /// generic CBOR tools. Sizes and numbers are stored as integers rather
+    /// than decimal text, which is most of the saving over JSON. Requires the
+    /// `binary` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::BinaryEncode`] if the snapshot cannot be
+    /// encoded, such as when [`SnapshotMeta::taken_at`](crate::SnapshotMeta::taken_at)
+    /// is before the Unix epoch.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{BlockDevices, parse_lsblk};
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/"]}
+    /// ]}"#).unwrap();
+    ///
+    /// let bytes = devices.to_bytes().unwrap();
+    /// assert_eq!(BlockDevices::from_bytes(&bytes).unwrap(), devices);
+    /// ```
+    pub fn to_bytes(&self) -> Result<Vec<u8>, BlockDevError> {
+        let mut bytes = vec![BINARY_FORMAT_VERSION];
+        ciborium::into_writer(self, &mut bytes)
+            .map_err(|e| BlockDevError::BinaryEncode(e.to_string()))?;
+        Ok(bytes)
+    }
+
+    /// Decodes a snapshot written by [`to_bytes`](BlockDevices::to_bytes).
+    ///
+    /// Requires the `binary` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::UnsupportedBinaryVersion`] if the snapshot was
+    /// written by a different version of the format, and
+    /// [`BlockDevError::BinaryDecode`] if it is empty, truncated, followed by
+    /// trailing bytes or otherwise not a valid snapshot.
+    pub fn from_bytes(bytes: &[u8]) -> Result<BlockDevices, BlockDevError> {
+        let Some((&version, mut payload)) = bytes.split_first() else {
+            return Err(BlockDevError::BinaryDecode("empty input".to_string()));
+        };
+        if version != BINARY_FORMAT_VERSION {
+            return Err(BlockDevError::UnsupportedBinaryVersion {
+                found: version,
+                expected: BINARY_FORMAT_VERSION,
+            });
+        }
+        let devices = ciborium::from_reader(&mut payload)
+            .map_err(|e| BlockDevError::BinaryDecode(e.to_string()))?;
+        if !payload.is_empty() {
+            return Err(BlockDevError::BinaryDecode(format!(
+                "{} trailing bytes",
+                payload.len()
+            )));
+        }
+        Ok(devices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::{BlockDevice, DeviceType, MajMin, SnapshotMeta, SourceKind, parse_lsblk};
+    use std::time::{Duration, SystemTime};
+
+    /// The `SAMPLE_JSON` snapshot with fixed metadata, as written by version 1.
+    const SAMPLE_V1: &[u8] = include_bytes!("../tests/fixtures/binary/sample-v1.bin");
+
+    fn sample() -> BlockDevices {
+        let mut devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        devices.meta = Some(SnapshotMeta {
+            taken_at: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            hostname: Some("storage01".to_string()),
+            source: SourceKind::Lsblk,
+            lsblk_path: None,
+        });
+        devices
+    }
+
+    /// A xorshift generator, so the randomized trees are reproducible.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+
+        fn maybe<T>(&mut self, value: impl FnOnce(&mut Self) -> T) -> Option<T> {
+            (self.below(2) == 0).then(|| value(self))
+        }
+    }
+
+    fn random_device(rng: &mut Rng, name: String, depth: u32) -> BlockDevice {
+        const TYPES: [DeviceType; 5] = [
+            DeviceType::Disk,
+            DeviceType::Part,
+            DeviceType::Lvm,
+            DeviceType::Crypt,
+            DeviceType::Raid1,
+        ];
+        let mountpoints = match rng.below(3) {
+            0 => vec![None],
+            1 => vec![Some(format!("/mnt/{name}"))],
+            _ => vec![Some("/srv".to_string()), Some(format!("/srv/{name}"))],
+        };
+        let children = if depth < 3 {
+            rng.maybe(|rng| {
+                (0..rng.below(4))
+                    .map(|i| random_device(rng, format!("{name}{i}"), depth + 1))
+                    .collect()
+            })
+        } else {
+            None
+        };
+        BlockDevice {
+            maj_min: MajMin {
+                major: u32::try_from(rng.below(300)).unwrap(),
+                minor: u32::try_from(rng.below(256)).unwrap(),
+            },
+            rm: rng.below(2) == 0,
+            size: rng.next(),
+            ro: rng.below(2) == 0,
+            device_type: TYPES[usize::try_from(rng.below(5)).unwrap()].clone(),
+            mountpoints,
+            children,
+            start: rng.maybe(|rng| rng.below(1 << 40)),
+            log_sec: rng.maybe(|_| 4096),
+            phy_sec: rng.maybe(|_| 512),
+            uuid: rng.maybe(|rng| format!("{:032x}", rng.next())),
+            fstype: rng.maybe(|_| "ext4".to_string()),
+            label: rng.maybe(|_| format!("läbel {name}")),
+            rota: rng.maybe(|rng| rng.below(2) == 0),
+            fssize: rng.maybe(Rng::next),
+            name,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let devices = sample();
+        let bytes = devices.to_bytes().unwrap();
+        assert_eq!(bytes[0], BINARY_FORMAT_VERSION);
+        assert_eq!(BlockDevices::from_bytes(&bytes).unwrap(), devices);
+
+        let json = serde_json::to_vec(&devices).unwrap();
+        assert!(bytes.len() < json.len());
+
+        assert_eq!(
+            BlockDevices::from_bytes(&BlockDevices::default().to_bytes().unwrap()).unwrap(),
+            BlockDevices::default()
+        );
+    }
+
+    #[test]
+    fn test_round_trip_random_trees() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for case in 0..200 {
+            let devices = BlockDevices {
+                blockdevices: (0..rng.below(6))
+                    .map(|i| random_device(&mut rng, format!("sd{i}"), 0))
+                    .collect(),
+                meta: rng.maybe(|rng| SnapshotMeta {
+                    taken_at: SystemTime::UNIX_EPOCH + Duration::from_nanos(rng.next() >> 4),
+                    hostname: rng.maybe(|_| "host".to_string()),
+                    source: SourceKind::Manual,
+                    lsblk_path: rng.maybe(|_| "/usr/bin/lsblk".into()),
+                }),
+                warnings: rng
+                    .maybe(|_| vec!["lsblk: skipped".to_string()])
+                    .unwrap_or_default(),
+            };
+            let decoded = BlockDevices::from_bytes(&devices.to_bytes().unwrap()).unwrap();
+            assert_eq!(decoded, devices, "case {case}");
+        }
+    }
+
+    #[test]
+    fn test_frozen_fixture() {
+        assert_eq!(BlockDevices::from_bytes(SAMPLE_V1).unwrap(), sample());
+        assert_eq!(sample().to_bytes().unwrap(), SAMPLE_V1);
+    }
+
+    #[test]
+    fn test_rejects_time_before_epoch() {
+        let devices = BlockDevices {
+            meta: Some(SnapshotMeta {
+                taken_at: SystemTime::UNIX_EPOCH - Duration::from_secs(1),
+                hostname: None,
+                source: SourceKind::Manual,
+                lsblk_path: None,
+            }),
+            ..BlockDevices::default()
+        };
+        assert!(matches!(
+            devices.to_bytes(),
+            Err(BlockDevError::BinaryEncode(_))
+        ));
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        let err = BlockDevices::from_bytes(&[]).unwrap_err();
+        assert!(matches!(err, BlockDevError::BinaryDecode(_)));
+
+        let mut bytes = sample().to_bytes().unwrap();
+        bytes[0] = 2;
+        let err = BlockDevices::from_bytes(&bytes).unwrap_err();
+        assert!(matches!(
+            err,
+            BlockDevError::UnsupportedBinaryVersion {
+                found: 2,
+                expected: 1
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "unsupported binary snapshot version 2 (expected 1)"
+        );
+
+        // JSON is not mistaken for a snapshot.
+        let err = BlockDevices::from_bytes(SAMPLE_JSON.as_bytes()).unwrap_err();
+        assert!(matches!(
+            err,
+            BlockDevError::UnsupportedBinaryVersion { .. }
+        ));
+
+        let bytes = sample().to_bytes().unwrap();
+        let err = BlockDevices::from_bytes(&bytes[..bytes.len() / 2]).unwrap_err();
+        assert!(matches!(err, BlockDevError::BinaryDecode(_)));
+
+        let mut bytes = sample().to_bytes().unwrap();
+        bytes.push(0);
+        let err = BlockDevices::from_bytes(&bytes).unwrap_err();
+        assert_eq!(err.to_string(), "invalid binary snapshot: 1 trailing bytes");
+    }
+}
@@ -0,0 +1,529 @@
+use crate::proc_partitions::device_type_from_name;
+use crate::{
+    BlockDevError, BlockDevice, BlockDevices, DeviceType, MajMin, SnapshotMeta, SourceKind,
+};
+use std::path::Path;
+
+/// The identifiers blkid reports for one device node.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+struct BlkidRecord {
+    /// The device node, such as `/dev/sda1` or `/dev/mapper/luks-root`.
+    devname: String,
+    /// The device number, from `DEVNO` in the cache or the device node.
+    maj_min: Option<MajMin>,
+    uuid: Option<String>,
+    fstype: Option<String>,
+    label: Option<String>,
+}
+
+impl BlkidRecord {
+    /// Stores `value` if `key` is one of the tags this crate keeps.
+    fn set(&mut self, key: &str, value: String) {
+        match key {
+            "DEVNAME" => self.devname = value,
+            "UUID" => self.uuid = Some(value),
+            "TYPE" => self.fstype = Some(value),
+            "LABEL" => self.label = Some(value),
+            _ => {}
+        }
+    }
+}
+
+/// Decodes a `dev_t` in the glibc encoding, the inverse of
+/// [`BlockDevice::device_number`].
+fn maj_min_from_device_number(number: u64) -> MajMin {
+    MajMin {
+        major: (((number >> 32) & 0xffff_f000) | ((number >> 8) & 0x0000_0fff)) as u32,
+        minor: (((number >> 12) & 0xffff_ff00) | (number & 0x0000_00ff)) as u32,
+    }
+}
+
+/// Removes the backslashes blkid puts before quotes and shell metacharacters.
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.extend(chars.next()),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Parses the `KEY=value` blocks written by `blkid -o export`, one block per
+/// device separated by blank lines. Blocks without a `DEVNAME` are skipped.
+#[cfg(feature = "exec")]
+fn parse_blkid_export(output: &str) -> Vec<BlkidRecord> {
+    let mut records = Vec::new();
+    let mut record = BlkidRecord::default();
+    for line in output.lines().chain([""]) {
+        match line.trim().split_once('=') {
+            Some((key, value)) => record.set(key, unescape(value)),
+            None if line.trim().is_empty() => {
+                let record = std::mem::take(&mut record);
+                if !record.devname.is_empty() {
+                    records.push(record);
+                }
+            }
+            None => {}
+        }
+    }
+    records
+}
+
+/// Parses one `<device KEY="value" ...>/dev/name</device>` line of a blkid
+/// cache file.
+fn parse_cache_line(line: &str) -> Option<BlkidRecord> {
+    let rest = line.trim().strip_prefix("<device")?;
+    let (mut attributes, devname) = rest.strip_suffix("</device>")?.split_once('>')?;
+    let mut record = BlkidRecord {
+        devname: devname.trim().to_string(),
+        ..BlkidRecord::default()
+    };
+    loop {
+        attributes = attributes.trim_start();
+        if attributes.is_empty() {
+            break;
+        }
+        let (key, quoted) = attributes.split_once("=\"")?;
+        // The value runs to the first quote that is not escaped.
+        let mut end = None;
+        let mut escaped = false;
+        for (i, c) in quoted.char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => {
+                    end = Some(i);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let end = end?;
+        let value = unescape(&quoted[..end]);
+        if key == "DEVNO" {
+            let number = u64::from_str_radix(value.strip_prefix("0x")?, 16).ok()?;
+            record.maj_min = Some(maj_min_from_device_number(number));
+        } else {
+            record.set(key, value);
+        }
+        attributes = &quoted[end + 1..];
+    }
+    (!record.devname.is_empty()).then_some(record)
+}
+
+/// Parses the contents of a blkid cache file such as `/run/blkid/blkid.tab`.
+fn parse_blkid_cache(contents: &str) -> Result<Vec<BlkidRecord>, BlockDevError> {
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            parse_cache_line(line).ok_or_else(|| {
+                BlockDevError::InvalidInput(format!("invalid blkid cache line: '{line}'"))
+            })
+        })
+        .collect()
+}
+
+/// Returns the name lsblk uses for a device node: the mapper name for
+/// `/dev/mapper/<name>` and the file name otherwise.
+fn name_from_devname(devname: &str) -> &str {
+    let path = Path::new(devname);
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(devname)
+}
+
+/// Copies the identifiers of `record` into `device`, keeping the existing
+/// values of tags blkid did not report.
+#[cfg(feature = "exec")]
+fn apply_record(device: &mut BlockDevice, record: &BlkidRecord) {
+    if record.uuid.is_some() {
+        device.uuid.clone_from(&record.uuid);
+    }
+    if record.fstype.is_some() {
+        device.fstype.clone_from(&record.fstype);
+    }
+    if record.label.is_some() {
+        device.label.clone_from(&record.label);
+    }
+}
+
+/// Merges `records` into every occurrence of the matching device, matching by
+/// device path and then by device number.
+#[cfg(feature = "exec")]
+fn merge_records(devices: &mut BlockDevices, records: &[BlkidRecord]) {
+    fn merge_all(
+        devices: &mut [BlockDevice],
+        record: &BlkidRecord,
+        matches: &dyn Fn(&BlockDevice) -> bool,
+    ) -> bool {
+        let mut found = false;
+        for device in devices {
+            if matches(device) {
+                apply_record(device, record);
+                found = true;
+            }
+            if let Some(children) = &mut device.children {
+                found |= merge_all(children, record, matches);
+            }
+        }
+        found
+    }
+
+    for record in records {
+        let path = Path::new(&record.devname);
+        let by_path = |d: &BlockDevice| d.path() == path || Path::new("/dev").join(&d.name) == path;
+        if !merge_all(&mut devices.blockdevices, record, &by_path) {
+            if let Some(maj_min) = record.maj_min {
+                merge_all(&mut devices.blockdevices, record, &|d| d.maj_min == maj_min);
+            }
+        }
+    }
+}
+
+/// Reads the device number of the device node at `path`.
+#[cfg(feature = "exec")]
+fn device_node_maj_min(path: &Path) -> Option<MajMin> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(maj_min_from_device_number(metadata.rdev()))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// Runs `blkid -c <cache> -o export` and returns its output.
+///
+/// `blkid` is resolved and run like lsblk: with the `secure-exec` feature,
+/// from a trusted system directory and with only `LC_ALL=C` set.
+#[cfg(feature = "exec")]
+fn run_blkid(cache: &Path) -> Result<String, BlockDevError> {
+    let output = crate::exec::tool_command("blkid")
+        .arg("-c")
+        .arg(cache)
+        .args(["-o", "export"])
+        .output()?;
+    // blkid exits with status 2 when it finds no device with identifiers.
+    match output.status.code() {
+        Some(0) => Ok(String::from_utf8(output.stdout)?),
+        Some(2) if output.stderr.is_empty() => Ok(String::new()),
+        _ => Err(BlockDevError::BlkidError(
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        )),
+    }
+}
+
+impl BlockDevices {
+    /// Runs `lsblk` and `blkid` and fills in the `uuid`, `fstype` and `label`
+    /// of every device from blkid's probe results, with blkid maintaining its
+    /// cache of device identifiers at `path`.
+    ///
+    /// blkid's records are matched to devices by device path (`/dev/sda1`,
+    /// `/dev/mapper/luks-root`) and then by the device number of the node.
+    /// Tags blkid does not report leave lsblk's value in place. blkid only
+    /// probes devices, and writes the cache, when it can read them, which
+    /// usually means running as root; pass `/dev/null` to not keep a cache.
+    /// Read the cache back with [`BlockDevices::from_blkid_cache`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`get_devices`](crate::get_devices),
+    /// [`BlockDevError::CommandFailed`] if `blkid` cannot be run,
+    /// [`BlockDevError::BlkidError`] if it fails, and
+    /// [`BlockDevError::InvalidUtf8`] if its output is not UTF-8.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use blockdev::BlockDevices;
+    /// use std::path::Path;
+    ///
+    /// let devices = BlockDevices::generate_blkid_cache(Path::new("/run/blkid/blkid.tab"))
+    ///     .expect("Failed to run blkid");
+    /// for device in devices.iter_all().filter(|d| d.uuid.is_some()) {
+    ///     println!("{} {}", device.name, device.uuid.as_deref().unwrap_or_default());
+    /// }
+    /// ```
+    #[cfg(feature = "exec")]
+    pub fn generate_blkid_cache(path: &Path) -> Result<BlockDevices, BlockDevError> {
+        let mut devices = crate::get_devices()?;
+        let mut records = parse_blkid_export(&run_blkid(path)?);
+        for record in &mut records {
+            record.maj_min = device_node_maj_min(Path::new(&record.devname));
+        }
+        merge_records(&mut devices, &records);
+        Ok(devices)
+    }
+
+    /// Reads a blkid cache file, such as one written by
+    /// `BlockDevices::generate_blkid_cache` or `/run/blkid/blkid.tab`, into
+    /// a flat list of devices.
+    ///
+    /// The cache only records device identifiers, so apart from `name`,
+    /// `maj_min`, `uuid`, `fstype` and `label` the devices are as minimal as
+    /// those of [`BlockDevices::from_proc_partitions`]: types are guessed from
+    /// names, device-mapper devices are [`DeviceType::Other`], sizes are `0`
+    /// and there are no mountpoints or children. Devices under `/dev/mapper`
+    /// are named by their mapper name, as lsblk names them. Merge the result
+    /// into an lsblk snapshot with
+    /// [`merge_extended_into`](BlockDevices::merge_extended_into).
+    ///
+    /// The snapshot's [`meta`](BlockDevices::meta) records [`SourceKind::BlkidCache`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::IoError`] if the file cannot be read and
+    /// [`BlockDevError::InvalidInput`] if a line is not a `<device>` entry.
+    pub fn from_blkid_cache(path: &Path) -> Result<BlockDevices, BlockDevError> {
+        let contents = std::fs::read_to_string(path).map_err(BlockDevError::IoError)?;
+        let blockdevices = parse_blkid_cache(&contents)?
+            .into_iter()
+            .map(|record| {
+                let name = name_from_devname(&record.devname);
+                // The cache does not say whether a mapping is LVM or crypt.
+                let device_type = if record.devname.starts_with("/dev/mapper/") {
+                    DeviceType::Other
+                } else {
+                    device_type_from_name(name)
+                };
+                BlockDevice {
+                    device_type,
+                    name: name.to_string(),
+                    maj_min: record.maj_min.unwrap_or_default(),
+                    mountpoints: vec![None],
+                    uuid: record.uuid,
+                    fstype: record.fstype,
+                    label: record.label,
+                    ..Default::default()
+                }
+            })
+            .collect();
+        Ok(BlockDevices {
+            blockdevices,
+            meta: Some(SnapshotMeta::new(SourceKind::BlkidCache)),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_lsblk;
+
+    /// `blkid -o export` on a LUKS root and an EFI partition.
+    #[cfg(feature = "exec")]
+    const BLKID_EXPORT: &str = "\
+DEVNAME=/dev/nvme0n1p1
+UUID=7A1B-2C3D
+BLOCK_SIZE=512
+TYPE=vfat
+PARTUUID=2f6b1c7e-01
+
+DEVNAME=/dev/nvme0n1p2
+UUID=3c1f8a52-0d4e-4c4b-9b7e-2a6f5d8c9e01
+TYPE=crypto_LUKS
+PARTUUID=2f6b1c7e-02
+
+DEVNAME=/dev/mapper/luks-root
+UUID=b2e4c6a8-1f3d-4e5a-8c7b-9d0e1f2a3b4c
+BLOCK_SIZE=4096
+LABEL=My\\ Root
+TYPE=ext4
+";
+
+    /// A blkid cache file for the same devices, plus a disk lsblk does not list.
+    const BLKID_CACHE: &str = r#"<device DEVNO="0x10301" TIME="1700000000.123456" PRI="0" UUID="7A1B-2C3D" BLOCK_SIZE="512" TYPE="vfat" PARTUUID="2f6b1c7e-01">/dev/nvme0n1p1</device>
+<device DEVNO="0x10302" TIME="1700000000.123456" UUID="3c1f8a52-0d4e-4c4b-9b7e-2a6f5d8c9e01" TYPE="crypto_LUKS" PARTUUID="2f6b1c7e-02">/dev/nvme0n1p2</device>
+<device DEVNO="0xfc00" TIME="1700000000.123456" PRI="40" UUID="b2e4c6a8-1f3d-4e5a-8c7b-9d0e1f2a3b4c" LABEL="say \"hi\"" TYPE="ext4">/dev/mapper/luks-root</device>
+<device DEVNO="0x11032c" TIME="1700000001.5" UUID="0b1c2d3e-4f50-6172-8394-a5b6c7d8e9f0" TYPE="xfs">/dev/nvme1n1</device>
+"#;
+
+    const LSBLK_JSON: &str = r#"{"blockdevices": [
+        {"name": "nvme0n1", "maj:min": "259:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "nvme0n1p1", "maj:min": "259:1", "rm": false, "size": "512M", "ro": false, "type": "part", "mountpoints": ["/boot/efi"]},
+            {"name": "nvme0n1p2", "maj:min": "259:2", "rm": false, "size": "999G", "ro": false, "type": "part", "mountpoints": [null],
+             "children": [
+                {"name": "luks-root", "maj:min": "252:0", "rm": false, "size": "999G", "ro": false, "type": "crypt", "mountpoints": ["/"],
+                 "fstype": "ext3", "label": "old"}
+             ]}
+         ]}
+    ]}"#;
+
+    #[cfg(feature = "exec")]
+    #[test]
+    fn test_parse_blkid_export() {
+        let records = parse_blkid_export(BLKID_EXPORT);
+        let uuids: Vec<(&str, Option<&str>)> = records
+            .iter()
+            .map(|r| (r.devname.as_str(), r.uuid.as_deref()))
+            .collect();
+        assert_eq!(
+            uuids,
+            vec![
+                ("/dev/nvme0n1p1", Some("7A1B-2C3D")),
+                (
+                    "/dev/nvme0n1p2",
+                    Some("3c1f8a52-0d4e-4c4b-9b7e-2a6f5d8c9e01")
+                ),
+                (
+                    "/dev/mapper/luks-root",
+                    Some("b2e4c6a8-1f3d-4e5a-8c7b-9d0e1f2a3b4c")
+                ),
+            ]
+        );
+        assert_eq!(records[1].fstype.as_deref(), Some("crypto_LUKS"));
+        assert_eq!(records[2].label.as_deref(), Some("My Root"));
+        assert_eq!(records[0].label, None);
+        assert!(parse_blkid_export("").is_empty());
+        assert!(parse_blkid_export("UUID=1234\n\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_blkid_cache() {
+        let records = parse_blkid_cache(BLKID_CACHE).expect("Failed to parse cache");
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].devname, "/dev/nvme0n1p1");
+        assert_eq!(records[0].uuid.as_deref(), Some("7A1B-2C3D"));
+        assert_eq!(
+            records[0].maj_min,
+            Some(MajMin {
+                major: 259,
+                minor: 1
+            })
+        );
+        assert_eq!(
+            records[2].maj_min,
+            Some(MajMin {
+                major: 252,
+                minor: 0
+            })
+        );
+        assert_eq!(records[2].label.as_deref(), Some(r#"say "hi""#));
+        // Minors above 255 use the high bits of the glibc encoding.
+        assert_eq!(
+            records[3].maj_min,
+            Some(MajMin {
+                major: 259,
+                minor: 300
+            })
+        );
+
+        for bad in [
+            r#"<device DEVNO="0x800"></device>"#,
+            r#"<device DEVNO="0x800">/dev/sda"#,
+            r#"<device DEVNO="2048">/dev/sda</device>"#,
+            r#"<device UUID="unterminated>/dev/sda</device>"#,
+            "/dev/sda UUID=1234",
+        ] {
+            assert!(
+                matches!(parse_blkid_cache(bad), Err(BlockDevError::InvalidInput(_))),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_maj_min_round_trip() {
+        for (major, minor) in [(8, 0), (259, 3), (8, 416), (4095, 1 << 20)] {
+            let device = BlockDevice {
+                maj_min: MajMin { major, minor },
+                ..Default::default()
+            };
+            assert_eq!(
+                maj_min_from_device_number(device.device_number()),
+                device.maj_min
+            );
+        }
+    }
+
+    #[cfg(feature = "exec")]
+    #[test]
+    fn test_merge_records() {
+        let mut devices = parse_lsblk(LSBLK_JSON).expect("Failed to parse JSON");
+        let mut records = parse_blkid_export(BLKID_EXPORT);
+        // Matched by device number when the node is not named like the device.
+        records[0].devname = "/dev/disk/by-partlabel/esp".to_string();
+        records[0].maj_min = Some(MajMin {
+            major: 259,
+            minor: 1,
+        });
+        merge_records(&mut devices, &records);
+
+        let uuids: Vec<(&str, Option<&str>)> = devices
+            .iter_all()
+            .map(|d| (d.name.as_str(), d.uuid.as_deref()))
+            .collect();
+        assert_eq!(
+            uuids,
+            vec![
+                ("nvme0n1", None),
+                ("nvme0n1p1", Some("7A1B-2C3D")),
+                ("nvme0n1p2", Some("3c1f8a52-0d4e-4c4b-9b7e-2a6f5d8c9e01")),
+                ("luks-root", Some("b2e4c6a8-1f3d-4e5a-8c7b-9d0e1f2a3b4c")),
+            ]
+        );
+        let root = devices.iter_all().find(|d| d.name == "luks-root").unwrap();
+        assert_eq!(root.fstype.as_deref(), Some("ext4"));
+        assert_eq!(root.label.as_deref(), Some("My Root"));
+    }
+
+    #[test]
+    fn test_from_blkid_cache() {
+        let path = std::env::temp_dir().join(format!("blockdev-blkid-{}.tab", std::process::id()));
+        std::fs::write(&path, BLKID_CACHE).unwrap();
+        let devices = BlockDevices::from_blkid_cache(&path);
+        std::fs::remove_file(&path).unwrap();
+        let devices = devices.expect("Failed to read cache");
+
+        assert_eq!(
+            devices.meta.as_ref().unwrap().source,
+            SourceKind::BlkidCache
+        );
+        let summary: Vec<(&str, DeviceType, Option<&str>)> = devices
+            .iter()
+            .map(|d| (d.name.as_str(), d.device_type.clone(), d.uuid.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("nvme0n1p1", DeviceType::Part, Some("7A1B-2C3D")),
+                (
+                    "nvme0n1p2",
+                    DeviceType::Part,
+                    Some("3c1f8a52-0d4e-4c4b-9b7e-2a6f5d8c9e01")
+                ),
+                (
+                    "luks-root",
+                    DeviceType::Other,
+                    Some("b2e4c6a8-1f3d-4e5a-8c7b-9d0e1f2a3b4c")
+                ),
+                (
+                    "nvme1n1",
+                    DeviceType::Disk,
+                    Some("0b1c2d3e-4f50-6172-8394-a5b6c7d8e9f0")
+                ),
+            ]
+        );
+
+        // The cache carries what lsblk needs blkid for.
+        let mut lsblk = parse_lsblk(LSBLK_JSON).expect("Failed to parse JSON");
+        lsblk.merge_extended_into(&devices);
+        let esp = lsblk.iter_all().find(|d| d.name == "nvme0n1p1").unwrap();
+        assert_eq!(esp.fstype.as_deref(), Some("vfat"));
+
+        assert!(matches!(
+            BlockDevices::from_blkid_cache(Path::new("/nonexistent/blkid.tab")),
+            Err(BlockDevError::IoError(_))
+        ));
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{BlockDevice, BlockDevices, DeviceType, MajMin, MountEntry, SnapshotMeta};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A borrowed view of a [`BlockDevice`] that serializes to the same JSON.
+///
+/// Created by [`BlockDevice::as_ref_tree`]. Because `children` is an owned
+/// `Vec` of views, a subtree can be filtered (for example with `retain`) and
+/// serialized without cloning any device data.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlockDeviceRef<'a> {
+    /// See [`BlockDevice::name`].
+    pub name: &'a str,
+    /// See [`BlockDevice::maj_min`].
+    #[serde(rename = "maj:min")]
+    pub maj_min: MajMin,
+    /// See [`BlockDevice::rm`].
+    pub rm: bool,
+    /// See [`BlockDevice::size`].
+    pub size: u64,
+    /// See [`BlockDevice::ro`].
+    pub ro: bool,
+    /// See [`BlockDevice::device_type`].
+    #[serde(rename = "type")]
+    pub device_type: &'a DeviceType,
+    /// See [`BlockDevice::mountpoints`].
+    pub mountpoints: &'a [Option<String>],
+    /// Views of the children, or `None` if the device has none.
+    pub children: Option<Vec<BlockDeviceRef<'a>>>,
+    /// See [`BlockDevice::start`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start: Option<u64>,
+    /// See [`BlockDevice::log_sec`].
+    #[serde(rename = "log-sec", skip_serializing_if = "Option::is_none")]
+    pub log_sec: Option<u64>,
+    /// See [`BlockDevice::phy_sec`].
+    #[serde(rename = "phy-sec", skip_serializing_if = "Option::is_none")]
+    pub phy_sec: Option<u64>,
+    /// See [`BlockDevice::uuid`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<&'a str>,
+    /// See [`BlockDevice::partuuid`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partuuid: Option<&'a str>,
+    /// See [`BlockDevice::parttype`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parttype: Option<&'a str>,
+    /// See [`BlockDevice::fstype`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fstype: Option<&'a str>,
+    /// See [`BlockDevice::fsver`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fsver: Option<&'a str>,
+    /// See [`BlockDevice::pttype`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pttype: Option<&'a str>,
+    /// See [`BlockDevice::rota`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rota: Option<bool>,
+    /// See [`BlockDevice::hotplug`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hotplug: Option<bool>,
+    /// See [`BlockDevice::tran`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tran: Option<&'a str>,
+    /// See [`BlockDevice::fssize`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fssize: Option<u64>,
+    /// See [`BlockDevice::fsused`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fsused: Option<u64>,
+    /// See [`BlockDevice::label`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<&'a str>,
+    /// See [`BlockDevice::serial`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub serial: Option<&'a str>,
+    /// See [`BlockDevice::wwn`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wwn: Option<&'a str>,
+    /// See [`BlockDevice::sched`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sched: Option<&'a str>,
+    /// See [`BlockDevice::rq_size`].
+    #[serde(rename = "rq-size", skip_serializing_if = "Option::is_none")]
+    pub rq_size: Option<u64>,
+    /// See [`BlockDevice::vendor`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vendor: Option<&'a str>,
+    /// See [`BlockDevice::model`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<&'a str>,
+    /// See [`BlockDevice::mounts`].
+    #[serde(rename = "_blockdev_mounts", skip_serializing_if = "Option::is_none")]
+    pub mounts: Option<&'a [MountEntry]>,
+    /// See [`BlockDevice::udev`].
+    #[serde(rename = "_blockdev_udev", skip_serializing_if = "Option::is_none")]
+    pub udev: Option<&'a BTreeMap<String, String>>,
+}
+
+/// A borrowed view of a [`BlockDevices`] snapshot that serializes to the same JSON.
+///
+/// Created by [`BlockDevices::as_ref_tree`].
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlockDevicesRef<'a> {
+    /// Views of the top-level devices.
+    pub blockdevices: Vec<BlockDeviceRef<'a>>,
+    /// See [`BlockDevices::meta`].
+    #[serde(rename = "_blockdev_meta", skip_serializing_if = "Option::is_none")]
+    pub meta: Option<&'a SnapshotMeta>,
+    /// See [`BlockDevices::warnings`].
+    #[serde(
+        rename = "_blockdev_warnings",
+        skip_serializing_if = "<[String]>::is_empty"
+    )]
+    pub warnings: &'a [String],
+}
+
+impl BlockDevice {
+    /// Returns a borrowed view of this device and its children for serialization.
+    #[must_use]
+    pub fn as_ref_tree(&self) -> BlockDeviceRef<'_> {
+        BlockDeviceRef {
+            name: &self.name,
+            maj_min: self.maj_min,
+            rm: self.rm,
+            size: self.size,
+            ro: self.ro,
+            device_type: &self.device_type,
+            mountpoints: &self.mountpoints,
+            children: self
+                .children
+                .as_ref()
+                .map(|children| children.iter().map(BlockDevice::as_ref_tree).collect()),
+            start: self.start,
+            log_sec: self.log_sec,
+            phy_sec: self.phy_sec,
+            uuid: self.uuid.as_deref(),
+            partuuid: self.partuuid.as_deref(),
+            parttype: self.parttype.as_deref(),
+            fstype: self.fstype.as_deref(),
+            fsver: self.fsver.as_deref(),
+            pttype: self.pttype.as_deref(),
+            rota: self.rota,
+            hotplug: self.hotplug,
+            tran: self.tran.as_deref(),
+            fssize: self.fssize,
+            fsused: self.fsused,
+            label: self.label.as_deref(),
+            serial: self.serial.as_deref(),
+            wwn: self.wwn.as_deref(),
+            sched: self.sched.as_deref(),
+            rq_size: self.rq_size,
+            vendor: self.vendor.as_deref(),
+            model: self.model.as_deref(),
+            mounts: self.mounts.as_deref(),
+            udev: self.udev.as_ref(),
+        }
+    }
+}
+
+impl BlockDevices {
+    /// Returns a borrowed view of the whole tree for serialization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null]},
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/"]}
+    /// ]}"#).unwrap();
+    ///
+    /// let mut view = devices.as_ref_tree();
+    /// view.blockdevices.retain(|d| d.name != "sdb");
+    /// let filtered = parse_lsblk(&serde_json::to_string(&view).unwrap()).unwrap();
+    /// assert_eq!(filtered.len(), 1);
+    /// ```
+    #[must_use]
+    pub fn as_ref_tree(&self) -> BlockDevicesRef<'_> {
+        BlockDevicesRef {
+            blockdevices: self.iter().map(BlockDevice::as_ref_tree).collect(),
+            meta: self.meta.as_ref(),
+            warnings: &self.warnings,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    #[test]
+    fn test_ref_tree_matches_owned_serialization() {
+        let mut devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        devices.warnings = vec!["lsblk: /dev/sr0: unknown device".to_string()];
+        devices.blockdevices[0].uuid = Some("0f3c2a9e".to_string());
+        devices.blockdevices[0].start = Some(2048);
+
+        let owned = serde_json::to_value(&devices).expect("Failed to serialize");
+        let borrowed = serde_json::to_value(devices.as_ref_tree()).expect("Failed to serialize");
+        assert_eq!(borrowed, owned);
+
+        let json = serde_json::to_string(&devices.as_ref_tree()).expect("Failed to serialize");
+        assert_eq!(parse_lsblk(&json).expect("Failed to parse JSON"), devices);
+    }
+
+    #[test]
+    fn test_ref_tree_filtered() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let mut view = devices.as_ref_tree();
+        view.blockdevices.retain(|d| d.name == "nvme3n1");
+        if let Some(children) = &mut view.blockdevices[0].children {
+            children.retain(|c| c.children.is_some());
+        }
+
+        let json = serde_json::to_string(&view).expect("Failed to serialize");
+        let filtered = parse_lsblk(&json).expect("Failed to parse JSON");
+        assert_eq!(filtered.len(), 1);
+        let names: Vec<&str> = filtered.iter_all().map(|d| d.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "nvme3n1",
+                "nvme3n1p3",
+                "md0",
+                "nvme3n1p4",
+                "md1",
+                "nvme3n1p5",
+                "md2"
+            ]
+        );
+    }
+}
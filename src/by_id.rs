@@ -0,0 +1,291 @@
+use crate::{BlockDevError, BlockDevice, BlockDevices};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The directory in which udev creates the persistent `by-id` links.
+const DEV_DISK_BY_ID: &str = "/dev/disk/by-id";
+
+/// The directory holding the device nodes.
+const DEV: &str = "/dev";
+
+fn by_id_symlinks_in(dir: &Path, device_path: &Path) -> Result<Vec<PathBuf>, BlockDevError> {
+    let target = std::fs::canonicalize(device_path).map_err(BlockDevError::IoError)?;
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(BlockDevError::IoError(err)),
+    };
+
+    let mut links = Vec::new();
+    for entry in entries {
+        let link = entry.map_err(BlockDevError::IoError)?.path();
+        if !link.is_symlink() {
+            continue;
+        }
+        // Dangling links are left behind by removed devices and simply do not match.
+        if std::fs::canonicalize(&link).is_ok_and(|resolved| resolved == target) {
+            links.push(link);
+        }
+    }
+    links.sort();
+    Ok(links)
+}
+
+/// Returns every `/dev/disk/by-id` symlink that resolves to `device_path`.
+///
+/// A single device usually has several: one by WWN, one by model and serial,
+/// and sometimes more from firmware or the partition table. Both the links and
+/// `device_path` are canonicalized before comparing, so `device_path` may
+/// itself be a symlink such as `/dev/mapper/luks-root`. The result is sorted
+/// by link name and is empty if the directory does not exist, as in many
+/// containers. This relies on udev and is only meaningful on Linux.
+///
+/// # Errors
+///
+/// Returns [`BlockDevError::IoError`] if `device_path` cannot be resolved or
+/// `/dev/disk/by-id` cannot be read.
+///
+/// # Examples
+///
+/// ```no_run
+/// use blockdev::by_id_symlinks;
+/// use std::path::Path;
+///
+/// for link in by_id_symlinks(Path::new("/dev/nvme0n1")).expect("Failed to read by-id links") {
+///     println!("{}", link.display());
+/// }
+/// ```
+pub fn by_id_symlinks(device_path: &Path) -> Result<Vec<PathBuf>, BlockDevError> {
+    by_id_symlinks_in(Path::new(DEV_DISK_BY_ID), device_path)
+}
+
+fn resolve_device_symlink_in(dev: &Path, symlink_path: &Path) -> Result<PathBuf, BlockDevError> {
+    let target = std::fs::canonicalize(symlink_path).map_err(BlockDevError::IoError)?;
+    let dev = std::fs::canonicalize(dev).map_err(BlockDevError::IoError)?;
+    target
+        .strip_prefix(&dev)
+        .map(Path::to_path_buf)
+        .map_err(|_| {
+            BlockDevError::InvalidInput(format!(
+                "{} does not resolve to a device node: {}",
+                symlink_path.display(),
+                target.display()
+            ))
+        })
+}
+
+/// Resolves a device symlink such as `/dev/disk/by-uuid/<uuid>` and returns
+/// the canonical device name relative to `/dev`, such as `nvme0n1p2`.
+///
+/// Device-mapper links resolve to the kernel name (`dm-0`), not the mapper
+/// name that lsblk reports.
+///
+/// # Errors
+///
+/// Returns [`BlockDevError::IoError`] if the path cannot be resolved and
+/// [`BlockDevError::InvalidInput`] if it resolves to something outside `/dev`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use blockdev::resolve_device_symlink;
+/// use std::path::Path;
+///
+/// let name = resolve_device_symlink(Path::new("/dev/disk/by-label/ROOT")).expect("Failed to resolve");
+/// println!("{}", name.display());
+/// ```
+pub fn resolve_device_symlink(symlink_path: &Path) -> Result<PathBuf, BlockDevError> {
+    resolve_device_symlink_in(Path::new(DEV), symlink_path)
+}
+
+impl BlockDevices {
+    /// Finds a device by any path that names it: its [`path`](BlockDevice::path)
+    /// such as `/dev/sda1` or `/dev/mapper/luks-root`, or a udev link under
+    /// `/dev/disk/by-*` such as `/dev/disk/by-uuid/<uuid>`.
+    ///
+    /// Links are resolved with [`resolve_device_symlink`] and looked up by
+    /// kernel name, which finds everything except device-mapper devices; use
+    /// their `/dev/mapper` path instead. Returns `None` if no device matches or
+    /// a link cannot be resolved.
+    #[must_use]
+    pub fn find_by_any_path(&self, path: &Path) -> Option<&BlockDevice> {
+        self.find_by_any_path_in(Path::new(DEV), path)
+    }
+
+    fn find_by_any_path_in(&self, dev: &Path, path: &Path) -> Option<&BlockDevice> {
+        if let Some(device) = self.iter_all().find(|d| d.path() == path) {
+            return Some(device);
+        }
+        let is_udev_link = path
+            .strip_prefix(dev.join("disk"))
+            .ok()
+            .and_then(|rest| rest.iter().next())
+            .is_some_and(|dir| dir.to_string_lossy().starts_with("by-"));
+        if !is_udev_link {
+            return None;
+        }
+        let name = resolve_device_symlink_in(dev, path).ok()?;
+        self.iter_all().find(|d| Path::new(&d.name) == name)
+    }
+
+    /// Returns the first device in the tree whose filesystem label is `label`.
+    ///
+    /// Both the label as lsblk prints it and with its `\xNN` escapes decoded
+    /// (see [`BlockDevice::label_unescaped`]) are compared. Needs the `LABEL`
+    /// column, which `get_devices_extended` requests.
+    #[must_use]
+    pub fn find_by_label(&self, label: &str) -> Option<&BlockDevice> {
+        self.iter_all().find(|d| {
+            d.label.as_deref() == Some(label) || d.label_unescaped().as_deref() == Some(label)
+        })
+    }
+}
+
+impl BlockDevice {
+    /// Returns every `/dev/disk/by-id` symlink for this device's
+    /// [`path`](BlockDevice::path). See [`by_id_symlinks`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::IoError`] if the device node does not exist or
+    /// `/dev/disk/by-id` cannot be read.
+    pub fn by_id_symlinks(&self) -> Result<Vec<PathBuf>, BlockDevError> {
+        by_id_symlinks(&self.path())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn test_by_id_symlinks_in() {
+        let root = std::env::temp_dir().join(format!("blockdev-by-id-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let dev = root.join("dev");
+        let by_id = dev.join("disk/by-id");
+        std::fs::create_dir_all(&by_id).unwrap();
+        for node in ["nvme0n1", "nvme0n1p1", "sda"] {
+            std::fs::write(dev.join(node), "").unwrap();
+        }
+        let links = [
+            ("nvme-eui.0025388b71b2c3d4", "../../nvme0n1"),
+            (
+                "nvme-Samsung_SSD_980_PRO_1TB_S5GXNF0R123456",
+                "../../nvme0n1",
+            ),
+            (
+                "nvme-Samsung_SSD_980_PRO_1TB_S5GXNF0R123456-part1",
+                "../../nvme0n1p1",
+            ),
+            ("wwn-0x5000c500a1b2c3d4", "../../sda"),
+            ("ata-gone", "../../sdz"),
+        ];
+        for (name, target) in links {
+            symlink(target, by_id.join(name)).unwrap();
+        }
+        std::fs::write(by_id.join("README"), "").unwrap();
+
+        // The device path may itself be a symlink.
+        std::fs::create_dir_all(dev.join("block")).unwrap();
+        symlink("../nvme0n1", dev.join("block/259:0")).unwrap();
+
+        let expected = vec![
+            by_id.join("nvme-Samsung_SSD_980_PRO_1TB_S5GXNF0R123456"),
+            by_id.join("nvme-eui.0025388b71b2c3d4"),
+        ];
+        assert_eq!(
+            by_id_symlinks_in(&by_id, &dev.join("nvme0n1")).unwrap(),
+            expected
+        );
+        assert_eq!(
+            by_id_symlinks_in(&by_id, &dev.join("block/259:0")).unwrap(),
+            expected
+        );
+        assert_eq!(
+            by_id_symlinks_in(&by_id, &dev.join("sda")).unwrap(),
+            vec![by_id.join("wwn-0x5000c500a1b2c3d4")]
+        );
+        assert!(matches!(
+            by_id_symlinks_in(&by_id, &dev.join("sdz")),
+            Err(BlockDevError::IoError(_))
+        ));
+        assert!(
+            by_id_symlinks_in(&root.join("missing"), &dev.join("sda"))
+                .unwrap()
+                .is_empty()
+        );
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_find_by_any_path() {
+        let devices = crate::parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": ["/boot"],
+                     "label": "BOOT"},
+                    {"name": "sda2", "maj:min": "8:2", "rm": false, "size": "999G", "ro": false, "type": "part", "mountpoints": [null],
+                     "label": "my\\x20data",
+                     "children": [
+                        {"name": "luks-root", "maj:min": "253:0", "rm": false, "size": "999G", "ro": false, "type": "crypt", "mountpoints": ["/"]}
+                     ]}
+                 ]}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+
+        let root = std::env::temp_dir().join(format!("blockdev-any-path-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let dev = root.join("dev");
+        std::fs::create_dir_all(dev.join("disk/by-label")).unwrap();
+        std::fs::create_dir_all(dev.join("disk/by-uuid")).unwrap();
+        for node in ["sda", "sda1", "sda2", "dm-0"] {
+            std::fs::write(dev.join(node), "").unwrap();
+        }
+        symlink("../../sda1", dev.join("disk/by-label/BOOT")).unwrap();
+        symlink("../../sda2", dev.join("disk/by-uuid/0f3c2a9e")).unwrap();
+        symlink("../../dm-0", dev.join("disk/by-uuid/5d2f6c1e")).unwrap();
+        symlink("../../sdz", dev.join("disk/by-uuid/gone")).unwrap();
+        symlink(&root, dev.join("disk/by-uuid/outside")).unwrap();
+
+        assert_eq!(
+            resolve_device_symlink_in(&dev, &dev.join("disk/by-label/BOOT")).unwrap(),
+            PathBuf::from("sda1")
+        );
+        assert!(matches!(
+            resolve_device_symlink_in(&dev, &dev.join("disk/by-uuid/gone")),
+            Err(BlockDevError::IoError(_))
+        ));
+        assert!(matches!(
+            resolve_device_symlink_in(&dev, &dev.join("disk/by-uuid/outside")),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+
+        let find = |path: &Path| {
+            devices
+                .find_by_any_path_in(&dev, path)
+                .map(|d| d.name.as_str())
+        };
+        assert_eq!(find(&dev.join("disk/by-label/BOOT")), Some("sda1"));
+        assert_eq!(find(&dev.join("disk/by-uuid/0f3c2a9e")), Some("sda2"));
+        assert_eq!(find(Path::new("/dev/sda2")), Some("sda2"));
+        assert_eq!(find(Path::new("/dev/mapper/luks-root")), Some("luks-root"));
+        // Mapper links resolve to the kernel name, which lsblk does not report.
+        assert_eq!(find(&dev.join("disk/by-uuid/5d2f6c1e")), None);
+        assert_eq!(find(&dev.join("disk/by-uuid/gone")), None);
+        // Only udev links are resolved.
+        assert_eq!(find(&dev.join("sda1")), None);
+        assert_eq!(find(Path::new("/dev/sdz")), None);
+
+        assert_eq!(devices.find_by_label("BOOT").unwrap().name, "sda1");
+        assert_eq!(devices.find_by_label("my data").unwrap().name, "sda2");
+        assert_eq!(devices.find_by_label("my\\x20data").unwrap().name, "sda2");
+        assert!(devices.find_by_label("boot").is_none());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{BlockDevice, BlockDevices};
+use serde_json::{Map, Value};
+use std::time::SystemTime;
+
+/// Options for [`BlockDevices::to_canonical_json_with`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct CanonicalJsonOptions {
+    /// Whether to keep fields that change without any hardware change: `fsused`,
+    /// the mountinfo `mounts`, and the snapshot's `_blockdev_meta` and
+    /// `_blockdev_warnings`. Off by default.
+    pub include_volatile: bool,
+}
+
+/// Sorts devices by device number (then name), and their children and mountpoints
+/// recursively, removing volatile fields unless they were requested.
+fn canonicalize(devices: &mut [BlockDevice], options: &CanonicalJsonOptions) {
+    devices.sort_by(|a, b| {
+        (a.maj_min.major, a.maj_min.minor, &a.name).cmp(&(
+            b.maj_min.major,
+            b.maj_min.minor,
+            &b.name,
+        ))
+    });
+    for device in devices {
+        // Sorting `Option`s puts unmounted (`None`) entries first.
+        device.mountpoints.sort();
+        if !options.include_volatile {
+            device.fsused = None;
+            device.mounts = None;
+        }
+        if let Some(children) = &mut device.children {
+            canonicalize(children, options);
+        }
+    }
+}
+
+/// Rebuilds every JSON object with its keys in sorted order, which `serde_json`
+/// does not guarantee when its `preserve_order` feature is enabled.
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect::<Map<_, _>>(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        other => other,
+    }
+}
+
+impl BlockDevices {
+    /// Serializes the snapshot as canonical, pretty-printed JSON for committing to
+    /// version control, leaving out volatile fields.
+    ///
+    /// See [`BlockDevices::to_canonical_json_with`] for the exact format.
+    #[must_use]
+    pub fn to_canonical_json(&self) -> String {
+        self.to_canonical_json_with(&CanonicalJsonOptions::default())
+    }
+
+    /// Serializes the snapshot as canonical, pretty-printed JSON.
+    ///
+    /// Two snapshots of the same hardware produce byte-identical output, whatever
+    /// order lsblk listed the devices in and whichever machine serializes them:
+    ///
+    /// - Object keys are sorted alphabetically at every level.
+    /// - Devices at every level are sorted by `maj:min`, then by name.
+    /// - Each device's `mountpoints` are sorted, with `null` entries first.
+    /// - Unless `options.include_volatile` is set, `fsused`, `_blockdev_mounts`,
+    ///   `_blockdev_meta` (which holds the time and hostname) and
+    ///   `_blockdev_warnings` are left out. `_blockdev_meta` is also left out
+    ///   when its time is before the Unix epoch, which JSON cannot represent.
+    /// - Indentation is two spaces and the output ends with a newline.
+    ///
+    /// The output can be read back with [`parse_lsblk`](crate::parse_lsblk).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let a = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]}
+    /// ]}"#).unwrap();
+    /// let mut b = a.clone();
+    /// b.blockdevices.reverse();
+    /// assert_eq!(a.to_canonical_json(), b.to_canonical_json());
+    /// ```
+    #[must_use]
+    pub fn to_canonical_json_with(&self, options: &CanonicalJsonOptions) -> String {
+        let mut devices = self.clone();
+        canonicalize(&mut devices.blockdevices, options);
+        if !options.include_volatile {
+            devices.meta = None;
+            devices.warnings.clear();
+        }
+        if devices
+            .meta
+            .as_ref()
+            .is_some_and(|meta| meta.taken_at < SystemTime::UNIX_EPOCH)
+        {
+            devices.meta = None;
+        }
+
+        let value = serde_json::to_value(&devices).expect("block devices always serialize");
+        let mut json =
+            serde_json::to_string_pretty(&sort_keys(value)).expect("JSON values always serialize");
+        json.push('\n');
+        json
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::{SnapshotMeta, SourceKind, parse_lsblk};
+
+    const GOLDEN: &str = include_str!("../tests/fixtures/canonical/sample.json");
+
+    #[test]
+    fn test_canonical_json_golden() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        assert_eq!(devices.to_canonical_json(), GOLDEN);
+
+        let reparsed = parse_lsblk(GOLDEN).expect("Failed to parse golden file");
+        assert_eq!(reparsed.to_canonical_json(), GOLDEN);
+    }
+
+    #[test]
+    fn test_canonical_json_ignores_order_and_volatile_fields() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+
+        fn permute(devices: &mut [BlockDevice]) {
+            devices.reverse();
+            devices.rotate_left(devices.len() / 3);
+            for device in devices {
+                device.mountpoints.reverse();
+                device.fsused = Some(device.size / 2);
+                if let Some(children) = &mut device.children {
+                    permute(children);
+                }
+            }
+        }
+        let mut permuted = devices.clone();
+        permute(&mut permuted.blockdevices);
+        permuted.meta = Some(SnapshotMeta::new(SourceKind::Lsblk));
+        permuted.warnings = vec!["lsblk: /dev/sr0: unknown device".to_string()];
+        assert_ne!(permuted, devices);
+        assert_eq!(permuted.to_canonical_json(), devices.to_canonical_json());
+
+        let options = CanonicalJsonOptions {
+            include_volatile: true,
+        };
+        let volatile = permuted.to_canonical_json_with(&options);
+        assert!(volatile.contains("\"fsused\""));
+        assert!(volatile.contains("\"_blockdev_meta\""));
+        assert_eq!(
+            volatile,
+            permuted.to_canonical_json_with(&options),
+            "output must be stable across runs"
+        );
+    }
+
+    #[test]
+    fn test_canonical_json_leaves_out_time_before_epoch() {
+        let mut devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let mut meta = SnapshotMeta::new(SourceKind::Manual);
+        meta.taken_at = SystemTime::UNIX_EPOCH - std::time::Duration::from_secs(1);
+        devices.meta = Some(meta);
+        let json = devices.to_canonical_json_with(&CanonicalJsonOptions {
+            include_volatile: true,
+        });
+        assert!(!json.contains("\"_blockdev_meta\""));
+        assert_eq!(
+            parse_lsblk(&json).unwrap().blockdevices,
+            devices.blockdevices
+        );
+    }
+}
@@ -0,0 +1,580 @@
+use crate::parse::{MAX_JSON_START_ATTEMPTS, parse_lsblk};
+use crate::{BlockDevice, BlockDevices, DeviceType};
+use serde::de::Error as _;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// What [`parse_lsblk_with_report`] had to adjust to parse a document.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ParseWarningKind {
+    /// Non-JSON lines before the document were skipped.
+    SkippedPrefix {
+        /// The skipped lines, trimmed, without empty ones.
+        lines: Vec<String>,
+    },
+    /// A flag written as a string, such as `"rm": "0"`, was read as a boolean.
+    StringFlag {
+        /// The JSON key.
+        field: String,
+        /// The original string.
+        value: String,
+    },
+    /// A flag written as `null`, such as `"rm": null`, was read as `false`.
+    NullFlag {
+        /// The JSON key.
+        field: String,
+    },
+    /// A single `mountpoint` was read as a one-element `mountpoints` list.
+    /// Older lsblk versions show only one mountpoint per device.
+    SingleMountpoint,
+    /// The device number was built from separate `maj` and `min` fields.
+    SeparateDeviceNumber,
+    /// A required field was missing and filled with its default value.
+    MissingField {
+        /// The JSON key.
+        field: String,
+    },
+    /// The `type` is not one this crate knows, so it was read as [`DeviceType::Other`].
+    UnknownDeviceType {
+        /// The original type.
+        value: String,
+    },
+}
+
+/// One adjustment made by [`parse_lsblk_with_report`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParseWarning {
+    /// The device the adjustment applies to, or `None` for the document as a whole.
+    pub device: Option<String>,
+    /// What was adjusted.
+    pub kind: ParseWarningKind,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(device) = &self.device {
+            write!(f, "{device}: ")?;
+        }
+        match &self.kind {
+            ParseWarningKind::SkippedPrefix { lines } => {
+                write!(f, "skipped {} line(s) before the JSON", lines.len())
+            }
+            ParseWarningKind::StringFlag { field, value } => {
+                write!(f, "read string \"{value}\" in '{field}' as a boolean")
+            }
+            ParseWarningKind::NullFlag { field } => {
+                write!(f, "read null in '{field}' as false")
+            }
+            ParseWarningKind::SingleMountpoint => {
+                write!(f, "only a single 'mountpoint' was reported")
+            }
+            ParseWarningKind::SeparateDeviceNumber => {
+                write!(f, "built 'maj:min' from 'maj' and 'min'")
+            }
+            ParseWarningKind::MissingField { field } => {
+                write!(f, "missing '{field}' was filled with its default")
+            }
+            ParseWarningKind::UnknownDeviceType { value } => {
+                write!(f, "unknown type \"{value}\" was read as 'other'")
+            }
+        }
+    }
+}
+
+/// Everything [`parse_lsblk_with_report`] adjusted while parsing, in document order.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ParseReport {
+    /// The adjustments, in document order.
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl ParseReport {
+    /// Returns `true` if the document parsed without any adjustment.
+    #[must_use]
+    pub fn is_clean(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    fn push(&mut self, device: Option<&str>, kind: ParseWarningKind) {
+        self.warnings.push(ParseWarning {
+            device: device.map(str::to_string),
+            kind,
+        });
+    }
+}
+
+/// The JSON shapes [`parse_any`] recognizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    /// `lsblk --json` from util-linux 2.33 or newer: JSON booleans, and
+    /// `mountpoints` arrays from 2.37 on.
+    Lsblk,
+    /// `lsblk --json` from util-linux 2.32 or older, which writes `rm` and `ro`
+    /// as the strings `"0"` and `"1"`.
+    LegacyLsblk,
+    /// This crate's own `Serialize` output, which writes `children` explicitly
+    /// (as `null` on leaves) and may carry `_blockdev_*` keys.
+    Blockdev,
+}
+
+fn detect_shape(root: &Map<String, Value>) -> Shape {
+    fn any_device(devices: &[Value], f: &dyn Fn(&Map<String, Value>) -> bool) -> bool {
+        devices.iter().filter_map(Value::as_object).any(|device| {
+            f(device)
+                || device
+                    .get("children")
+                    .and_then(Value::as_array)
+                    .is_some_and(|children| any_device(children, f))
+        })
+    }
+
+    let devices = root
+        .get("blockdevices")
+        .and_then(Value::as_array)
+        .map_or(&[][..], Vec::as_slice);
+    if root.keys().any(|key| key.starts_with("_blockdev_"))
+        || any_device(devices, &|d| d.get("children").is_some_and(Value::is_null))
+    {
+        Shape::Blockdev
+    } else if any_device(devices, &|d| {
+        ["rm", "ro"]
+            .iter()
+            .any(|key| d.get(*key).is_some_and(Value::is_string))
+    }) {
+        Shape::LegacyLsblk
+    } else {
+        Shape::Lsblk
+    }
+}
+
+/// Converts the string flags written by old lsblk versions to booleans.
+///
+/// Returns the original string if the value was converted.
+fn coerce_flag(value: &mut Value) -> Option<String> {
+    let flag = match value.as_str() {
+        Some("0" | "false") => false,
+        Some("1" | "true") => true,
+        _ => return None,
+    };
+    std::mem::replace(value, Value::Bool(flag))
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Adapts one device object to the current [`BlockDevice`] shape, recursing into
+/// children, and records every adjustment in `report`.
+fn normalize_device(
+    device: &mut Value,
+    shape: Shape,
+    defaults: &Map<String, Value>,
+    report: &mut ParseReport,
+) {
+    let Some(object) = device.as_object_mut() else {
+        return;
+    };
+    let name = object
+        .get("name")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let name = name.as_deref();
+
+    if shape == Shape::LegacyLsblk {
+        for key in ["rm", "ro"] {
+            if let Some(value) = object.get_mut(key).and_then(coerce_flag) {
+                let field = key.to_string();
+                report.push(name, ParseWarningKind::StringFlag { field, value });
+            }
+        }
+    }
+    for key in ["rm", "ro"] {
+        if object.get(key).is_some_and(Value::is_null) {
+            let field = key.to_string();
+            report.push(name, ParseWarningKind::NullFlag { field });
+        }
+    }
+    if let Some(mountpoint) = object.remove("mountpoint") {
+        if !object.contains_key("mountpoints") {
+            object.insert("mountpoints".to_string(), mountpoint);
+            report.push(name, ParseWarningKind::SingleMountpoint);
+        }
+    }
+    if !object.contains_key("maj:min") && object.contains_key("maj") {
+        report.push(name, ParseWarningKind::SeparateDeviceNumber);
+    }
+    if let Some(Value::String(value)) = object.get("type") {
+        let known = serde_json::from_value::<DeviceType>(Value::String(value.clone()))
+            .is_ok_and(|t| t != DeviceType::Other || value == "other");
+        if !known {
+            let value = value.clone();
+            report.push(name, ParseWarningKind::UnknownDeviceType { value });
+        }
+    }
+
+    // Required fields missing from an older snapshot get their `Default` values.
+    for (key, value) in defaults {
+        let has_device_number = key == "maj:min" && object.contains_key("maj");
+        if !has_device_number && !object.contains_key(key) {
+            object.insert(key.clone(), value.clone());
+            // Leaves without `children` are normal lsblk output.
+            if key != "children" {
+                let field = key.clone();
+                report.push(name, ParseWarningKind::MissingField { field });
+            }
+        }
+    }
+
+    if let Some(children) = object.get_mut("children").and_then(Value::as_array_mut) {
+        for child in children {
+            normalize_device(child, shape, defaults, report);
+        }
+    }
+}
+
+/// Normalizes a parsed document in place and deserializes it.
+fn parse_value(
+    mut root: Map<String, Value>,
+    report: &mut ParseReport,
+) -> Result<BlockDevices, serde_json::Error> {
+    let shape = detect_shape(&root);
+    let defaults = match serde_json::to_value(BlockDevice::default())? {
+        Value::Object(defaults) => defaults,
+        _ => unreachable!("BlockDevice serializes to an object"),
+    };
+    if let Some(devices) = root.get_mut("blockdevices").and_then(Value::as_array_mut) {
+        for device in devices {
+            normalize_device(device, shape, &defaults, report);
+        }
+    }
+    serde_json::from_value(Value::Object(root))
+}
+
+/// Parses any supported snapshot format into a [`BlockDevices`] tree.
+///
+/// Unlike [`parse_lsblk`], which expects the output of a current `lsblk`, this
+/// accepts every shape that has been archived in practice and is the entry
+/// point to use for stored snapshots. The following shapes are guaranteed to
+/// keep parsing, and frozen fixtures of each live in `tests/fixtures/compat`:
+///
+/// - `lsblk --json` from util-linux 2.37 and newer (`mountpoints` arrays).
+/// - `lsblk --json` from util-linux 2.33 to 2.36 (a single `mountpoint`).
+/// - `lsblk --json` from util-linux 2.32 and older, which writes `rm` and `ro`
+///   as the strings `"0"` and `"1"`.
+/// - This crate's own serialized output, from 0.3.1 on (`children` written as
+///   `null`, sizes in bytes, `_blockdev_meta` and other `_blockdev_*` keys).
+///
+/// A field that [`BlockDevice`] requires but the snapshot predates is filled
+/// with its [`Default`] value instead of failing. Input that is not a JSON
+/// object is handed to [`parse_lsblk`], so leading lsblk warnings are still
+/// recorded in [`BlockDevices::warnings`].
+///
+/// # Errors
+///
+/// Returns a `serde_json::Error` if the input is not JSON or has no
+/// `blockdevices` array.
+///
+/// # Examples
+///
+/// ```
+/// use blockdev::parse_any;
+///
+/// let legacy = r#"{"blockdevices": [{"name": "sda", "maj:min": "8:0", "rm": "0", "size": "500G", "ro": "1", "type": "disk", "mountpoint": null}]}"#;
+/// let devices = parse_any(legacy).unwrap();
+/// assert!(devices.find_by_name("sda").unwrap().ro);
+/// ```
+pub fn parse_any(json: &str) -> Result<BlockDevices, serde_json::Error> {
+    match serde_json::from_str::<Value>(json) {
+        Ok(Value::Object(root)) => parse_value(root, &mut ParseReport::default()),
+        Ok(_) => Err(serde_json::Error::custom("expected a JSON object")),
+        Err(_) => parse_lsblk(json),
+    }
+}
+
+/// Parses like [`parse_any`] and also reports everything that had to be
+/// adjusted on the way: string flags, single mountpoints, separate `maj` and
+/// `min` fields, missing required fields, unknown device types and non-JSON
+/// lines before the document.
+///
+/// The adjustments are made on the raw JSON before typed deserialization, so
+/// each one can be attributed to a device. Clean output from a current lsblk
+/// produces an empty report.
+///
+/// # Errors
+///
+/// Returns a `serde_json::Error` if no JSON object can be found in the input
+/// or it has no valid `blockdevices` array.
+///
+/// # Examples
+///
+/// ```
+/// use blockdev::{parse_lsblk_with_report, ParseWarningKind};
+///
+/// let legacy = r#"{"blockdevices": [{"name": "sda", "maj:min": "8:0", "rm": "0", "size": "500G", "ro": "0", "type": "disk", "mountpoints": [null]}]}"#;
+/// let (devices, report) = parse_lsblk_with_report(legacy).unwrap();
+/// assert!(!devices.blockdevices[0].rm);
+/// assert_eq!(report.warnings.len(), 2);
+/// assert_eq!(report.warnings[0].device.as_deref(), Some("sda"));
+/// assert!(matches!(report.warnings[0].kind, ParseWarningKind::StringFlag { .. }));
+/// ```
+pub fn parse_lsblk_with_report(
+    json: &str,
+) -> Result<(BlockDevices, ParseReport), serde_json::Error> {
+    let mut report = ParseReport::default();
+    let (root, start) = match serde_json::from_str::<Value>(json) {
+        Ok(Value::Object(root)) => (root, 0),
+        Ok(_) => return Err(serde_json::Error::custom("expected a JSON object")),
+        Err(error) => json
+            .match_indices('{')
+            .take(MAX_JSON_START_ATTEMPTS)
+            .find_map(|(start, _)| match serde_json::from_str(&json[start..]) {
+                Ok(Value::Object(root)) => Some((root, start)),
+                _ => None,
+            })
+            .ok_or(error)?,
+    };
+
+    let skipped: Vec<String> = json[..start]
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    if !skipped.is_empty() {
+        report.push(
+            None,
+            ParseWarningKind::SkippedPrefix {
+                lines: skipped.clone(),
+            },
+        );
+    }
+    let mut devices = parse_value(root, &mut report)?;
+    if !skipped.is_empty() {
+        devices.warnings = skipped;
+    }
+    Ok((devices, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DeviceType;
+
+    /// Frozen snapshots that every future version must keep parsing. Add a new
+    /// file whenever the serialized shape changes; never edit an existing one.
+    const FIXTURES: &[(&str, &str, Shape)] = &[
+        (
+            "lsblk-2.32",
+            include_str!("../tests/fixtures/compat/lsblk-2.32.json"),
+            Shape::LegacyLsblk,
+        ),
+        (
+            "lsblk-2.36",
+            include_str!("../tests/fixtures/compat/lsblk-2.36.json"),
+            Shape::Lsblk,
+        ),
+        (
+            "lsblk-2.38",
+            include_str!("../tests/fixtures/compat/lsblk-2.38.json"),
+            Shape::Lsblk,
+        ),
+        (
+            "blockdev-0.3.1",
+            include_str!("../tests/fixtures/compat/blockdev-0.3.1.json"),
+            Shape::Blockdev,
+        ),
+        (
+            "blockdev-0.4.0",
+            include_str!("../tests/fixtures/compat/blockdev-0.4.0.json"),
+            Shape::Blockdev,
+        ),
+    ];
+
+    #[test]
+    fn test_compat_fixtures() {
+        for (version, json, shape) in FIXTURES {
+            let root = serde_json::from_str(json).expect(version);
+            assert_eq!(detect_shape(&root), *shape, "{version}");
+
+            let devices = parse_any(json).unwrap_or_else(|e| panic!("{version}: {e}"));
+            let summary: Vec<(&str, bool, DeviceType, Vec<&str>)> = devices
+                .iter_all()
+                .map(|d| {
+                    (
+                        d.name.as_str(),
+                        d.rm,
+                        d.device_type.clone(),
+                        d.active_mountpoints(),
+                    )
+                })
+                .collect();
+            assert_eq!(
+                summary,
+                vec![
+                    ("sda", false, DeviceType::Disk, vec![]),
+                    ("sda1", false, DeviceType::Part, vec!["/boot/efi"]),
+                    ("sda2", false, DeviceType::Part, vec!["[SWAP]"]),
+                    ("sda3", false, DeviceType::Part, vec!["/"]),
+                    ("sr0", true, DeviceType::Rom, vec![]),
+                ],
+                "{version}"
+            );
+            let sda3 = devices
+                .find_by_name("sda")
+                .unwrap()
+                .find_child("sda3")
+                .unwrap();
+            assert_eq!(sda3.size, 491_022_136_115, "{version}");
+            assert!(
+                devices.find_by_name("sda").unwrap().is_system(),
+                "{version}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_current_serialization_matches_latest_fixture() {
+        let (_, json, _) = FIXTURES.last().unwrap();
+        let devices = parse_any(json).expect("Failed to parse fixture");
+        let reserialized = serde_json::to_value(&devices).expect("Failed to serialize");
+        let frozen: Value = serde_json::from_str(json).expect("Failed to parse fixture");
+        assert_eq!(reserialized, frozen);
+    }
+
+    #[test]
+    fn test_missing_required_fields_are_defaulted() {
+        // A snapshot written before `ro`, `type` and `mountpoints` existed.
+        let json =
+            r#"{"blockdevices": [{"name": "sda", "maj:min": "8:0", "rm": false, "size": 1024}]}"#;
+        assert!(parse_lsblk(json).is_err());
+
+        let devices = parse_any(json).expect("Failed to parse");
+        let sda = devices.find_by_name("sda").unwrap();
+        assert!(!sda.ro);
+        assert_eq!(sda.device_type, DeviceType::Other);
+        assert!(sda.mountpoints.is_empty());
+
+        let split = r#"{"blockdevices": [{"name": "sda", "maj": 8, "min": 0, "rm": false, "size": 1024, "ro": false, "type": "disk"}]}"#;
+        assert_eq!(parse_any(split).unwrap().blockdevices[0].maj_min.minor, 0);
+    }
+
+    #[test]
+    fn test_report_null_flags() {
+        let json = r#"{"blockdevices": [
+            {"name": "vda", "maj:min": "253:0", "rm": false, "size": "20G", "ro": false, "type": "disk", "mountpoints": [null],
+             "children": [
+                {"name": "vg0-root", "maj:min": "252:0", "rm": null, "size": "20G", "ro": false, "type": "lvm", "mountpoints": ["/"]}
+             ]}
+        ]}"#;
+        let (devices, report) = parse_lsblk_with_report(json).expect("Failed to parse");
+        assert_eq!(devices, parse_lsblk(json).unwrap());
+        assert!(!devices.iter_all().any(BlockDevice::is_removable));
+        assert_eq!(
+            report.warnings,
+            vec![ParseWarning {
+                device: Some("vg0-root".to_string()),
+                kind: ParseWarningKind::NullFlag {
+                    field: "rm".to_string()
+                },
+            }]
+        );
+        assert_eq!(
+            report.warnings[0].to_string(),
+            "vg0-root: read null in 'rm' as false"
+        );
+    }
+
+    #[test]
+    fn test_report_legacy_fixture() {
+        let (devices, report) = parse_lsblk_with_report(FIXTURES[0].1).expect("Failed to parse");
+        assert_eq!(devices, parse_any(FIXTURES[0].1).unwrap());
+
+        let warning = |device: &str, kind: ParseWarningKind| ParseWarning {
+            device: Some(device.to_string()),
+            kind,
+        };
+        let flag = |field: &str, value: &str| ParseWarningKind::StringFlag {
+            field: field.to_string(),
+            value: value.to_string(),
+        };
+        assert_eq!(
+            &report.warnings[..3],
+            &[
+                warning("sda", flag("rm", "0")),
+                warning("sda", flag("ro", "0")),
+                warning("sda", ParseWarningKind::SingleMountpoint),
+            ]
+        );
+        assert!(report.warnings.contains(&warning("sr0", flag("rm", "1"))));
+        // Five devices, each with two string flags and a single mountpoint.
+        assert_eq!(report.warnings.len(), 15);
+        assert_eq!(
+            report.warnings[0].to_string(),
+            "sda: read string \"0\" in 'rm' as a boolean"
+        );
+    }
+
+    #[test]
+    fn test_report_clean_output() {
+        let (_, report) = parse_lsblk_with_report(FIXTURES[2].1).expect("Failed to parse");
+        assert!(report.is_clean(), "{:?}", report.warnings);
+        let (_, report) =
+            parse_lsblk_with_report(crate::fixtures::SAMPLE_JSON).expect("Failed to parse");
+        assert!(
+            report
+                .warnings
+                .iter()
+                .all(|w| w.kind == ParseWarningKind::SingleMountpoint)
+        );
+    }
+
+    #[test]
+    fn test_report_adjustments() {
+        let json = "lsblk: /dev/sr0: unknown device\n\n{\"blockdevices\": [
+            {\"name\": \"sda\", \"maj\": 8, \"min\": 0, \"rm\": false, \"size\": 1024, \"ro\": false, \"type\": \"disk\", \"mountpoints\": [null]},
+            {\"name\": \"zd0\", \"maj:min\": \"230:0\", \"rm\": false, \"size\": 1024, \"ro\": false, \"type\": \"zvol\"}
+        ]}";
+        let (devices, report) = parse_lsblk_with_report(json).expect("Failed to parse");
+        assert_eq!(devices.warnings, vec!["lsblk: /dev/sr0: unknown device"]);
+        assert_eq!(devices.blockdevices[1].device_type, DeviceType::Other);
+        let summary: Vec<(Option<&str>, &ParseWarningKind)> = report
+            .warnings
+            .iter()
+            .map(|w| (w.device.as_deref(), &w.kind))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    None,
+                    &ParseWarningKind::SkippedPrefix {
+                        lines: vec!["lsblk: /dev/sr0: unknown device".to_string()]
+                    }
+                ),
+                (Some("sda"), &ParseWarningKind::SeparateDeviceNumber),
+                (
+                    Some("zd0"),
+                    &ParseWarningKind::UnknownDeviceType {
+                        value: "zvol".to_string()
+                    }
+                ),
+                (
+                    Some("zd0"),
+                    &ParseWarningKind::MissingField {
+                        field: "mountpoints".to_string()
+                    }
+                ),
+            ]
+        );
+        assert!(parse_lsblk_with_report("not json").is_err());
+        assert!(parse_lsblk_with_report("[]").is_err());
+    }
+
+    #[test]
+    fn test_parse_any_invalid() {
+        assert!(parse_any("[]").is_err());
+        assert!(parse_any("{}").is_err());
+        assert!(parse_any("not json").is_err());
+
+        let prefixed = "lsblk: /dev/sr0: unknown device\n{\"blockdevices\": []}";
+        assert_eq!(parse_any(prefixed).unwrap().warnings.len(), 1);
+    }
+}
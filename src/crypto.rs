@@ -0,0 +1,248 @@
+use crate::{BlockDevice, BlockDevices};
+use std::collections::HashSet;
+
+/// The kind of encrypted container a device holds, derived from its `fstype`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CryptoType {
+    /// A LUKS version 1 container.
+    Luks1,
+    /// A LUKS version 2 container.
+    Luks2,
+    /// A VeraCrypt or TrueCrypt container.
+    VeraCrypt,
+    /// Another encryption format, holding the raw `fstype`, such as `BitLocker`
+    /// or a LUKS container whose version is unknown.
+    Other(String),
+    /// The device does not hold a recognized encrypted container.
+    NotEncrypted,
+}
+
+/// `fstype` values that blkid reports for encrypted containers other than LUKS.
+const OTHER_CRYPTO_FSTYPES: &[&str] = &["BitLocker"];
+
+impl BlockDevice {
+    /// Returns the kind of encrypted container on this device.
+    ///
+    /// This needs the `fstype` column, and the `fsver` column to tell LUKS1 from
+    /// LUKS2; both are requested by `get_devices_extended`. Without `fsver`, a
+    /// LUKS container is reported as `CryptoType::Other("crypto_LUKS")`.
+    /// VeraCrypt containers have no plaintext signature, so they are only
+    /// recognized when `fstype` has been set to `crypto_TCRYPT` by another tool.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{BlockDevice, CryptoType};
+    ///
+    /// let luks = BlockDevice {
+    ///     fstype: Some("crypto_LUKS".to_string()),
+    ///     fsver: Some("2".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(luks.crypto_type(), CryptoType::Luks2);
+    /// assert!(luks.is_luks());
+    /// ```
+    #[must_use]
+    pub fn crypto_type(&self) -> CryptoType {
+        let Some(fstype) = self.fstype.as_deref() else {
+            return CryptoType::NotEncrypted;
+        };
+        match fstype {
+            "crypto_LUKS" => match self.fsver.as_deref().map(str::trim) {
+                Some("1") => CryptoType::Luks1,
+                Some("2") => CryptoType::Luks2,
+                _ => CryptoType::Other(fstype.to_string()),
+            },
+            "crypto_TCRYPT" => CryptoType::VeraCrypt,
+            _ if fstype.starts_with("crypto_") || OTHER_CRYPTO_FSTYPES.contains(&fstype) => {
+                CryptoType::Other(fstype.to_string())
+            }
+            _ => CryptoType::NotEncrypted,
+        }
+    }
+
+    /// Returns `true` if this device holds a LUKS container of any version.
+    #[must_use]
+    pub fn is_luks(&self) -> bool {
+        self.fstype.as_deref() == Some("crypto_LUKS")
+    }
+
+    /// Returns an `/etc/crypttab` line that unlocks this LUKS container as
+    /// `/dev/mapper/<mapper_name>`, prompting for the passphrase at boot.
+    ///
+    /// The container is referenced by its `UUID`, which `get_devices_extended`
+    /// requests. Returns `None` if this is not a LUKS container, its UUID is
+    /// missing or empty, or `mapper_name` is empty or contains whitespace.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::BlockDevice;
+    ///
+    /// let luks = BlockDevice {
+    ///     name: "sda2".to_string(),
+    ///     fstype: Some("crypto_LUKS".to_string()),
+    ///     uuid: Some("3f9a1c2e-7b4d-4e8a-9c1f-2d5e6a7b8c9d".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(
+    ///     luks.to_crypttab_entry("cryptroot").as_deref(),
+    ///     Some("cryptroot UUID=3f9a1c2e-7b4d-4e8a-9c1f-2d5e6a7b8c9d none luks")
+    /// );
+    /// ```
+    #[must_use]
+    pub fn to_crypttab_entry(&self, mapper_name: &str) -> Option<String> {
+        if !self.is_luks() || mapper_name.is_empty() || mapper_name.contains(char::is_whitespace) {
+            return None;
+        }
+        let uuid = self
+            .uuid
+            .as_deref()
+            .map(str::trim)
+            .filter(|u| !u.is_empty())?;
+        Some(format!("{mapper_name} UUID={uuid} none luks"))
+    }
+}
+
+impl BlockDevices {
+    /// Returns the first occurrence of every device matching `predicate`, in tree order.
+    fn unique_matching(&self, predicate: impl Fn(&BlockDevice) -> bool) -> Vec<&BlockDevice> {
+        let mut seen = HashSet::new();
+        self.iter_all()
+            .filter(|d| predicate(d) && seen.insert(d.name.as_str()))
+            .collect()
+    }
+
+    /// Returns every device in the tree that holds a LUKS container.
+    #[must_use]
+    pub fn luks_devices(&self) -> Vec<&BlockDevice> {
+        self.unique_matching(BlockDevice::is_luks)
+    }
+
+    /// Returns every device in the tree that holds an encrypted container of any kind.
+    #[must_use]
+    pub fn encrypted_devices(&self) -> Vec<&BlockDevice> {
+        self.unique_matching(|d| d.crypto_type() != CryptoType::NotEncrypted)
+    }
+
+    /// Returns a [`BlockDevice::to_crypttab_entry`] line for every LUKS
+    /// container in the tree, in tree order, naming each mapping after the
+    /// container's device name with any `/` replaced by `_`.
+    ///
+    /// Containers without a UUID are skipped.
+    #[must_use]
+    pub fn all_crypttab_entries(&self) -> Vec<String> {
+        self.luks_devices()
+            .into_iter()
+            .filter_map(|d| d.to_crypttab_entry(&d.name.replace('/', "_")))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_lsblk;
+
+    const ENCRYPTED_JSON: &str = r#"{
+        "blockdevices": [
+            {"name": "sda", "maj:min": "8:0", "rm": false, "size": "100G", "ro": false, "type": "disk", "mountpoints": [null],
+             "children": [
+                {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": ["/boot"],
+                 "fstype": "ext4", "fsver": "1.0"},
+                {"name": "sda2", "maj:min": "8:2", "rm": false, "size": "99G", "ro": false, "type": "part", "mountpoints": [null],
+                 "fstype": "crypto_LUKS", "fsver": "2", "uuid": "3f9a1c2e-7b4d-4e8a-9c1f-2d5e6a7b8c9d",
+                 "children": [
+                    {"name": "luks-root", "maj:min": "253:0", "rm": false, "size": "99G", "ro": false, "type": "crypt", "mountpoints": ["/"],
+                     "fstype": "xfs"}
+                 ]}
+             ]},
+            {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+             "fstype": "crypto_LUKS", "fsver": "1", "uuid": "b7e2d4a1-0c3f-4a5b-8d6e-9f1a2b3c4d5e"},
+            {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+             "fstype": "crypto_LUKS"},
+            {"name": "sdd", "maj:min": "8:48", "rm": true, "size": "64G", "ro": false, "type": "disk", "mountpoints": [null],
+             "fstype": "BitLocker"},
+            {"name": "sde", "maj:min": "8:64", "rm": true, "size": "64G", "ro": false, "type": "disk", "mountpoints": [null]}
+        ]
+    }"#;
+
+    #[test]
+    fn test_crypto_type() {
+        let devices = parse_lsblk(ENCRYPTED_JSON).expect("Failed to parse JSON");
+        let types: Vec<(&str, CryptoType)> = devices
+            .iter_all()
+            .map(|d| (d.name.as_str(), d.crypto_type()))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("sda", CryptoType::NotEncrypted),
+                ("sda1", CryptoType::NotEncrypted),
+                ("sda2", CryptoType::Luks2),
+                ("luks-root", CryptoType::NotEncrypted),
+                ("sdb", CryptoType::Luks1),
+                ("sdc", CryptoType::Other("crypto_LUKS".to_string())),
+                ("sdd", CryptoType::Other("BitLocker".to_string())),
+                ("sde", CryptoType::NotEncrypted),
+            ]
+        );
+
+        let tcrypt = BlockDevice {
+            fstype: Some("crypto_TCRYPT".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(tcrypt.crypto_type(), CryptoType::VeraCrypt);
+        assert!(!tcrypt.is_luks());
+    }
+
+    #[test]
+    fn test_luks_and_encrypted_devices() {
+        let devices = parse_lsblk(ENCRYPTED_JSON).expect("Failed to parse JSON");
+        let names = |list: Vec<&BlockDevice>| -> Vec<String> {
+            list.iter().map(|d| d.name.clone()).collect()
+        };
+        assert_eq!(names(devices.luks_devices()), vec!["sda2", "sdb", "sdc"]);
+        assert_eq!(
+            names(devices.encrypted_devices()),
+            vec!["sda2", "sdb", "sdc", "sdd"]
+        );
+    }
+
+    #[test]
+    fn test_crypttab_entries() {
+        let devices = parse_lsblk(ENCRYPTED_JSON).expect("Failed to parse JSON");
+        let sda2 = devices
+            .find_by_name("sda")
+            .unwrap()
+            .find_child("sda2")
+            .unwrap();
+        assert_eq!(
+            sda2.to_crypttab_entry("cryptroot").as_deref(),
+            Some("cryptroot UUID=3f9a1c2e-7b4d-4e8a-9c1f-2d5e6a7b8c9d none luks")
+        );
+        assert_eq!(sda2.to_crypttab_entry(""), None);
+        assert_eq!(sda2.to_crypttab_entry("crypt root"), None);
+
+        // sdc has no UUID, sdd is BitLocker and luks-root is the opened mapping.
+        for name in ["sdc", "sdd"] {
+            let device = devices.find_by_name(name).unwrap();
+            assert_eq!(device.to_crypttab_entry("data"), None, "{name}");
+        }
+        assert_eq!(
+            sda2.find_child("luks-root")
+                .unwrap()
+                .to_crypttab_entry("data"),
+            None
+        );
+
+        assert_eq!(
+            devices.all_crypttab_entries(),
+            vec![
+                "sda2 UUID=3f9a1c2e-7b4d-4e8a-9c1f-2d5e6a7b8c9d none luks",
+                "sdb UUID=b7e2d4a1-0c3f-4a5b-8d6e-9f1a2b3c4d5e none luks",
+            ]
+        );
+        assert!(BlockDevices::default().all_crypttab_entries().is_empty());
+    }
+}
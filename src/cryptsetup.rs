@@ -0,0 +1,274 @@
+use crate::exec::tool_command;
+use crate::naming::validate_device_name;
+use crate::{BlockDevError, BlockDevice, DeviceType};
+use std::path::Path;
+
+/// The parameters of an open dm-crypt mapping, as reported by
+/// `cryptsetup status`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EncryptionStatus {
+    /// The cipher specification, such as `aes-xts-plain64`.
+    pub cipher: String,
+    /// The size of the volume key in bits, such as `512` for AES-256 in XTS mode.
+    pub keysize_bits: u32,
+    /// The IV generator taken from [`cipher`](Self::cipher), such as `plain64`
+    /// or `essiv:sha256`, or empty if the cipher uses none.
+    pub iv_mode: String,
+    /// The backing device holding the encrypted data, such as `/dev/nvme0n1p3`.
+    pub device: String,
+}
+
+/// Returns the IV generator of a cipher specification: the part after the
+/// chain mode in `cipher-chainmode-ivmode`, or after the last `-` in the
+/// kernel crypto API form `capi:xts(aes)-plain64`.
+fn iv_mode(cipher: &str) -> &str {
+    let iv = match cipher.strip_prefix("capi:") {
+        Some(capi) => capi.rsplit_once('-').map(|(_, iv)| iv),
+        None => cipher.splitn(3, '-').nth(2),
+    };
+    iv.unwrap_or_default()
+}
+
+/// Parses the output of `cryptsetup status <name>`.
+///
+/// Returns `Ok(None)` for a mapping that is not active. The fields are the
+/// indented `key: value` lines following the `<path> is active` line.
+fn parse_cryptsetup_status(output: &str) -> Result<Option<EncryptionStatus>, BlockDevError> {
+    let mut lines = output.lines();
+    let headline = lines.next().unwrap_or_default().trim();
+    if headline.ends_with("is inactive.") {
+        return Ok(None);
+    }
+    if !headline.contains(" is active") {
+        return Err(BlockDevError::InvalidInput(format!(
+            "unexpected cryptsetup status output '{headline}'"
+        )));
+    }
+
+    let mut cipher = None;
+    let mut keysize = None;
+    let mut device = None;
+    for line in lines {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim() {
+            "cipher" => cipher = Some(value),
+            "keysize" => keysize = Some(value),
+            "device" => device = Some(value),
+            _ => {}
+        }
+    }
+    let missing =
+        |field: &str| BlockDevError::InvalidInput(format!("cryptsetup status has no {field}"));
+    let cipher = cipher.ok_or_else(|| missing("cipher"))?;
+    let keysize = keysize.ok_or_else(|| missing("keysize"))?;
+    let keysize_bits = keysize
+        .strip_suffix("bits")
+        .and_then(|bits| bits.trim().parse().ok())
+        .ok_or_else(|| {
+            BlockDevError::InvalidInput(format!("invalid cryptsetup keysize '{keysize}'"))
+        })?;
+    Ok(Some(EncryptionStatus {
+        cipher: cipher.to_string(),
+        keysize_bits,
+        iv_mode: iv_mode(cipher).to_string(),
+        device: device.ok_or_else(|| missing("device"))?.to_string(),
+    }))
+}
+
+/// Runs `cryptsetup status` on `path` and parses its output.
+///
+/// `cryptsetup` is resolved and run like lsblk: with the `secure-exec`
+/// feature, from a trusted system directory and with only `LC_ALL=C` set.
+fn query_status(path: &Path) -> Result<Option<EncryptionStatus>, BlockDevError> {
+    let output = tool_command("cryptsetup")
+        .arg("status")
+        .arg(path)
+        .output()?;
+    let stdout = String::from_utf8(output.stdout)?;
+    // cryptsetup exits with status 4 for a mapping that is not active.
+    if output.status.success() || stdout.trim_end().ends_with("is inactive.") {
+        return parse_cryptsetup_status(&stdout);
+    }
+    Err(BlockDevError::CryptsetupError(
+        String::from_utf8_lossy(&output.stderr).into_owned(),
+    ))
+}
+
+/// Returns the cipher, key size, IV mode and backing device of the open
+/// dm-crypt mapping `/dev/mapper/<mapper_name>`, by running
+/// `cryptsetup status`, or `None` if the mapping is not active.
+///
+/// `cryptsetup status` usually needs root. Requires the `cryptsetup` feature.
+///
+/// # Errors
+///
+/// Returns [`BlockDevError::InvalidInput`] if `mapper_name` is not a bare
+/// name or the output cannot be parsed, [`BlockDevError::CommandFailed`] if
+/// `cryptsetup` cannot be run, [`BlockDevError::CryptsetupError`] if it fails
+/// for another reason than the mapping being inactive, such as missing
+/// permissions, and [`BlockDevError::InvalidUtf8`] if its output is not UTF-8.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use blockdev::encryption_status;
+/// if let Some(status) = encryption_status("luks-root").expect("Failed to run cryptsetup") {
+///     println!("{} with a {}-bit key", status.cipher, status.keysize_bits);
+/// }
+/// ```
+pub fn encryption_status(mapper_name: &str) -> Result<Option<EncryptionStatus>, BlockDevError> {
+    validate_device_name(mapper_name)?;
+    query_status(&Path::new("/dev/mapper").join(mapper_name))
+}
+
+impl BlockDevice {
+    /// Returns the [`EncryptionStatus`] of this device if it is an open
+    /// dm-crypt mapping, using [`encryption_status`](crate::encryption_status).
+    ///
+    /// The device's lsblk `name` is passed to `cryptsetup` as the mapper name:
+    /// lsblk names dm-crypt devices after their mapping, and no `DM-NAME`
+    /// column is fetched to look it up separately. Devices whose type is not
+    /// `crypt`, including the LUKS containers themselves, return `Ok(None)`
+    /// without running it. Requires the `cryptsetup` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`encryption_status`](crate::encryption_status).
+    pub fn encryption_status(&self) -> Result<Option<EncryptionStatus>, BlockDevError> {
+        self.encryption_status_with(encryption_status)
+    }
+
+    fn encryption_status_with<F>(
+        &self,
+        status: F,
+    ) -> Result<Option<EncryptionStatus>, BlockDevError>
+    where
+        F: FnOnce(&str) -> Result<Option<EncryptionStatus>, BlockDevError>,
+    {
+        if self.device_type != DeviceType::Crypt {
+            return Ok(None);
+        }
+        status(&self.name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `cryptsetup status luks-root` for a LUKS2 root filesystem.
+    const STATUS_LUKS2: &str = "\
+/dev/mapper/luks-root is active and is in use.
+  type:    LUKS2
+  cipher:  aes-xts-plain64
+  keysize: 512 bits
+  key location: keyring
+  device:  /dev/nvme0n1p3
+  sector size:  4096
+  offset:  32768 sectors
+  size:    1951432704 sectors
+  mode:    read/write
+  flags:   discards
+";
+
+    /// `cryptsetup status` for an old LUKS1 volume using ESSIV.
+    const STATUS_LUKS1: &str = "\
+/dev/mapper/backup is active.
+  type:    LUKS1
+  cipher:  aes-cbc-essiv:sha256
+  keysize: 256 bits
+  key location: dm-crypt
+  device:  /dev/sdb1
+  sector size:  512
+  offset:  4096 sectors
+  size:    976769024 sectors
+  mode:    read/write
+";
+
+    #[test]
+    fn test_parse_cryptsetup_status() {
+        assert_eq!(
+            parse_cryptsetup_status(STATUS_LUKS2).unwrap(),
+            Some(EncryptionStatus {
+                cipher: "aes-xts-plain64".to_string(),
+                keysize_bits: 512,
+                iv_mode: "plain64".to_string(),
+                device: "/dev/nvme0n1p3".to_string(),
+            })
+        );
+        let luks1 = parse_cryptsetup_status(STATUS_LUKS1).unwrap().unwrap();
+        assert_eq!(luks1.cipher, "aes-cbc-essiv:sha256");
+        assert_eq!(luks1.keysize_bits, 256);
+        assert_eq!(luks1.iv_mode, "essiv:sha256");
+        assert_eq!(luks1.device, "/dev/sdb1");
+
+        assert_eq!(
+            parse_cryptsetup_status("/dev/mapper/luks-home is inactive.\n").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_cryptsetup_status_errors() {
+        for output in [
+            "",
+            "Device luks-root not found\n",
+            "/dev/mapper/x is active.\n  cipher:  aes-xts-plain64\n  device:  /dev/sda1\n",
+            "/dev/mapper/x is active.\n  cipher:  aes-xts-plain64\n  keysize: many bits\n  device:  /dev/sda1\n",
+        ] {
+            assert!(
+                matches!(
+                    parse_cryptsetup_status(output),
+                    Err(BlockDevError::InvalidInput(_))
+                ),
+                "{output}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_iv_mode() {
+        assert_eq!(iv_mode("aes-xts-plain64"), "plain64");
+        assert_eq!(iv_mode("serpent-cbc-essiv:sha256"), "essiv:sha256");
+        assert_eq!(iv_mode("capi:xts(aes)-plain64"), "plain64");
+        assert_eq!(iv_mode("cipher_null-ecb"), "");
+    }
+
+    #[test]
+    fn test_device_encryption_status() {
+        let crypt = BlockDevice {
+            name: "luks-root".to_string(),
+            device_type: DeviceType::Crypt,
+            ..Default::default()
+        };
+        let status = crypt
+            .encryption_status_with(|name| {
+                assert_eq!(name, "luks-root");
+                parse_cryptsetup_status(STATUS_LUKS2)
+            })
+            .unwrap();
+        assert_eq!(status.unwrap().keysize_bits, 512);
+
+        let container = BlockDevice {
+            name: "nvme0n1p3".to_string(),
+            device_type: DeviceType::Part,
+            fstype: Some("crypto_LUKS".to_string()),
+            ..Default::default()
+        };
+        let status = container
+            .encryption_status_with(|_| panic!("cryptsetup must not run for a partition"))
+            .unwrap();
+        assert_eq!(status, None);
+    }
+
+    #[test]
+    fn test_encryption_status_rejects_paths() {
+        assert!(matches!(
+            encryption_status("../sda"),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+    }
+}
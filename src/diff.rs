@@ -0,0 +1,476 @@
+use crate::{BlockDevice, BlockDevices, DeviceType};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// A device whose own properties differ between two snapshots.
+///
+/// Both copies have their `children` removed; changes to descendants are
+/// reported as separate entries.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DeviceChange {
+    /// The device as it was in the older snapshot.
+    pub before: BlockDevice,
+    /// The device as it is in the newer snapshot.
+    pub after: BlockDevice,
+}
+
+/// The differences between two [`BlockDevices`] snapshots.
+///
+/// Devices are matched by name across the whole tree. A device that appears under
+/// several parents (such as a RAID array) is compared once, using its first
+/// occurrence. Every device listed here has its `children` removed so that each
+/// change is reported exactly once.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct DeviceDiff {
+    /// Devices present only in the newer snapshot, in tree order.
+    pub added: Vec<BlockDevice>,
+    /// Devices present only in the older snapshot, in tree order.
+    pub removed: Vec<BlockDevice>,
+    /// Devices present in both snapshots whose own properties changed, in tree order.
+    pub changed: Vec<DeviceChange>,
+}
+
+impl DeviceDiff {
+    /// Returns `true` if the two snapshots describe the same devices.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Returns a copy of `device` without its children.
+fn shallow(device: &BlockDevice) -> BlockDevice {
+    BlockDevice {
+        children: None,
+        ..device.clone()
+    }
+}
+
+/// Returns the first occurrence of every device name, in tree order.
+fn unique_devices(devices: &BlockDevices) -> Vec<&BlockDevice> {
+    let mut seen = HashSet::new();
+    devices
+        .iter_all()
+        .filter(|d| seen.insert(d.name.as_str()))
+        .collect()
+}
+
+impl BlockDevices {
+    /// Compares this snapshot with a newer one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let before = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null]}
+    /// ]}"#).unwrap();
+    /// let after = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": ["/data"]},
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]}
+    /// ]}"#).unwrap();
+    ///
+    /// let diff = before.diff(&after);
+    /// assert_eq!(diff.added[0].name, "sdb");
+    /// assert_eq!(diff.changed[0].after.mountpoints, vec![Some("/data".to_string())]);
+    /// ```
+    #[must_use]
+    pub fn diff(&self, newer: &BlockDevices) -> DeviceDiff {
+        let old = unique_devices(self);
+        let new = unique_devices(newer);
+        let old_by_name: HashMap<&str, &BlockDevice> =
+            old.iter().map(|d| (d.name.as_str(), *d)).collect();
+        let new_by_name: HashMap<&str, &BlockDevice> =
+            new.iter().map(|d| (d.name.as_str(), *d)).collect();
+
+        let removed = old
+            .iter()
+            .filter(|d| !new_by_name.contains_key(d.name.as_str()))
+            .map(|d| shallow(d))
+            .collect();
+
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+        for device in new {
+            match old_by_name.get(device.name.as_str()) {
+                None => added.push(shallow(device)),
+                Some(previous) => {
+                    let before = shallow(previous);
+                    let after = shallow(device);
+                    if before != after {
+                        changed.push(DeviceChange { before, after });
+                    }
+                }
+            }
+        }
+
+        DeviceDiff {
+            added,
+            removed,
+            changed,
+        }
+    }
+}
+
+/// An old and a new value of a property reported by [`ChildChange`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ValueChange<T> {
+    /// The value in the older device.
+    pub before: T,
+    /// The value in the newer device.
+    pub after: T,
+}
+
+/// Returns a [`ValueChange`] if `before` and `after` differ.
+fn value_change<T: PartialEq + Clone>(before: &T, after: &T) -> Option<ValueChange<T>> {
+    (before != after).then(|| ValueChange {
+        before: before.clone(),
+        after: after.clone(),
+    })
+}
+
+/// A direct child matched in both devices by [`BlockDevice::diff_children`]
+/// that was renamed, resized, retyped or reformatted.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChildChange {
+    /// The child as it was in the older device, without its children.
+    pub before: BlockDevice,
+    /// The child as it is in the newer device, without its children.
+    pub after: BlockDevice,
+    /// The size in bytes, if it changed.
+    pub size: Option<ValueChange<u64>>,
+    /// The device type, if it changed.
+    pub device_type: Option<ValueChange<DeviceType>>,
+    /// The partition type (`PARTTYPE` column), if it changed.
+    pub parttype: Option<ValueChange<Option<String>>>,
+    /// The filesystem signature (`FSTYPE` column), if it changed.
+    pub fstype: Option<ValueChange<Option<String>>>,
+}
+
+impl ChildChange {
+    /// Returns `true` if the child was matched under a different name, by
+    /// UUID or partition number.
+    #[must_use]
+    pub fn is_rename(&self) -> bool {
+        self.before.name != self.after.name
+    }
+}
+
+/// The differences between the direct children of two versions of a device,
+/// as returned by [`BlockDevice::diff_children`].
+///
+/// Every device listed here has its `children` removed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct ChildDiff {
+    /// Children present only in the newer device, in its order.
+    pub added: Vec<BlockDevice>,
+    /// Children present only in the older device, in its order.
+    pub removed: Vec<BlockDevice>,
+    /// Children present in both whose name, size, type, partition type or
+    /// filesystem signature changed, in the newer device's order.
+    pub changed: Vec<ChildChange>,
+}
+
+impl ChildDiff {
+    /// Returns `true` if the two devices have the same children.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Returns the partition number of `child` on the disk `parent`, such as `2`
+/// for `sda2` on `sda` or `nvme0n1p2` on `nvme0n1`.
+fn partition_number(parent: &str, child: &str) -> Option<u32> {
+    let suffix = child.strip_prefix(parent)?;
+    let digits = suffix.strip_prefix('p').unwrap_or(suffix);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+impl BlockDevice {
+    /// Compares the direct children of this device with those of a newer
+    /// version of it, such as a disk before and after running a partitioner.
+    ///
+    /// Unlike [`BlockDevices::diff`], only the direct children are compared,
+    /// and the two devices need not have the same name. Children are matched
+    /// by filesystem UUID when both have one, then by name, then by partition
+    /// number, so that a disk that came back as `sdb` instead of `sda` still
+    /// lines up. Only the name, size, device type, partition type and
+    /// filesystem signature are compared; mountpoints and other columns are
+    /// ignored.
+    ///
+    /// Partitions that were renumbered, for example `sda4` becoming `sda3`
+    /// after `sda3` was deleted, can only be recognized by their UUID. Without
+    /// one, they are reported as `sda4` removed and `sda3` changed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let before = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "100G", "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "children": [
+    ///         {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "10G", "ro": false, "type": "part", "mountpoints": [null]}
+    ///      ]}
+    /// ]}"#).unwrap();
+    /// let after = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "100G", "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "children": [
+    ///         {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "20G", "ro": false, "type": "part", "mountpoints": [null]},
+    ///         {"name": "sda2", "maj:min": "8:2", "rm": false, "size": "80G", "ro": false, "type": "part", "mountpoints": [null]}
+    ///      ]}
+    /// ]}"#).unwrap();
+    ///
+    /// let diff = before.blockdevices[0].diff_children(&after.blockdevices[0]);
+    /// assert_eq!(diff.added[0].name, "sda2");
+    /// let grown = diff.changed[0].size.as_ref().unwrap();
+    /// assert_eq!(grown.after, 2 * grown.before);
+    /// ```
+    #[must_use]
+    pub fn diff_children(&self, other: &BlockDevice) -> ChildDiff {
+        let old = self.children.as_deref().unwrap_or_default();
+        let new = other.children.as_deref().unwrap_or_default();
+
+        // For every newer child, the index of the older child it matches.
+        let mut matches: Vec<Option<usize>> = vec![None; new.len()];
+        let mut claimed = vec![false; old.len()];
+        type SameChild<'a> = &'a dyn Fn(&BlockDevice, &BlockDevice) -> bool;
+        let passes: [SameChild; 3] = [
+            &|a, b| a.uuid.is_some() && a.uuid == b.uuid,
+            &|a, b| a.name == b.name,
+            &|a, b| {
+                partition_number(&self.name, &a.name)
+                    .is_some_and(|number| partition_number(&other.name, &b.name) == Some(number))
+            },
+        ];
+        for same in passes {
+            for (j, child) in new.iter().enumerate() {
+                if matches[j].is_some() {
+                    continue;
+                }
+                let found = (0..old.len()).find(|&i| !claimed[i] && same(&old[i], child));
+                if let Some(i) = found {
+                    claimed[i] = true;
+                    matches[j] = Some(i);
+                }
+            }
+        }
+
+        let mut diff = ChildDiff {
+            removed: old
+                .iter()
+                .zip(&claimed)
+                .filter(|(_, claimed)| !**claimed)
+                .map(|(child, _)| shallow(child))
+                .collect(),
+            ..ChildDiff::default()
+        };
+        for (child, matched) in new.iter().zip(matches) {
+            let Some(i) = matched else {
+                diff.added.push(shallow(child));
+                continue;
+            };
+            let previous = &old[i];
+            let change = ChildChange {
+                size: value_change(&previous.size, &child.size),
+                device_type: value_change(&previous.device_type, &child.device_type),
+                parttype: value_change(&previous.parttype, &child.parttype),
+                fstype: value_change(&previous.fstype, &child.fstype),
+                before: shallow(previous),
+                after: shallow(child),
+            };
+            if change.is_rename()
+                || change.size.is_some()
+                || change.device_type.is_some()
+                || change.parttype.is_some()
+                || change.fstype.is_some()
+            {
+                diff.changed.push(change);
+            }
+        }
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    #[test]
+    fn test_diff_identical() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        assert!(devices.diff(&devices.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_diff_added_removed_changed() {
+        let before = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let mut after = before.clone();
+
+        // Remove nvme9n1 entirely, mount nvme0n1p1 and add a new USB disk.
+        after.blockdevices.retain(|d| d.name != "nvme9n1");
+        let nvme0n1 = after
+            .blockdevices
+            .iter_mut()
+            .find(|d| d.name == "nvme0n1")
+            .unwrap();
+        nvme0n1.children.as_mut().unwrap()[0].mountpoints = vec![Some("/data".to_string())];
+        after.blockdevices.push(BlockDevice {
+            name: "sdz".to_string(),
+            size: 1 << 30,
+            rm: true,
+            device_type: crate::DeviceType::Disk,
+            mountpoints: vec![None],
+            ..BlockDevice::default()
+        });
+
+        let diff = before.diff(&after);
+        let removed: Vec<&str> = diff.removed.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(removed, vec!["nvme9n1", "nvme9n1p1", "nvme9n1p9"]);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].name, "sdz");
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].before.name, "nvme0n1p1");
+        assert!(!diff.changed[0].before.is_mounted());
+        assert_eq!(diff.changed[0].after.active_mountpoints(), vec!["/data"]);
+        assert!(diff.removed.iter().all(|d| d.children.is_none()));
+    }
+
+    /// Parses a single disk `name` with `children`, given as lsblk JSON objects.
+    fn disk(name: &str, children: &str) -> BlockDevice {
+        let json = format!(
+            r#"{{"blockdevices": [
+                {{"name": "{name}", "maj:min": "8:0", "rm": false, "size": 1000000000000, "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [{children}]}}
+            ]}}"#
+        );
+        let mut devices = parse_lsblk(&json).expect("Failed to parse JSON");
+        devices.blockdevices.remove(0)
+    }
+
+    const BEFORE_CHILDREN: &str = r#"
+        {"name": "sda1", "maj:min": "8:1", "rm": false, "size": 1073741824, "ro": false, "type": "part", "mountpoints": ["/boot/efi"],
+         "uuid": "7A1B-2C3D", "parttype": "c12a7328-f81f-11d2-ba4b-00a0c93ec93b", "fstype": "vfat"},
+        {"name": "sda2", "maj:min": "8:2", "rm": false, "size": 107374182400, "ro": false, "type": "part", "mountpoints": ["/"],
+         "uuid": "0f6d7a3e-1111-4a55-9a2b-3c4d5e6f7a8b", "parttype": "0fc63daf-8483-4772-8e79-3d69d8477de4", "fstype": "ext4"},
+        {"name": "sda3", "maj:min": "8:3", "rm": false, "size": 8589934592, "ro": false, "type": "part", "mountpoints": [null]}"#;
+
+    #[test]
+    fn test_diff_children_add_remove_grow() {
+        let before = disk("sda", BEFORE_CHILDREN);
+        assert!(before.diff_children(&before).is_empty());
+
+        // sda3 is deleted, sda2 grows into its space and sda4 is added.
+        let after = disk(
+            "sda",
+            r#"
+            {"name": "sda1", "maj:min": "8:1", "rm": false, "size": 1073741824, "ro": false, "type": "part", "mountpoints": [null],
+             "uuid": "7A1B-2C3D", "parttype": "c12a7328-f81f-11d2-ba4b-00a0c93ec93b", "fstype": "vfat"},
+            {"name": "sda2", "maj:min": "8:2", "rm": false, "size": 115964116992, "ro": false, "type": "part", "mountpoints": ["/"],
+             "uuid": "0f6d7a3e-1111-4a55-9a2b-3c4d5e6f7a8b", "parttype": "0fc63daf-8483-4772-8e79-3d69d8477de4", "fstype": "ext4"},
+            {"name": "sda4", "maj:min": "8:4", "rm": false, "size": 536870912000, "ro": false, "type": "part", "mountpoints": [null],
+             "parttype": "a19d880f-05fc-4d3b-a006-743f0f84911e", "fstype": "linux_raid_member"}"#,
+        );
+        let diff = before.diff_children(&after);
+        let added: Vec<&str> = diff.added.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(added, vec!["sda4"]);
+        let removed: Vec<&str> = diff.removed.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(removed, vec!["sda3"]);
+
+        // Unmounting sda1 is not a layout change.
+        assert_eq!(diff.changed.len(), 1);
+        let grown = &diff.changed[0];
+        assert_eq!(grown.after.name, "sda2");
+        assert!(!grown.is_rename());
+        assert_eq!(
+            grown.size,
+            Some(ValueChange {
+                before: 107_374_182_400,
+                after: 115_964_116_992
+            })
+        );
+        assert_eq!(grown.device_type, None);
+        assert_eq!(grown.parttype, None);
+        assert_eq!(grown.fstype, None);
+        assert!(diff.added.iter().all(|d| d.children.is_none()));
+    }
+
+    #[test]
+    fn test_diff_children_retype_and_reformat() {
+        let before = disk("sda", BEFORE_CHILDREN);
+        // sda3 becomes a swap partition, matched by name as it had no UUID.
+        let after = disk(
+            "sda",
+            r#"
+            {"name": "sda1", "maj:min": "8:1", "rm": false, "size": 1073741824, "ro": false, "type": "part", "mountpoints": ["/boot/efi"],
+             "uuid": "7A1B-2C3D", "parttype": "c12a7328-f81f-11d2-ba4b-00a0c93ec93b", "fstype": "vfat"},
+            {"name": "sda2", "maj:min": "8:2", "rm": false, "size": 107374182400, "ro": false, "type": "part", "mountpoints": ["/"],
+             "uuid": "0f6d7a3e-1111-4a55-9a2b-3c4d5e6f7a8b", "parttype": "0fc63daf-8483-4772-8e79-3d69d8477de4", "fstype": "ext4"},
+            {"name": "sda3", "maj:min": "8:3", "rm": false, "size": 8589934592, "ro": false, "type": "part", "mountpoints": ["[SWAP]"],
+             "uuid": "5d1c0a77-2f0e-4b3e-8d4c-6a7b8c9d0e1f", "parttype": "0657fd6d-a4ab-43c4-84e5-0933c84b4f4f", "fstype": "swap"}"#,
+        );
+        let diff = before.diff_children(&after);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(diff.changed.len(), 1);
+        let change = &diff.changed[0];
+        assert_eq!(change.before.name, "sda3");
+        assert_eq!(change.size, None);
+        assert_eq!(
+            change.parttype.as_ref().unwrap().after.as_deref(),
+            Some("0657fd6d-a4ab-43c4-84e5-0933c84b4f4f")
+        );
+        assert_eq!(
+            change.fstype,
+            Some(ValueChange {
+                before: None,
+                after: Some("swap".to_string())
+            })
+        );
+    }
+
+    #[test]
+    fn test_diff_children_uuid_matched_rename() {
+        let before = disk("sda", BEFORE_CHILDREN);
+        // sda1 is deleted and the kernel renumbers the others, so sda2 keeps
+        // its data as sda1; the disk itself also came back as sdb.
+        let after = disk(
+            "sdb",
+            r#"
+            {"name": "sdb1", "maj:min": "8:17", "rm": false, "size": 107374182400, "ro": false, "type": "part", "mountpoints": [null],
+             "uuid": "0f6d7a3e-1111-4a55-9a2b-3c4d5e6f7a8b", "parttype": "0fc63daf-8483-4772-8e79-3d69d8477de4", "fstype": "ext4"},
+            {"name": "sdb3", "maj:min": "8:19", "rm": false, "size": 8589934592, "ro": false, "type": "part", "mountpoints": [null]}"#,
+        );
+        let diff = before.diff_children(&after);
+        assert!(diff.added.is_empty());
+        let removed: Vec<&str> = diff.removed.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(removed, vec!["sda1"]);
+
+        // sdb1 is the old sda2 by UUID, sdb3 the old sda3 by partition number.
+        assert_eq!(diff.changed.len(), 2);
+        let renamed: Vec<(&str, &str)> = diff
+            .changed
+            .iter()
+            .map(|c| (c.before.name.as_str(), c.after.name.as_str()))
+            .collect();
+        assert_eq!(renamed, vec![("sda2", "sdb1"), ("sda3", "sdb3")]);
+        assert!(diff.changed.iter().all(ChildChange::is_rename));
+        assert!(diff.changed.iter().all(|c| c.size.is_none()));
+    }
+
+    #[test]
+    fn test_partition_number() {
+        assert_eq!(partition_number("sda", "sda12"), Some(12));
+        assert_eq!(partition_number("nvme0n1", "nvme0n1p3"), Some(3));
+        assert_eq!(partition_number("mmcblk0", "mmcblk0p1"), Some(1));
+        assert_eq!(partition_number("sda", "sdb1"), None);
+        assert_eq!(partition_number("sda", "sda"), None);
+        assert_eq!(partition_number("nvme0n1", "vg0-root"), None);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::natural::natural_cmp;
+use crate::{BlockDevice, BlockDevices, SystemPolicy};
+use std::path::PathBuf;
+
+/// Name prefixes of RAM-backed devices that lsblk reports with type `disk`.
+const VIRTUAL_DISK_PREFIXES: &[&str] = &["zram", "ram"];
+
+impl BlockDevices {
+    /// Returns the `/dev` paths of the physical disks that are not used by the
+    /// running system, in natural order (`nvme2n1` before `nvme10n1`).
+    ///
+    /// This is stricter than [`BlockDevices::non_system`]: a disk is skipped if
+    /// it or anything stacked on it is mounted at `/`, `/boot`, `/boot/efi` or
+    /// `/efi`, or is used as swap, as [`SystemPolicy::strict`] describes. Only
+    /// top-level devices of type `disk` are kept, and RAM-backed disks such as
+    /// `zram0` are left out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    /// use std::path::PathBuf;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "children": [
+    ///         {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": ["/boot"]}
+    ///      ]},
+    ///     {"name": "zram0", "maj:min": "252:0", "rm": false, "size": "8G", "ro": false, "type": "disk", "mountpoints": [null]}
+    /// ]}"#).unwrap();
+    ///
+    /// assert_eq!(devices.non_system_disk_paths(), vec![PathBuf::from("/dev/sdb")]);
+    /// ```
+    #[must_use]
+    pub fn non_system_disk_paths(&self) -> Vec<PathBuf> {
+        self.non_system_disk_paths_with(&SystemPolicy::strict())
+    }
+
+    /// Like [`BlockDevices::non_system_disk_paths`], but with `policy` deciding
+    /// which mounts make a disk part of the system.
+    #[must_use]
+    pub fn non_system_disk_paths_with(&self, policy: &SystemPolicy) -> Vec<PathBuf> {
+        let mut disks: Vec<&BlockDevice> = self
+            .iter()
+            .filter(|d| {
+                d.is_disk()
+                    && !VIRTUAL_DISK_PREFIXES
+                        .iter()
+                        .any(|prefix| d.name.starts_with(prefix))
+                    && !d.is_system_with(policy)
+            })
+            .collect();
+        disks.sort_by(|a, b| natural_cmp(&a.name, &b.name));
+        disks.iter().map(|d| d.path()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    #[test]
+    fn test_non_system_disk_paths_sample() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let expected: Vec<PathBuf> = [
+            "nvme0n1", "nvme1n1", "nvme4n1", "nvme5n1", "nvme6n1", "nvme7n1", "nvme8n1", "nvme9n1",
+        ]
+        .iter()
+        .map(|name| PathBuf::from("/dev").join(name))
+        .collect();
+        assert_eq!(devices.non_system_disk_paths(), expected);
+    }
+
+    #[test]
+    fn test_non_system_disk_paths_strict() {
+        let devices = parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "sdc1", "maj:min": "8:33", "rm": false, "size": "8G", "ro": false, "type": "part", "mountpoints": ["[SWAP]"]}
+                 ]},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "sdb1", "maj:min": "8:17", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": ["/data"]}
+                 ]},
+                {"name": "sr0", "maj:min": "11:0", "rm": true, "size": "1G", "ro": false, "type": "rom", "mountpoints": [null]},
+                {"name": "loop0", "maj:min": "7:0", "rm": false, "size": "64M", "ro": true, "type": "loop", "mountpoints": [null]},
+                {"name": "ram0", "maj:min": "1:0", "rm": false, "size": "16M", "ro": false, "type": "disk", "mountpoints": [null]}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+        assert_eq!(devices.non_system().len(), 5);
+        assert_eq!(
+            devices.non_system_disk_paths(),
+            vec![PathBuf::from("/dev/sdb")]
+        );
+    }
+}
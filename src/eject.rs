@@ -0,0 +1,270 @@
+use crate::raid::is_raid_type;
+use crate::{BlockDevice, BlockDevices, IterAll};
+use std::collections::HashSet;
+
+/// Why a device cannot be removed safely, as reported by [`BlockDevice::eject_safe`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EjectBlocker {
+    /// The device or a layer on it is mounted.
+    Mounted {
+        /// The mounted device.
+        device: String,
+        /// Where it is mounted.
+        mountpoint: String,
+    },
+    /// The device or a layer on it is in use as swap.
+    Swap {
+        /// The swap device.
+        device: String,
+    },
+    /// The device or a partition on it is a member of an assembled RAID array,
+    /// which would be degraded by pulling it.
+    ActiveRaidMember {
+        /// The member device.
+        device: String,
+        /// The name of the array, such as `md0`.
+        array: String,
+    },
+}
+
+/// The result of [`BlockDevice::eject_safe`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EjectSafety {
+    /// Nothing on the device is in use.
+    Safe,
+    /// The device is in use, for every listed reason.
+    Unsafe(Vec<EjectBlocker>),
+}
+
+impl EjectSafety {
+    /// Returns `true` for [`EjectSafety::Safe`].
+    #[must_use]
+    pub fn is_safe(&self) -> bool {
+        *self == EjectSafety::Safe
+    }
+}
+
+impl BlockDevice {
+    /// Returns `true` if the device is removable media (`rm`) or sits in a
+    /// hot-swap bay (`hotplug`).
+    ///
+    /// `hotplug` is only set by `get_devices_extended` or JSON that includes
+    /// the `HOTPLUG` column; without it, only `rm` is considered.
+    #[must_use]
+    pub fn is_hot_removable(&self) -> bool {
+        self.rm || self.hotplug == Some(true)
+    }
+
+    /// Returns `true` if this device or any device it sits on is
+    /// [hot-removable](Self::is_hot_removable), looked up by name in `ctx`.
+    ///
+    /// lsblk often reports `rm: false` for the partitions of a USB stick,
+    /// although pulling the stick removes them too. A device that appears
+    /// under several parents, such as a RAID array, is effectively removable
+    /// if any of them is. A device not in `ctx` only has its own flags checked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": true, "size": "32G", "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "children": [
+    ///         {"name": "sdb1", "maj:min": "8:17", "rm": false, "size": "32G", "ro": false, "type": "part", "mountpoints": ["/media/usb"]}
+    ///      ]}
+    /// ]}"#).unwrap();
+    ///
+    /// let sdb1 = devices.iter_all().find(|d| d.name == "sdb1").unwrap();
+    /// assert!(!sdb1.is_hot_removable());
+    /// assert!(sdb1.effectively_removable(&devices));
+    /// ```
+    #[must_use]
+    pub fn effectively_removable(&self, ctx: &BlockDevices) -> bool {
+        self.is_hot_removable()
+            || ctx
+                .iter_with_parent_path()
+                .filter(|(_, device)| device.name == self.name)
+                .any(|(ancestors, _)| ancestors.iter().any(|a| a.is_hot_removable()))
+    }
+
+    /// Checks whether anything on this device is in use, so that it can be
+    /// detached without losing data.
+    ///
+    /// The device and every layer on it are checked for mountpoints, swap, and
+    /// membership in a RAID array that lsblk shows as assembled. This only
+    /// analyzes the snapshot; nothing is unmounted or ejected. Whether the
+    /// hardware allows pulling the device is a separate question, answered by
+    /// [`BlockDevice::is_hot_removable`].
+    #[must_use]
+    pub fn eject_safe(&self) -> EjectSafety {
+        let mut blockers = Vec::new();
+        let mut seen = HashSet::new();
+        for device in IterAll::subtree(self).filter(|d| seen.insert(d.name.as_str())) {
+            for mountpoint in device.active_mountpoints() {
+                blockers.push(if mountpoint == "[SWAP]" {
+                    EjectBlocker::Swap {
+                        device: device.name.clone(),
+                    }
+                } else {
+                    EjectBlocker::Mounted {
+                        device: device.name.clone(),
+                        mountpoint: mountpoint.to_string(),
+                    }
+                });
+            }
+            for array in device
+                .children_iter()
+                .filter(|c| is_raid_type(&c.device_type))
+            {
+                blockers.push(EjectBlocker::ActiveRaidMember {
+                    device: device.name.clone(),
+                    array: array.name.clone(),
+                });
+            }
+        }
+
+        if blockers.is_empty() {
+            EjectSafety::Safe
+        } else {
+            EjectSafety::Unsafe(blockers)
+        }
+    }
+}
+
+impl BlockDevices {
+    /// Returns every device in the tree that is
+    /// [effectively removable](BlockDevice::effectively_removable), including
+    /// partitions and other layers on removable disks, once each in tree order.
+    #[must_use]
+    pub fn effectively_removable_devices(&self) -> Vec<&BlockDevice> {
+        let mut removable: Vec<&BlockDevice> = Vec::new();
+        for (ancestors, device) in self.iter_with_parent_path() {
+            let inherited =
+                device.is_hot_removable() || ancestors.iter().any(|a| a.is_hot_removable());
+            if inherited && !removable.iter().any(|d| d.name == device.name) {
+                removable.push(device);
+            }
+        }
+        removable
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    #[test]
+    fn test_eject_safe_removable_devices() {
+        let json = r#"{
+            "blockdevices": [
+                {"name": "sdb", "maj:min": "8:16", "rm": true, "size": "29.3G", "ro": false, "type": "disk", "mountpoints": [null],
+                 "hotplug": true, "tran": "usb",
+                 "children": [
+                    {"name": "sdb1", "maj:min": "8:17", "rm": true, "size": "29.3G", "ro": false, "type": "part",
+                     "mountpoints": ["/media/usb"], "hotplug": true}
+                 ]},
+                {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "16T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "hotplug": "1", "tran": "sata",
+                 "children": [
+                    {"name": "sdc1", "maj:min": "8:33", "rm": false, "size": "16T", "ro": false, "type": "part", "mountpoints": [null]}
+                 ]},
+                {"name": "sdd", "maj:min": "8:48", "rm": false, "size": "16T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "hotplug": false}
+            ]
+        }"#;
+        let devices = parse_lsblk(json).expect("Failed to parse JSON");
+        let usb = devices.find_by_name("sdb").unwrap();
+        let bay = devices.find_by_name("sdc").unwrap();
+        let internal = devices.find_by_name("sdd").unwrap();
+
+        assert!(usb.is_hot_removable());
+        assert_eq!(
+            usb.eject_safe(),
+            EjectSafety::Unsafe(vec![EjectBlocker::Mounted {
+                device: "sdb1".to_string(),
+                mountpoint: "/media/usb".to_string(),
+            }])
+        );
+
+        assert!(!bay.rm && bay.is_hot_removable());
+        assert!(bay.eject_safe().is_safe());
+
+        assert!(!internal.is_hot_removable());
+        assert!(internal.eject_safe().is_safe());
+    }
+
+    #[test]
+    fn test_effectively_removable() {
+        let devices = parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "nvme0n1", "maj:min": "259:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "nvme0n1p1", "maj:min": "259:1", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": ["/"]}
+                 ]},
+                {"name": "sdb", "maj:min": "8:16", "rm": true, "size": "64G", "ro": false, "type": "disk", "mountpoints": [null], "tran": "usb",
+                 "children": [
+                    {"name": "sdb1", "maj:min": "8:17", "rm": false, "size": "64G", "ro": false, "type": "part", "mountpoints": [null],
+                     "children": [
+                        {"name": "usb_crypt", "maj:min": "253:0", "rm": false, "size": "64G", "ro": false, "type": "crypt", "mountpoints": ["/media/backup"]}
+                     ]}
+                 ]},
+                {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "4T", "ro": false, "type": "disk", "mountpoints": [null], "hotplug": true,
+                 "children": [
+                    {"name": "md0", "maj:min": "9:0", "rm": false, "size": "4T", "ro": false, "type": "raid1", "mountpoints": ["/srv"]}
+                 ]},
+                {"name": "sdd", "maj:min": "8:48", "rm": false, "size": "4T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "md0", "maj:min": "9:0", "rm": false, "size": "4T", "ro": false, "type": "raid1", "mountpoints": ["/srv"]}
+                 ]}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+        let device = |name: &str| devices.iter_all().find(|d| d.name == name).unwrap();
+
+        assert!(!device("sdb1").is_hot_removable());
+        assert!(device("sdb1").effectively_removable(&devices));
+        assert!(device("usb_crypt").effectively_removable(&devices));
+        assert!(!device("nvme0n1p1").effectively_removable(&devices));
+        assert!(!device("nvme0n1").effectively_removable(&devices));
+        assert!(device("md0").effectively_removable(&devices));
+        assert!(!device("sdd").effectively_removable(&devices));
+        assert!(!device("sdb1").effectively_removable(&BlockDevices::default()));
+
+        let names: Vec<&str> = devices
+            .effectively_removable_devices()
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["sdb", "sdb1", "usb_crypt", "sdc", "md0"]);
+    }
+
+    #[test]
+    fn test_eject_safe_raid_and_swap() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let EjectSafety::Unsafe(blockers) = devices.find_by_name("nvme2n1").unwrap().eject_safe()
+        else {
+            panic!("nvme2n1 carries the root RAID");
+        };
+        assert!(blockers.contains(&EjectBlocker::ActiveRaidMember {
+            device: "nvme2n1p5".to_string(),
+            array: "md2".to_string(),
+        }));
+        assert!(blockers.contains(&EjectBlocker::Swap {
+            device: "md1".to_string(),
+        }));
+        assert!(blockers.contains(&EjectBlocker::Mounted {
+            device: "md2".to_string(),
+            mountpoint: "/".to_string(),
+        }));
+        assert!(
+            devices
+                .find_by_name("nvme0n1")
+                .unwrap()
+                .eject_safe()
+                .is_safe()
+        );
+    }
+}
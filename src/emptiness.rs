@@ -0,0 +1,141 @@
+use crate::{BlockDevice, BlockDevices};
+
+/// Whether a disk is completely blank, as reported by [`BlockDevice::emptiness`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Emptiness {
+    /// A disk with no partitions, no filesystem or partition table signature
+    /// and no mountpoints.
+    Empty,
+    /// Not a disk, or a disk that holds something.
+    NotEmpty,
+    /// A disk with no partitions or mountpoints whose signature columns were not
+    /// requested, so it may still hold a filesystem or partition table.
+    Unknown,
+}
+
+impl BlockDevice {
+    /// Returns whether this disk is blank, distinguishing "no signature" from
+    /// "signature not known".
+    ///
+    /// lsblk reports a missing signature as `null`, which cannot be told apart
+    /// from a column that was not requested. An absent `fstype` or `pttype`
+    /// therefore only counts as blank if `log_sec` is set, because lsblk reports
+    /// `LOG-SEC` for every device whenever the extended columns are requested (as
+    /// `get_devices_extended` does). An empty string always counts as blank.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{BlockDevice, DeviceType, Emptiness};
+    ///
+    /// let mut disk = BlockDevice {
+    ///     device_type: DeviceType::Disk,
+    ///     mountpoints: vec![None],
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(disk.emptiness(), Emptiness::Unknown);
+    ///
+    /// disk.log_sec = Some(512);
+    /// assert_eq!(disk.emptiness(), Emptiness::Empty);
+    ///
+    /// disk.pttype = Some("gpt".to_string());
+    /// assert_eq!(disk.emptiness(), Emptiness::NotEmpty);
+    /// ```
+    #[must_use]
+    pub fn emptiness(&self) -> Emptiness {
+        let has_signature =
+            |field: &Option<String>| field.as_deref().is_some_and(|s| !s.is_empty());
+        if !self.is_disk()
+            || self.has_children()
+            || !self.active_mountpoints().is_empty()
+            || has_signature(&self.fstype)
+            || has_signature(&self.pttype)
+        {
+            return Emptiness::NotEmpty;
+        }
+
+        let columns_requested = self.log_sec.is_some();
+        if (self.fstype.is_none() || self.pttype.is_none()) && !columns_requested {
+            Emptiness::Unknown
+        } else {
+            Emptiness::Empty
+        }
+    }
+
+    /// Returns `true` if this is a disk with no children, no `fstype` or `pttype`
+    /// signature and nothing mounted.
+    ///
+    /// Absent signature columns count as blank here. Use [`BlockDevice::emptiness`]
+    /// to tell a known-blank disk from one whose signature columns were not requested.
+    #[must_use]
+    pub fn is_empty_disk(&self) -> bool {
+        self.emptiness() != Emptiness::NotEmpty
+    }
+}
+
+impl BlockDevices {
+    /// Returns the top-level disks for which [`BlockDevice::is_empty_disk`] is `true`.
+    #[must_use]
+    pub fn empty_disks(&self) -> Vec<&BlockDevice> {
+        self.iter().filter(|d| d.is_empty_disk()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    #[test]
+    fn test_sample_has_no_empty_disks() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        assert!(devices.empty_disks().is_empty());
+        assert!(
+            devices
+                .iter_all()
+                .all(|d| d.emptiness() == Emptiness::NotEmpty)
+        );
+    }
+
+    #[test]
+    fn test_emptiness() {
+        let json = r#"{
+            "blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "log-sec": 512, "fstype": null, "pttype": null},
+                {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "log-sec": 512, "fstype": "xfs", "pttype": null},
+                {"name": "sdd", "maj:min": "8:48", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "log-sec": 512, "fstype": null, "pttype": "gpt"},
+                {"name": "sde", "maj:min": "8:64", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/data"]},
+                {"name": "loop0", "maj:min": "7:0", "rm": false, "size": "64M", "ro": false, "type": "loop", "mountpoints": [null],
+                 "log-sec": 512}
+            ]
+        }"#;
+        let devices = parse_lsblk(json).expect("Failed to parse JSON");
+        let emptiness: Vec<(&str, Emptiness)> = devices
+            .iter()
+            .map(|d| (d.name.as_str(), d.emptiness()))
+            .collect();
+        assert_eq!(
+            emptiness,
+            vec![
+                ("sda", Emptiness::Unknown),
+                ("sdb", Emptiness::Empty),
+                ("sdc", Emptiness::NotEmpty),
+                ("sdd", Emptiness::NotEmpty),
+                ("sde", Emptiness::NotEmpty),
+                ("loop0", Emptiness::NotEmpty),
+            ]
+        );
+
+        let names: Vec<&str> = devices
+            .empty_disks()
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["sda", "sdb"]);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::naming::validate_device_name;
+use crate::select::is_mounted_recursive;
+use crate::{BlockDevError, BlockDevice, DeviceType};
+
+/// The temporary ATA security password set before a SATA secure erase; the
+/// drive clears it when the erase completes.
+const ATA_ERASE_PASSWORD: &str = "erase";
+
+/// Returns `true` if `name` can be pasted into a shell command unquoted.
+fn is_shell_safe(name: &str) -> bool {
+    name.chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '+' | ':'))
+}
+
+impl BlockDevice {
+    /// Returns a shell command that securely erases this device, for
+    /// decommissioning. The command is only generated, never run.
+    ///
+    /// **The commands are destructive and irreversible**: they destroy every
+    /// byte on the device, including all partitions and volumes stacked on
+    /// it. Check the device name and serial before running one, and run it as
+    /// root.
+    ///
+    /// - NVMe disks (`tran` is `nvme`, or the name starts with `nvme`) get
+    ///   `nvme format <path> --ses=1`, a user-data erase done by the
+    ///   controller.
+    /// - SATA disks (`tran` is `sata`) get an ATA Security Erase: `hdparm`
+    ///   first sets the temporary user password `erase`, which the erase
+    ///   needs and then clears, and `--security-erase` then erases the drive.
+    ///   If the erase fails or is interrupted, the drive stays locked with
+    ///   that password; unlock it with
+    ///   `hdparm --user-master u --security-disable erase /dev/sdX`. Drives
+    ///   in the "frozen" security state refuse this until suspended and
+    ///   resumed.
+    /// - Everything else, including partitions, RAID arrays and device-mapper
+    ///   volumes, gets `shred -vzn3 <path>`: three random passes and a final
+    ///   pass of zeros. Overwriting does not reliably reach every cell of an
+    ///   SSD, so prefer the whole-disk commands for flash.
+    ///
+    /// Partitions never get a drive-level erase, since that would also wipe
+    /// the rest of their disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::InvalidInput`] if this device or anything on
+    /// it is mounted, or if the name contains characters that would need
+    /// shell quoting.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{BlockDevice, DeviceType};
+    ///
+    /// let disk = BlockDevice {
+    ///     name: "nvme3n1".to_string(),
+    ///     device_type: DeviceType::Disk,
+    ///     mountpoints: vec![None],
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(disk.secure_erase_command().unwrap(), "nvme format /dev/nvme3n1 --ses=1");
+    /// ```
+    pub fn secure_erase_command(&self) -> Result<String, BlockDevError> {
+        validate_device_name(&self.name)?;
+        if !is_shell_safe(&self.name) {
+            return Err(BlockDevError::InvalidInput(format!(
+                "device name '{}' cannot be used in a shell command unquoted",
+                self.name
+            )));
+        }
+        if is_mounted_recursive(self) {
+            return Err(BlockDevError::InvalidInput(format!(
+                "refusing to erase {}: it or a device on it is mounted",
+                self.name
+            )));
+        }
+
+        let path = self.path();
+        let path = path.display();
+        let tran = self.tran.as_deref();
+        let command = if self.device_type != DeviceType::Disk {
+            format!("shred -vzn3 {path}")
+        } else if tran == Some("nvme") || (tran.is_none() && self.name.starts_with("nvme")) {
+            format!("nvme format {path} --ses=1")
+        } else if tran == Some("sata") {
+            format!(
+                "hdparm --user-master u --security-set-pass {ATA_ERASE_PASSWORD} {path} && \
+                 hdparm --user-master u --security-erase {ATA_ERASE_PASSWORD} {path}"
+            )
+        } else {
+            format!("shred -vzn3 {path}")
+        };
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    fn disk(name: &str, tran: Option<&str>) -> BlockDevice {
+        BlockDevice {
+            name: name.to_string(),
+            device_type: DeviceType::Disk,
+            mountpoints: vec![None],
+            tran: tran.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_secure_erase_command() {
+        assert_eq!(
+            disk("nvme0n1", Some("nvme"))
+                .secure_erase_command()
+                .unwrap(),
+            "nvme format /dev/nvme0n1 --ses=1"
+        );
+        assert_eq!(
+            disk("sda", Some("sata")).secure_erase_command().unwrap(),
+            "hdparm --user-master u --security-set-pass erase /dev/sda && \
+             hdparm --user-master u --security-erase erase /dev/sda"
+        );
+        assert_eq!(
+            disk("sdb", Some("usb")).secure_erase_command().unwrap(),
+            "shred -vzn3 /dev/sdb"
+        );
+        assert_eq!(
+            disk("vda", None).secure_erase_command().unwrap(),
+            "shred -vzn3 /dev/vda"
+        );
+
+        // Without TRAN, the name still identifies an NVMe namespace.
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let nvme1n1 = devices.find_by_name("nvme1n1").unwrap();
+        assert_eq!(
+            nvme1n1.secure_erase_command().unwrap(),
+            "nvme format /dev/nvme1n1 --ses=1"
+        );
+
+        // A partition is overwritten rather than erased with its whole disk.
+        let partition = BlockDevice {
+            name: "nvme0n1p2".to_string(),
+            device_type: DeviceType::Part,
+            mountpoints: vec![None],
+            tran: Some("nvme".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            partition.secure_erase_command().unwrap(),
+            "shred -vzn3 /dev/nvme0n1p2"
+        );
+        let crypt = BlockDevice {
+            name: "luks-data".to_string(),
+            device_type: DeviceType::Crypt,
+            mountpoints: vec![None],
+            ..Default::default()
+        };
+        assert_eq!(
+            crypt.secure_erase_command().unwrap(),
+            "shred -vzn3 /dev/mapper/luks-data"
+        );
+    }
+
+    #[test]
+    fn test_secure_erase_command_refuses_mounted_devices() {
+        let mut mounted = disk("sda", Some("sata"));
+        mounted.mountpoints = vec![Some("/srv".to_string())];
+        assert!(matches!(
+            mounted.secure_erase_command(),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+
+        // nvme3n1 holds the root filesystem on one of its partitions.
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let system = devices.find_by_name("nvme3n1").unwrap();
+        assert!(!system.is_mounted());
+        assert!(matches!(
+            system.secure_erase_command(),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+
+        for name in ["sda;reboot", "sd a", "../sda", ""] {
+            assert!(
+                matches!(
+                    disk(name, None).secure_erase_command(),
+                    Err(BlockDevError::InvalidInput(_))
+                ),
+                "{name}"
+            );
+        }
+    }
+}
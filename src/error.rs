@@ -0,0 +1,85 @@
+use std::string::FromUtf8Error;
+use thiserror::Error;
+
+/// Error type for blockdev operations.
+#[derive(Debug, Error)]
+pub enum BlockDevError {
+    /// The lsblk command failed to execute.
+    #[error("failed to execute lsblk: {0}")]
+    CommandFailed(#[from] std::io::Error),
+
+    /// The lsblk command returned a non-zero exit status.
+    #[error("lsblk returned error: {0}")]
+    LsblkError(String),
+
+    /// The output from lsblk was not valid UTF-8.
+    #[error("invalid UTF-8 in lsblk output: {0}")]
+    InvalidUtf8(#[from] FromUtf8Error),
+
+    /// Failed to parse the JSON output from lsblk.
+    #[error("failed to parse lsblk JSON: {0}")]
+    JsonParse(#[from] serde_json::Error),
+
+    /// Reading a system file such as `/proc/self/mountinfo` failed.
+    #[error("I/O error: {0}")]
+    IoError(std::io::Error),
+
+    /// An argument passed to a blockdev function was rejected.
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+
+    /// Partitions on a disk overlap or extend past its end.
+    #[error("invalid partition layout: {0}")]
+    InvalidPartitionLayout(String),
+
+    /// The udevadm command returned a non-zero exit status.
+    #[error("udevadm returned error: {0}")]
+    UdevadmError(String),
+
+    /// The cryptsetup command returned a non-zero exit status.
+    #[error("cryptsetup returned error: {0}")]
+    CryptsetupError(String),
+
+    /// The blkid command returned a non-zero exit status.
+    #[error("blkid returned error: {0}")]
+    BlkidError(String),
+
+    /// A binary snapshot was written by an incompatible version of the format.
+    #[error("unsupported binary snapshot version {found} (expected {expected})")]
+    UnsupportedBinaryVersion {
+        /// The version byte found at the start of the snapshot.
+        found: u8,
+        /// The version this build reads and writes.
+        expected: u8,
+    },
+
+    /// A binary snapshot is empty, truncated or otherwise malformed.
+    #[error("invalid binary snapshot: {0}")]
+    BinaryDecode(String),
+
+    /// A snapshot could not be encoded, such as when its metadata holds a
+    /// time before the Unix epoch.
+    #[error("cannot encode binary snapshot: {0}")]
+    BinaryEncode(String),
+
+    /// lsblk did not report the requested device.
+    #[error("device not found: {0}")]
+    DeviceNotFound(String),
+
+    /// The device is mounted or has I/O in flight, so it was left alone.
+    #[error("device is busy: {0}")]
+    DeviceBusy(String),
+
+    /// A per-device closure passed to `for_each_parallel` panicked.
+    #[error("worker panicked: {0}")]
+    WorkerPanicked(String),
+
+    /// Every attempt of `get_devices_with_retry` failed.
+    #[error("gave up after {attempts} attempts: {last_error}")]
+    RetryExhausted {
+        /// How many times the operation was attempted.
+        attempts: u32,
+        /// The error from the final attempt.
+        last_error: Box<BlockDevError>,
+    },
+}
@@ -0,0 +1,207 @@
+use crate::DeviceDiff;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, Write};
+use std::time::SystemTime;
+
+/// A [`DeviceDiff`] together with the time it was recorded.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TimestampedDiff {
+    /// When the diff was recorded.
+    pub timestamp: SystemTime,
+    /// The recorded diff.
+    pub diff: DeviceDiff,
+}
+
+/// Borrowing counterpart of [`TimestampedDiff`] used for writing.
+#[derive(Serialize)]
+struct TimestampedDiffRef<'a> {
+    timestamp: SystemTime,
+    diff: &'a DeviceDiff,
+}
+
+impl DeviceDiff {
+    /// Serializes this diff, stamped with the current time, as a single-line JSON
+    /// object suitable for a JSON Lines log.
+    ///
+    /// The line does not end with a newline. It parses back into a [`TimestampedDiff`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the system clock is set before the Unix epoch, since
+    /// such a timestamp cannot be serialized.
+    pub fn to_json_line(&self) -> serde_json::Result<String> {
+        serde_json::to_string(&TimestampedDiffRef {
+            timestamp: SystemTime::now(),
+            diff: self,
+        })
+    }
+}
+
+/// The diffs recovered from a JSON Lines event log by [`EventLogEntries::read`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct EventLogEntries {
+    /// The diffs that parsed cleanly, in log order.
+    pub diffs: Vec<TimestampedDiff>,
+    /// The number of corrupt lines that were skipped, including a truncated final line.
+    pub skipped: usize,
+}
+
+impl EventLogEntries {
+    /// Parses a JSON Lines event log.
+    ///
+    /// Blank lines are ignored. Lines that are not valid entries, including a
+    /// final line truncated by an interrupted write, are skipped and counted in
+    /// [`EventLogEntries::skipped`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error only if reading from `reader` fails.
+    pub fn read<R: BufRead>(mut reader: R) -> io::Result<EventLogEntries> {
+        let mut entries = EventLogEntries::default();
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            match serde_json::from_slice(&line) {
+                Ok(diff) => entries.diffs.push(diff),
+                Err(_) => entries.skipped += 1,
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// An append-only JSON Lines audit trail of [`DeviceDiff`]s.
+///
+/// Each appended diff is written as one line and flushed immediately, so a crash
+/// loses at most the line being written. [`EventLogEntries::read`] tolerates
+/// such a truncated final line.
+///
+/// # Examples
+///
+/// ```
+/// use blockdev::{DeviceDiff, EventLog, EventLogEntries};
+///
+/// let mut log = EventLog::new(Vec::new());
+/// log.append(&DeviceDiff::default()).unwrap();
+/// let bytes = log.into_inner();
+///
+/// let entries = EventLogEntries::read(bytes.as_slice()).unwrap();
+/// assert_eq!(entries.diffs.len(), 1);
+/// assert_eq!(entries.skipped, 0);
+/// ```
+#[derive(Debug)]
+pub struct EventLog<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> EventLog<W> {
+    /// Creates a log that appends to `writer`, such as a file opened in append mode.
+    pub fn new(writer: W) -> Self {
+        EventLog { writer }
+    }
+
+    /// Appends `diff`, stamped with the current time, as one line and flushes the writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing or flushing fails, or if the system clock is
+    /// set before the Unix epoch.
+    pub fn append(&mut self, diff: &DeviceDiff) -> io::Result<()> {
+        self.append_at(diff, SystemTime::now())
+    }
+
+    /// Appends `diff` stamped with `timestamp`. The line is serialized in full
+    /// before anything is written, so a failure leaves no partial line behind.
+    fn append_at(&mut self, diff: &DeviceDiff, timestamp: SystemTime) -> io::Result<()> {
+        let mut line = serde_json::to_vec(&TimestampedDiffRef { timestamp, diff })?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+        self.writer.flush()
+    }
+
+    /// Returns the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    fn sample_diffs() -> Vec<DeviceDiff> {
+        let before = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let mut after = before.clone();
+        after.blockdevices.retain(|d| d.name != "nvme9n1");
+        let removed = before.diff(&after);
+        let added = after.diff(&before);
+        vec![removed, added.clone(), added]
+    }
+
+    #[test]
+    fn test_to_json_line() {
+        let diff = &sample_diffs()[0];
+        let line = diff.to_json_line().unwrap();
+        assert!(!line.contains('\n'));
+        let parsed: TimestampedDiff = serde_json::from_str(&line).expect("Invalid JSON line");
+        assert_eq!(&parsed.diff, diff);
+    }
+
+    #[test]
+    fn test_event_log_roundtrip_with_truncated_line() {
+        let diffs = sample_diffs();
+        let mut log = EventLog::new(Vec::new());
+        for diff in &diffs {
+            log.append(diff).expect("Failed to append");
+        }
+        let mut bytes = log.into_inner();
+        assert_eq!(bytes.iter().filter(|&&b| b == b'\n').count(), 3);
+
+        // Simulate a crash halfway through writing the last line.
+        let last_line_start = bytes[..bytes.len() - 1]
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .unwrap()
+            + 1;
+        let midpoint = last_line_start + (bytes.len() - last_line_start) / 2;
+        bytes.truncate(midpoint);
+
+        let entries = EventLogEntries::read(bytes.as_slice()).expect("Failed to read log");
+        assert_eq!(entries.diffs.len(), 2);
+        assert_eq!(entries.skipped, 1);
+        assert_eq!(entries.diffs[0].diff, diffs[0]);
+        assert_eq!(entries.diffs[1].diff, diffs[1]);
+    }
+
+    #[test]
+    fn test_event_log_append_failure_writes_nothing() {
+        let mut log = EventLog::new(Vec::new());
+        log.append(&DeviceDiff::default())
+            .expect("Failed to append");
+        let pre_epoch = SystemTime::UNIX_EPOCH - std::time::Duration::from_secs(1);
+        assert!(log.append_at(&DeviceDiff::default(), pre_epoch).is_err());
+
+        let bytes = log.into_inner();
+        assert!(bytes.ends_with(b"\n"));
+        let entries = EventLogEntries::read(bytes.as_slice()).expect("Failed to read log");
+        assert_eq!(entries.diffs.len(), 1);
+        assert_eq!(entries.skipped, 0);
+    }
+
+    #[test]
+    fn test_event_log_skips_corrupt_lines() {
+        let good = DeviceDiff::default().to_json_line().unwrap();
+        let input = format!("{good}\nnot json\n\n{{\"timestamp\": 5}}\n{good}\n");
+        let entries = EventLogEntries::read(input.as_bytes()).expect("Failed to read log");
+        assert_eq!(entries.diffs.len(), 2);
+        assert_eq!(entries.skipped, 2);
+    }
+}
@@ -0,0 +1,1010 @@
+use crate::naming::validate_device_name;
+use crate::{BlockDevError, BlockDevice, BlockDevices, SnapshotMeta, SourceKind, parse_lsblk};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+/// The columns requested by [`get_devices_extended`].
+const EXTENDED_COLUMNS: &[&str] = &[
+    "NAME",
+    "MAJ:MIN",
+    "RM",
+    "SIZE",
+    "RO",
+    "TYPE",
+    "MOUNTPOINTS",
+    "START",
+    "LOG-SEC",
+    "PHY-SEC",
+    "UUID",
+    "PARTUUID",
+    "PARTTYPE",
+    "FSTYPE",
+    "FSVER",
+    "PTTYPE",
+    "ROTA",
+    "HOTPLUG",
+    "TRAN",
+    "FSSIZE",
+    "FSUSED",
+    "LABEL",
+    "SERIAL",
+    "WWN",
+    "SCHED",
+    "RQ-SIZE",
+    "VENDOR",
+    "MODEL",
+];
+
+/// The leading columns of [`EXTENDED_COLUMNS`] that `get_devices` always gets;
+/// a snapshot without them is not worth returning.
+const BASE_COLUMNS: &[&str] = &["NAME", "MAJ:MIN", "RM", "SIZE", "RO", "TYPE", "MOUNTPOINTS"];
+
+/// Returns the columns an lsblk error message names as unknown, such as
+/// `SERIAL` in `lsblk: unknown column: SERIAL`.
+fn unknown_columns(stderr: &str) -> Vec<&str> {
+    stderr
+        .lines()
+        .filter_map(|line| line.split_once("unknown column:"))
+        .map(|(_, column)| column.trim().trim_matches(['\'', '"']))
+        .filter(|column| !column.is_empty())
+        .collect()
+}
+
+/// Calls `run` with `columns`, and whenever lsblk rejects some of them as
+/// unknown, calls it again without those columns.
+///
+/// `MOUNTPOINTS`, which lsblk only knows since util-linux 2.37, is replaced by
+/// `MOUNTPOINT` instead of being dropped. Every column dropped or replaced is
+/// recorded in the snapshot's [`warnings`](BlockDevices::warnings). The
+/// error is returned unchanged if it is not about unknown columns or if lsblk
+/// rejects a column of [`BASE_COLUMNS`].
+fn run_with_column_fallback(
+    columns: &[&str],
+    mut run: impl FnMut(&[&str]) -> Result<BlockDevices, BlockDevError>,
+) -> Result<BlockDevices, BlockDevError> {
+    let mut columns = columns.to_vec();
+    let mut warnings = Vec::new();
+    loop {
+        let stderr = match run(&columns) {
+            Ok(mut devices) => {
+                devices.warnings.extend(warnings);
+                return Ok(devices);
+            }
+            Err(BlockDevError::LsblkError(stderr)) => stderr,
+            Err(error) => return Err(error),
+        };
+        let before = columns.clone();
+        for unknown in unknown_columns(&stderr) {
+            if unknown.eq_ignore_ascii_case("MOUNTPOINTS") {
+                if let Some(column) = columns.iter_mut().find(|c| c.eq_ignore_ascii_case(unknown)) {
+                    *column = "MOUNTPOINT";
+                    warnings.push(format!(
+                        "lsblk does not support column {unknown}; requested MOUNTPOINT instead"
+                    ));
+                }
+            } else if !BASE_COLUMNS.iter().any(|c| c.eq_ignore_ascii_case(unknown)) {
+                let count = columns.len();
+                columns.retain(|c| !c.eq_ignore_ascii_case(unknown));
+                if columns.len() < count {
+                    warnings.push(format!(
+                        "lsblk does not support column {unknown}; dropped it"
+                    ));
+                }
+            }
+        }
+        if columns == before {
+            return Err(BlockDevError::LsblkError(stderr));
+        }
+    }
+}
+
+/// Where a trusted `lsblk` is looked for, in order, before `PATH`.
+#[cfg(feature = "secure-exec")]
+const TRUSTED_LSBLK_PATHS: &[&str] = &["/usr/bin/lsblk", "/bin/lsblk", "/usr/sbin/lsblk"];
+
+/// Where trusted system tools such as `cryptsetup` and `blkid` are looked for, in order,
+/// before `PATH`.
+#[cfg(feature = "secure-exec")]
+const TRUSTED_TOOL_DIRS: &[&str] = &["/usr/sbin", "/sbin", "/usr/bin", "/bin"];
+
+/// Returns the first of `candidates` that is a file, or plain `program` to be
+/// looked up in `PATH` if there is none.
+#[cfg(feature = "secure-exec")]
+fn resolve_program_in<P: AsRef<Path>>(program: &str, candidates: &[P]) -> PathBuf {
+    candidates
+        .iter()
+        .map(AsRef::as_ref)
+        .find(|path| path.is_file())
+        .map_or_else(|| PathBuf::from(program), Path::to_path_buf)
+}
+
+/// Returns the `lsblk` to run.
+///
+/// With the `secure-exec` feature this is the first of [`TRUSTED_LSBLK_PATHS`]
+/// that exists, so a directory early in `PATH` cannot shadow it; otherwise it
+/// is looked up in `PATH`.
+fn resolve_lsblk() -> PathBuf {
+    #[cfg(feature = "secure-exec")]
+    return resolve_program_in("lsblk", TRUSTED_LSBLK_PATHS);
+    #[cfg(not(feature = "secure-exec"))]
+    PathBuf::from("lsblk")
+}
+
+/// Builds the command that runs `program` with `LC_ALL=C`, so that messages
+/// such as `unknown column` are never localized.
+///
+/// With the `secure-exec` feature, the rest of the environment is cleared too,
+/// which also keeps `LSBLK_COLUMNS` or `LIBMOUNT_*` variables from changing
+/// the output.
+fn lsblk_command(program: &Path) -> Command {
+    let mut command = Command::new(program);
+    #[cfg(feature = "secure-exec")]
+    command.env_clear();
+    command.env("LC_ALL", "C");
+    command
+}
+
+/// Builds the command that runs the system tool `name`, such as `cryptsetup`,
+/// with the environment of [`lsblk_command`].
+///
+/// With the `secure-exec` feature the tool is the first one found in
+/// [`TRUSTED_TOOL_DIRS`], as for [`resolve_lsblk`]; otherwise it is looked up
+/// in `PATH`.
+pub(crate) fn tool_command(name: &str) -> Command {
+    #[cfg(feature = "secure-exec")]
+    let program = {
+        let candidates: Vec<PathBuf> = TRUSTED_TOOL_DIRS
+            .iter()
+            .map(|dir| Path::new(dir).join(name))
+            .collect();
+        resolve_program_in(name, &candidates)
+    };
+    #[cfg(not(feature = "secure-exec"))]
+    let program = PathBuf::from(name);
+    lsblk_command(&program)
+}
+
+/// Runs `lsblk --json --bytes` on the given device paths (all devices when empty)
+/// and parses the output. When `columns` is empty, lsblk's default columns are used.
+fn run_lsblk(columns: &[&str], paths: &[PathBuf]) -> Result<BlockDevices, BlockDevError> {
+    run_lsblk_program(resolve_lsblk(), columns, paths)
+}
+
+/// Runs `program` as lsblk; see [`run_lsblk`].
+///
+/// With the `tracing` feature, the run is an `info` span named `lsblk` that
+/// records the argv, the duration, the stdout byte count, and the device and
+/// warning counts of the snapshot; a failure is a `warn` event.
+fn run_lsblk_program(
+    program: PathBuf,
+    columns: &[&str],
+    paths: &[PathBuf],
+) -> Result<BlockDevices, BlockDevError> {
+    let mut command = lsblk_command(&program);
+    command.arg("--json").arg("--bytes");
+    if !columns.is_empty() {
+        command.arg("--output").arg(columns.join(","));
+    }
+    command.args(paths);
+
+    #[cfg(feature = "tracing")]
+    let span = tracing::info_span!(
+        "lsblk",
+        argv = ?std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .collect::<Vec<_>>(),
+        duration_ms = tracing::field::Empty,
+        stdout_bytes = tracing::field::Empty,
+        device_count = tracing::field::Empty,
+        warning_count = tracing::field::Empty,
+    )
+    .entered();
+    #[cfg(feature = "tracing")]
+    let started = std::time::Instant::now();
+
+    let result = (|| {
+        let output = command.output()?;
+        #[cfg(feature = "tracing")]
+        {
+            span.record(
+                "duration_ms",
+                u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+            );
+            span.record("stdout_bytes", output.stdout.len());
+        }
+
+        if !output.status.success() {
+            return Err(BlockDevError::LsblkError(
+                String::from_utf8_lossy(&output.stderr).into_owned(),
+            ));
+        }
+
+        let json_output = String::from_utf8(output.stdout)?;
+        let mut lsblk = parse_lsblk(&json_output)?;
+        lsblk.meta = Some(SnapshotMeta {
+            lsblk_path: Some(program),
+            ..SnapshotMeta::new(SourceKind::Lsblk)
+        });
+        Ok(lsblk)
+    })();
+
+    #[cfg(feature = "tracing")]
+    match &result {
+        Ok(devices) => {
+            span.record("device_count", devices.iter_all().count());
+            span.record("warning_count", devices.warnings.len());
+        }
+        Err(error) => tracing::warn!(%error, "lsblk failed"),
+    }
+    result
+}
+
+/// Runs the `lsblk --json` command, captures its output, and parses it
+/// into a `BlockDevices` struct. If the command fails or the output cannot be parsed,
+/// an error is returned.
+///
+/// The returned snapshot carries [`SnapshotMeta`] recording when and on which host
+/// it was taken, and which `lsblk` binary was run. With the default
+/// `secure-exec` feature, `/usr/bin/lsblk`, `/bin/lsblk` and `/usr/sbin/lsblk`
+/// are tried before `PATH`, and lsblk runs with only `LC_ALL=C` in its
+/// environment.
+///
+/// # Errors
+///
+/// Returns an error if the `lsblk` command fails or if the output cannot be parsed as valid JSON.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use blockdev::get_devices;
+/// let devices = get_devices().expect("Failed to get block devices");
+/// ```
+pub fn get_devices() -> Result<BlockDevices, BlockDevError> {
+    run_lsblk(&[], &[])
+}
+
+/// Like [`get_devices`], but also requests the optional columns that populate
+/// the `Option` fields of [`BlockDevice`], such as `start` and `log_sec`.
+///
+/// Older lsblk versions reject columns they do not know, such as `SERIAL` or
+/// `MOUNTPOINTS`. The call is then repeated without them (or with `MOUNTPOINT`
+/// in place of `MOUNTPOINTS`), leaving the matching fields `None`, and each
+/// dropped column is listed in the snapshot's [`warnings`](BlockDevices::warnings).
+///
+/// # Errors
+///
+/// Returns an error if the `lsblk` command fails for any other reason, rejects
+/// one of the basic columns such as `NAME`, or if the output cannot be parsed.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use blockdev::get_devices_extended;
+/// let devices = get_devices_extended().expect("Failed to get block devices");
+/// ```
+pub fn get_devices_extended() -> Result<BlockDevices, BlockDevError> {
+    run_with_column_fallback(EXTENDED_COLUMNS, |columns| run_lsblk(columns, &[]))
+}
+
+/// Runs `lsblk` and returns the `/dev` paths of the disks the running system
+/// does not use, in natural order.
+///
+/// This is [`BlockDevices::non_system_disk_paths`] on the result of [`get_devices`].
+///
+/// # Errors
+///
+/// Returns an error if the `lsblk` command fails or if the output cannot be parsed.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use blockdev::non_system_disk_paths;
+/// for path in non_system_disk_paths().expect("Failed to list disks") {
+///     println!("{}", path.display());
+/// }
+/// ```
+pub fn non_system_disk_paths() -> Result<Vec<PathBuf>, BlockDevError> {
+    Ok(get_devices()?.non_system_disk_paths())
+}
+
+/// Returns `true` for errors that may go away on their own, such as lsblk
+/// failing while udev is processing a hot-plug event.
+fn is_transient(error: &BlockDevError) -> bool {
+    matches!(
+        error,
+        BlockDevError::CommandFailed(_) | BlockDevError::LsblkError(_)
+    )
+}
+
+/// Runs `attempt` until it succeeds, a non-transient error occurs, or it has been
+/// retried `max_retries` times, sleeping for `delay` between attempts.
+fn retry<T>(
+    max_retries: u32,
+    delay: Duration,
+    mut attempt: impl FnMut() -> Result<T, BlockDevError>,
+) -> Result<T, BlockDevError> {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match attempt() {
+            Ok(value) => return Ok(value),
+            Err(error) if !is_transient(&error) => return Err(error),
+            Err(error) if attempts > max_retries => {
+                #[cfg(feature = "tracing")]
+                tracing::error!(attempts, %error, "lsblk failed on every attempt");
+                return Err(BlockDevError::RetryExhausted {
+                    attempts,
+                    last_error: Box::new(error),
+                });
+            }
+            Err(_error) => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    attempt = attempts,
+                    max_retries,
+                    delay_ms = u64::try_from(delay.as_millis()).unwrap_or(u64::MAX),
+                    error = %_error,
+                    "transient lsblk failure; retrying"
+                );
+                thread::sleep(delay);
+            }
+        }
+    }
+}
+
+/// Like [`get_devices`], but retries up to `max_retries` times, sleeping for
+/// `delay` between attempts, if lsblk fails transiently.
+///
+/// Only failures to run lsblk ([`BlockDevError::CommandFailed`]) and non-zero
+/// exit statuses ([`BlockDevError::LsblkError`]), which can happen during
+/// hot-plug events or a udev rescan, are retried. Invalid UTF-8 or JSON parse
+/// errors are returned immediately, since running lsblk again will not fix them.
+///
+/// # Errors
+///
+/// Returns [`BlockDevError::RetryExhausted`] with the last error if every
+/// attempt failed transiently, or the first non-transient error.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use blockdev::get_devices_with_retry;
+/// # use std::time::Duration;
+/// let devices = get_devices_with_retry(3, Duration::from_millis(500))
+///     .expect("Failed to get block devices");
+/// ```
+pub fn get_devices_with_retry(
+    max_retries: u32,
+    delay: Duration,
+) -> Result<BlockDevices, BlockDevError> {
+    retry(max_retries, delay, get_devices)
+}
+
+/// Runs `lsblk --json` for a single device path (e.g. `/dev/sda`) and returns
+/// that device with its children.
+///
+/// # Errors
+///
+/// Returns an error if the `lsblk` command fails, if its output cannot be parsed,
+/// or [`BlockDevError::DeviceNotFound`] if lsblk reports no device.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use blockdev::get_device_info;
+/// # use std::path::Path;
+/// let sda = get_device_info(Path::new("/dev/sda")).expect("Failed to query /dev/sda");
+/// ```
+pub fn get_device_info(path: &Path) -> Result<BlockDevice, BlockDevError> {
+    run_lsblk(&[], &[path.to_path_buf()])?
+        .blockdevices
+        .into_iter()
+        .next()
+        .ok_or_else(|| BlockDevError::DeviceNotFound(path.display().to_string()))
+}
+
+/// Replaces every occurrence of the device called `name` in `devices` with
+/// `fresh`, keeping its position, or removes them all when `fresh` is `None`.
+///
+/// Returns [`BlockDevError::DeviceNotFound`] if `name` was not in the tree or
+/// if it was removed.
+fn splice_device(
+    devices: &mut BlockDevices,
+    name: &str,
+    fresh: Option<&BlockDevice>,
+) -> Result<(), BlockDevError> {
+    fn splice(devices: &mut Vec<BlockDevice>, name: &str, fresh: Option<&BlockDevice>) -> bool {
+        let mut found = false;
+        devices.retain_mut(|device| {
+            if device.name == name {
+                found = true;
+                match fresh {
+                    Some(fresh) => *device = fresh.clone(),
+                    None => return false,
+                }
+            } else if let Some(children) = &mut device.children {
+                found |= splice(children, name, fresh);
+            }
+            true
+        });
+        found
+    }
+
+    if !splice(&mut devices.blockdevices, name, fresh) || fresh.is_none() {
+        return Err(BlockDevError::DeviceNotFound(name.to_string()));
+    }
+    Ok(())
+}
+
+impl BlockDevice {
+    /// Runs `lsblk --json` for the device `/dev/<name>` and returns it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::InvalidInput`] if `name` is empty, `.`/`..`, or
+    /// contains a path separator, and otherwise any error from [`get_device_info`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use blockdev::BlockDevice;
+    /// let sda = BlockDevice::from_name("sda").expect("Failed to query sda");
+    /// ```
+    pub fn from_name(name: &str) -> Result<BlockDevice, BlockDevError> {
+        validate_device_name(name)?;
+        get_device_info(&Path::new("/dev").join(name))
+    }
+}
+
+impl BlockDevices {
+    /// Runs a single `lsblk --json` call for `/dev/<name>` of every given name.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::InvalidInput`] if any name is empty, `.`/`..`, or
+    /// contains a path separator, and otherwise any error from running or parsing `lsblk`.
+    pub fn from_names(names: &[&str]) -> Result<BlockDevices, BlockDevError> {
+        let paths = names
+            .iter()
+            .map(|name| validate_device_name(name).map(|()| Path::new("/dev").join(name)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if paths.is_empty() {
+            return Ok(BlockDevices::default());
+        }
+        run_lsblk(&[], &paths)
+    }
+
+    /// Re-queries the device called `name` with `lsblk` and replaces that
+    /// device and its subtree in place, without re-enumerating every other
+    /// device.
+    ///
+    /// The device is queried at its [`path`](BlockDevice::path) in this
+    /// snapshot, so LVM and crypt devices are looked up under `/dev/mapper`.
+    /// The device keeps its position among its siblings. A device that appears
+    /// under several parents (such as a RAID array) is replaced everywhere. Only
+    /// lsblk's default columns are requested, so optional fields such as `start`
+    /// are `None` on the refreshed subtree. [`BlockDevices::meta`] is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::InvalidInput`] if `name` is not a bare device name.
+    /// Returns [`BlockDevError::DeviceNotFound`] if `name` is not in this snapshot,
+    /// or if the device has vanished from the system, in which case it is also
+    /// removed from the snapshot. Other `lsblk` failures leave the snapshot unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use blockdev::get_devices;
+    /// let mut devices = get_devices().expect("Failed to get block devices");
+    /// // ... repartition sdb ...
+    /// devices.refresh_device("sdb").expect("Failed to refresh sdb");
+    /// ```
+    pub fn refresh_device(&mut self, name: &str) -> Result<(), BlockDevError> {
+        self.refresh_device_with(name, get_device_info)
+    }
+
+    fn refresh_device_with<F>(&mut self, name: &str, query: F) -> Result<(), BlockDevError>
+    where
+        F: FnOnce(&Path) -> Result<BlockDevice, BlockDevError>,
+    {
+        validate_device_name(name)?;
+        let path = self
+            .iter_all()
+            .find(|device| device.name == name)
+            .map(BlockDevice::path)
+            .ok_or_else(|| BlockDevError::DeviceNotFound(name.to_string()))?;
+        let fresh = match query(&path) {
+            Ok(device) => Some(device),
+            Err(BlockDevError::DeviceNotFound(_)) => None,
+            Err(_) if !path.exists() => None,
+            Err(err) => return Err(err),
+        };
+        splice_device(self, name, fresh.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_succeeds_after_transient_failures() {
+        let mut calls = 0;
+        let result = retry(3, Duration::ZERO, || {
+            calls += 1;
+            if calls <= 2 {
+                Err(BlockDevError::LsblkError("udev busy".to_string()))
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_retry_exhausted() {
+        let mut calls = 0;
+        let result: Result<(), _> = retry(2, Duration::ZERO, || {
+            calls += 1;
+            Err(BlockDevError::CommandFailed(std::io::Error::other(
+                "spawn failed",
+            )))
+        });
+        assert_eq!(calls, 3);
+        match result {
+            Err(BlockDevError::RetryExhausted {
+                attempts,
+                last_error,
+            }) => {
+                assert_eq!(attempts, 3);
+                assert!(matches!(*last_error, BlockDevError::CommandFailed(_)));
+            }
+            other => panic!("unexpected result {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_retry_does_not_retry_parse_errors() {
+        let mut calls = 0;
+        let result: Result<(), _> = retry(5, Duration::ZERO, || {
+            calls += 1;
+            Err(serde_json::from_str::<BlockDevices>("{")
+                .unwrap_err()
+                .into())
+        });
+        assert_eq!(calls, 1);
+        assert!(matches!(result, Err(BlockDevError::JsonParse(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "secure-exec")]
+    fn test_resolve_program_in() {
+        let root =
+            std::env::temp_dir().join(format!("blockdev-resolve-lsblk-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for dir in ["usr/bin", "bin", "usr/sbin"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        let candidates: Vec<PathBuf> = ["usr/bin/lsblk", "bin/lsblk", "usr/sbin/lsblk"]
+            .iter()
+            .map(|path| root.join(path))
+            .collect();
+
+        assert_eq!(
+            resolve_program_in("lsblk", &candidates),
+            PathBuf::from("lsblk")
+        );
+        std::fs::write(&candidates[2], "").unwrap();
+        assert_eq!(resolve_program_in("lsblk", &candidates), candidates[2]);
+        std::fs::write(&candidates[1], "").unwrap();
+        assert_eq!(resolve_program_in("lsblk", &candidates), candidates[1]);
+        std::fs::write(&candidates[0], "").unwrap();
+        assert_eq!(resolve_program_in("lsblk", &candidates), candidates[0]);
+
+        // Directories named lsblk are skipped.
+        std::fs::remove_file(&candidates[0]).unwrap();
+        std::fs::create_dir(&candidates[0]).unwrap();
+        assert_eq!(resolve_program_in("lsblk", &candidates), candidates[1]);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_lsblk_command() {
+        use std::ffi::OsStr;
+
+        let command = lsblk_command(Path::new("/usr/bin/lsblk"));
+        assert_eq!(command.get_program(), "/usr/bin/lsblk");
+        let envs: Vec<(&OsStr, Option<&OsStr>)> = command.get_envs().collect();
+        assert_eq!(envs, vec![(OsStr::new("LC_ALL"), Some(OsStr::new("C")))]);
+    }
+
+    #[test]
+    #[cfg(feature = "secure-exec")]
+    fn test_tool_command() {
+        use std::ffi::OsStr;
+
+        // No trusted directory has this tool, so it is left to `PATH`.
+        let command = tool_command("blockdev-no-such-tool");
+        assert_eq!(command.get_program(), "blockdev-no-such-tool");
+        let envs: Vec<(&OsStr, Option<&OsStr>)> = command.get_envs().collect();
+        assert_eq!(envs, vec![(OsStr::new("LC_ALL"), Some(OsStr::new("C")))]);
+
+        let command = tool_command("sh");
+        assert!(Path::new(command.get_program()).is_absolute());
+    }
+
+    #[test]
+    fn test_unknown_columns() {
+        assert_eq!(
+            unknown_columns("lsblk: unknown column: SERIAL\n"),
+            vec!["SERIAL"]
+        );
+        assert_eq!(
+            unknown_columns("lsblk: unknown column: 'FSUSE%'\nlsblk: unknown column: WWN"),
+            vec!["FSUSE%", "WWN"]
+        );
+        assert!(unknown_columns("lsblk: /dev/sdz: not a block device").is_empty());
+    }
+
+    #[test]
+    fn test_column_fallback() {
+        // An lsblk that reports one unknown column per run, like util-linux 2.32.
+        let supported = [
+            "NAME",
+            "MAJ:MIN",
+            "RM",
+            "SIZE",
+            "RO",
+            "TYPE",
+            "MOUNTPOINT",
+            "START",
+            "UUID",
+            "FSTYPE",
+        ];
+        let mut calls = Vec::new();
+        let devices = run_with_column_fallback(EXTENDED_COLUMNS, |columns| {
+            calls.push(columns.join(","));
+            match columns.iter().find(|c| !supported.contains(c)) {
+                Some(unknown) => Err(BlockDevError::LsblkError(format!(
+                    "lsblk: unknown column: {unknown}\n"
+                ))),
+                None => Ok(parse_lsblk(crate::fixtures::SAMPLE_JSON).unwrap()),
+            }
+        })
+        .expect("Fallback failed");
+
+        let dropped = EXTENDED_COLUMNS.len() - supported.len();
+        assert_eq!(calls.len(), dropped + 2);
+        assert_eq!(calls.last().unwrap(), &supported.join(","));
+        assert_eq!(devices.warnings.len(), dropped + 1);
+        assert_eq!(
+            devices.warnings[0],
+            "lsblk does not support column MOUNTPOINTS; requested MOUNTPOINT instead"
+        );
+        assert_eq!(
+            devices.warnings[1],
+            "lsblk does not support column LOG-SEC; dropped it"
+        );
+        assert!(
+            devices
+                .warnings
+                .contains(&"lsblk does not support column SERIAL; dropped it".to_string())
+        );
+    }
+
+    #[test]
+    fn test_column_fallback_hard_failures() {
+        let mut calls = 0;
+        let err = run_with_column_fallback(EXTENDED_COLUMNS, |_| {
+            calls += 1;
+            Err(BlockDevError::LsblkError(
+                "lsblk: unknown column: MAJ:MIN\n".to_string(),
+            ))
+        })
+        .unwrap_err();
+        assert_eq!(calls, 1);
+        assert!(matches!(err, BlockDevError::LsblkError(ref e) if e.contains("MAJ:MIN")));
+
+        let mut calls = 0;
+        let err = run_with_column_fallback(EXTENDED_COLUMNS, |columns| {
+            calls += 1;
+            if columns.contains(&"SERIAL") {
+                Err(BlockDevError::LsblkError(
+                    "lsblk: unknown column: SERIAL".to_string(),
+                ))
+            } else {
+                Err(BlockDevError::LsblkError(
+                    "lsblk: failed to access sysfs directory".to_string(),
+                ))
+            }
+        })
+        .unwrap_err();
+        assert_eq!(calls, 2);
+        assert_eq!(
+            err.to_string(),
+            "lsblk returned error: lsblk: failed to access sysfs directory"
+        );
+
+        // Columns lsblk complains about but that were not requested change nothing.
+        let err = run_with_column_fallback(&["NAME", "SIZE"], |_| {
+            Err(BlockDevError::LsblkError(
+                "lsblk: unknown column: SERIAL".to_string(),
+            ))
+        })
+        .unwrap_err();
+        assert!(matches!(err, BlockDevError::LsblkError(_)));
+    }
+
+    /// Warning: This test will attempt to run the `lsblk` command on your system.
+    /// It may fail if `lsblk` is not available or if the test environment does not permit running commands.
+    #[test]
+    #[ignore = "requires lsblk command to be available on the system"]
+    fn test_get_devices() {
+        let dev = get_devices().expect("Failed to get block devices");
+        // This assertion is simplistic; adjust according to your environment's expected output.
+        assert!(!dev.blockdevices.is_empty());
+    }
+
+    #[test]
+    #[ignore = "requires lsblk command to be available on the system"]
+    fn test_get_devices_extended() {
+        let dev = get_devices_extended().expect("Failed to get block devices");
+        assert!(dev.iter().all(|d| d.log_sec.is_some()));
+    }
+
+    #[test]
+    fn test_from_name_rejects_path_injection() {
+        // Validation happens before lsblk is run, so this does not touch the system.
+        let err = BlockDevice::from_name("sda/../sdb").unwrap_err();
+        assert!(matches!(err, BlockDevError::InvalidInput(_)));
+        assert!(err.to_string().contains("sda/../sdb"));
+
+        let err = BlockDevices::from_names(&["sda", "../sdb"]).unwrap_err();
+        assert!(matches!(err, BlockDevError::InvalidInput(_)));
+    }
+
+    #[test]
+    fn test_splice_device_replaces_in_place() {
+        let mut devices = parse_lsblk(crate::fixtures::SAMPLE_JSON).expect("Failed to parse JSON");
+        let original = devices.clone();
+        let mut fresh = devices.find_by_name("nvme0n1").unwrap().clone();
+        fresh.children = Some(Vec::new());
+        fresh.size = 42;
+
+        splice_device(&mut devices, "nvme0n1", Some(&fresh)).expect("Failed to splice");
+        let names: Vec<&str> = devices.iter().map(|d| d.name.as_str()).collect();
+        let original_names: Vec<&str> = original.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, original_names);
+        assert_eq!(devices.find_by_name("nvme0n1"), Some(&fresh));
+        for (after, before) in devices.iter().zip(original.iter()) {
+            if after.name != "nvme0n1" {
+                assert_eq!(after, before);
+            }
+        }
+    }
+
+    #[test]
+    fn test_splice_device_nested_and_shared() {
+        let mut devices = parse_lsblk(crate::fixtures::SAMPLE_JSON).expect("Failed to parse JSON");
+        let fresh = BlockDevice {
+            name: "md0".to_string(),
+            mountpoints: vec![Some("/boot2".to_string())],
+            ..Default::default()
+        };
+        splice_device(&mut devices, "md0", Some(&fresh)).expect("Failed to splice");
+        let md0s: Vec<&BlockDevice> = devices.iter_all().filter(|d| d.name == "md0").collect();
+        assert_eq!(md0s.len(), 2);
+        assert!(
+            md0s.iter()
+                .all(|d| d.active_mountpoints() == vec!["/boot2"])
+        );
+    }
+
+    #[test]
+    fn test_splice_device_vanished_or_unknown() {
+        let mut devices = parse_lsblk(crate::fixtures::SAMPLE_JSON).expect("Failed to parse JSON");
+        let err = splice_device(&mut devices, "nvme9n1p9", None).unwrap_err();
+        assert!(matches!(err, BlockDevError::DeviceNotFound(_)));
+        assert!(devices.iter_all().all(|d| d.name != "nvme9n1p9"));
+        assert!(devices.iter_all().any(|d| d.name == "nvme9n1p1"));
+
+        let before = devices.clone();
+        let fresh = BlockDevice::default();
+        let err = splice_device(&mut devices, "sdz", Some(&fresh)).unwrap_err();
+        assert!(matches!(err, BlockDevError::DeviceNotFound(_)));
+        assert_eq!(devices, before);
+    }
+
+    #[test]
+    fn test_refresh_device_queries_device_path() {
+        let mut devices = parse_lsblk(crate::fixtures::SAMPLE_JSON).expect("Failed to parse JSON");
+        let mut fresh = devices.find_by_name("nvme0n1").unwrap().clone();
+        fresh.size = 42;
+        devices
+            .refresh_device_with("nvme0n1", |path| {
+                assert_eq!(path, Path::new("/dev/nvme0n1"));
+                Ok(fresh.clone())
+            })
+            .expect("Failed to refresh");
+        assert_eq!(devices.find_by_name("nvme0n1"), Some(&fresh));
+
+        // A crypt device only exists under /dev/mapper.
+        let mut devices = BlockDevices {
+            blockdevices: vec![BlockDevice {
+                name: "sda".to_string(),
+                children: Some(vec![BlockDevice {
+                    name: "luks-data".to_string(),
+                    device_type: crate::DeviceType::Crypt,
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut queried = PathBuf::new();
+        devices
+            .refresh_device_with("luks-data", |path| {
+                queried = path.to_path_buf();
+                Ok(BlockDevice {
+                    name: "luks-data".to_string(),
+                    device_type: crate::DeviceType::Crypt,
+                    size: 7,
+                    ..Default::default()
+                })
+            })
+            .expect("Failed to refresh");
+        assert_eq!(queried, Path::new("/dev/mapper/luks-data"));
+        assert_eq!(devices.iter_all().nth(1).unwrap().size, 7);
+    }
+
+    #[test]
+    fn test_refresh_device_unknown_or_vanished() {
+        let mut devices = parse_lsblk(crate::fixtures::SAMPLE_JSON).expect("Failed to parse JSON");
+        let before = devices.clone();
+        let err = devices
+            .refresh_device_with("sdz", |_| panic!("lsblk must not run for an unknown name"))
+            .unwrap_err();
+        assert!(matches!(err, BlockDevError::DeviceNotFound(_)));
+        assert_eq!(devices, before);
+
+        // lsblk fails and the device node is gone, so the device is removed.
+        let err = devices
+            .refresh_device_with("nvme9n1p9", |_| {
+                Err(BlockDevError::LsblkError("not a block device".to_string()))
+            })
+            .unwrap_err();
+        assert!(matches!(err, BlockDevError::DeviceNotFound(_)));
+        assert!(devices.iter_all().all(|d| d.name != "nvme9n1p9"));
+        assert!(devices.iter_all().any(|d| d.name == "nvme9n1p1"));
+    }
+
+    #[test]
+    fn test_refresh_device_keeps_device_on_other_failures() {
+        // `/dev/null` exists, so an lsblk failure does not mean it vanished.
+        let mut devices = BlockDevices {
+            blockdevices: vec![BlockDevice {
+                name: "null".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let before = devices.clone();
+        let err = devices
+            .refresh_device_with("null", |_| {
+                Err(BlockDevError::LsblkError("not a block device".to_string()))
+            })
+            .unwrap_err();
+        assert!(matches!(err, BlockDevError::LsblkError(_)));
+        assert_eq!(devices, before);
+    }
+
+    #[test]
+    fn test_from_names_empty() {
+        let devices = BlockDevices::from_names(&[]).expect("Empty name list should succeed");
+        assert!(devices.is_empty());
+    }
+
+    /// The name and fields of each span, indexed by span ID minus one.
+    #[cfg(feature = "tracing")]
+    type RecordedSpans = Vec<(&'static str, std::collections::BTreeMap<String, String>)>;
+
+    /// A subscriber that keeps the name and fields of every span.
+    #[cfg(feature = "tracing")]
+    #[derive(Clone, Default)]
+    struct SpanRecorder {
+        spans: std::sync::Arc<std::sync::Mutex<RecordedSpans>>,
+    }
+
+    #[cfg(feature = "tracing")]
+    impl SpanRecorder {
+        fn visit(
+            &self,
+            id: &tracing::span::Id,
+            fields: impl FnOnce(&mut dyn tracing::field::Visit),
+        ) {
+            struct Fields<'a>(&'a mut std::collections::BTreeMap<String, String>);
+            impl tracing::field::Visit for Fields<'_> {
+                fn record_debug(
+                    &mut self,
+                    field: &tracing::field::Field,
+                    value: &dyn std::fmt::Debug,
+                ) {
+                    self.0
+                        .insert(field.name().to_string(), format!("{value:?}"));
+                }
+            }
+            let mut spans = self.spans.lock().unwrap();
+            let index = usize::try_from(id.into_u64()).unwrap() - 1;
+            fields(&mut Fields(&mut spans[index].1));
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for SpanRecorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            let id = {
+                let mut spans = self.spans.lock().unwrap();
+                spans.push((span.metadata().name(), std::collections::BTreeMap::new()));
+                tracing::span::Id::from_u64(spans.len() as u64)
+            };
+            self.visit(&id, |visitor| span.record(visitor));
+            id
+        }
+
+        fn record(&self, id: &tracing::span::Id, values: &tracing::span::Record<'_>) {
+            self.visit(id, |visitor| values.record(visitor));
+        }
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, _: &tracing::Event<'_>) {}
+
+        fn enter(&self, _: &tracing::span::Id) {}
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[cfg(all(feature = "tracing", unix))]
+    #[test]
+    fn test_tracing_records_lsblk_span() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // A stand-in for lsblk that prints a fixed snapshot and ignores its arguments.
+        let dir = std::env::temp_dir().join(format!("blockdev-tracing-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let program = dir.join("lsblk");
+        let json = r#"{"blockdevices": [{"name": "sda", "maj:min": "8:0", "rm": false, "size": 1000, "ro": false, "type": "disk", "mountpoints": [null], "children": [{"name": "sda1", "maj:min": "8:1", "rm": false, "size": 1000, "ro": false, "type": "part", "mountpoints": ["/"]}]}]}"#;
+        std::fs::write(&program, format!("#!/bin/sh\nprintf '%s\\n' '{json}'\n")).unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let recorder = SpanRecorder::default();
+        let devices = tracing::subscriber::with_default(recorder.clone(), || {
+            run_lsblk_program(program.clone(), &["NAME", "SIZE"], &[])
+        })
+        .unwrap();
+        assert_eq!(devices.len(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+
+        let spans = recorder.spans.lock().unwrap();
+        let (_, lsblk) = spans.iter().find(|(name, _)| *name == "lsblk").unwrap();
+        assert_eq!(lsblk["device_count"], "2");
+        assert_eq!(lsblk["warning_count"], "0");
+        let stdout_bytes = (json.len() + 1).to_string();
+        assert_eq!(lsblk["stdout_bytes"], stdout_bytes);
+        assert!(lsblk["argv"].ends_with(r#""--json", "--bytes", "--output", "NAME,SIZE"]"#));
+        assert!(lsblk.contains_key("duration_ms"));
+        let (_, parse) = spans
+            .iter()
+            .find(|(name, _)| *name == "parse_lsblk")
+            .unwrap();
+        assert_eq!(parse["bytes"], stdout_bytes);
+    }
+}
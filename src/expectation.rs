@@ -0,0 +1,395 @@
+use crate::natural::natural_cmp;
+use crate::summary::format_size;
+use crate::{BlockDevice, BlockDevices, DeviceType};
+use std::fmt;
+
+/// A group of devices a host is expected to have, checked by
+/// [`BlockDevices::check`].
+///
+/// The default expects no devices and accepts any top-level device; set
+/// `count` and the criteria that matter.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Expectation {
+    /// The number of devices expected.
+    pub count: usize,
+    /// The minimum size in bytes, inclusive.
+    pub min_size: Option<u64>,
+    /// The maximum size in bytes, inclusive.
+    pub max_size: Option<u64>,
+    /// The required device type, such as [`DeviceType::Disk`].
+    pub device_type: Option<DeviceType>,
+    /// A pattern the device name must match, where `*` matches any run of
+    /// characters and `?` any single character, such as `nvme*`.
+    pub name_pattern: Option<String>,
+}
+
+/// Returns `true` if `name` matches the glob `pattern` of `*` and `?`.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // The position of the last `*` and the name position it was tried at.
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, at)) => {
+                    p = star + 1;
+                    n = at + 1;
+                    backtrack = Some((star, at + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+impl Expectation {
+    /// Returns `true` if `device` meets every criterion of this expectation.
+    #[must_use]
+    pub fn matches(&self, device: &BlockDevice) -> bool {
+        self.min_size.is_none_or(|min| device.size >= min)
+            && self.max_size.is_none_or(|max| device.size <= max)
+            && self
+                .device_type
+                .as_ref()
+                .is_none_or(|device_type| device.device_type == *device_type)
+            && self
+                .name_pattern
+                .as_deref()
+                .is_none_or(|pattern| glob_match(pattern, &device.name))
+    }
+}
+
+impl fmt::Display for Expectation {
+    /// Formats the expectation as, for example, `8 nvme* disk(s) of 3.4T to 3.6T`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.count)?;
+        if let Some(pattern) = &self.name_pattern {
+            write!(f, " {pattern}")?;
+        }
+        match &self.device_type {
+            Some(device_type) => write!(f, " {device_type}(s)")?,
+            None => write!(f, " device(s)")?,
+        }
+        match (self.min_size, self.max_size) {
+            (Some(min), Some(max)) => {
+                write!(f, " of {} to {}", format_size(min), format_size(max))
+            }
+            (Some(min), None) => write!(f, " of at least {}", format_size(min)),
+            (None, Some(max)) => write!(f, " of at most {}", format_size(max)),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// How one [`Expectation`] fared in a [`CheckResult`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectationOutcome<'a> {
+    /// The position of the expectation in the list passed to
+    /// [`BlockDevices::check`].
+    pub index: usize,
+    /// The expectation.
+    pub expectation: Expectation,
+    /// The devices claimed for it, largest first. There are never more than
+    /// `expectation.count`.
+    pub matched: Vec<&'a BlockDevice>,
+}
+
+impl ExpectationOutcome<'_> {
+    /// Returns `true` if enough devices were claimed.
+    #[must_use]
+    pub fn is_satisfied(&self) -> bool {
+        self.shortfall() == 0
+    }
+
+    /// Returns the number of devices missing, or zero if satisfied.
+    #[must_use]
+    pub fn shortfall(&self) -> usize {
+        self.expectation.count.saturating_sub(self.matched.len())
+    }
+}
+
+impl fmt::Display for ExpectationOutcome<'_> {
+    /// Formats the outcome as, for example,
+    /// `expected 10 disk(s) of at least 3T, found 8 (nvme0n1, ...): 2 short`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expected {}, found {}",
+            self.expectation,
+            self.matched.len()
+        )?;
+        if !self.matched.is_empty() {
+            let names: Vec<&str> = self.matched.iter().map(|d| d.name.as_str()).collect();
+            write!(f, " ({})", names.join(", "))?;
+        }
+        match self.shortfall() {
+            0 => Ok(()),
+            missing => write!(f, ": {missing} short"),
+        }
+    }
+}
+
+/// The result of [`BlockDevices::check`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CheckResult<'a> {
+    /// The expectations that claimed enough devices, in manifest order.
+    pub satisfied: Vec<ExpectationOutcome<'a>>,
+    /// The expectations that fell short, in manifest order.
+    pub unsatisfied: Vec<ExpectationOutcome<'a>>,
+    /// The top-level devices no expectation claimed, in tree order.
+    pub leftovers: Vec<&'a BlockDevice>,
+}
+
+impl CheckResult<'_> {
+    /// Returns `true` if every expectation was satisfied. Leftover devices do
+    /// not count against it.
+    #[must_use]
+    pub fn is_satisfied(&self) -> bool {
+        self.unsatisfied.is_empty()
+    }
+}
+
+impl BlockDevices {
+    /// Checks the top-level devices of this snapshot against a manifest of
+    /// expected devices, such as "8 NVMe disks of 3.5T and 2 boot disks of
+    /// 900G".
+    ///
+    /// Each device is claimed by at most one expectation. Expectations with
+    /// the largest `min_size` pick first, so that a loose expectation cannot
+    /// take the disks a stricter one needs; ties keep manifest order. Each
+    /// expectation then claims up to `count` matching devices, largest first,
+    /// with ties broken by natural name order (`nvme2n1` before `nvme10n1`).
+    /// The result depends only on the snapshot and the manifest.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{DeviceType, Expectation, parse_lsblk};
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "900G", "ro": false, "type": "disk", "mountpoints": ["/"]},
+    ///     {"name": "nvme0n1", "maj:min": "259:0", "rm": false, "size": "3.5T", "ro": false, "type": "disk", "mountpoints": [null]}
+    /// ]}"#).unwrap();
+    ///
+    /// let manifest = [Expectation {
+    ///     count: 2,
+    ///     min_size: Some(3 << 40),
+    ///     device_type: Some(DeviceType::Disk),
+    ///     name_pattern: Some("nvme*".to_string()),
+    ///     ..Default::default()
+    /// }];
+    /// let result = devices.check(&manifest);
+    /// assert!(!result.is_satisfied());
+    /// assert_eq!(result.unsatisfied[0].shortfall(), 1);
+    /// assert_eq!(result.leftovers[0].name, "sda");
+    /// ```
+    #[must_use]
+    pub fn check(&self, expectations: &[Expectation]) -> CheckResult<'_> {
+        // Claims are tracked by position in tree order, so that devices
+        // sharing a name are still told apart.
+        let devices: Vec<&BlockDevice> = self.iter().collect();
+        let mut by_size: Vec<usize> = (0..devices.len()).collect();
+        by_size.sort_by(|&a, &b| {
+            devices[b]
+                .size
+                .cmp(&devices[a].size)
+                .then_with(|| natural_cmp(&devices[a].name, &devices[b].name))
+        });
+        let mut claimed = vec![false; devices.len()];
+
+        let mut order: Vec<usize> = (0..expectations.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(expectations[i].min_size.unwrap_or(0)));
+        let mut outcomes: Vec<ExpectationOutcome<'_>> = Vec::with_capacity(expectations.len());
+        for index in order {
+            let expectation = &expectations[index];
+            let mut matched = Vec::new();
+            for &position in &by_size {
+                if matched.len() == expectation.count {
+                    break;
+                }
+                if !claimed[position] && expectation.matches(devices[position]) {
+                    claimed[position] = true;
+                    matched.push(devices[position]);
+                }
+            }
+            outcomes.push(ExpectationOutcome {
+                index,
+                expectation: expectation.clone(),
+                matched,
+            });
+        }
+        outcomes.sort_by_key(|outcome| outcome.index);
+
+        let mut result = CheckResult {
+            leftovers: devices
+                .iter()
+                .zip(&claimed)
+                .filter(|(_, claimed)| !**claimed)
+                .map(|(device, _)| *device)
+                .collect(),
+            ..CheckResult::default()
+        };
+        for outcome in outcomes {
+            if outcome.is_satisfied() {
+                result.satisfied.push(outcome);
+            } else {
+                result.unsatisfied.push(outcome);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    const TIB: u64 = 1 << 40;
+    const GIB: u64 = 1 << 30;
+
+    /// 8x 3.5T NVMe data disks and 2x 900G boot disks, as in `SAMPLE_JSON`.
+    fn manifest(data_disks: usize) -> [Expectation; 2] {
+        [
+            Expectation {
+                count: 2,
+                min_size: Some(800 * GIB),
+                max_size: Some(TIB),
+                device_type: Some(DeviceType::Disk),
+                ..Default::default()
+            },
+            Expectation {
+                count: data_disks,
+                min_size: Some(3 * TIB),
+                device_type: Some(DeviceType::Disk),
+                name_pattern: Some("nvme*n1".to_string()),
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn names<'a>(devices: &[&'a BlockDevice]) -> Vec<&'a str> {
+        devices.iter().map(|d| d.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_check_sample_manifest() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let result = devices.check(&manifest(8));
+        assert!(result.is_satisfied());
+        assert!(result.leftovers.is_empty());
+
+        let indexes: Vec<usize> = result.satisfied.iter().map(|o| o.index).collect();
+        assert_eq!(indexes, vec![0, 1]);
+        assert_eq!(
+            names(&result.satisfied[0].matched),
+            vec!["nvme2n1", "nvme3n1"]
+        );
+        assert_eq!(
+            names(&result.satisfied[1].matched),
+            vec![
+                "nvme0n1", "nvme1n1", "nvme4n1", "nvme5n1", "nvme6n1", "nvme7n1", "nvme8n1",
+                "nvme9n1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_shortfall() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let result = devices.check(&manifest(10));
+        assert!(!result.is_satisfied());
+        assert_eq!(result.satisfied.len(), 1);
+        assert_eq!(result.unsatisfied.len(), 1);
+
+        let big = &result.unsatisfied[0];
+        assert_eq!(big.index, 1);
+        assert_eq!(big.matched.len(), 8);
+        assert_eq!(big.shortfall(), 2);
+        assert_eq!(
+            big.to_string(),
+            "expected 10 nvme*n1 disk(s) of at least 3T, found 8 \
+             (nvme0n1, nvme1n1, nvme4n1, nvme5n1, nvme6n1, nvme7n1, nvme8n1, nvme9n1): 2 short"
+        );
+        assert!(result.leftovers.is_empty());
+    }
+
+    #[test]
+    fn test_check_claims_each_device_once() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        // A loose expectation listed first still leaves the big disks to the
+        // stricter one, and a device is never claimed twice.
+        let loose = Expectation {
+            count: 3,
+            ..Default::default()
+        };
+        let big = Expectation {
+            count: 7,
+            min_size: Some(3 * TIB),
+            ..Default::default()
+        };
+        let result = devices.check(&[loose, big]);
+        assert!(result.is_satisfied());
+        let big_matched = names(&result.satisfied[1].matched);
+        assert_eq!(big_matched.len(), 7);
+        assert!(!big_matched.contains(&"nvme9n1"));
+        assert_eq!(
+            names(&result.satisfied[0].matched),
+            vec!["nvme9n1", "nvme2n1", "nvme3n1"]
+        );
+
+        let none = devices.check(&[]);
+        assert!(none.is_satisfied());
+        assert_eq!(none.leftovers.len(), 10);
+        assert_eq!(none.leftovers[0].name, "nvme1n1");
+    }
+
+    #[test]
+    fn test_check_leftovers_with_duplicate_names() {
+        // Merged snapshots can hold two devices of the same name; claiming
+        // one must not hide the other.
+        let disk = |size| BlockDevice {
+            name: "sda".to_string(),
+            size,
+            device_type: DeviceType::Disk,
+            mountpoints: vec![None],
+            ..Default::default()
+        };
+        let devices = BlockDevices {
+            blockdevices: vec![disk(GIB), disk(2 * GIB)],
+            ..Default::default()
+        };
+        let result = devices.check(&[Expectation {
+            count: 1,
+            ..Default::default()
+        }]);
+        assert!(result.is_satisfied());
+        assert_eq!(result.satisfied[0].matched[0].size, 2 * GIB);
+        assert_eq!(result.leftovers.len(), 1);
+        assert_eq!(result.leftovers[0].size, GIB);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("nvme*", "nvme0n1"));
+        assert!(glob_match("nvme?n1", "nvme7n1"));
+        assert!(glob_match("*n1", "nvme10n1"));
+        assert!(glob_match("sd*a*", "sdaa"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("nvme?n1", "nvme10n1"));
+        assert!(!glob_match("sd*", "nvme0n1"));
+        assert!(!glob_match("", "sda"));
+    }
+}
@@ -0,0 +1,335 @@
+use crate::{BlockDevice, BlockDevices, CryptoType, DeviceType, IterAll};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// What a device's `fstype` signature says about its contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilesystemCategory {
+    /// A mountable filesystem such as `ext4`, `xfs`, `btrfs` or `vfat`.
+    DataFilesystem,
+    /// A container for other block devices: an encrypted volume, a RAID member,
+    /// an LVM physical volume or a bcache backing device.
+    ContainerType,
+    /// A swap area.
+    SwapSpace,
+    /// No signature, or `fstype` was not requested.
+    Unknown,
+}
+
+/// `fstype` values of container formats that are not covered by
+/// [`BlockDevice::crypto_type`] or the `*_raid_member` suffix.
+const CONTAINER_FSTYPES: &[&str] = &["LVM2_member", "LVM1_member", "bcache"];
+
+impl BlockDevice {
+    /// Classifies this device's `fstype` signature.
+    ///
+    /// Encrypted containers are those recognized by [`BlockDevice::crypto_type`];
+    /// RAID members are any `*_raid_member` signature, such as
+    /// `linux_raid_member` or `isw_raid_member`. This needs the `fstype` column,
+    /// which `get_devices_extended` requests.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{BlockDevice, FilesystemCategory};
+    ///
+    /// let pv = BlockDevice {
+    ///     fstype: Some("LVM2_member".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(pv.filesystem_category(), FilesystemCategory::ContainerType);
+    /// assert!(!pv.has_filesystem());
+    /// ```
+    #[must_use]
+    pub fn filesystem_category(&self) -> FilesystemCategory {
+        match self.fstype.as_deref().map(str::trim) {
+            None | Some("") => FilesystemCategory::Unknown,
+            Some("swap") => FilesystemCategory::SwapSpace,
+            Some(fstype)
+                if fstype.ends_with("_raid_member")
+                    || CONTAINER_FSTYPES.contains(&fstype)
+                    || self.crypto_type() != CryptoType::NotEncrypted =>
+            {
+                FilesystemCategory::ContainerType
+            }
+            Some(_) => FilesystemCategory::DataFilesystem,
+        }
+    }
+
+    /// Returns `true` if `fstype` shows a formatted device rather than an
+    /// unformatted one or a container for other devices.
+    ///
+    /// This is `true` for data filesystems and swap areas, and `false` for no
+    /// signature and for the containers listed under
+    /// [`FilesystemCategory::ContainerType`], such as `crypto_LUKS`,
+    /// `linux_raid_member` and `LVM2_member`.
+    #[must_use]
+    pub fn has_filesystem(&self) -> bool {
+        matches!(
+            self.filesystem_category(),
+            FilesystemCategory::DataFilesystem | FilesystemCategory::SwapSpace
+        )
+    }
+
+    /// Returns `true` if this device can be used for data on its own, without
+    /// disturbing the system or a RAID, LVM or LUKS stack.
+    ///
+    /// That requires the device not to be a system device under
+    /// [`SystemPolicy::strict`](crate::SystemPolicy::strict), which also
+    /// protects boot filesystems and swap, and neither it nor anything
+    /// stacked on it to be an md RAID member, an LVM volume or physical
+    /// volume, or a LUKS container. The device itself must hold a data filesystem or no
+    /// filesystem at all, so swap areas and other containers are not safe
+    /// either. This needs the `fstype` column, which `get_devices_extended`
+    /// requests; without it, only the system and LVM checks can apply.
+    #[must_use]
+    pub fn is_safe_for_data_use(&self) -> bool {
+        self.is_safe_for_data_use_with(&crate::SystemPolicy::strict())
+    }
+
+    /// Like [`is_safe_for_data_use`](Self::is_safe_for_data_use), with system
+    /// devices defined by `policy`.
+    #[must_use]
+    pub fn is_safe_for_data_use_with(&self, policy: &crate::SystemPolicy) -> bool {
+        let in_stack = |d: &BlockDevice| {
+            d.is_md_member()
+                || d.is_luks()
+                || d.device_type == DeviceType::Lvm
+                || d.fstype.as_deref() == Some("LVM2_member")
+        };
+        !self.is_system_with(policy)
+            && !IterAll::subtree(self).any(in_stack)
+            && matches!(
+                self.filesystem_category(),
+                FilesystemCategory::DataFilesystem | FilesystemCategory::Unknown
+            )
+    }
+}
+
+impl BlockDevices {
+    /// Returns each device in the tree once, with its non-empty `fstype`.
+    ///
+    /// A RAID array is listed under every member, so devices are deduplicated by name.
+    fn fstypes(&self) -> impl Iterator<Item = (&BlockDevice, &str)> {
+        let mut seen = HashSet::new();
+        self.iter_all().filter_map(move |d| {
+            let fstype = d.fstype.as_deref().filter(|f| !f.is_empty())?;
+            seen.insert(d.name.as_str()).then_some((d, fstype))
+        })
+    }
+
+    /// Returns every device in the tree whose `fstype` matches `fstype`,
+    /// ignoring ASCII case, in tree order.
+    ///
+    /// Needs the `FSTYPE` column, which `get_devices_extended` requests.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "children": [
+    ///         {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": ["/boot"], "fstype": "ext4"},
+    ///         {"name": "sda2", "maj:min": "8:2", "rm": false, "size": "999G", "ro": false, "type": "part", "mountpoints": ["/"], "fstype": "xfs"}
+    ///      ]}
+    /// ]}"#).unwrap();
+    ///
+    /// let xfs = devices.filter_by_filesystem("XFS");
+    /// assert_eq!(xfs.len(), 1);
+    /// assert_eq!(xfs[0].name, "sda2");
+    /// ```
+    #[must_use]
+    pub fn filter_by_filesystem(&self, fstype: &str) -> Vec<&BlockDevice> {
+        self.fstypes()
+            .filter(|(_, f)| f.eq_ignore_ascii_case(fstype))
+            .map(|(d, _)| d)
+            .collect()
+    }
+
+    /// Counts the devices in the tree per `fstype` value, as reported by lsblk.
+    ///
+    /// Devices without an `fstype` are not counted. Container signatures such
+    /// as `crypto_LUKS` and `linux_raid_member` are counted like filesystems.
+    #[must_use]
+    pub fn filesystem_counts(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for (_, fstype) in self.fstypes() {
+            *counts.entry(fstype.to_string()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Returns the distinct `fstype` values in the tree, sorted.
+    #[must_use]
+    pub fn unique_filesystem_types(&self) -> Vec<String> {
+        self.fstypes()
+            .map(|(_, f)| f)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_lsblk;
+
+    const STACKS_JSON: &str = r#"{"blockdevices": [
+        {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "512G", "ro": false, "type": "part", "mountpoints": [null],
+             "fstype": "linux_raid_member",
+             "children": [
+                {"name": "md0", "maj:min": "9:0", "rm": false, "size": "512G", "ro": false, "type": "raid1", "mountpoints": ["/srv"],
+                 "fstype": "ext4"}
+             ]},
+            {"name": "sda2", "maj:min": "8:2", "rm": false, "size": "500G", "ro": false, "type": "part", "mountpoints": [null],
+             "fstype": "xfs"},
+            {"name": "sda3", "maj:min": "8:3", "rm": false, "size": "12G", "ro": false, "type": "part", "mountpoints": ["[SWAP]"],
+             "fstype": "swap"}
+         ]},
+        {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "sdb1", "maj:min": "8:17", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": [null],
+             "fstype": "LVM2_member",
+             "children": [
+                {"name": "vg0-data", "maj:min": "253:0", "rm": false, "size": "1T", "ro": false, "type": "lvm", "mountpoints": ["/data"],
+                 "fstype": "ext4"}
+             ]}
+         ]},
+        {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "fstype": "crypto_LUKS", "fsver": "2"},
+        {"name": "sdd", "maj:min": "8:48", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+        {"name": "sde", "maj:min": "8:64", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "sde1", "maj:min": "8:65", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": ["/"],
+             "fstype": "ext4"}
+         ]}
+    ]}"#;
+
+    fn with_fstype(fstype: Option<&str>) -> BlockDevice {
+        BlockDevice {
+            fstype: fstype.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_filesystem_category() {
+        let cases = [
+            (Some("ext4"), FilesystemCategory::DataFilesystem, true),
+            (Some("xfs"), FilesystemCategory::DataFilesystem, true),
+            (Some("btrfs"), FilesystemCategory::DataFilesystem, true),
+            (Some("vfat"), FilesystemCategory::DataFilesystem, true),
+            (Some("swap"), FilesystemCategory::SwapSpace, true),
+            (
+                Some("crypto_LUKS"),
+                FilesystemCategory::ContainerType,
+                false,
+            ),
+            (Some("BitLocker"), FilesystemCategory::ContainerType, false),
+            (
+                Some("LVM2_member"),
+                FilesystemCategory::ContainerType,
+                false,
+            ),
+            (
+                Some("linux_raid_member"),
+                FilesystemCategory::ContainerType,
+                false,
+            ),
+            (Some(""), FilesystemCategory::Unknown, false),
+            (None, FilesystemCategory::Unknown, false),
+        ];
+        for (fstype, category, has_filesystem) in cases {
+            let device = with_fstype(fstype);
+            assert_eq!(device.filesystem_category(), category, "{fstype:?}");
+            assert_eq!(device.has_filesystem(), has_filesystem, "{fstype:?}");
+        }
+    }
+
+    #[test]
+    fn test_is_safe_for_data_use() {
+        let devices = parse_lsblk(STACKS_JSON).expect("Failed to parse JSON");
+        let safe: Vec<(&str, bool)> = devices
+            .iter_all()
+            .map(|d| (d.name.as_str(), d.is_safe_for_data_use()))
+            .collect();
+        assert_eq!(
+            safe,
+            vec![
+                // sda carries an md member, sda1 is one.
+                ("sda", false),
+                ("sda1", false),
+                ("md0", true),
+                ("sda2", true),
+                ("sda3", false),
+                ("sdb", false),
+                ("sdb1", false),
+                ("vg0-data", false),
+                ("sdc", false),
+                ("sdd", true),
+                ("sde", false),
+                ("sde1", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filesystem_inventory() {
+        let json = STACKS_JSON.replace(
+            r#""name": "sdd", "maj:min": "8:48", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]"#,
+            r#""name": "sdd", "maj:min": "8:48", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null], "fstype": "btrfs""#,
+        );
+        let devices = parse_lsblk(&json).expect("Failed to parse JSON");
+
+        let ext4: Vec<&str> = devices
+            .filter_by_filesystem("ext4")
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(ext4, vec!["md0", "vg0-data", "sde1"]);
+        assert_eq!(devices.filter_by_filesystem("EXT4").len(), 3);
+        assert_eq!(devices.filter_by_filesystem("xfs")[0].name, "sda2");
+        assert!(devices.filter_by_filesystem("zfs").is_empty());
+
+        let counts = devices.filesystem_counts();
+        assert_eq!(counts.len(), 7);
+        assert_eq!(counts["ext4"], 3);
+        assert_eq!(counts["xfs"], 1);
+        assert_eq!(counts["btrfs"], 1);
+        assert_eq!(counts["swap"], 1);
+        assert_eq!(counts["crypto_LUKS"], 1);
+        assert_eq!(
+            devices.unique_filesystem_types(),
+            vec![
+                "LVM2_member",
+                "btrfs",
+                "crypto_LUKS",
+                "ext4",
+                "linux_raid_member",
+                "swap",
+                "xfs"
+            ]
+        );
+
+        // md0 is listed under both members but counted once.
+        let raid = parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "fstype": "linux_raid_member",
+                 "children": [{"name": "md0", "maj:min": "9:0", "rm": false, "size": "1T", "ro": false, "type": "raid1", "mountpoints": ["/srv"], "fstype": "ext4"}]},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "fstype": "linux_raid_member",
+                 "children": [{"name": "md0", "maj:min": "9:0", "rm": false, "size": "1T", "ro": false, "type": "raid1", "mountpoints": ["/srv"], "fstype": "ext4"}]}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(raid.filesystem_counts()["ext4"], 1);
+        assert_eq!(raid.filesystem_counts()["linux_raid_member"], 2);
+        assert!(BlockDevices::default().unique_filesystem_types().is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+//! Shared JSON fixtures for unit tests.
+
+/// `lsblk --json` output from a host with ten NVMe disks, two of which carry
+/// RAID1 arrays for `/boot`, swap and `/`.
+pub(crate) const SAMPLE_JSON: &str = r#"
+{
+    "blockdevices": [
+        {"name":"nvme1n1", "maj:min":"259:0", "rm":false, "size":"3.5T", "ro":false, "type":"disk", "mountpoint":null,
+            "children": [
+                {"name":"nvme1n1p1", "maj:min":"259:1", "rm":false, "size":"3.5T", "ro":false, "type":"part", "mountpoint":null},
+                {"name":"nvme1n1p9", "maj:min":"259:2", "rm":false, "size":"8M", "ro":false, "type":"part", "mountpoint":null}
+            ]
+        },
+        {"name":"nvme7n1", "maj:min":"259:3", "rm":false, "size":"3.5T", "ro":false, "type":"disk", "mountpoint":null,
+            "children": [
+                {"name":"nvme7n1p1", "maj:min":"259:7", "rm":false, "size":"3.5T", "ro":false, "type":"part", "mountpoint":null},
+                {"name":"nvme7n1p9", "maj:min":"259:8", "rm":false, "size":"8M", "ro":false, "type":"part", "mountpoint":null}
+            ]
+        },
+        {"name":"nvme5n1", "maj:min":"259:4", "rm":false, "size":"3.5T", "ro":false, "type":"disk", "mountpoint":null,
+            "children": [
+                {"name":"nvme5n1p1", "maj:min":"259:5", "rm":false, "size":"3.5T", "ro":false, "type":"part", "mountpoint":null},
+                {"name":"nvme5n1p9", "maj:min":"259:6", "rm":false, "size":"8M", "ro":false, "type":"part", "mountpoint":null}
+            ]
+        },
+        {"name":"nvme9n1", "maj:min":"259:9", "rm":false, "size":"3.5T", "ro":false, "type":"disk", "mountpoint":null,
+            "children": [
+                {"name":"nvme9n1p1", "maj:min":"259:13", "rm":false, "size":"3.5T", "ro":false, "type":"part", "mountpoint":null},
+                {"name":"nvme9n1p9", "maj:min":"259:14", "rm":false, "size":"8M", "ro":false, "type":"part", "mountpoint":null}
+            ]
+        },
+        {"name":"nvme4n1", "maj:min":"259:10", "rm":false, "size":"3.5T", "ro":false, "type":"disk", "mountpoint":null,
+            "children": [
+                {"name":"nvme4n1p1", "maj:min":"259:11", "rm":false, "size":"3.5T", "ro":false, "type":"part", "mountpoint":null},
+                {"name":"nvme4n1p9", "maj:min":"259:12", "rm":false, "size":"8M", "ro":false, "type":"part", "mountpoint":null}
+            ]
+        },
+        {"name":"nvme8n1", "maj:min":"259:15", "rm":false, "size":"3.5T", "ro":false, "type":"disk", "mountpoint":null,
+            "children": [
+                {"name":"nvme8n1p1", "maj:min":"259:20", "rm":false, "size":"3.5T", "ro":false, "type":"part", "mountpoint":null},
+                {"name":"nvme8n1p9", "maj:min":"259:21", "rm":false, "size":"8M", "ro":false, "type":"part", "mountpoint":null}
+            ]
+        },
+        {"name":"nvme6n1", "maj:min":"259:16", "rm":false, "size":"3.5T", "ro":false, "type":"disk", "mountpoint":null,
+            "children": [
+                {"name":"nvme6n1p1", "maj:min":"259:17", "rm":false, "size":"3.5T", "ro":false, "type":"part", "mountpoint":null},
+                {"name":"nvme6n1p9", "maj:min":"259:18", "rm":false, "size":"8M", "ro":false, "type":"part", "mountpoint":null}
+            ]
+        },
+        {"name":"nvme3n1", "maj:min":"259:19", "rm":false, "size":"894.3G", "ro":false, "type":"disk", "mountpoint":null,
+            "children": [
+                {"name":"nvme3n1p1", "maj:min":"259:23", "rm":false, "size":"1M", "ro":false, "type":"part", "mountpoint":null},
+                {"name":"nvme3n1p2", "maj:min":"259:24", "rm":false, "size":"244M", "ro":false, "type":"part", "mountpoint":"/boot/efi"},
+                {"name":"nvme3n1p3", "maj:min":"259:25", "rm":false, "size":"488M", "ro":false, "type":"part", "mountpoint":null,
+                "children": [
+                    {"name":"md0", "maj:min":"9:0", "rm":false, "size":"487M", "ro":false, "type":"raid1", "mountpoint":"/boot"}
+                ]
+                },
+                {"name":"nvme3n1p4", "maj:min":"259:26", "rm":false, "size":"7.6G", "ro":false, "type":"part", "mountpoint":null,
+                "children": [
+                    {"name":"md1", "maj:min":"9:1", "rm":false, "size":"7.6G", "ro":false, "type":"raid1", "mountpoint":"[SWAP]"}
+                ]
+                },
+                {"name":"nvme3n1p5", "maj:min":"259:27", "rm":false, "size":"19.1G", "ro":false, "type":"part", "mountpoint":null,
+                "children": [
+                    {"name":"md2", "maj:min":"9:2", "rm":false, "size":"19.1G", "ro":false, "type":"raid1", "mountpoint":"/"}
+                ]
+                },
+                {"name":"nvme3n1p6", "maj:min":"259:28", "rm":false, "size":"866.8G", "ro":false, "type":"part", "mountpoint":null}
+            ]
+        },
+        {"name":"nvme0n1", "maj:min":"259:22", "rm":false, "size":"3.5T", "ro":false, "type":"disk", "mountpoint":null,
+            "children": [
+                {"name":"nvme0n1p1", "maj:min":"259:29", "rm":false, "size":"3.5T", "ro":false, "type":"part", "mountpoint":null},
+                {"name":"nvme0n1p9", "maj:min":"259:30", "rm":false, "size":"8M", "ro":false, "type":"part", "mountpoint":null}
+            ]
+        },
+        {"name":"nvme2n1", "maj:min":"259:31", "rm":false, "size":"894.3G", "ro":false, "type":"disk", "mountpoint":null,
+            "children": [
+                {"name":"nvme2n1p1", "maj:min":"259:32", "rm":false, "size":"1M", "ro":false, "type":"part", "mountpoint":null},
+                {"name":"nvme2n1p2", "maj:min":"259:33", "rm":false, "size":"244M", "ro":false, "type":"part", "mountpoint":null},
+                {"name":"nvme2n1p3", "maj:min":"259:34", "rm":false, "size":"488M", "ro":false, "type":"part", "mountpoint":null,
+                "children": [
+                    {"name":"md0", "maj:min":"9:0", "rm":false, "size":"487M", "ro":false, "type":"raid1", "mountpoint":"/boot"}
+                ]
+                },
+                {"name":"nvme2n1p4", "maj:min":"259:35", "rm":false, "size":"7.6G", "ro":false, "type":"part", "mountpoint":null,
+                "children": [
+                    {"name":"md1", "maj:min":"9:1", "rm":false, "size":"7.6G", "ro":false, "type":"raid1", "mountpoint":"[SWAP]"}
+                ]
+                },
+                {"name":"nvme2n1p5", "maj:min":"259:36", "rm":false, "size":"19.1G", "ro":false, "type":"part", "mountpoint":null,
+                "children": [
+                    {"name":"md2", "maj:min":"9:2", "rm":false, "size":"19.1G", "ro":false, "type":"raid1", "mountpoint":"/"}
+                ]
+                },
+                {"name":"nvme2n1p6", "maj:min":"259:37", "rm":false, "size":"866.8G", "ro":false, "type":"part", "mountpoint":null}
+            ]
+        }
+    ]
+}
+"#;
+
+/// A root filesystem on sda plus a btrfs RAID1 that lsblk reports on both sdb and sdc.
+pub(crate) const BTRFS_RAID1_JSON: &str = r#"{"blockdevices": [
+    {"name": "sda", "maj:min": "8:0", "rm": false, "size": "100G", "ro": false, "type": "disk", "mountpoints": [null],
+     "children": [
+        {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "100G", "ro": false, "type": "part", "mountpoints": ["/"],
+         "uuid": "0f3c2a9e-1111-4a4a-8b8b-000000000001", "fstype": "ext4", "fssize": "98G", "fsused": "20G"}
+     ]},
+    {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/data", "/srv/data"],
+     "uuid": "5d2f6c1e-4c1b-4f57-9a51-0b8f6b3c2a10", "fstype": "btrfs", "fssize": "1T", "fsused": "400G"},
+    {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/data", "/srv/data"],
+     "uuid": "5d2f6c1e-4c1b-4f57-9a51-0b8f6b3c2a10", "fstype": "btrfs", "fssize": "1T", "fsused": "400G"},
+    {"name": "sdd", "maj:min": "8:48", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]}
+]}"#;
+
+/// A SATA system disk, a USB card reader with no card (sdf, reported as 0B) and
+/// a USB stick with a mounted partition (sdg).
+pub(crate) const CARD_READER_JSON: &str = r#"{"blockdevices": [
+    {"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null],
+     "children": [
+        {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "500G", "ro": false, "type": "part", "mountpoints": ["/"]}
+     ]},
+    {"name": "sdf", "maj:min": "8:80", "rm": true, "size": "0B", "ro": false, "type": "disk", "mountpoints": [null]},
+    {"name": "sdg", "maj:min": "8:96", "rm": true, "size": "28.9G", "ro": false, "type": "disk", "mountpoints": [null],
+     "children": [
+        {"name": "sdg1", "maj:min": "8:97", "rm": true, "size": "28.9G", "ro": false, "type": "part", "mountpoints": ["/media/usb"]}
+     ]}
+]}"#;
+
+/// `/proc/partitions` from a host with NVMe, SATA and SD card disks plus md,
+/// loop, optical and device-mapper devices.
+pub(crate) const PROC_PARTITIONS: &str = "major minor  #blocks  name
+
+ 259        0 3750738264 nvme0n1
+ 259        1 3750727680 nvme0n1p1
+ 259        2       8192 nvme0n1p9
+   8        0  976762584 sda
+   8        1  976761560 sda1
+ 179        0   31166976 mmcblk0
+ 179        1     262144 mmcblk0p1
+   9        0     498688 md0
+   7        0      65536 loop0
+  11        0    1048575 sr0
+ 253        0   20971520 dm-0
+";
@@ -0,0 +1,227 @@
+use crate::{BlockDevice, BlockDevices, DeviceDiff, DeviceType};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// The latest snapshot of each host in a fleet, keyed by hostname.
+///
+/// Queries walk every host's whole tree. A fleet serializes as a JSON object
+/// mapping each hostname to its snapshot, so it can be persisted and loaded
+/// with `serde_json`.
+///
+/// # Examples
+///
+/// ```
+/// use blockdev::{Fleet, parse_lsblk};
+///
+/// let mut fleet = Fleet::new();
+/// fleet.insert(
+///     "db01".to_string(),
+///     parse_lsblk(r#"{"blockdevices": [
+///         {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+///          "serial": "S3Z9NB0K123456"}
+///     ]}"#).unwrap(),
+/// );
+///
+/// let hits = fleet.find_serial("S3Z9NB0K123456");
+/// assert_eq!(hits[0].0, "db01");
+/// assert_eq!(hits[0].1.name, "sda");
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(transparent)]
+pub struct Fleet {
+    hosts: BTreeMap<String, BlockDevices>,
+}
+
+impl Fleet {
+    /// Creates an empty fleet.
+    #[must_use]
+    pub fn new() -> Self {
+        Fleet::default()
+    }
+
+    /// Stores the snapshot of `hostname`, replacing any previous one.
+    ///
+    /// Returns the [`DeviceDiff`] from the replaced snapshot to `devices`, or
+    /// `None` if the host is new.
+    pub fn insert(&mut self, hostname: String, devices: BlockDevices) -> Option<DeviceDiff> {
+        let diff = self.hosts.get(&hostname).map(|old| old.diff(&devices));
+        self.hosts.insert(hostname, devices);
+        diff
+    }
+
+    /// Removes `hostname` from the fleet and returns its snapshot.
+    pub fn remove(&mut self, hostname: &str) -> Option<BlockDevices> {
+        self.hosts.remove(hostname)
+    }
+
+    /// Returns the snapshot of `hostname`.
+    #[must_use]
+    pub fn get(&self, hostname: &str) -> Option<&BlockDevices> {
+        self.hosts.get(hostname)
+    }
+
+    /// Returns the hostnames in the fleet, sorted.
+    #[must_use]
+    pub fn hosts(&self) -> Vec<&str> {
+        self.hosts.keys().map(String::as_str).collect()
+    }
+
+    /// Returns the number of hosts in the fleet.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.hosts.len()
+    }
+
+    /// Returns `true` if the fleet has no hosts.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.hosts.is_empty()
+    }
+
+    /// Iterates over each hostname and its snapshot, sorted by hostname.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &BlockDevices)> {
+        self.hosts
+            .iter()
+            .map(|(host, devices)| (host.as_str(), devices))
+    }
+
+    /// Returns every device whose `serial` is `serial`, together with its
+    /// hostname, sorted by hostname and then in tree order.
+    ///
+    /// lsblk reports a partition's serial as that of its disk, so partitions
+    /// are skipped. The `SERIAL` column is only requested by `get_devices_extended`.
+    #[must_use]
+    pub fn find_serial(&self, serial: &str) -> Vec<(&str, &BlockDevice)> {
+        let mut hits = Vec::new();
+        for (host, devices) in self.iter() {
+            let mut seen = HashSet::new();
+            hits.extend(
+                devices
+                    .iter_all()
+                    .filter(|d| d.device_type != DeviceType::Part)
+                    .filter(|d| d.serial.as_deref() == Some(serial))
+                    .filter(|d| seen.insert(d.name.as_str()))
+                    .map(|d| (host, d)),
+            );
+        }
+        hits
+    }
+
+    /// Returns the combined size in bytes of the top-level disks of every host.
+    ///
+    /// Loop devices, optical drives and other top-level devices that are not
+    /// disks are not counted, so RAID and LVM capacity is not counted twice.
+    #[must_use]
+    pub fn total_capacity(&self) -> u64 {
+        self.hosts
+            .values()
+            .flat_map(BlockDevices::iter)
+            .filter(|d| d.is_disk())
+            .map(|d| d.size)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_lsblk;
+
+    const DB01_JSON: &str = r#"{"blockdevices": [
+        {"name": "sda", "maj:min": "8:0", "rm": false, "size": 1000000000000, "ro": false, "type": "disk", "mountpoints": [null],
+         "serial": "S3Z9NB0K123456",
+         "children": [
+            {"name": "sda1", "maj:min": "8:1", "rm": false, "size": 1000000000000, "ro": false, "type": "part", "mountpoints": ["/"],
+             "serial": "S3Z9NB0K123456"}
+         ]},
+        {"name": "loop0", "maj:min": "7:0", "rm": false, "size": 67108864, "ro": true, "type": "loop", "mountpoints": ["/snap/core"]}
+    ]}"#;
+
+    const DB02_JSON: &str = r#"{"blockdevices": [
+        {"name": "nvme0n1", "maj:min": "259:0", "rm": false, "size": 2000000000000, "ro": false, "type": "disk", "mountpoints": [null],
+         "serial": "S4EWNX0R123456"},
+        {"name": "nvme1n1", "maj:min": "259:1", "rm": false, "size": 2000000000000, "ro": false, "type": "disk", "mountpoints": [null],
+         "serial": "S4EWNX0R654321"}
+    ]}"#;
+
+    // web01 received the disk pulled from db01.
+    const WEB01_JSON: &str = r#"{"blockdevices": [
+        {"name": "sdb", "maj:min": "8:16", "rm": false, "size": 1000000000000, "ro": false, "type": "disk", "mountpoints": [null],
+         "serial": "S3Z9NB0K123456"}
+    ]}"#;
+
+    fn fleet() -> Fleet {
+        let mut fleet = Fleet::new();
+        for (host, json) in [
+            ("web01", WEB01_JSON),
+            ("db01", DB01_JSON),
+            ("db02", DB02_JSON),
+        ] {
+            let devices = parse_lsblk(json).expect("Failed to parse JSON");
+            assert!(fleet.insert(host.to_string(), devices).is_none());
+        }
+        fleet
+    }
+
+    #[test]
+    fn test_find_serial() {
+        let fleet = fleet();
+        assert_eq!(fleet.hosts(), vec!["db01", "db02", "web01"]);
+        assert_eq!(fleet.len(), 3);
+
+        let hits: Vec<(&str, &str)> = fleet
+            .find_serial("S3Z9NB0K123456")
+            .into_iter()
+            .map(|(host, d)| (host, d.name.as_str()))
+            .collect();
+        assert_eq!(hits, vec![("db01", "sda"), ("web01", "sdb")]);
+        assert_eq!(fleet.find_serial("S4EWNX0R654321")[0].1.name, "nvme1n1");
+        assert!(fleet.find_serial("missing").is_empty());
+
+        assert_eq!(fleet.total_capacity(), 6_000_000_000_000);
+        assert_eq!(Fleet::new().total_capacity(), 0);
+    }
+
+    #[test]
+    fn test_replace_host() {
+        let mut fleet = fleet();
+        let mut db02 = fleet.get("db02").unwrap().clone();
+        db02.blockdevices.pop();
+        db02.blockdevices[0].mountpoints = vec![Some("/var/lib/pgsql".to_string())];
+
+        let diff = fleet.insert("db02".to_string(), db02).unwrap();
+        assert!(diff.added.is_empty());
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].name, "nvme1n1");
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].after.name, "nvme0n1");
+        assert_eq!(fleet.len(), 3);
+        assert!(fleet.find_serial("S4EWNX0R654321").is_empty());
+
+        let unchanged = fleet.get("web01").unwrap().clone();
+        assert!(
+            fleet
+                .insert("web01".to_string(), unchanged)
+                .unwrap()
+                .is_empty()
+        );
+
+        assert!(fleet.remove("db01").is_some());
+        assert!(fleet.remove("db01").is_none());
+        assert_eq!(fleet.hosts(), vec!["db02", "web01"]);
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let fleet = fleet();
+        let json = serde_json::to_string(&fleet).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["db02"]["blockdevices"][1]["name"], "nvme1n1");
+        let reloaded: Fleet = serde_json::from_str(&json).unwrap();
+        assert_eq!(reloaded, fleet);
+        assert_eq!(
+            reloaded.iter().map(|(host, _)| host).collect::<Vec<_>>(),
+            vec!["db01", "db02", "web01"]
+        );
+    }
+}
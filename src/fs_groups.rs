@@ -0,0 +1,160 @@
+use crate::{BlockDevice, BlockDevices};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// The devices that carry one filesystem, grouped by filesystem UUID, as found
+/// by [`BlockDevices::filesystem_groups`].
+///
+/// Most filesystems live on a single device. Multi-device filesystems such as
+/// btrfs RAID1 report the same UUID, size, usage and mountpoints on every
+/// member, so they should be counted once per group rather than once per device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FsGroup<'a> {
+    /// The filesystem UUID shared by all members.
+    pub uuid: String,
+    /// The devices carrying the filesystem, once each, in tree order.
+    pub members: Vec<&'a BlockDevice>,
+    /// The distinct active mountpoints of all members, in tree order.
+    pub mountpoints: Vec<String>,
+    /// `true` if the filesystem spans more than one block device.
+    pub spans_devices: bool,
+}
+
+impl<'a> FsGroup<'a> {
+    /// Returns the member whose `fssize` and `fsused` describe the whole
+    /// filesystem, which is the first member that has both.
+    #[must_use]
+    pub fn representative(&self) -> Option<&'a BlockDevice> {
+        self.members
+            .iter()
+            .copied()
+            .find(|d| d.fssize.is_some() && d.fsused.is_some())
+    }
+}
+
+impl BlockDevices {
+    /// Groups the devices in the tree by their filesystem UUID, in order of first
+    /// appearance.
+    ///
+    /// Devices without a UUID, or with an empty one, are not part of any group.
+    /// This needs the `UUID` column, which `get_devices_extended` requests.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/data"],
+    ///      "uuid": "5d2f6c1e-4c1b-4f57-9a51-0b8f6b3c2a10", "fstype": "btrfs"},
+    ///     {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/data"],
+    ///      "uuid": "5d2f6c1e-4c1b-4f57-9a51-0b8f6b3c2a10", "fstype": "btrfs"}
+    /// ]}"#).unwrap();
+    ///
+    /// let groups = devices.filesystem_groups();
+    /// assert_eq!(groups.len(), 1);
+    /// assert!(groups[0].spans_devices);
+    /// assert_eq!(groups[0].mountpoints, vec!["/data"]);
+    /// ```
+    #[must_use]
+    pub fn filesystem_groups(&self) -> Vec<FsGroup<'_>> {
+        let mut groups: Vec<FsGroup<'_>> = Vec::new();
+        let mut seen = HashSet::new();
+        for device in self.iter_all() {
+            let Some(uuid) = device.uuid.as_deref().map(str::trim) else {
+                continue;
+            };
+            if uuid.is_empty() || !seen.insert(device.name.as_str()) {
+                continue;
+            }
+            let index = match groups.iter().position(|g| g.uuid == uuid) {
+                Some(index) => index,
+                None => {
+                    groups.push(FsGroup {
+                        uuid: uuid.to_string(),
+                        members: Vec::new(),
+                        mountpoints: Vec::new(),
+                        spans_devices: false,
+                    });
+                    groups.len() - 1
+                }
+            };
+            let group = &mut groups[index];
+            group.members.push(device);
+            for mountpoint in device.active_mountpoints() {
+                if !group.mountpoints.iter().any(|m| m == mountpoint) {
+                    group.mountpoints.push(mountpoint.to_string());
+                }
+            }
+        }
+        for group in &mut groups {
+            group.spans_devices = group.members.len() > 1;
+        }
+        groups
+    }
+
+    /// Like [`BlockDevices::device_for_path`], but returns the whole filesystem
+    /// group of the device that contains `path`, so every member of a
+    /// multi-device filesystem is reported instead of an arbitrary one.
+    ///
+    /// Returns `None` if no device contains `path` or that device has no UUID;
+    /// use [`BlockDevices::device_for_path`] in that case.
+    #[must_use]
+    pub fn filesystem_group_for_path(&self, path: &Path) -> Option<FsGroup<'_>> {
+        let uuid = self.device_for_path(path)?.uuid.as_deref()?.trim();
+        self.filesystem_groups()
+            .into_iter()
+            .find(|group| group.uuid == uuid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::BTRFS_RAID1_JSON;
+
+    #[test]
+    fn test_filesystem_groups() {
+        let devices = crate::parse_lsblk(BTRFS_RAID1_JSON).expect("Failed to parse JSON");
+        let groups = devices.filesystem_groups();
+        assert_eq!(groups.len(), 2);
+
+        let root = &groups[0];
+        assert_eq!(root.members.len(), 1);
+        assert_eq!(root.mountpoints, vec!["/"]);
+        assert!(!root.spans_devices);
+
+        let btrfs = &groups[1];
+        assert_eq!(btrfs.uuid, "5d2f6c1e-4c1b-4f57-9a51-0b8f6b3c2a10");
+        let names: Vec<&str> = btrfs.members.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["sdb", "sdc"]);
+        assert_eq!(btrfs.mountpoints, vec!["/data", "/srv/data"]);
+        assert!(btrfs.spans_devices);
+        assert_eq!(btrfs.representative().unwrap().name, "sdb");
+
+        let sample = crate::parse_lsblk(crate::fixtures::SAMPLE_JSON).unwrap();
+        assert!(sample.filesystem_groups().is_empty());
+    }
+
+    #[test]
+    fn test_filesystem_group_for_path() {
+        let devices = crate::parse_lsblk(BTRFS_RAID1_JSON).expect("Failed to parse JSON");
+        let group = devices
+            .filesystem_group_for_path(Path::new("/srv/data/file"))
+            .unwrap();
+        assert_eq!(group.members.len(), 2);
+        assert_eq!(
+            devices
+                .filesystem_group_for_path(Path::new("/etc"))
+                .unwrap()
+                .members[0]
+                .name,
+            "sda1"
+        );
+        assert!(
+            devices
+                .filesystem_group_for_path(Path::new("relative"))
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{BlockDevError, BlockDevice, DeviceType, IterAll};
+use std::collections::{BTreeMap, HashSet};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::{Path, PathBuf};
+
+/// The directory holding one entry per running process.
+const PROC: &str = "/proc";
+
+/// A process that holds a device open, found by [`BlockDevice::open_handles`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProcessRef {
+    /// The process ID.
+    pub pid: u32,
+    /// The command name from `/proc/<pid>/comm`, or empty if it could not be read.
+    pub comm: String,
+}
+
+/// The device node paths and device numbers that identify `device` and
+/// everything stacked on it.
+fn targets(device: &BlockDevice) -> (HashSet<PathBuf>, HashSet<u64>) {
+    let mut paths = HashSet::new();
+    let mut numbers = HashSet::new();
+    for d in IterAll::subtree(device) {
+        paths.insert(PathBuf::from("/dev").join(&d.name));
+        if matches!(d.device_type, DeviceType::Lvm | DeviceType::Crypt) {
+            paths.insert(d.path());
+        }
+        numbers.insert(d.device_number());
+    }
+    (paths, numbers)
+}
+
+/// Returns `true` if the open file behind `fd` is one of the targets, either by
+/// the path the kernel reports or by the device number of the node it resolves to.
+fn fd_matches(fd: &Path, paths: &HashSet<PathBuf>, numbers: &HashSet<u64>) -> bool {
+    if std::fs::read_link(fd).is_ok_and(|target| paths.contains(&target)) {
+        return true;
+    }
+    // Device-mapper nodes opened through /dev/mapper show up as /dev/dm-N.
+    std::fs::metadata(fd).is_ok_and(|metadata| {
+        metadata.file_type().is_block_device() && numbers.contains(&metadata.rdev())
+    })
+}
+
+impl BlockDevice {
+    /// Returns every process that has this device or anything stacked on it
+    /// (partitions, RAID arrays, LVM volumes, crypt mappings) open, sorted by PID.
+    ///
+    /// This scans the `fd` directory of every process in `/proc`, so it finds
+    /// handles that do not show up as mounts, such as a running `dd` or a
+    /// virtual machine with the raw disk attached. Each process is listed once,
+    /// however many handles it holds. Processes whose `fd` directory cannot be
+    /// read, usually because they belong to another user or exited during the
+    /// scan, are skipped; run as root to see everything. This only reads
+    /// `/proc` and is available on Unix only.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::IoError`] if `/proc` itself cannot be listed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use blockdev::BlockDevice;
+    /// # let device = BlockDevice::default();
+    /// for process in device.open_handles().expect("Failed to scan /proc") {
+    ///     println!("{} ({}) has {} open", process.comm, process.pid, device.name);
+    /// }
+    /// ```
+    pub fn open_handles(&self) -> Result<Vec<ProcessRef>, BlockDevError> {
+        self.open_handles_in(Path::new(PROC))
+    }
+
+    /// Returns `true` if [`BlockDevice::open_handles`] finds any process.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::IoError`] if `/proc` itself cannot be listed.
+    pub fn is_busy(&self) -> Result<bool, BlockDevError> {
+        Ok(!self.open_handles()?.is_empty())
+    }
+
+    fn open_handles_in(&self, proc_root: &Path) -> Result<Vec<ProcessRef>, BlockDevError> {
+        let (paths, numbers) = targets(self);
+        let mut holders = BTreeMap::new();
+        for entry in std::fs::read_dir(proc_root).map_err(BlockDevError::IoError)? {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            let Some(pid) = entry
+                .file_name()
+                .to_str()
+                .and_then(|n| n.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+                continue;
+            };
+            let holds_device = fds
+                .filter_map(Result::ok)
+                .any(|fd| fd_matches(&fd.path(), &paths, &numbers));
+            if holds_device {
+                let comm = std::fs::read_to_string(entry.path().join("comm"))
+                    .map(|comm| comm.trim().to_string())
+                    .unwrap_or_default();
+                holders.insert(pid, comm);
+            }
+        }
+        Ok(holders
+            .into_iter()
+            .map(|(pid, comm)| ProcessRef { pid, comm })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_lsblk;
+    use std::os::unix::fs::{PermissionsExt, symlink};
+
+    /// Creates `<root>/<pid>/comm` and one `fd` symlink per target.
+    fn fake_process(root: &Path, pid: u32, comm: &str, fds: &[&str]) {
+        let fd_dir = root.join(pid.to_string()).join("fd");
+        std::fs::create_dir_all(&fd_dir).unwrap();
+        std::fs::write(root.join(pid.to_string()).join("comm"), format!("{comm}\n")).unwrap();
+        for (n, target) in fds.iter().enumerate() {
+            symlink(target, fd_dir.join(n.to_string())).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_open_handles_in() {
+        let root =
+            std::env::temp_dir().join(format!("blockdev-open-handles-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        fake_process(&root, 1, "systemd", &["/dev/null", "socket:[1234]"]);
+        fake_process(&root, 4242, "dd", &["/dev/zero", "/dev/sdb"]);
+        fake_process(
+            &root,
+            300,
+            "qemu-system-x86",
+            &["/dev/sdb2", "/dev/sdb2", "/dev/kvm"],
+        );
+        fake_process(&root, 77, "cryptsetup", &["/dev/mapper/luks-backup"]);
+        fake_process(&root, 88, "blkid", &["/dev/sdc"]);
+        std::fs::create_dir_all(root.join("self")).unwrap();
+        std::fs::write(root.join("uptime"), "1.0 1.0\n").unwrap();
+
+        // A process whose fd directory cannot be read is skipped.
+        fake_process(&root, 999, "secret", &["/dev/sdb"]);
+        let hidden = root.join("999/fd");
+        std::fs::set_permissions(&hidden, std::fs::Permissions::from_mode(0o000)).unwrap();
+        let readable_by_us = std::fs::read_dir(&hidden).is_ok();
+
+        let devices = parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "sdb1", "maj:min": "8:17", "rm": false, "size": "512G", "ro": false, "type": "part", "mountpoints": [null]},
+                    {"name": "sdb2", "maj:min": "8:18", "rm": false, "size": "512G", "ro": false, "type": "part", "mountpoints": [null],
+                     "children": [
+                        {"name": "luks-backup", "maj:min": "253:3", "rm": false, "size": "512G", "ro": false, "type": "crypt", "mountpoints": [null]}
+                     ]}
+                 ]}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+        let sdb = &devices.blockdevices[0];
+        let mut expected = vec![
+            ProcessRef {
+                pid: 77,
+                comm: "cryptsetup".to_string(),
+            },
+            ProcessRef {
+                pid: 300,
+                comm: "qemu-system-x86".to_string(),
+            },
+            ProcessRef {
+                pid: 4242,
+                comm: "dd".to_string(),
+            },
+        ];
+        // Root can read the directory regardless of its mode.
+        if readable_by_us {
+            expected.insert(
+                2,
+                ProcessRef {
+                    pid: 999,
+                    comm: "secret".to_string(),
+                },
+            );
+        }
+        assert_eq!(sdb.open_handles_in(&root).unwrap(), expected);
+
+        let sdb1 = sdb.find_child("sdb1").unwrap();
+        assert!(sdb1.open_handles_in(&root).unwrap().is_empty());
+
+        assert!(matches!(
+            sdb.open_handles_in(&root.join("missing")),
+            Err(BlockDevError::IoError(_))
+        ));
+
+        std::fs::set_permissions(&hidden, std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
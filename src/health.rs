@@ -0,0 +1,324 @@
+use crate::{BlockDevice, BlockDevices, DeviceType, PartitionRole, SystemPolicy};
+use std::collections::HashSet;
+use std::fmt;
+
+/// More top-level disks than this carrying the root filesystem is reported as
+/// [`HealthWarning::TooManySystemDevices`]. Two allows a mirrored root.
+const MAX_SYSTEM_DEVICES: usize = 2;
+
+/// A potential storage problem found by [`BlockDevices::health_check`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HealthWarning {
+    /// The named device has the `ro` flag set. Devices stacked on it are not
+    /// reported separately, and neither are optical drives.
+    ReadOnlyDevice(String),
+    /// The second device is mounted inside a mountpoint of the first, hiding
+    /// part of its filesystem. See [`BlockDevices::overlapping_mountpoints`].
+    OverlappingMountpoints(String, String),
+    /// More than one disk reports this World Wide Name, which usually means
+    /// multipath without device-mapper or a cloned identity.
+    DuplicateWwn(String),
+    /// The named EFI System Partition on a system disk is not mounted, so
+    /// bootloader updates will not reach it.
+    UnmountedSystemPartition(String),
+    /// This many top-level disks carry the root filesystem.
+    TooManySystemDevices(usize),
+}
+
+impl fmt::Display for HealthWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HealthWarning::ReadOnlyDevice(name) => write!(f, "{name} is read-only"),
+            HealthWarning::OverlappingMountpoints(outer, inner) => {
+                write!(f, "{inner} is mounted inside a mountpoint of {outer}")
+            }
+            HealthWarning::DuplicateWwn(wwn) => {
+                write!(f, "WWN {wwn} is reported by more than one disk")
+            }
+            HealthWarning::UnmountedSystemPartition(name) => {
+                write!(
+                    f,
+                    "EFI System Partition {name} on the system disk is not mounted"
+                )
+            }
+            HealthWarning::TooManySystemDevices(count) => write!(
+                f,
+                "{count} disks carry the root filesystem, expected at most {MAX_SYSTEM_DEVICES}"
+            ),
+        }
+    }
+}
+
+/// The result of [`BlockDevices::health_check`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HealthReport {
+    /// Every problem found, grouped by kind in the order of [`HealthWarning`]'s
+    /// variants and in tree order within each kind.
+    pub warnings: Vec<HealthWarning>,
+}
+
+impl HealthReport {
+    /// Returns `true` if no problems were found.
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.warnings.is_empty()
+    }
+
+    /// Returns the number of problems found.
+    #[must_use]
+    pub fn warning_count(&self) -> usize {
+        self.warnings.len()
+    }
+}
+
+/// Reports `device` if it is read-only, otherwise looks further down.
+fn read_only_roots(device: &BlockDevice, seen: &mut HashSet<String>, out: &mut Vec<HealthWarning>) {
+    if device.ro && device.device_type != DeviceType::Rom {
+        if seen.insert(device.name.clone()) {
+            out.push(HealthWarning::ReadOnlyDevice(device.name.clone()));
+        }
+        return;
+    }
+    for child in device.children_iter() {
+        read_only_roots(child, seen, out);
+    }
+}
+
+impl BlockDevices {
+    /// Checks the snapshot for common storage problems and returns them with
+    /// human-readable messages.
+    ///
+    /// The checks are: read-only devices, nested mountpoints on different
+    /// devices, disks sharing a WWN, unmounted EFI System Partitions on a
+    /// system disk, and more than two disks carrying `/`. The WWN and EFI checks
+    /// need the `WWN` and `PARTTYPE` columns, which `get_devices_extended`
+    /// requests; without them they find nothing. System disks are those of
+    /// [`BlockDevice::is_system`]; see [`health_check_with`](Self::health_check_with).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{HealthWarning, parse_lsblk};
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/"]},
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": true, "type": "disk", "mountpoints": [null]}
+    /// ]}"#).unwrap();
+    ///
+    /// let report = devices.health_check();
+    /// assert_eq!(report.warnings, vec![HealthWarning::ReadOnlyDevice("sdb".to_string())]);
+    /// assert_eq!(report.warnings[0].to_string(), "sdb is read-only");
+    /// ```
+    #[must_use]
+    pub fn health_check(&self) -> HealthReport {
+        self.health_check_with(&SystemPolicy::default())
+    }
+
+    /// Like [`health_check`](Self::health_check), with system disks defined
+    /// by `policy`, so that the EFI and system disk count checks also cover
+    /// disks holding only boot filesystems or swap under
+    /// [`SystemPolicy::strict`].
+    #[must_use]
+    pub fn health_check_with(&self, policy: &SystemPolicy) -> HealthReport {
+        let mut warnings = Vec::new();
+
+        let mut seen = HashSet::new();
+        for device in self.iter() {
+            read_only_roots(device, &mut seen, &mut warnings);
+        }
+
+        for (outer, inner, _) in self.overlapping_mountpoints() {
+            warnings.push(HealthWarning::OverlappingMountpoints(
+                outer.name.clone(),
+                inner.name.clone(),
+            ));
+        }
+
+        let mut wwns: Vec<(&str, HashSet<&str>)> = Vec::new();
+        for device in self
+            .iter_all()
+            .filter(|d| d.device_type != DeviceType::Part)
+        {
+            let Some(wwn) = device.wwn.as_deref().map(str::trim) else {
+                continue;
+            };
+            if wwn.is_empty() {
+                continue;
+            }
+            match wwns.iter_mut().find(|(w, _)| *w == wwn) {
+                Some((_, names)) => {
+                    names.insert(device.name.as_str());
+                }
+                None => wwns.push((wwn, HashSet::from([device.name.as_str()]))),
+            }
+        }
+        for (wwn, names) in wwns {
+            if names.len() > 1 {
+                warnings.push(HealthWarning::DuplicateWwn(wwn.to_string()));
+            }
+        }
+
+        let system_disks: Vec<&BlockDevice> =
+            self.iter().filter(|d| d.is_system_with(policy)).collect();
+        for disk in &system_disks {
+            for partition in disk.children_iter() {
+                if partition.partition_role() == Some(PartitionRole::EfiSystem)
+                    && !partition.is_mounted()
+                {
+                    warnings.push(HealthWarning::UnmountedSystemPartition(
+                        partition.name.clone(),
+                    ));
+                }
+            }
+        }
+
+        if system_disks.len() > MAX_SYSTEM_DEVICES {
+            warnings.push(HealthWarning::TooManySystemDevices(system_disks.len()));
+        }
+
+        HealthReport { warnings }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    const ESP: &str = "c12a7328-f81f-11d2-ba4b-00a0c93ec93b";
+
+    fn check(json: &str) -> Vec<HealthWarning> {
+        parse_lsblk(json)
+            .expect("Failed to parse JSON")
+            .health_check()
+            .warnings
+    }
+
+    #[test]
+    fn test_read_only_device() {
+        let warnings = check(
+            r#"{"blockdevices": [
+                {"name": "mmcblk0", "maj:min": "179:0", "rm": true, "size": "32G", "ro": true, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "mmcblk0p1", "maj:min": "179:1", "rm": true, "size": "32G", "ro": true, "type": "part", "mountpoints": [null]}
+                 ]},
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1T", "ro": true, "type": "part", "mountpoints": [null]}
+                 ]},
+                {"name": "sr0", "maj:min": "11:0", "rm": true, "size": "1024M", "ro": true, "type": "rom", "mountpoints": [null]}
+            ]}"#,
+        );
+        assert_eq!(
+            warnings,
+            vec![
+                HealthWarning::ReadOnlyDevice("mmcblk0".to_string()),
+                HealthWarning::ReadOnlyDevice("sda1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_overlapping_mountpoints() {
+        let warnings = check(
+            r#"{"blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/data"]},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/data/db"]}
+            ]}"#,
+        );
+        assert_eq!(
+            warnings,
+            vec![HealthWarning::OverlappingMountpoints(
+                "sda".to_string(),
+                "sdb".to_string()
+            )]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "sdb is mounted inside a mountpoint of sda"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_wwn() {
+        let warnings = check(
+            r#"{"blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "wwn": "0x5000c500a1b2c3d4",
+                 "children": [
+                    {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": [null],
+                     "wwn": "0x5000c500a1b2c3d4"}
+                 ]},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "wwn": "0x5000c500a1b2c3d4"},
+                {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "wwn": "0x5000c500ffffffff"},
+                {"name": "sdd", "maj:min": "8:48", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "wwn": ""},
+                {"name": "sde", "maj:min": "8:64", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "wwn": ""}
+            ]}"#,
+        );
+        assert_eq!(
+            warnings,
+            vec![HealthWarning::DuplicateWwn(
+                "0x5000c500a1b2c3d4".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_unmounted_system_partition() {
+        let json = format!(
+            r#"{{"blockdevices": [
+                {{"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                  "children": [
+                    {{"name": "sda1", "maj:min": "8:1", "rm": false, "size": "512M", "ro": false, "type": "part", "mountpoints": [null], "parttype": "{ESP}"}},
+                    {{"name": "sda2", "maj:min": "8:2", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": ["/"]}}
+                  ]}},
+                {{"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                  "children": [
+                    {{"name": "sdb1", "maj:min": "8:17", "rm": false, "size": "512M", "ro": false, "type": "part", "mountpoints": [null], "parttype": "{ESP}"}}
+                  ]}}
+            ]}}"#
+        );
+        assert_eq!(
+            check(&json),
+            vec![HealthWarning::UnmountedSystemPartition("sda1".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_too_many_system_devices() {
+        let disk = |name: &str, minor: u32| {
+            format!(
+                r#"{{"name": "{name}", "maj:min": "8:{minor}", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                  "children": [{{"name": "md0", "maj:min": "9:0", "rm": false, "size": "1T", "ro": false, "type": "raid1", "mountpoints": ["/"]}}]}}"#
+            )
+        };
+        let json = format!(
+            r#"{{"blockdevices": [{}, {}, {}]}}"#,
+            disk("sda", 0),
+            disk("sdb", 16),
+            disk("sdc", 32)
+        );
+        let warnings = check(&json);
+        assert_eq!(warnings, vec![HealthWarning::TooManySystemDevices(3)]);
+        assert_eq!(
+            warnings[0].to_string(),
+            "3 disks carry the root filesystem, expected at most 2"
+        );
+    }
+
+    #[test]
+    fn test_health_report() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let report = devices.health_check();
+        // md0 at /boot contains nvme3n1p2 at /boot/efi.
+        assert_eq!(report.warning_count(), 1);
+        assert!(!report.is_healthy());
+        assert!(BlockDevices::default().health_check().is_healthy());
+        assert_eq!(HealthReport::default().warning_count(), 0);
+    }
+}
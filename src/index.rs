@@ -0,0 +1,199 @@
+use crate::{BlockDevice, BlockDevices, MajMin};
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+/// A lookup table over every device in a [`BlockDevices`] tree.
+///
+/// Built once by [`BlockDevices::index`] so that repeated lookups are `O(1)`
+/// instead of walking the tree each time. The index borrows the snapshot it was
+/// built from.
+///
+/// Devices that appear under several parents (such as a RAID array spanning two
+/// disks) are stored once, using their first occurrence in depth-first order.
+/// Their names are available from [`DeviceIndex::duplicates`].
+#[derive(Debug, Clone)]
+pub struct DeviceIndex<'a> {
+    by_name: HashMap<&'a str, &'a BlockDevice>,
+    by_maj_min: HashMap<MajMin, &'a BlockDevice>,
+    duplicates: Vec<&'a str>,
+}
+
+impl<'a> DeviceIndex<'a> {
+    /// Returns the device with the given name anywhere in the tree.
+    #[must_use]
+    pub fn get_by_name(&self, name: &str) -> Option<&'a BlockDevice> {
+        self.by_name.get(name).copied()
+    }
+
+    /// Returns the device with the given major and minor numbers anywhere in the tree.
+    #[must_use]
+    pub fn get_by_majmin(&self, maj_min: MajMin) -> Option<&'a BlockDevice> {
+        self.by_maj_min.get(&maj_min).copied()
+    }
+
+    /// Returns `true` if a device with the given name exists anywhere in the tree.
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.by_name.contains_key(name)
+    }
+
+    /// Returns the names of devices that appear more than once in the tree, in
+    /// the order their second occurrence was found.
+    #[must_use]
+    pub fn duplicates(&self) -> &[&'a str] {
+        &self.duplicates
+    }
+
+    /// Returns the number of distinct device names in the index.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.by_name.len()
+    }
+
+    /// Returns `true` if the index contains no devices.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+}
+
+impl BlockDevices {
+    /// Builds a [`DeviceIndex`] for fast lookups by name or device number.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{parse_lsblk, MajMin};
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "children": [
+    ///         {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "500G", "ro": false, "type": "part", "mountpoints": ["/data"]}
+    ///      ]}
+    /// ]}"#).unwrap();
+    ///
+    /// let index = devices.index();
+    /// assert_eq!(index.get_by_majmin(MajMin { major: 8, minor: 1 }).unwrap().name, "sda1");
+    /// assert!(index.contains("sda"));
+    /// ```
+    #[must_use]
+    pub fn index(&self) -> DeviceIndex<'_> {
+        let mut index = DeviceIndex {
+            by_name: HashMap::new(),
+            by_maj_min: HashMap::new(),
+            duplicates: Vec::new(),
+        };
+        for device in self.iter_all() {
+            match index.by_name.entry(device.name.as_str()) {
+                Entry::Vacant(entry) => {
+                    entry.insert(device);
+                    index.by_maj_min.entry(device.maj_min).or_insert(device);
+                }
+                Entry::Occupied(_) => {
+                    if !index.duplicates.contains(&device.name.as_str()) {
+                        index.duplicates.push(device.name.as_str());
+                    }
+                }
+            }
+        }
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::{DeviceType, parse_lsblk};
+
+    #[test]
+    fn test_index_sample() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let index = devices.index();
+
+        // 44 occurrences, minus the second copy of each of md0-md2.
+        assert_eq!(index.len(), 41);
+        assert_eq!(index.duplicates(), &["md0", "md1", "md2"]);
+
+        let md0 = index.get_by_name("md0").expect("md0 should be indexed");
+        assert_eq!(md0.device_type, DeviceType::Raid1);
+        assert!(std::ptr::eq(
+            md0,
+            devices
+                .find_by_name("nvme3n1")
+                .unwrap()
+                .find_child("nvme3n1p3")
+                .unwrap()
+                .find_child("md0")
+                .unwrap()
+        ));
+        assert_eq!(index.get_by_majmin(md0.maj_min).unwrap().name, "md0");
+        assert_eq!(
+            index
+                .get_by_majmin(MajMin {
+                    major: 259,
+                    minor: 0
+                })
+                .unwrap()
+                .name,
+            "nvme1n1"
+        );
+
+        assert!(index.contains("nvme9n1p9"));
+        assert!(!index.contains("sda"));
+        assert!(index.get_by_name("sda").is_none());
+        assert!(BlockDevices::default().index().is_empty());
+    }
+
+    #[test]
+    fn test_index_large_tree() {
+        let blockdevices = (0..1000u32)
+            .map(|disk| BlockDevice {
+                name: format!("sd{disk}"),
+                maj_min: MajMin {
+                    major: 8,
+                    minor: disk * 5,
+                },
+                device_type: DeviceType::Disk,
+                children: Some(
+                    (1..5u32)
+                        .map(|part| BlockDevice {
+                            name: format!("sd{disk}p{part}"),
+                            maj_min: MajMin {
+                                major: 8,
+                                minor: disk * 5 + part,
+                            },
+                            size: u64::from(part),
+                            device_type: DeviceType::Part,
+                            ..Default::default()
+                        })
+                        .collect(),
+                ),
+                ..Default::default()
+            })
+            .collect();
+        let devices = BlockDevices {
+            blockdevices,
+            meta: None,
+            warnings: Vec::new(),
+        };
+
+        let index = devices.index();
+        assert_eq!(index.len(), 5000);
+        assert!(index.duplicates().is_empty());
+        for disk in (0..1000u32).step_by(37) {
+            for part in 1..5u32 {
+                let name = format!("sd{disk}p{part}");
+                let device = index
+                    .get_by_name(&name)
+                    .expect("partition should be indexed");
+                assert_eq!(device.size, u64::from(part));
+                let maj_min = MajMin {
+                    major: 8,
+                    minor: disk * 5 + part,
+                };
+                assert_eq!(index.get_by_majmin(maj_min).unwrap().name, name);
+            }
+        }
+    }
+}
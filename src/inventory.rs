@@ -0,0 +1,280 @@
+use crate::BlockDevices;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// The label used for a missing or blank vendor, model or transport.
+const UNKNOWN: &str = "unknown";
+
+/// The disks of one vendor and model in an [`InventoryReport`].
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct InventoryGroup {
+    /// The vendor with lsblk's padding removed, or `unknown`.
+    pub vendor: String,
+    /// The model, or `unknown`.
+    pub model: String,
+    /// The number of disks.
+    pub units: usize,
+    /// The combined size of the disks in bytes.
+    pub capacity_bytes: u64,
+    /// The serial numbers of the disks that report one, in tree order.
+    pub serials: Vec<String>,
+}
+
+/// The disks reached over one transport in an [`InventoryReport`].
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TransportTotal {
+    /// The transport, such as `nvme` or `sata`, or `unknown`.
+    pub transport: String,
+    /// The number of disks.
+    pub units: usize,
+    /// The combined size of the disks in bytes.
+    pub capacity_bytes: u64,
+}
+
+/// The result of [`BlockDevices::inventory_report`].
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct InventoryReport {
+    /// One entry per vendor and model, sorted by vendor and then model.
+    pub groups: Vec<InventoryGroup>,
+    /// One entry per transport, sorted by transport.
+    pub transports: Vec<TransportTotal>,
+    /// The number of disks in the report.
+    pub total_units: usize,
+    /// The combined size of the disks in the report in bytes.
+    pub total_capacity_bytes: u64,
+}
+
+/// Trims `value` and falls back to [`UNKNOWN`] if nothing is left.
+fn label(value: Option<&str>) -> String {
+    match value.map(str::trim) {
+        Some(value) if !value.is_empty() => value.to_string(),
+        _ => UNKNOWN.to_string(),
+    }
+}
+
+/// Formats a byte count in decimal units, as drive capacities are sold, e.g.
+/// `3.8TB` or `960GB`.
+fn format_decimal_size(bytes: u64) -> String {
+    const UNITS: [&str; 7] = ["B", "KB", "MB", "GB", "TB", "PB", "EB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1000.0 && unit < UNITS.len() - 1 {
+        value /= 1000.0;
+        unit += 1;
+    }
+    let rounded = format!("{value:.1}");
+    let rounded = rounded.strip_suffix(".0").unwrap_or(&rounded);
+    format!("{rounded}{}", UNITS[unit])
+}
+
+/// Escapes `|` so that `cell` stays in its markdown table column.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+impl InventoryReport {
+    /// Renders the report as two GitHub-flavored markdown tables, one row per
+    /// vendor and model followed by a total, then one row per transport.
+    ///
+    /// Capacities use decimal units (`1TB` is 10¹² bytes), as drive
+    /// capacities are sold.
+    #[must_use]
+    pub fn to_markdown_table(&self) -> String {
+        let mut out = String::from(
+            "| Vendor | Model | Units | Capacity | Serials |\n|---|---|--:|--:|---|\n",
+        );
+        for group in &self.groups {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} |",
+                cell(&group.vendor),
+                cell(&group.model),
+                group.units,
+                format_decimal_size(group.capacity_bytes),
+                cell(&group.serials.join(", "))
+            );
+        }
+        let _ = writeln!(
+            out,
+            "| **Total** | | {} | {} | |",
+            self.total_units,
+            format_decimal_size(self.total_capacity_bytes)
+        );
+
+        out.push_str("\n| Transport | Units | Capacity |\n|---|--:|--:|\n");
+        for transport in &self.transports {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} |",
+                cell(&transport.transport),
+                transport.units,
+                format_decimal_size(transport.capacity_bytes)
+            );
+        }
+        out
+    }
+}
+
+impl BlockDevices {
+    /// Counts the top-level physical disks by vendor and model and by
+    /// transport, summing their capacity, for procurement and capacity
+    /// planning.
+    ///
+    /// [Synthetic](crate::BlockDevice::is_synthetic) devices and top-level
+    /// devices that are not disks, such as optical drives, are left out. A
+    /// missing or blank vendor, model or transport is reported as `unknown`.
+    /// Needs the `VENDOR`, `MODEL`, `SERIAL` and `TRAN` columns, which
+    /// `get_devices_extended` requests.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "nvme0n1", "maj:min": "259:0", "rm": false, "size": 3840755982336, "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "model": "SAMSUNG MZQL23T8HCLS-00A07", "serial": "S64HNE0R100001", "tran": "nvme"},
+    ///     {"name": "nvme1n1", "maj:min": "259:1", "rm": false, "size": 3840755982336, "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "model": "SAMSUNG MZQL23T8HCLS-00A07", "serial": "S64HNE0R100002", "tran": "nvme"}
+    /// ]}"#).unwrap();
+    ///
+    /// let report = devices.inventory_report();
+    /// assert_eq!(report.groups[0].units, 2);
+    /// assert_eq!(report.groups[0].vendor, "unknown");
+    /// assert_eq!(report.total_capacity_bytes, 7_681_511_964_672);
+    /// ```
+    #[must_use]
+    pub fn inventory_report(&self) -> InventoryReport {
+        let mut groups: BTreeMap<(String, String), InventoryGroup> = BTreeMap::new();
+        let mut transports: BTreeMap<String, TransportTotal> = BTreeMap::new();
+        let mut report = InventoryReport::default();
+        for disk in self.iter().filter(|d| d.is_disk() && !d.is_synthetic()) {
+            let vendor = label(disk.vendor.as_deref());
+            let model = label(disk.model.as_deref());
+            let group = groups
+                .entry((vendor.clone(), model.clone()))
+                .or_insert_with(|| InventoryGroup {
+                    vendor,
+                    model,
+                    units: 0,
+                    capacity_bytes: 0,
+                    serials: Vec::new(),
+                });
+            group.units += 1;
+            group.capacity_bytes += disk.size;
+            if let Some(serial) = disk.serial.as_deref().map(str::trim) {
+                if !serial.is_empty() {
+                    group.serials.push(serial.to_string());
+                }
+            }
+
+            let transport = label(disk.tran.as_deref());
+            let total = transports
+                .entry(transport.clone())
+                .or_insert_with(|| TransportTotal {
+                    transport,
+                    units: 0,
+                    capacity_bytes: 0,
+                });
+            total.units += 1;
+            total.capacity_bytes += disk.size;
+
+            report.total_units += 1;
+            report.total_capacity_bytes += disk.size;
+        }
+        report.groups = groups.into_values().collect();
+        report.transports = transports.into_values().collect();
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_lsblk;
+
+    const INVENTORY_JSON: &str = r#"{"blockdevices": [
+        {"name": "nvme0n1", "maj:min": "259:0", "rm": false, "size": 3840755982336, "ro": false, "type": "disk", "mountpoints": [null],
+         "model": "SAMSUNG MZQL23T8HCLS-00A07", "serial": "S64HNE0R100001", "tran": "nvme",
+         "children": [
+            {"name": "nvme0n1p1", "maj:min": "259:1", "rm": false, "size": 3840755982336, "ro": false, "type": "part", "mountpoints": ["/"],
+             "model": "SAMSUNG MZQL23T8HCLS-00A07", "serial": "S64HNE0R100001"}
+         ]},
+        {"name": "nvme1n1", "maj:min": "259:2", "rm": false, "size": 3840755982336, "ro": false, "type": "disk", "mountpoints": [null],
+         "model": "SAMSUNG MZQL23T8HCLS-00A07", "serial": "S64HNE0R100002", "tran": "nvme"},
+        {"name": "sda", "maj:min": "8:0", "rm": false, "size": 960197124096, "ro": false, "type": "disk", "mountpoints": [null],
+         "vendor": "ATA     ", "model": "INTEL SSDSC2KB960G8", "serial": "PHYF0001", "tran": "sata"},
+        {"name": "sdb", "maj:min": "8:16", "rm": false, "size": 960197124096, "ro": false, "type": "disk", "mountpoints": [null],
+         "vendor": "ATA     ", "model": "INTEL SSDSC2KB960G8", "serial": "PHYF0002", "tran": "sata"},
+        {"name": "sdc", "maj:min": "8:32", "rm": true, "size": 64000000000, "ro": false, "type": "disk", "mountpoints": [null],
+         "vendor": "  ", "tran": "usb"},
+        {"name": "sr0", "maj:min": "11:0", "rm": true, "size": 1073741312, "ro": false, "type": "rom", "mountpoints": [null],
+         "vendor": "HL-DT-ST", "model": "DVDRAM GUD0N", "tran": "sata"},
+        {"name": "loop0", "maj:min": "7:0", "rm": false, "size": 67108864, "ro": true, "type": "loop", "mountpoints": ["/snap/core"]}
+    ]}"#;
+
+    #[test]
+    fn test_inventory_report() {
+        let devices = parse_lsblk(INVENTORY_JSON).expect("Failed to parse JSON");
+        let report = devices.inventory_report();
+        assert_eq!(report.total_units, 5);
+        assert_eq!(report.total_capacity_bytes, 9_665_906_212_864);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["groups"].as_array().unwrap().len(), 3);
+        assert_eq!(json["groups"][0]["vendor"], "ATA");
+        assert_eq!(json["groups"][0]["model"], "INTEL SSDSC2KB960G8");
+        assert_eq!(json["groups"][0]["units"], 2);
+        assert_eq!(json["groups"][0]["capacity_bytes"], 1_920_394_248_192_u64);
+        assert_eq!(
+            json["groups"][0]["serials"],
+            serde_json::json!(["PHYF0001", "PHYF0002"])
+        );
+        assert_eq!(json["groups"][1]["vendor"], "unknown");
+        assert_eq!(json["groups"][1]["model"], "SAMSUNG MZQL23T8HCLS-00A07");
+        assert_eq!(json["groups"][2]["model"], "unknown");
+        assert_eq!(json["groups"][2]["serials"], serde_json::json!([]));
+        assert_eq!(
+            json["transports"],
+            serde_json::json!([
+                {"transport": "nvme", "units": 2, "capacity_bytes": 7_681_511_964_672_u64},
+                {"transport": "sata", "units": 2, "capacity_bytes": 1_920_394_248_192_u64},
+                {"transport": "usb", "units": 1, "capacity_bytes": 64_000_000_000_u64},
+            ])
+        );
+
+        assert_eq!(
+            BlockDevices::default().inventory_report(),
+            InventoryReport::default()
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_table() {
+        let devices = parse_lsblk(INVENTORY_JSON).expect("Failed to parse JSON");
+        let expected = "\
+| Vendor | Model | Units | Capacity | Serials |
+|---|---|--:|--:|---|
+| ATA | INTEL SSDSC2KB960G8 | 2 | 1.9TB | PHYF0001, PHYF0002 |
+| unknown | SAMSUNG MZQL23T8HCLS-00A07 | 2 | 7.7TB | S64HNE0R100001, S64HNE0R100002 |
+| unknown | unknown | 1 | 64GB |  |
+| **Total** | | 5 | 9.7TB | |
+
+| Transport | Units | Capacity |
+|---|--:|--:|
+| nvme | 2 | 7.7TB |
+| sata | 2 | 1.9TB |
+| usb | 1 | 64GB |
+";
+        assert_eq!(devices.inventory_report().to_markdown_table(), expected);
+    }
+
+    #[test]
+    fn test_format_decimal_size() {
+        assert_eq!(format_decimal_size(0), "0B");
+        assert_eq!(format_decimal_size(960_197_124_096), "960.2GB");
+        assert_eq!(format_decimal_size(28_000_000_000_000), "28TB");
+        assert_eq!(cell("a|b"), "a\\|b");
+    }
+}
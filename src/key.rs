@@ -0,0 +1,434 @@
+use crate::{BlockDevice, BlockDevices, DeviceType, MajMin};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A totally ordered identity for a device, suitable as a `BTreeMap` or
+/// `HashMap` key and for storing in configuration files. Built by
+/// [`BlockDevice::key`].
+///
+/// The first usable identifier wins, in this order:
+///
+/// 1. [`Wwn`](DeviceKey::Wwn): the World Wide Name, for devices other than partitions.
+/// 2. [`Serial`](DeviceKey::Serial): the serial number, for devices other than
+///    partitions, with the namespace ID for NVMe namespaces that share their
+///    controller's serial.
+/// 3. [`PartUuid`](DeviceKey::PartUuid): the partition UUID from the partition
+///    table, which lsblk only reports for partitions.
+/// 4. [`Uuid`](DeviceKey::Uuid): the filesystem or container UUID.
+/// 5. [`DeviceNumber`](DeviceKey::DeviceNumber): the major and minor numbers
+///    together with the kernel name.
+///
+/// lsblk reports a partition's WWN and serial as those of its disk, so
+/// partitions skip the first two. Missing and empty columns are skipped, and
+/// `WWN`, `SERIAL`, `PARTUUID` and `UUID` are only requested by
+/// `get_devices_extended`.
+///
+/// Keys built from WWNs, serials and UUIDs survive reboots and re-plugging.
+/// [`DeviceNumber`](DeviceKey::DeviceNumber) keys do not: the kernel assigns
+/// device numbers and names in discovery order, so the same disk can come back
+/// as a different `sdX` with different numbers. Members of a multi-device
+/// filesystem or RAID array share one UUID, so partitions are told apart by
+/// their PARTUUID; whole-disk members without a WWN or serial share one key.
+///
+/// Keys order by kind in the order above, then by value.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceKey {
+    /// The device's World Wide Name, such as `0x5002538e40a1b2c3`.
+    Wwn(String),
+    /// The device's serial number.
+    Serial {
+        /// The serial number as reported by lsblk.
+        serial: String,
+        /// The NVMe namespace ID, or `None` for other devices.
+        namespace: Option<u32>,
+    },
+    /// The partition UUID from the partition table.
+    PartUuid(String),
+    /// The filesystem or container UUID.
+    Uuid(String),
+    /// The major and minor numbers and kernel name, which are not stable across boots.
+    DeviceNumber {
+        /// The major and minor device numbers.
+        maj_min: MajMin,
+        /// The kernel name, such as `sda`.
+        name: String,
+    },
+}
+
+impl fmt::Display for DeviceKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeviceKey::Wwn(wwn) => write!(f, "wwn:{wwn}"),
+            DeviceKey::Serial {
+                serial,
+                namespace: None,
+            } => write!(f, "serial:{serial}"),
+            DeviceKey::Serial {
+                serial,
+                namespace: Some(namespace),
+            } => write!(f, "serial:{serial}/ns{namespace}"),
+            DeviceKey::PartUuid(partuuid) => write!(f, "partuuid:{partuuid}"),
+            DeviceKey::Uuid(uuid) => write!(f, "uuid:{uuid}"),
+            DeviceKey::DeviceNumber { maj_min, name } => write!(f, "devnum:{maj_min}/{name}"),
+        }
+    }
+}
+
+/// Returns the trimmed column value, or `None` if it is missing or empty.
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|v| !v.is_empty())
+}
+
+/// Returns the serial, model and NVMe namespace ID that identify a whole
+/// device, or `None` if the serial or model is missing or empty.
+fn serial_and_model(device: &BlockDevice) -> Option<(&str, &str, Option<u32>)> {
+    Some((
+        non_empty(device.serial.as_deref())?,
+        non_empty(device.model.as_deref())?,
+        device.nvme_namespace_id(),
+    ))
+}
+
+impl BlockDevice {
+    /// Returns this device's [`DeviceKey`], following the precedence documented there.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{BlockDevice, DeviceKey, DeviceType};
+    ///
+    /// let disk = BlockDevice {
+    ///     name: "nvme0n2".to_string(),
+    ///     device_type: DeviceType::Disk,
+    ///     serial: Some("S4EWNX0R123456".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(disk.key().to_string(), "serial:S4EWNX0R123456/ns2");
+    /// ```
+    #[must_use]
+    pub fn key(&self) -> DeviceKey {
+        if self.device_type != DeviceType::Part {
+            if let Some(wwn) = non_empty(self.wwn.as_deref()) {
+                return DeviceKey::Wwn(wwn.to_string());
+            }
+            if let Some(serial) = non_empty(self.serial.as_deref()) {
+                return DeviceKey::Serial {
+                    serial: serial.to_string(),
+                    namespace: self.nvme_namespace_id(),
+                };
+            }
+        }
+        if let Some(partuuid) = non_empty(self.partuuid.as_deref()) {
+            return DeviceKey::PartUuid(partuuid.to_string());
+        }
+        if let Some(uuid) = non_empty(self.uuid.as_deref()) {
+            return DeviceKey::Uuid(uuid.to_string());
+        }
+        DeviceKey::DeviceNumber {
+            maj_min: self.maj_min,
+            name: self.name.clone(),
+        }
+    }
+
+    /// Returns `true` if `self` and `other` describe the same device, such as
+    /// the same disk in two lsblk runs whose other fields differ.
+    ///
+    /// The first identifier both devices have decides, in this order:
+    ///
+    /// 1. `maj_min`, unless either is `0:0`, the value of devices built with
+    ///    `Default` rather than read from lsblk.
+    /// 2. `wwn`, for devices other than partitions.
+    /// 3. `serial` and `model` together, with the NVMe namespace ID, for
+    ///    devices other than partitions.
+    /// 4. `name`, compared exactly.
+    ///
+    /// As for [`BlockDevice::key`], partitions skip WWN and serial because
+    /// lsblk reports those of their disk, and missing or empty columns are
+    /// skipped. Device numbers are only stable within one boot, so comparing
+    /// snapshots across reboots should use [`BlockDevice::key`] instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{BlockDevice, MajMin};
+    ///
+    /// let before = BlockDevice {
+    ///     name: "sda".to_string(),
+    ///     maj_min: MajMin { major: 8, minor: 0 },
+    ///     ..Default::default()
+    /// };
+    /// let after = BlockDevice {
+    ///     name: "sdb".to_string(),
+    ///     ..before.clone()
+    /// };
+    /// assert!(before.is_identical_to(&after));
+    /// ```
+    #[must_use]
+    pub fn is_identical_to(&self, other: &BlockDevice) -> bool {
+        let unset = MajMin::default();
+        if self.maj_min != unset && other.maj_min != unset {
+            return self.maj_min == other.maj_min;
+        }
+        if self.device_type != DeviceType::Part && other.device_type != DeviceType::Part {
+            let wwns = (
+                non_empty(self.wwn.as_deref()),
+                non_empty(other.wwn.as_deref()),
+            );
+            if let (Some(a), Some(b)) = wwns {
+                return a == b;
+            }
+            if let (Some(a), Some(b)) = (serial_and_model(self), serial_and_model(other)) {
+                return a == b;
+            }
+        }
+        self.name == other.name
+    }
+}
+
+impl BlockDevices {
+    /// Returns every device in the tree keyed by [`BlockDevice::key`].
+    ///
+    /// Devices that share a key, such as a RAID array listed under each of its
+    /// members or whole-disk members of a multi-device filesystem that have no
+    /// WWN or serial, are stored once, using their first occurrence in
+    /// depth-first order.
+    #[must_use]
+    pub fn as_map(&self) -> BTreeMap<DeviceKey, &BlockDevice> {
+        let mut map = BTreeMap::new();
+        for device in self.iter_all() {
+            map.entry(device.key()).or_insert(device);
+        }
+        map
+    }
+
+    /// Removes top-level devices that are
+    /// [identical](BlockDevice::is_identical_to) to an earlier one, keeping
+    /// the first occurrence.
+    ///
+    /// Useful after concatenating the devices of several lsblk runs.
+    pub fn deduplicate(&mut self) {
+        let mut kept: Vec<BlockDevice> = Vec::with_capacity(self.blockdevices.len());
+        for device in self.blockdevices.drain(..) {
+            if !kept.iter().any(|k| k.is_identical_to(&device)) {
+                kept.push(device);
+            }
+        }
+        self.blockdevices = kept;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::BTRFS_RAID1_JSON;
+    use crate::parse_lsblk;
+
+    const KEYED_JSON: &str = r#"{"blockdevices": [
+        {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "wwn": "0x5002538e40a1b2c3", "serial": "S3Z9NB0K123456",
+         "children": [
+            {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": [null],
+             "wwn": "0x5002538e40a1b2c3", "uuid": "a1b2c3d4-0000-4000-8000-000000000001",
+             "children": [
+                {"name": "md0", "maj:min": "9:0", "rm": false, "size": "1T", "ro": false, "type": "raid1", "mountpoints": ["/srv"]}
+             ]}
+         ]},
+        {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "wwn": " ", "serial": "WD-WCC4N1234567",
+         "children": [
+            {"name": "sdb1", "maj:min": "8:17", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": [null],
+             "serial": "WD-WCC4N1234567",
+             "children": [
+                {"name": "md0", "maj:min": "9:0", "rm": false, "size": "1T", "ro": false, "type": "raid1", "mountpoints": ["/srv"]}
+             ]}
+         ]},
+        {"name": "nvme0n1", "maj:min": "259:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "serial": "S4EWNX0R123456"},
+        {"name": "nvme0n2", "maj:min": "259:1", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "serial": "S4EWNX0R123456"}
+    ]}"#;
+
+    fn keys(devices: &BlockDevices) -> Vec<(String, String)> {
+        devices
+            .iter_all()
+            .map(|d| (d.name.clone(), d.key().to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_key_precedence() {
+        let devices = parse_lsblk(KEYED_JSON).expect("Failed to parse JSON");
+        let expected = [
+            ("sda", "wwn:0x5002538e40a1b2c3"),
+            ("sda1", "uuid:a1b2c3d4-0000-4000-8000-000000000001"),
+            ("md0", "devnum:9:0/md0"),
+            ("sdb", "serial:WD-WCC4N1234567"),
+            ("sdb1", "devnum:8:17/sdb1"),
+            ("md0", "devnum:9:0/md0"),
+            ("nvme0n1", "serial:S4EWNX0R123456/ns1"),
+            ("nvme0n2", "serial:S4EWNX0R123456/ns2"),
+        ];
+        assert_eq!(
+            keys(&devices),
+            expected
+                .iter()
+                .map(|(n, k)| ((*n).to_string(), (*k).to_string()))
+                .collect::<Vec<_>>()
+        );
+
+        let json = serde_json::to_string(&devices.blockdevices[2].key()).unwrap();
+        assert_eq!(
+            json,
+            r#"{"serial":{"serial":"S4EWNX0R123456","namespace":1}}"#
+        );
+        let round_trip: DeviceKey = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_trip, devices.blockdevices[2].key());
+        let md0 = DeviceKey::DeviceNumber {
+            maj_min: MajMin { major: 9, minor: 0 },
+            name: "md0".to_string(),
+        };
+        let json = serde_json::to_string(&md0).unwrap();
+        assert_eq!(json, r#"{"device_number":{"maj_min":"9:0","name":"md0"}}"#);
+        assert_eq!(serde_json::from_str::<DeviceKey>(&json).unwrap(), md0);
+    }
+
+    #[test]
+    fn test_as_map() {
+        let devices = parse_lsblk(KEYED_JSON).expect("Failed to parse JSON");
+        let map = devices.as_map();
+        assert_eq!(map.len(), 7);
+        let names: Vec<&str> = map.values().map(|d| d.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["sda", "nvme0n1", "nvme0n2", "sdb", "sda1", "sdb1", "md0"]
+        );
+        assert_eq!(
+            map[&DeviceKey::Wwn("0x5002538e40a1b2c3".to_string())].name,
+            "sda"
+        );
+        assert!(BlockDevices::default().as_map().is_empty());
+    }
+
+    #[test]
+    fn test_as_map_shared_uuid() {
+        const RAID_MEMBERS_JSON: &str = r#"{"blockdevices": [
+            {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+             "children": [
+                {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": [null],
+                 "uuid": "3f0e7c1a-5b2d-4e8f-9a6c-1d2e3f4a5b6c", "partuuid": "9c1f2e3d-0001-4a4b-8c8d-000000000001",
+                 "fstype": "linux_raid_member"}
+             ]},
+            {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+             "children": [
+                {"name": "sdb1", "maj:min": "8:17", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": [null],
+                 "uuid": "3f0e7c1a-5b2d-4e8f-9a6c-1d2e3f4a5b6c", "partuuid": "9c1f2e3d-0002-4a4b-8c8d-000000000002",
+                 "fstype": "linux_raid_member"}
+             ]}
+        ]}"#;
+
+        // RAID member partitions share their UUID but not their PARTUUID.
+        let mut devices = parse_lsblk(RAID_MEMBERS_JSON).expect("Failed to parse JSON");
+        let map = devices.as_map();
+        assert_eq!(map.len(), 4);
+        assert_eq!(
+            map[&DeviceKey::PartUuid("9c1f2e3d-0002-4a4b-8c8d-000000000002".to_string())].name,
+            "sdb1"
+        );
+
+        // Without PARTUUID, as from an lsblk that lacks the column, they collapse.
+        for disk in &mut devices.blockdevices {
+            disk.children.as_mut().unwrap()[0].partuuid = None;
+        }
+        let map = devices.as_map();
+        assert_eq!(map.len(), 3);
+        assert_eq!(
+            map[&DeviceKey::Uuid("3f0e7c1a-5b2d-4e8f-9a6c-1d2e3f4a5b6c".to_string())].name,
+            "sda1"
+        );
+
+        // Whole-disk btrfs members without a WWN or serial share one key, too.
+        let devices = parse_lsblk(BTRFS_RAID1_JSON).expect("Failed to parse JSON");
+        let map = devices.as_map();
+        assert_eq!(map.len(), 4);
+        assert_eq!(
+            map[&DeviceKey::Uuid("5d2f6c1e-4c1b-4f57-9a51-0b8f6b3c2a10".to_string())].name,
+            "sdb"
+        );
+    }
+
+    fn device(name: &str, major: u32, minor: u32) -> BlockDevice {
+        BlockDevice {
+            name: name.to_string(),
+            maj_min: MajMin { major, minor },
+            device_type: DeviceType::Disk,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_is_identical_to_precedence() {
+        // Device numbers win over everything else.
+        let sda = BlockDevice {
+            wwn: Some("0x5000c500a1b2c3d4".to_string()),
+            ..device("sda", 8, 0)
+        };
+        assert!(sda.is_identical_to(&device("sdb", 8, 0)));
+        assert!(!sda.is_identical_to(&BlockDevice {
+            wwn: sda.wwn.clone(),
+            ..device("sda", 8, 16)
+        }));
+
+        // Without device numbers, the WWN decides, then serial and model.
+        let wwn = |name: &str, wwn: &str| BlockDevice {
+            wwn: Some(wwn.to_string()),
+            ..device(name, 0, 0)
+        };
+        assert!(wwn("sda", "0x1").is_identical_to(&wwn("sdc", "0x1")));
+        assert!(!wwn("sda", "0x1").is_identical_to(&wwn("sda", "0x2")));
+        let serial = |name: &str, serial: &str, model: &str| BlockDevice {
+            serial: Some(serial.to_string()),
+            model: Some(model.to_string()),
+            ..device(name, 0, 0)
+        };
+        assert!(serial("sda", "S1", "PM9A3").is_identical_to(&serial("sdc", "S1", "PM9A3")));
+        assert!(!serial("sda", "S1", "PM9A3").is_identical_to(&serial("sda", "S1", "S4510")));
+        assert!(
+            !serial("nvme0n1", "S1", "PM9A3").is_identical_to(&serial("nvme0n2", "S1", "PM9A3"))
+        );
+        // A blank WWN falls through to serial and model.
+        let blank_wwn = BlockDevice {
+            wwn: Some(" ".to_string()),
+            ..serial("sdc", "S1", "PM9A3")
+        };
+        assert!(blank_wwn.is_identical_to(&serial("sda", "S1", "PM9A3")));
+
+        // Otherwise only the name is left; partitions never use WWN or serial.
+        assert!(device("sda", 0, 0).is_identical_to(&device("sda", 8, 0)));
+        assert!(!device("sda", 0, 0).is_identical_to(&device("sdb", 0, 0)));
+        let part = |name: &str| BlockDevice {
+            device_type: DeviceType::Part,
+            ..wwn(name, "0x1")
+        };
+        assert!(!part("sda1").is_identical_to(&part("sda2")));
+    }
+
+    #[test]
+    fn test_deduplicate() {
+        let mut devices = parse_lsblk(KEYED_JSON).expect("Failed to parse JSON");
+        let second_run = parse_lsblk(KEYED_JSON).expect("Failed to parse JSON");
+        devices.blockdevices.extend(second_run.blockdevices);
+        devices.blockdevices.push(device("sdz", 8, 0));
+        assert_eq!(devices.blockdevices.len(), 9);
+
+        devices.deduplicate();
+        let names: Vec<&str> = devices.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["sda", "sdb", "nvme0n1", "nvme0n2"]);
+        assert!(devices.blockdevices[0].children.is_some());
+
+        let mut empty = BlockDevices::default();
+        empty.deduplicate();
+        assert_eq!(empty, BlockDevices::default());
+    }
+}
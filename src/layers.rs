@@ -0,0 +1,299 @@
+use crate::raid::is_raid_type;
+use crate::{BlockDevice, BlockDevices, DeviceType};
+use std::collections::HashSet;
+
+/// One layer of a storage stack, as returned by [`BlockDevice::stack_layers`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LayerKind {
+    /// A partition of a disk.
+    Partition,
+    /// A software RAID array of any level.
+    Raid,
+    /// A dm-crypt mapping, such as an opened LUKS container.
+    Crypt,
+    /// An LVM logical volume.
+    Lvm,
+    /// A filesystem or swap area on top of the stack.
+    Filesystem,
+}
+
+impl LayerKind {
+    /// Returns the layer a device of `device_type` forms, or `None` for disks,
+    /// loop devices and other bottom-of-stack types.
+    fn of(device_type: &DeviceType) -> Option<LayerKind> {
+        match device_type {
+            DeviceType::Part => Some(LayerKind::Partition),
+            DeviceType::Crypt => Some(LayerKind::Crypt),
+            DeviceType::Lvm => Some(LayerKind::Lvm),
+            t if is_raid_type(t) => Some(LayerKind::Raid),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the layers formed by `chain`, a lineage from a top-level device
+/// down to the device whose stack is wanted.
+fn chain_layers(chain: &[&BlockDevice]) -> Vec<LayerKind> {
+    let mut layers: Vec<LayerKind> = chain
+        .iter()
+        .filter_map(|d| LayerKind::of(&d.device_type))
+        .collect();
+    if chain
+        .last()
+        .is_some_and(|d| d.has_filesystem() || d.is_mounted())
+    {
+        layers.push(LayerKind::Filesystem);
+    }
+    layers
+}
+
+/// Returns every lineage of `name` in `ctx`, from a top-level device down to
+/// the device itself, in tree order.
+fn lineages<'a>(ctx: &'a BlockDevices, name: &str) -> Vec<Vec<&'a BlockDevice>> {
+    ctx.iter_with_parent_path()
+        .filter(|(_, device)| device.name == name)
+        .map(|(mut ancestors, device)| {
+            ancestors.push(device);
+            ancestors
+        })
+        .collect()
+}
+
+/// Whether a mounted filesystem reported by
+/// [`BlockDevices::encryption_coverage`] sits on an encrypted layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptionCoverage<'a> {
+    /// The device holding the filesystem.
+    pub device: &'a BlockDevice,
+    /// The device's non-null mountpoints.
+    pub mountpoints: Vec<&'a str>,
+    /// The layers of the device's first lineage, as
+    /// [`BlockDevice::stack_layers`] returns them.
+    pub layers: Vec<LayerKind>,
+    /// `true` if every lineage of the device includes a crypt layer, so no
+    /// data written to the filesystem reaches a disk unencrypted.
+    pub encrypted: bool,
+}
+
+impl BlockDevice {
+    /// Returns the layers of the storage stack from the disk up to this
+    /// device, ending with [`LayerKind::Filesystem`] if this device holds a
+    /// filesystem or swap area or is mounted.
+    ///
+    /// The order tells the two common encrypted setups apart: LUKS below LVM
+    /// (`[Partition, Crypt, Lvm, Filesystem]`) encrypts every logical volume,
+    /// while LUKS on a logical volume (`[Partition, Lvm, Crypt, Filesystem]`)
+    /// encrypts only that volume. Disks and other bottom-of-stack devices add
+    /// no layer.
+    ///
+    /// The stack is taken from the first occurrence of this device's name in
+    /// `ctx`; a device stacked on several others, such as a RAID array, has
+    /// the same layers above each of them. If the device is not in `ctx`,
+    /// only its own layers are returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{LayerKind, parse_lsblk};
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "children": [
+    ///         {"name": "sda2", "maj:min": "8:2", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": [null],
+    ///          "children": [
+    ///             {"name": "luks-root", "maj:min": "253:0", "rm": false, "size": "1T", "ro": false, "type": "crypt", "mountpoints": ["/"]}
+    ///          ]}
+    ///      ]}
+    /// ]}"#).unwrap();
+    ///
+    /// let root = devices.iter_all().find(|d| d.name == "luks-root").unwrap();
+    /// assert_eq!(
+    ///     root.stack_layers(&devices),
+    ///     vec![LayerKind::Partition, LayerKind::Crypt, LayerKind::Filesystem]
+    /// );
+    /// ```
+    #[must_use]
+    pub fn stack_layers(&self, ctx: &BlockDevices) -> Vec<LayerKind> {
+        match lineages(ctx, &self.name).first() {
+            Some(chain) => chain_layers(chain),
+            None => chain_layers(&[self]),
+        }
+    }
+}
+
+impl BlockDevices {
+    /// Reports, for every mounted device in tree order, whether a crypt
+    /// layer lies between its filesystem and the disks.
+    ///
+    /// A device that appears under several parents, such as a logical volume
+    /// spanning two physical volumes, is listed once and only counts as
+    /// encrypted if every one of its lineages has a crypt layer. Swap areas
+    /// are included, with the mountpoint `[SWAP]`.
+    #[must_use]
+    pub fn encryption_coverage(&self) -> Vec<EncryptionCoverage<'_>> {
+        let mut seen = HashSet::new();
+        self.iter_all()
+            .filter(|d| d.is_mounted() && seen.insert(d.name.as_str()))
+            .map(|device| {
+                let chains = lineages(self, &device.name);
+                let layers = chains.first().map(|c| chain_layers(c)).unwrap_or_default();
+                let encrypted = chains
+                    .iter()
+                    .all(|c| c.iter().any(|d| d.device_type == DeviceType::Crypt));
+                EncryptionCoverage {
+                    device,
+                    mountpoints: device.active_mountpoints(),
+                    layers,
+                    encrypted,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    // LUKS on a partition with LVM inside: every volume is encrypted.
+    const LVM_ON_LUKS_JSON: &str = r#"{"blockdevices": [
+        {"name": "nvme0n1", "maj:min": "259:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "nvme0n1p1", "maj:min": "259:1", "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": ["/boot"],
+             "fstype": "ext4"},
+            {"name": "nvme0n1p2", "maj:min": "259:2", "rm": false, "size": "999G", "ro": false, "type": "part", "mountpoints": [null],
+             "fstype": "crypto_LUKS",
+             "children": [
+                {"name": "cryptlvm", "maj:min": "253:0", "rm": false, "size": "999G", "ro": false, "type": "crypt", "mountpoints": [null],
+                 "fstype": "LVM2_member",
+                 "children": [
+                    {"name": "vg0-root", "maj:min": "253:1", "rm": false, "size": "100G", "ro": false, "type": "lvm", "mountpoints": ["/"],
+                     "fstype": "ext4"},
+                    {"name": "vg0-swap", "maj:min": "253:2", "rm": false, "size": "16G", "ro": false, "type": "lvm", "mountpoints": ["[SWAP]"],
+                     "fstype": "swap"}
+                 ]}
+             ]}
+         ]}
+    ]}"#;
+
+    // LVM on a partition with LUKS on one volume: only /home is encrypted.
+    const LUKS_ON_LVM_JSON: &str = r#"{"blockdevices": [
+        {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": [null],
+             "fstype": "LVM2_member",
+             "children": [
+                {"name": "vg0-root", "maj:min": "253:0", "rm": false, "size": "100G", "ro": false, "type": "lvm", "mountpoints": ["/"],
+                 "fstype": "xfs"},
+                {"name": "vg0-home", "maj:min": "253:1", "rm": false, "size": "900G", "ro": false, "type": "lvm", "mountpoints": [null],
+                 "fstype": "crypto_LUKS",
+                 "children": [
+                    {"name": "home", "maj:min": "253:2", "rm": false, "size": "900G", "ro": false, "type": "crypt", "mountpoints": ["/home"],
+                     "fstype": "xfs"}
+                 ]}
+             ]}
+         ]}
+    ]}"#;
+
+    fn find<'a>(devices: &'a BlockDevices, name: &str) -> &'a BlockDevice {
+        devices.iter_all().find(|d| d.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_stack_layers_orderings() {
+        use LayerKind::{Crypt, Filesystem, Lvm, Partition};
+
+        let lvm_on_luks = parse_lsblk(LVM_ON_LUKS_JSON).expect("Failed to parse JSON");
+        let luks_on_lvm = parse_lsblk(LUKS_ON_LVM_JSON).expect("Failed to parse JSON");
+        let encrypted_below = find(&lvm_on_luks, "vg0-root").stack_layers(&lvm_on_luks);
+        let encrypted_above = find(&luks_on_lvm, "home").stack_layers(&luks_on_lvm);
+        assert_eq!(encrypted_below, vec![Partition, Crypt, Lvm, Filesystem]);
+        assert_eq!(encrypted_above, vec![Partition, Lvm, Crypt, Filesystem]);
+        assert_ne!(encrypted_below, encrypted_above);
+
+        assert_eq!(
+            find(&luks_on_lvm, "vg0-root").stack_layers(&luks_on_lvm),
+            vec![Partition, Lvm, Filesystem]
+        );
+        assert_eq!(
+            find(&luks_on_lvm, "vg0-home").stack_layers(&luks_on_lvm),
+            vec![Partition, Lvm]
+        );
+        assert!(
+            find(&luks_on_lvm, "sda")
+                .stack_layers(&luks_on_lvm)
+                .is_empty()
+        );
+
+        // A device missing from the context only contributes its own layers.
+        assert_eq!(
+            find(&luks_on_lvm, "home").stack_layers(&BlockDevices::default()),
+            vec![Crypt, Filesystem]
+        );
+    }
+
+    #[test]
+    fn test_stack_layers_raid() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        assert_eq!(
+            find(&devices, "md2").stack_layers(&devices),
+            vec![LayerKind::Partition, LayerKind::Raid, LayerKind::Filesystem]
+        );
+    }
+
+    #[test]
+    fn test_encryption_coverage() {
+        let lvm_on_luks = parse_lsblk(LVM_ON_LUKS_JSON).expect("Failed to parse JSON");
+        let coverage: Vec<(&str, bool)> = lvm_on_luks
+            .encryption_coverage()
+            .iter()
+            .map(|c| (c.mountpoints[0], c.encrypted))
+            .collect();
+        assert_eq!(
+            coverage,
+            vec![("/boot", false), ("/", true), ("[SWAP]", true)]
+        );
+
+        let luks_on_lvm = parse_lsblk(LUKS_ON_LVM_JSON).expect("Failed to parse JSON");
+        let coverage = luks_on_lvm.encryption_coverage();
+        let summary: Vec<(&str, bool)> = coverage
+            .iter()
+            .map(|c| (c.device.name.as_str(), c.encrypted))
+            .collect();
+        assert_eq!(summary, vec![("vg0-root", false), ("home", true)]);
+        assert_eq!(
+            coverage[1].layers,
+            find(&luks_on_lvm, "home").stack_layers(&luks_on_lvm)
+        );
+    }
+
+    #[test]
+    fn test_encryption_coverage_needs_every_lineage() {
+        // vg0-data spans an encrypted and a plain physical volume.
+        let devices = parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "luks-sda", "maj:min": "253:0", "rm": false, "size": "1T", "ro": false, "type": "crypt", "mountpoints": [null],
+                     "children": [
+                        {"name": "vg0-data", "maj:min": "253:2", "rm": false, "size": "2T", "ro": false, "type": "lvm", "mountpoints": ["/srv"]}
+                     ]}
+                 ]},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "vg0-data", "maj:min": "253:2", "rm": false, "size": "2T", "ro": false, "type": "lvm", "mountpoints": ["/srv"]}
+                 ]}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+        let coverage = devices.encryption_coverage();
+        assert_eq!(coverage.len(), 1);
+        assert_eq!(
+            coverage[0].layers,
+            vec![LayerKind::Crypt, LayerKind::Lvm, LayerKind::Filesystem]
+        );
+        assert!(!coverage[0].encrypted);
+    }
+}
@@ -0,0 +1,333 @@
+use crate::{BlockDevError, BlockDevice};
+
+/// The unit of the `START` column: the kernel reports partition offsets in
+/// 512-byte sectors regardless of the device's logical sector size.
+const START_SECTOR_SIZE: u64 = 512;
+
+/// The partition alignment modern partitioning tools default to.
+const DEFAULT_PARTITION_ALIGNMENT: u64 = 1024 * 1024;
+
+/// A contiguous byte range on a disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Region {
+    /// The offset of the first byte of the region from the start of the disk.
+    pub start_bytes: u64,
+    /// The length of the region in bytes.
+    pub len_bytes: u64,
+}
+
+impl Region {
+    /// Returns the offset one past the last byte of the region.
+    #[must_use]
+    pub fn end_bytes(&self) -> u64 {
+        self.start_bytes + self.len_bytes
+    }
+}
+
+/// Whether a partition starts on its disk's expected alignment boundary, as
+/// returned by [`BlockDevice::check_partition_alignment`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlignmentStatus {
+    /// The partition starts on an alignment boundary.
+    Aligned,
+    /// The partition starts `offset_bytes` past the preceding alignment boundary.
+    Misaligned {
+        /// The distance from the preceding boundary to the partition start.
+        offset_bytes: u64,
+    },
+}
+
+impl BlockDevice {
+    /// Returns the partition start alignment this device should use:
+    /// the larger of its physical sector size (`phy_sec`) and 1 MiB.
+    ///
+    /// Returns 1 MiB when the sector size is unknown, so 512e and 4Kn drives
+    /// alike get the 1 MiB alignment that partitioning tools default to.
+    #[must_use]
+    pub fn expected_partition_alignment_bytes(&self) -> u64 {
+        self.phy_sec.map_or(DEFAULT_PARTITION_ALIGNMENT, |phy_sec| {
+            phy_sec.max(DEFAULT_PARTITION_ALIGNMENT)
+        })
+    }
+
+    /// Checks whether this partition starts on the boundary given by
+    /// [`expected_partition_alignment_bytes`](Self::expected_partition_alignment_bytes).
+    ///
+    /// Returns `None` if this device is not a partition or lacks the `start`
+    /// column (see `get_devices_extended`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{AlignmentStatus, BlockDevice, DeviceType};
+    ///
+    /// let legacy = BlockDevice {
+    ///     name: "sda1".to_string(),
+    ///     device_type: DeviceType::Part,
+    ///     start: Some(63),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(
+    ///     legacy.check_partition_alignment(),
+    ///     Some(AlignmentStatus::Misaligned { offset_bytes: 63 * 512 })
+    /// );
+    /// ```
+    #[must_use]
+    pub fn check_partition_alignment(&self) -> Option<AlignmentStatus> {
+        if !self.is_partition() {
+            return None;
+        }
+        let start_bytes = self.start?.checked_mul(START_SECTOR_SIZE)?;
+        let offset_bytes = start_bytes % self.expected_partition_alignment_bytes();
+        Some(if offset_bytes == 0 {
+            AlignmentStatus::Aligned
+        } else {
+            AlignmentStatus::Misaligned { offset_bytes }
+        })
+    }
+
+    /// Returns the unpartitioned byte ranges of this disk, sorted by offset.
+    ///
+    /// Requires the `start` column on every partition (see `get_devices_extended`).
+    /// Gaps smaller than one logical sector (`log_sec`, assumed to be 512 bytes when
+    /// unknown) are ignored. The regions are raw gaps between partitions and include
+    /// space the partition table itself reserves, such as the GPT headers.
+    ///
+    /// Returns `Ok(None)` if this device is not a disk or any partition lacks a
+    /// start offset, and an empty list if a non-partition child (for example a
+    /// RAID member or LVM PV) occupies the whole disk.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::InvalidPartitionLayout`] if two partitions overlap or
+    /// a partition extends past the end of the disk.
+    pub fn free_regions(&self) -> Result<Option<Vec<Region>>, BlockDevError> {
+        if !self.is_disk() {
+            return Ok(None);
+        }
+
+        let mut extents = Vec::new();
+        for child in self.children_iter() {
+            if !child.is_partition() {
+                return Ok(Some(Vec::new()));
+            }
+            let Some(start) = child.start else {
+                return Ok(None);
+            };
+            let start_bytes = start.checked_mul(START_SECTOR_SIZE).ok_or_else(|| {
+                BlockDevError::InvalidPartitionLayout(format!(
+                    "{} starts at an out-of-range sector {start}",
+                    child.name
+                ))
+            })?;
+            extents.push((start_bytes, child.size, child.name.as_str()));
+        }
+        extents.sort_by_key(|&(start, _, _)| start);
+
+        let min_len = self.log_sec.unwrap_or(START_SECTOR_SIZE);
+        let mut regions = Vec::new();
+        let mut cursor = 0;
+        let mut previous: Option<&str> = None;
+        for (start, len, name) in extents {
+            if start < cursor {
+                return Err(BlockDevError::InvalidPartitionLayout(format!(
+                    "{name} overlaps {} on {}",
+                    previous.unwrap_or_default(),
+                    self.name
+                )));
+            }
+            if start - cursor >= min_len {
+                regions.push(Region {
+                    start_bytes: cursor,
+                    len_bytes: start - cursor,
+                });
+            }
+            cursor = start.saturating_add(len);
+            if cursor > self.size {
+                return Err(BlockDevError::InvalidPartitionLayout(format!(
+                    "{name} extends past the end of {}",
+                    self.name
+                )));
+            }
+            previous = Some(name);
+        }
+        if self.size - cursor >= min_len {
+            regions.push(Region {
+                start_bytes: cursor,
+                len_bytes: self.size - cursor,
+            });
+        }
+        Ok(Some(regions))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    const MIB: u64 = 1024 * 1024;
+
+    // A 100 MiB disk with partitions at 1-11 MiB, 11-31 MiB and 40-70 MiB.
+    const GAP_JSON: &str = r#"{
+        "blockdevices": [
+            {"name": "sda", "maj:min": "8:0", "rm": false, "size": 104857600, "ro": false, "type": "disk", "mountpoints": [null], "log-sec": 512,
+                "children": [
+                    {"name": "sda3", "maj:min": "8:3", "rm": false, "size": 31457280, "ro": false, "type": "part", "mountpoints": [null], "start": 81920, "log-sec": 512},
+                    {"name": "sda1", "maj:min": "8:1", "rm": false, "size": 10485760, "ro": false, "type": "part", "mountpoints": [null], "start": 2048, "log-sec": 512},
+                    {"name": "sda2", "maj:min": "8:2", "rm": false, "size": 20971520, "ro": false, "type": "part", "mountpoints": [null], "start": "22528", "log-sec": 512}
+                ]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_free_regions() {
+        let devices = parse_lsblk(GAP_JSON).expect("Failed to parse JSON");
+        let sda = devices.find_by_name("sda").unwrap();
+        let regions = sda.free_regions().unwrap().expect("Expected regions");
+        assert_eq!(
+            regions,
+            vec![
+                Region {
+                    start_bytes: 0,
+                    len_bytes: MIB
+                },
+                Region {
+                    start_bytes: 31 * MIB,
+                    len_bytes: 9 * MIB
+                },
+                Region {
+                    start_bytes: 70 * MIB,
+                    len_bytes: 30 * MIB
+                },
+            ]
+        );
+        assert_eq!(regions[2].end_bytes(), sda.size);
+
+        // Partitions themselves have no free-space map.
+        assert!(
+            sda.find_child("sda1")
+                .unwrap()
+                .free_regions()
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_free_regions_overlap() {
+        let json = GAP_JSON.replace("\"start\": 81920", "\"start\": 40960");
+        let devices = parse_lsblk(&json).expect("Failed to parse JSON");
+        let err = devices
+            .find_by_name("sda")
+            .unwrap()
+            .free_regions()
+            .unwrap_err();
+        assert!(matches!(err, BlockDevError::InvalidPartitionLayout(_)));
+        assert!(err.to_string().contains("sda3 overlaps sda2"));
+    }
+
+    #[test]
+    fn test_free_regions_past_end() {
+        let json = GAP_JSON.replace("\"start\": 81920", "\"start\": 200000");
+        let devices = parse_lsblk(&json).expect("Failed to parse JSON");
+        let err = devices
+            .find_by_name("sda")
+            .unwrap()
+            .free_regions()
+            .unwrap_err();
+        assert!(err.to_string().contains("sda3 extends past the end of sda"));
+    }
+
+    #[test]
+    fn test_free_regions_unknown_or_full() {
+        // The sample was captured without the START column.
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        assert!(
+            devices
+                .find_by_name("nvme0n1")
+                .unwrap()
+                .free_regions()
+                .unwrap()
+                .is_none()
+        );
+
+        let json = r#"{"blockdevices": [
+            {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                "children": [{"name": "md0", "maj:min": "9:0", "rm": false, "size": "1T", "ro": false, "type": "raid1", "mountpoints": ["/srv"]}]
+            },
+            {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]}
+        ]}"#;
+        let devices = parse_lsblk(json).expect("Failed to parse JSON");
+        assert_eq!(
+            devices.find_by_name("sdb").unwrap().free_regions().unwrap(),
+            Some(vec![])
+        );
+        let sdc = devices.find_by_name("sdc").unwrap();
+        assert_eq!(
+            sdc.free_regions().unwrap(),
+            Some(vec![Region {
+                start_bytes: 0,
+                len_bytes: sdc.size
+            }])
+        );
+    }
+
+    #[test]
+    fn test_expected_partition_alignment() {
+        let mut disk = BlockDevice::default();
+        assert_eq!(disk.expected_partition_alignment_bytes(), MIB);
+        // 4Kn and 512e drives both align to 1 MiB.
+        disk.phy_sec = Some(4096);
+        disk.log_sec = Some(4096);
+        assert_eq!(disk.expected_partition_alignment_bytes(), MIB);
+        disk.phy_sec = Some(512);
+        assert_eq!(disk.expected_partition_alignment_bytes(), MIB);
+        disk.phy_sec = Some(4 * MIB);
+        assert_eq!(disk.expected_partition_alignment_bytes(), 4 * MIB);
+    }
+
+    #[test]
+    fn test_check_partition_alignment() {
+        let devices = parse_lsblk(GAP_JSON).expect("Failed to parse JSON");
+        let sda = devices.find_by_name("sda").unwrap();
+        assert_eq!(sda.check_partition_alignment(), None);
+        for child in sda.children_iter() {
+            assert_eq!(
+                child.check_partition_alignment(),
+                Some(AlignmentStatus::Aligned),
+                "{}",
+                child.name
+            );
+        }
+
+        // A 4Kn drive partitioned with DOS-era tools, starting at sector 63.
+        let json = GAP_JSON
+            .replace("\"start\": 2048", "\"start\": 63")
+            .replace("\"log-sec\": 512", "\"log-sec\": 4096, \"phy-sec\": 4096");
+        let devices = parse_lsblk(&json).expect("Failed to parse JSON");
+        let sda1 = devices
+            .find_by_name("sda")
+            .unwrap()
+            .find_child("sda1")
+            .unwrap();
+        assert_eq!(sda1.phy_sec, Some(4096));
+        assert_eq!(sda1.expected_partition_alignment_bytes(), MIB);
+        assert_eq!(
+            sda1.check_partition_alignment(),
+            Some(AlignmentStatus::Misaligned {
+                offset_bytes: 63 * 512
+            })
+        );
+
+        // The sample was captured without the START column.
+        let sample = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        assert!(
+            sample
+                .iter_all()
+                .all(|d| d.check_partition_alignment().is_none())
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use crate::{BlockDevice, BlockDevices, DeviceType};
+
+/// The LVM layout inferred from a snapshot by [`BlockDevices::lvm_summary`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LvmSummary<'a> {
+    /// The volume groups, in the order their first volume appears in the tree.
+    pub volume_groups: Vec<VolumeGroup<'a>>,
+    /// Devices of type `lvm` whose name is not a `<vg>-<lv>` device-mapper
+    /// name, in tree order.
+    pub unparsed: Vec<&'a BlockDevice>,
+}
+
+/// A volume group and the devices it is made of.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VolumeGroup<'a> {
+    /// The volume group name, with device-mapper escaping removed.
+    pub name: String,
+    /// The logical volumes, in tree order.
+    pub logical_volumes: Vec<LogicalVolume<'a>>,
+    /// The devices directly beneath the logical volumes, usually partitions
+    /// or crypt devices, in tree order.
+    pub physical_volumes: Vec<&'a BlockDevice>,
+}
+
+/// A logical volume of a [`VolumeGroup`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogicalVolume<'a> {
+    /// The logical volume name, with device-mapper escaping removed.
+    pub name: String,
+    /// The size in bytes.
+    pub size: u64,
+    /// The non-null mountpoints.
+    pub mountpoints: Vec<&'a str>,
+    /// The device-mapper device, such as `vg0-home`.
+    pub device: &'a BlockDevice,
+}
+
+/// Splits a device-mapper name such as `my--vg-root` into volume group and
+/// logical volume names (`my-vg` and `root`).
+///
+/// device-mapper joins the two with a single `-` and doubles every `-` inside
+/// them. Returns `None` unless there is exactly one single `-` with a
+/// non-empty name on each side.
+fn split_dm_name(name: &str) -> Option<(String, String)> {
+    let mut parts = vec![String::new()];
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '-' {
+            parts.last_mut()?.push(c);
+        } else if chars.next_if_eq(&'-').is_some() {
+            parts.last_mut()?.push('-');
+        } else {
+            parts.push(String::new());
+        }
+    }
+    match <[String; 2]>::try_from(parts) {
+        Ok([vg, lv]) if !vg.is_empty() && !lv.is_empty() => Some((vg, lv)),
+        _ => None,
+    }
+}
+
+impl BlockDevices {
+    /// Infers volume groups, logical volumes and physical volumes from the
+    /// devices of type `lvm` and their device-mapper names, without running
+    /// `lvs`.
+    ///
+    /// A device beneath a logical volume that is not itself of type `lvm` is a
+    /// physical volume of its volume group. A logical volume spanning several
+    /// physical volumes appears under each of them but is listed once. Thin
+    /// pools and other internal volumes are listed like any other volume, as
+    /// lsblk reports them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "children": [
+    ///         {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": [null],
+    ///          "children": [
+    ///             {"name": "data--vg-srv", "maj:min": "253:0", "rm": false, "size": "1T", "ro": false, "type": "lvm", "mountpoints": ["/srv"]}
+    ///          ]}
+    ///      ]}
+    /// ]}"#).unwrap();
+    ///
+    /// let lvm = devices.lvm_summary();
+    /// let vg = &lvm.volume_groups[0];
+    /// assert_eq!(vg.name, "data-vg");
+    /// assert_eq!(vg.logical_volumes[0].name, "srv");
+    /// assert_eq!(vg.logical_volumes[0].mountpoints, vec!["/srv"]);
+    /// assert_eq!(vg.physical_volumes[0].name, "sda1");
+    /// ```
+    #[must_use]
+    pub fn lvm_summary(&self) -> LvmSummary<'_> {
+        let mut summary = LvmSummary::default();
+        for (parent, device) in self.iter_pairs() {
+            if device.device_type != DeviceType::Lvm {
+                continue;
+            }
+            let Some((vg_name, lv_name)) = split_dm_name(&device.name) else {
+                if !summary.unparsed.iter().any(|d| d.name == device.name) {
+                    summary.unparsed.push(device);
+                }
+                continue;
+            };
+            let index = match summary
+                .volume_groups
+                .iter()
+                .position(|vg| vg.name == vg_name)
+            {
+                Some(index) => index,
+                None => {
+                    summary.volume_groups.push(VolumeGroup {
+                        name: vg_name,
+                        logical_volumes: Vec::new(),
+                        physical_volumes: Vec::new(),
+                    });
+                    summary.volume_groups.len() - 1
+                }
+            };
+            let vg = &mut summary.volume_groups[index];
+            if !vg.logical_volumes.iter().any(|lv| lv.name == lv_name) {
+                vg.logical_volumes.push(LogicalVolume {
+                    name: lv_name,
+                    size: device.size,
+                    mountpoints: device.active_mountpoints(),
+                    device,
+                });
+            }
+            if let Some(pv) = parent.filter(|p| p.device_type != DeviceType::Lvm) {
+                if !vg.physical_volumes.iter().any(|d| d.name == pv.name) {
+                    vg.physical_volumes.push(pv);
+                }
+            }
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_lsblk;
+
+    const LVM_JSON: &str = r#"{"blockdevices": [
+        {"name": "sda", "maj:min": "8:0", "rm": false, "size": 500000000000, "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "sda1", "maj:min": "8:1", "rm": false, "size": 1073741824, "ro": false, "type": "part", "mountpoints": ["/boot"]},
+            {"name": "sda2", "maj:min": "8:2", "rm": false, "size": 498926258176, "ro": false, "type": "part", "mountpoints": [null],
+             "children": [
+                {"name": "vg0-root", "maj:min": "253:0", "rm": false, "size": 107374182400, "ro": false, "type": "lvm", "mountpoints": ["/"]},
+                {"name": "vg0-home", "maj:min": "253:1", "rm": false, "size": 751619276800, "ro": false, "type": "lvm", "mountpoints": ["/home"]}
+             ]}
+         ]},
+        {"name": "sdb", "maj:min": "8:16", "rm": false, "size": 500000000000, "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "sdb1", "maj:min": "8:17", "rm": false, "size": 500000000000, "ro": false, "type": "part", "mountpoints": [null],
+             "children": [
+                {"name": "vg0-home", "maj:min": "253:1", "rm": false, "size": 751619276800, "ro": false, "type": "lvm", "mountpoints": ["/home"]},
+                {"name": "vg0-swap", "maj:min": "253:2", "rm": false, "size": 8589934592, "ro": false, "type": "lvm", "mountpoints": ["[SWAP]"]}
+             ]}
+         ]},
+        {"name": "sdc", "maj:min": "8:32", "rm": false, "size": 250000000000, "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "sdc_crypt", "maj:min": "253:3", "rm": false, "size": 249998000000, "ro": false, "type": "crypt", "mountpoints": [null],
+             "children": [
+                {"name": "backup--vg-daily--2024", "maj:min": "253:4", "rm": false, "size": 249998000000, "ro": false, "type": "lvm", "mountpoints": [null]}
+             ]},
+            {"name": "sdc2", "maj:min": "8:34", "rm": false, "size": 1048576, "ro": false, "type": "part", "mountpoints": [null],
+             "children": [
+                {"name": "orphan", "maj:min": "253:5", "rm": false, "size": 1048576, "ro": false, "type": "lvm", "mountpoints": [null]}
+             ]}
+         ]}
+    ]}"#;
+
+    #[test]
+    fn test_split_dm_name() {
+        let split = |name| split_dm_name(name).map(|(vg, lv)| format!("{vg}/{lv}"));
+        assert_eq!(split("vg0-root").as_deref(), Some("vg0/root"));
+        assert_eq!(split("my--vg-lv--x").as_deref(), Some("my-vg/lv-x"));
+        assert_eq!(split("vg---lv").as_deref(), Some("vg-/lv"));
+        assert_eq!(split("vg0-pool_tdata").as_deref(), Some("vg0/pool_tdata"));
+        for name in ["orphan", "vg--only", "-root", "vg0-", "a-b-c", ""] {
+            assert_eq!(split(name), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_lvm_summary() {
+        let devices = parse_lsblk(LVM_JSON).expect("Failed to parse JSON");
+        let lvm = devices.lvm_summary();
+        assert_eq!(lvm.volume_groups.len(), 2);
+
+        let vg0 = &lvm.volume_groups[0];
+        assert_eq!(vg0.name, "vg0");
+        let lvs: Vec<(&str, u64, Vec<&str>)> = vg0
+            .logical_volumes
+            .iter()
+            .map(|lv| (lv.name.as_str(), lv.size, lv.mountpoints.clone()))
+            .collect();
+        assert_eq!(
+            lvs,
+            vec![
+                ("root", 107_374_182_400, vec!["/"]),
+                ("home", 751_619_276_800, vec!["/home"]),
+                ("swap", 8_589_934_592, vec!["[SWAP]"]),
+            ]
+        );
+        assert_eq!(vg0.logical_volumes[1].device.name, "vg0-home");
+        let pvs: Vec<&str> = vg0
+            .physical_volumes
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(pvs, vec!["sda2", "sdb1"]);
+
+        let backup = &lvm.volume_groups[1];
+        assert_eq!(backup.name, "backup-vg");
+        assert_eq!(backup.logical_volumes[0].name, "daily-2024");
+        assert!(backup.logical_volumes[0].mountpoints.is_empty());
+        assert_eq!(backup.physical_volumes[0].name, "sdc_crypt");
+
+        let unparsed: Vec<&str> = lvm.unparsed.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(unparsed, vec!["orphan"]);
+
+        assert_eq!(BlockDevices::default().lvm_summary(), LvmSummary::default());
+    }
+
+    #[test]
+    fn test_lvm_summary_stacked_volumes() {
+        // A thin volume sits on its pool's tdata volume; only real devices are PVs.
+        let devices = parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "nvme0n1", "maj:min": "259:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "vg1-pool_tdata", "maj:min": "253:1", "rm": false, "size": "900G", "ro": false, "type": "lvm", "mountpoints": [null],
+                     "children": [
+                        {"name": "vg1-thin1", "maj:min": "253:3", "rm": false, "size": "2T", "ro": false, "type": "lvm", "mountpoints": ["/var/lib/vm"]}
+                     ]}
+                 ]}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+        let lvm = devices.lvm_summary();
+        let vg1 = &lvm.volume_groups[0];
+        let names: Vec<&str> = vg1
+            .logical_volumes
+            .iter()
+            .map(|lv| lv.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["pool_tdata", "thin1"]);
+        assert_eq!(vg1.physical_volumes.len(), 1);
+        assert_eq!(vg1.physical_volumes[0].name, "nvme0n1");
+        assert!(lvm.unparsed.is_empty());
+    }
+}
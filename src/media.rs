@@ -0,0 +1,77 @@
+use crate::{BlockDevice, BlockDevices};
+
+impl BlockDevice {
+    /// Returns whether a removable disk has media inserted.
+    ///
+    /// Card readers and other removable-media drives stay visible with a size
+    /// of zero when their slot is empty, so a removable disk of size zero
+    /// reports `Some(false)` and one with a size `Some(true)`. Returns `None` for
+    /// devices without removable media, such as fixed disks and partitions,
+    /// where the size says nothing about a slot.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sdf", "maj:min": "8:80", "rm": true, "size": "0B", "ro": false, "type": "disk", "mountpoints": [null]}
+    /// ]}"#).unwrap();
+    /// assert_eq!(devices.blockdevices[0].media_present(), Some(false));
+    /// ```
+    #[must_use]
+    pub fn media_present(&self) -> Option<bool> {
+        (self.is_disk() && self.rm).then_some(self.size > 0)
+    }
+}
+
+impl BlockDevices {
+    /// Returns the top-level removable disks, in snapshot order.
+    ///
+    /// With `include_empty` set to `false`, drives whose
+    /// [`media_present`](BlockDevice::media_present) is `Some(false)`, such as
+    /// card readers without a card, are left out.
+    #[must_use]
+    pub fn removable_disks(&self, include_empty: bool) -> Vec<&BlockDevice> {
+        self.iter()
+            .filter(|d| d.is_disk() && d.rm)
+            .filter(|d| include_empty || d.media_present() != Some(false))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::CARD_READER_JSON;
+    use crate::{BlockDevice, BlockDevices, parse_lsblk};
+
+    #[test]
+    fn test_media_present() {
+        let devices = parse_lsblk(CARD_READER_JSON).expect("Failed to parse JSON");
+        let present: Vec<(&str, Option<bool>)> = devices
+            .iter_all()
+            .map(|d| (d.name.as_str(), d.media_present()))
+            .collect();
+        assert_eq!(
+            present,
+            vec![
+                ("sda", None),
+                ("sda1", None),
+                ("sdf", Some(false)),
+                ("sdg", Some(true)),
+                ("sdg1", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_removable_disks() {
+        let devices = parse_lsblk(CARD_READER_JSON).expect("Failed to parse JSON");
+        let names = |disks: Vec<&BlockDevice>| -> Vec<String> {
+            disks.iter().map(|d| d.name.clone()).collect()
+        };
+        assert_eq!(names(devices.removable_disks(true)), vec!["sdf", "sdg"]);
+        assert_eq!(names(devices.removable_disks(false)), vec!["sdg"]);
+        assert!(BlockDevices::default().removable_disks(true).is_empty());
+    }
+}
@@ -0,0 +1,130 @@
+use crate::BlockDevices;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// How a [`BlockDevices`] snapshot was obtained.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    /// Captured by running `lsblk` on the host.
+    Lsblk,
+    /// Parsed from JSON captured elsewhere.
+    Json,
+    /// Assembled by hand.
+    Manual,
+    /// Read from `/proc/partitions` by `BlockDevices::from_proc_partitions`.
+    #[serde(rename = "proc_partitions")]
+    ProcPartitions,
+    /// Read from `/sys/block` by `BlockDevices::from_sysfs`.
+    Sysfs,
+    /// Read from a blkid cache file by `BlockDevices::from_blkid_cache`.
+    #[serde(rename = "blkid_cache")]
+    BlkidCache,
+}
+
+/// Acquisition metadata attached to a [`BlockDevices`] snapshot.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotMeta {
+    /// When the snapshot was taken.
+    pub taken_at: SystemTime,
+    /// The hostname of the machine the snapshot was taken on, if known.
+    pub hostname: Option<String>,
+    /// How the snapshot was obtained.
+    pub source: SourceKind,
+    /// The `lsblk` binary that was run, such as `/usr/bin/lsblk`, for
+    /// snapshots taken by `get_devices` and friends.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lsblk_path: Option<PathBuf>,
+}
+
+impl SnapshotMeta {
+    /// Creates metadata for a snapshot taken now on this machine.
+    ///
+    /// The hostname is read from `/proc/sys/kernel/hostname` (falling back to
+    /// `/etc/hostname`) and is `None` if neither can be read.
+    #[must_use]
+    pub fn new(source: SourceKind) -> Self {
+        SnapshotMeta {
+            taken_at: SystemTime::now(),
+            hostname: local_hostname(),
+            source,
+            lsblk_path: None,
+        }
+    }
+}
+
+/// Reads the local hostname without spawning a process.
+fn local_hostname() -> Option<String> {
+    ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .map(|s| s.trim().to_string())
+        .find(|s| !s.is_empty())
+}
+
+impl BlockDevices {
+    /// Returns how long ago this snapshot was taken.
+    ///
+    /// Returns `None` if the snapshot has no metadata or if its timestamp lies in the future.
+    #[must_use]
+    pub fn age(&self) -> Option<Duration> {
+        let meta = self.meta.as_ref()?;
+        SystemTime::now().duration_since(meta.taken_at).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    #[test]
+    fn test_raw_lsblk_has_no_meta() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        assert!(devices.meta.is_none());
+        assert!(devices.age().is_none());
+
+        let serialized = serde_json::to_string(&devices).expect("Failed to serialize");
+        assert!(!serialized.contains("_blockdev_meta"));
+    }
+
+    #[test]
+    fn test_meta_roundtrip() {
+        let mut devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        devices.meta = Some(SnapshotMeta {
+            taken_at: SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            hostname: Some("storage01".to_string()),
+            source: SourceKind::Lsblk,
+            lsblk_path: Some(PathBuf::from("/usr/bin/lsblk")),
+        });
+
+        let serialized = serde_json::to_string(&devices).expect("Failed to serialize");
+        let value: serde_json::Value = serde_json::from_str(&serialized).expect("Invalid JSON");
+        assert_eq!(value["_blockdev_meta"]["hostname"], "storage01");
+        assert_eq!(value["_blockdev_meta"]["source"], "lsblk");
+        assert_eq!(value["_blockdev_meta"]["lsblk_path"], "/usr/bin/lsblk");
+
+        let reparsed = parse_lsblk(&serialized).expect("Failed to parse JSON");
+        assert_eq!(reparsed, devices);
+        assert!(reparsed.age().unwrap() >= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_age() {
+        let mut devices = BlockDevices {
+            meta: Some(SnapshotMeta {
+                taken_at: SystemTime::now() - Duration::from_secs(60),
+                hostname: None,
+                source: SourceKind::Manual,
+                lsblk_path: None,
+            }),
+            ..BlockDevices::default()
+        };
+        assert!(devices.age().unwrap() >= Duration::from_secs(60));
+
+        devices.meta = Some(SnapshotMeta::new(SourceKind::Json));
+        assert!(devices.age().unwrap() < Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,1370 @@
+use crate::{MountEntry, SnapshotMeta};
+use serde::de::Error as DeError;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::slice::Iter;
+use std::vec::IntoIter;
+
+/// Represents the major and minor device numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct MajMin {
+    /// The major device number.
+    pub major: u32,
+    /// The minor device number.
+    pub minor: u32,
+}
+
+impl Serialize for MajMin {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&format!("{}:{}", self.major, self.minor))
+    }
+}
+
+impl<'de> Deserialize<'de> for MajMin {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        let parts: Vec<&str> = s.split(':').collect();
+        if parts.len() != 2 {
+            return Err(DeError::custom(format!(
+                "invalid maj:min format: expected 'major:minor', got '{s}'"
+            )));
+        }
+        let major = parts[0]
+            .parse()
+            .map_err(|_| DeError::custom(format!("invalid major number: {}", parts[0])))?;
+        let minor = parts[1]
+            .parse()
+            .map_err(|_| DeError::custom(format!("invalid minor number: {}", parts[1])))?;
+        Ok(MajMin { major, minor })
+    }
+}
+
+impl std::fmt::Display for MajMin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.major, self.minor)
+    }
+}
+
+/// Represents the type of a block device.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceType {
+    /// A physical disk device.
+    Disk,
+    /// A partition on a disk.
+    Part,
+    /// A loop device.
+    Loop,
+    /// A RAID1 (mirroring) device.
+    Raid1,
+    /// A RAID5 device.
+    Raid5,
+    /// A RAID6 device.
+    Raid6,
+    /// A RAID0 (striping) device.
+    Raid0,
+    /// A RAID10 device.
+    Raid10,
+    /// An LVM logical volume.
+    Lvm,
+    /// A device mapper crypt device.
+    Crypt,
+    /// A ROM device (e.g., CD/DVD drive).
+    Rom,
+    /// An unknown or unsupported device type.
+    #[default]
+    #[serde(other)]
+    Other,
+}
+
+impl std::fmt::Display for DeviceType {
+    /// Formats the type as lsblk prints it in the `TYPE` column, e.g. `disk` or `raid1`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DeviceType::Disk => "disk",
+            DeviceType::Part => "part",
+            DeviceType::Loop => "loop",
+            DeviceType::Raid1 => "raid1",
+            DeviceType::Raid5 => "raid5",
+            DeviceType::Raid6 => "raid6",
+            DeviceType::Raid0 => "raid0",
+            DeviceType::Raid10 => "raid10",
+            DeviceType::Lvm => "lvm",
+            DeviceType::Crypt => "crypt",
+            DeviceType::Rom => "rom",
+            DeviceType::Other => "other",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Represents the entire JSON output produced by `lsblk --json`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct BlockDevices {
+    /// A vector of block devices.
+    #[serde(deserialize_with = "crate::parse::deserialize_devices")]
+    pub blockdevices: Vec<BlockDevice>,
+    /// When, where and how this snapshot was taken.
+    ///
+    /// Raw `lsblk` output never carries this, so it is `None` after [`parse_lsblk`](crate::parse_lsblk)
+    /// unless the JSON was produced by serializing a `BlockDevices` that had it set.
+    /// `get_devices` fills it in. It is serialized under the
+    /// `"_blockdev_meta"` key, which consumers of raw `lsblk` JSON can ignore.
+    #[serde(
+        rename = "_blockdev_meta",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub meta: Option<SnapshotMeta>,
+    /// Non-JSON lines that [`parse_lsblk`](crate::parse_lsblk) skipped before the
+    /// JSON document, such as `lsblk: /dev/sr0: unknown device`.
+    ///
+    /// Empty for clean lsblk output. It is serialized under the
+    /// `"_blockdev_warnings"` key when not empty.
+    #[serde(
+        rename = "_blockdev_warnings",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub warnings: Vec<String>,
+}
+
+/// Represents a block device as output by `lsblk`.
+///
+/// Note that the `children` field is optional, as some devices might not have any nested children.
+///
+/// # Field Details
+///
+/// - `name`: The device name.
+/// - `maj_min`: The device's major and minor numbers. (Renamed from the JSON field "maj:min")
+/// - `rm`: Whether the device is removable.
+/// - `size`: The device size.
+/// - `ro`: Whether the device is read-only.
+/// - `device_type`: The device type (renamed from the reserved keyword "type").
+/// - `mountpoints`: A vector of mountpoints for the device. Uses a custom deserializer to support both single and multiple mountpoints.
+/// - `children`: Optional nested block devices.
+///
+/// The remaining fields are optional columns that `lsblk` only reports when asked
+/// for them (see `get_devices_extended`). They are `None` when absent and are
+/// omitted when serializing.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct BlockDevice {
+    /// The name of the block device.
+    pub name: String,
+    /// The major and minor numbers of the block device.
+    ///
+    /// This field corresponds to the JSON field `"maj:min"`. When that field is
+    /// missing, it is built from the separate `"maj"` and `"min"` fields that
+    /// newer `lsblk -O` output may contain instead. It is always serialized as `"maj:min"`.
+    #[serde(
+        flatten,
+        deserialize_with = "crate::parse::deserialize_device_number",
+        serialize_with = "crate::parse::serialize_device_number"
+    )]
+    pub maj_min: MajMin,
+    /// Indicates if the device is removable. `null` is read as `false`; see
+    /// [`BlockDevice::is_removable`].
+    #[serde(deserialize_with = "crate::parse::deserialize_flag")]
+    pub rm: bool,
+    /// The size of the block device in bytes.
+    #[serde(deserialize_with = "crate::parse::deserialize_size")]
+    pub size: u64,
+    /// Indicates if the device is read-only. `null` is read as `false`; see
+    /// [`BlockDevice::is_read_only`].
+    #[serde(deserialize_with = "crate::parse::deserialize_flag")]
+    pub ro: bool,
+    /// The type of the block device.
+    ///
+    /// The JSON field is `"type"`, which is a reserved keyword in Rust. It is renamed to `device_type`.
+    #[serde(rename = "type")]
+    pub device_type: DeviceType,
+    /// The mountpoints of the device.
+    ///
+    /// Uses a custom deserializer to handle both a single mountpoint (possibly null) and an array of mountpoints.
+    /// Paths are kept exactly as lsblk printed them, which may include `\xNN`
+    /// escapes; see [`BlockDevice::mountpoints_unescaped`].
+    #[serde(
+        default,
+        alias = "mountpoint",
+        deserialize_with = "crate::parse::deserialize_mountpoints"
+    )]
+    pub mountpoints: Vec<Option<String>>,
+    /// Optional nested children block devices.
+    #[serde(default, deserialize_with = "crate::parse::deserialize_children")]
+    pub children: Option<Vec<BlockDevice>>,
+    /// The partition start offset in 512-byte sectors (`START` column).
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::parse::deserialize_optional_u64"
+    )]
+    pub start: Option<u64>,
+    /// The logical sector size in bytes (`LOG-SEC` column).
+    #[serde(
+        rename = "log-sec",
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::parse::deserialize_optional_u64"
+    )]
+    pub log_sec: Option<u64>,
+    /// The physical sector size in bytes (`PHY-SEC` column).
+    #[serde(
+        rename = "phy-sec",
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::parse::deserialize_optional_u64"
+    )]
+    pub phy_sec: Option<u64>,
+    /// The filesystem UUID (`UUID` column).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+    /// The partition's own UUID from the partition table (`PARTUUID` column).
+    /// Unlike `uuid`, it differs between the members of one RAID array or
+    /// multi-device filesystem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partuuid: Option<String>,
+    /// The partition type, a GUID on GPT disks or a hex code such as `0x83` on
+    /// MBR disks (`PARTTYPE` column). See [`BlockDevice::partition_role`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parttype: Option<String>,
+    /// The filesystem or content signature, such as `ext4` or `crypto_LUKS` (`FSTYPE` column).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fstype: Option<String>,
+    /// The filesystem or format version, such as `2` for LUKS2 (`FSVER` column).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fsver: Option<String>,
+    /// The partition table type of a disk, such as `gpt` or `dos` (`PTTYPE` column).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pttype: Option<String>,
+    /// Whether the device is rotational, i.e. a spinning disk (`ROTA` column).
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::parse::deserialize_optional_bool"
+    )]
+    pub rota: Option<bool>,
+    /// Whether the device can be hot-plugged, such as a disk in a hot-swap bay
+    /// (`HOTPLUG` column).
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::parse::deserialize_optional_bool"
+    )]
+    pub hotplug: Option<bool>,
+    /// The transport used to reach the device, such as `nvme`, `sata` or `usb` (`TRAN` column).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tran: Option<String>,
+    /// The size of the mounted filesystem in bytes (`FSSIZE` column).
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::parse::deserialize_optional_size"
+    )]
+    pub fssize: Option<u64>,
+    /// The bytes used on the mounted filesystem (`FSUSED` column).
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::parse::deserialize_optional_size"
+    )]
+    pub fsused: Option<u64>,
+    /// The filesystem label (`LABEL` column) as lsblk prints it, possibly with
+    /// `\xNN` escapes. See [`BlockDevice::label_unescaped`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// The disk serial number (`SERIAL` column).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial: Option<String>,
+    /// The unique storage identifier, World Wide Name (`WWN` column).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wwn: Option<String>,
+    /// The I/O scheduler, such as `none`, `mq-deadline` or `bfq` (`SCHED` column).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sched: Option<String>,
+    /// The request queue size (`RQ-SIZE` column).
+    #[serde(
+        rename = "rq-size",
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "crate::parse::deserialize_optional_u64"
+    )]
+    pub rq_size: Option<u64>,
+    /// The device vendor as lsblk reports it, possibly padded with spaces,
+    /// such as `ATA     ` (`VENDOR` column).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vendor: Option<String>,
+    /// The device model, such as `SAMSUNG MZQL27T6HBLA-00A07` (`MODEL` column).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// The mountinfo entries for this device, attached by
+    /// [`BlockDevices::enrich_mountinfo`](crate::BlockDevices::enrich_mountinfo).
+    ///
+    /// This is not an lsblk column; it is serialized under `"_blockdev_mounts"`.
+    #[serde(
+        rename = "_blockdev_mounts",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mounts: Option<Vec<MountEntry>>,
+    /// Selected udev properties, such as `ID_BUS`, attached by
+    /// `BlockDevices::enrich_udev` with the `udev-props` feature.
+    ///
+    /// This is not an lsblk column; it is serialized under `"_blockdev_udev"`.
+    #[serde(
+        rename = "_blockdev_udev",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub udev: Option<BTreeMap<String, String>>,
+}
+
+impl BlockDevice {
+    /// Returns `true` if this device has any children.
+    #[must_use]
+    pub fn has_children(&self) -> bool {
+        self.children.as_ref().is_some_and(|c| !c.is_empty())
+    }
+
+    /// Returns an iterator over the children of this device.
+    ///
+    /// Returns an empty iterator if the device has no children.
+    pub fn children_iter(&self) -> impl Iterator<Item = &BlockDevice> {
+        self.children.iter().flat_map(|c| c.iter())
+    }
+
+    /// Finds a direct child device by name.
+    ///
+    /// Returns `None` if no child with the given name exists.
+    #[must_use]
+    pub fn find_child(&self, name: &str) -> Option<&BlockDevice> {
+        self.children.as_ref()?.iter().find(|c| c.name == name)
+    }
+
+    /// Returns all non-null mountpoints for this device.
+    #[must_use]
+    pub fn active_mountpoints(&self) -> Vec<&str> {
+        self.mountpoints
+            .iter()
+            .filter_map(|m| m.as_deref())
+            .collect()
+    }
+
+    /// Returns `true` if this device has at least one mountpoint.
+    #[must_use]
+    pub fn is_mounted(&self) -> bool {
+        self.mountpoints.iter().any(|m| m.is_some())
+    }
+
+    /// Determines if this block device or any of its recursive children has a mountpoint of `/`,
+    /// indicating a system mount.
+    ///
+    /// This is [`BlockDevice::is_system_with`] with the default
+    /// [`SystemPolicy`](crate::SystemPolicy).
+    #[must_use]
+    pub fn is_system(&self) -> bool {
+        self.is_system_with(&crate::SystemPolicy::default())
+    }
+
+    /// Returns `true` if this device is a disk.
+    #[must_use]
+    pub fn is_disk(&self) -> bool {
+        self.device_type == DeviceType::Disk
+    }
+
+    /// Returns the device number encoded as a Linux `dev_t`, as found in
+    /// `st_rdev` when calling `stat` on the device node.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{BlockDevice, MajMin};
+    ///
+    /// let nvme = BlockDevice {
+    ///     maj_min: MajMin { major: 259, minor: 1 },
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(nvme.device_number(), 0x10301);
+    /// ```
+    #[must_use]
+    pub fn device_number(&self) -> u64 {
+        let major = u64::from(self.maj_min.major);
+        let minor = u64::from(self.maj_min.minor);
+        ((major & 0xffff_f000) << 32)
+            | ((major & 0x0000_0fff) << 8)
+            | ((minor & 0xffff_ff00) << 12)
+            | (minor & 0x0000_00ff)
+    }
+
+    /// Returns `true` if lsblk reported this device as removable (`RM`).
+    ///
+    /// Some lsblk builds write `"rm": null` for device-mapper devices. That
+    /// is read as `false`, so `false` means "not reported as removable"
+    /// rather than "known to be fixed"; [`parse_lsblk_with_report`]
+    /// records a [`NullFlag`] warning for every such device.
+    ///
+    /// [`parse_lsblk_with_report`]: crate::parse_lsblk_with_report
+    /// [`NullFlag`]: crate::ParseWarningKind::NullFlag
+    #[must_use]
+    pub fn is_removable(&self) -> bool {
+        self.rm
+    }
+
+    /// Returns `true` if lsblk reported this device as read-only (`RO`).
+    ///
+    /// A `null` value is read as `false`, as for
+    /// [`is_removable`](BlockDevice::is_removable), so `false` means "not
+    /// reported as read-only". Check
+    /// [`effective_writability`](BlockDevice::effective_writability)
+    /// before writing to a device.
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        self.ro
+    }
+
+    /// Returns `true` if this device is a partition.
+    #[must_use]
+    pub fn is_partition(&self) -> bool {
+        self.device_type == DeviceType::Part
+    }
+
+    /// Returns `true` if at least one direct child of this device is a partition.
+    #[must_use]
+    pub fn is_partitioned(&self) -> bool {
+        self.children_iter().any(BlockDevice::is_partition)
+    }
+
+    /// Returns the number of direct children that are partitions.
+    #[must_use]
+    pub fn partition_count(&self) -> usize {
+        self.children_iter().filter(|c| c.is_partition()).count()
+    }
+
+    /// Returns `true` if the partition table leaves room for another partition.
+    ///
+    /// Always returns `false` for now: answering this needs the partition
+    /// table itself, via lsblk's `PARTN` column or `sfdisk`. See
+    /// [`BlockDevice::free_regions`] for a byte-level view when `START` is known.
+    #[must_use]
+    pub fn has_raw_partition_space(&self) -> bool {
+        false
+    }
+
+    /// Fills in optional fields of this device that are `None` from `extended`.
+    ///
+    /// Fields that are already `Some` are left untouched, as are the fields that
+    /// every lsblk invocation reports (name, size, mountpoints, ...) and the children.
+    /// Use [`BlockDevices::merge_extended_into`] to merge a whole tree.
+    pub fn merge_extended(&mut self, extended: &BlockDevice) {
+        fn fill<T: Clone>(field: &mut Option<T>, other: &Option<T>) {
+            if field.is_none() {
+                field.clone_from(other);
+            }
+        }
+
+        fill(&mut self.start, &extended.start);
+        fill(&mut self.log_sec, &extended.log_sec);
+        fill(&mut self.phy_sec, &extended.phy_sec);
+        fill(&mut self.uuid, &extended.uuid);
+        fill(&mut self.partuuid, &extended.partuuid);
+        fill(&mut self.parttype, &extended.parttype);
+        fill(&mut self.fstype, &extended.fstype);
+        fill(&mut self.fsver, &extended.fsver);
+        fill(&mut self.pttype, &extended.pttype);
+        fill(&mut self.rota, &extended.rota);
+        fill(&mut self.hotplug, &extended.hotplug);
+        fill(&mut self.tran, &extended.tran);
+        fill(&mut self.fssize, &extended.fssize);
+        fill(&mut self.fsused, &extended.fsused);
+        fill(&mut self.label, &extended.label);
+        fill(&mut self.serial, &extended.serial);
+        fill(&mut self.wwn, &extended.wwn);
+        fill(&mut self.sched, &extended.sched);
+        fill(&mut self.rq_size, &extended.rq_size);
+        fill(&mut self.vendor, &extended.vendor);
+        fill(&mut self.model, &extended.model);
+        fill(&mut self.mounts, &extended.mounts);
+        fill(&mut self.udev, &extended.udev);
+    }
+
+    /// Returns the populated fields of this device keyed by their lsblk `--output`
+    /// column names, such as `"NAME"`, `"MAJ:MIN"` or `"LOG-SEC"`.
+    ///
+    /// `SIZE` is in bytes and `MOUNTPOINTS` joins the active mountpoints with
+    /// commas. Optional columns that are `None` are left out. Children are not
+    /// included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let json = r#"{"blockdevices": [{"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null]}]}"#;
+    /// let devices = parse_lsblk(json).unwrap();
+    /// let columns = devices.find_by_name("sda").unwrap().as_column_map();
+    /// assert_eq!(columns["MAJ:MIN"], "8:0");
+    /// assert_eq!(columns["TYPE"], "disk");
+    /// assert!(!columns.contains_key("UUID"));
+    /// ```
+    #[must_use]
+    pub fn as_column_map(&self) -> HashMap<&'static str, String> {
+        let mut columns = HashMap::from([
+            ("NAME", self.name.clone()),
+            ("MAJ:MIN", self.maj_min.to_string()),
+            ("RM", self.rm.to_string()),
+            ("SIZE", self.size.to_string()),
+            ("RO", self.ro.to_string()),
+            ("TYPE", self.device_type.to_string()),
+            ("MOUNTPOINTS", self.active_mountpoints().join(",")),
+        ]);
+        if let Some(start) = self.start {
+            columns.insert("START", start.to_string());
+        }
+        if let Some(log_sec) = self.log_sec {
+            columns.insert("LOG-SEC", log_sec.to_string());
+        }
+        if let Some(phy_sec) = self.phy_sec {
+            columns.insert("PHY-SEC", phy_sec.to_string());
+        }
+        if let Some(uuid) = &self.uuid {
+            columns.insert("UUID", uuid.clone());
+        }
+        if let Some(partuuid) = &self.partuuid {
+            columns.insert("PARTUUID", partuuid.clone());
+        }
+        if let Some(parttype) = &self.parttype {
+            columns.insert("PARTTYPE", parttype.clone());
+        }
+        if let Some(fstype) = &self.fstype {
+            columns.insert("FSTYPE", fstype.clone());
+        }
+        if let Some(fsver) = &self.fsver {
+            columns.insert("FSVER", fsver.clone());
+        }
+        if let Some(pttype) = &self.pttype {
+            columns.insert("PTTYPE", pttype.clone());
+        }
+        if let Some(rota) = self.rota {
+            columns.insert("ROTA", rota.to_string());
+        }
+        if let Some(hotplug) = self.hotplug {
+            columns.insert("HOTPLUG", hotplug.to_string());
+        }
+        if let Some(tran) = &self.tran {
+            columns.insert("TRAN", tran.clone());
+        }
+        if let Some(fssize) = self.fssize {
+            columns.insert("FSSIZE", fssize.to_string());
+        }
+        if let Some(fsused) = self.fsused {
+            columns.insert("FSUSED", fsused.to_string());
+        }
+        if let Some(label) = &self.label {
+            columns.insert("LABEL", label.clone());
+        }
+        if let Some(serial) = &self.serial {
+            columns.insert("SERIAL", serial.clone());
+        }
+        if let Some(wwn) = &self.wwn {
+            columns.insert("WWN", wwn.clone());
+        }
+        if let Some(sched) = &self.sched {
+            columns.insert("SCHED", sched.clone());
+        }
+        if let Some(rq_size) = self.rq_size {
+            columns.insert("RQ-SIZE", rq_size.to_string());
+        }
+        if let Some(vendor) = &self.vendor {
+            columns.insert("VENDOR", vendor.clone());
+        }
+        if let Some(model) = &self.model {
+            columns.insert("MODEL", model.clone());
+        }
+        columns
+    }
+
+    /// Serializes this device, including its children, to a pretty-printed JSON object.
+    ///
+    /// The object is not wrapped in `{"blockdevices": [...]}`, which makes it suitable
+    /// for pasting into bug reports. Use [`BlockDevice::single_device_json`] to get a
+    /// document that can be fed back to [`parse_lsblk`](crate::parse_lsblk).
+    ///
+    /// # Errors
+    ///
+    /// Returns a `serde_json::Error` if the device cannot be serialized.
+    pub fn lsblk_repr(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Serializes this device wrapped in the canonical `{"blockdevices": [...]}` envelope.
+    ///
+    /// The result can be parsed with [`parse_lsblk`](crate::parse_lsblk) to get a
+    /// `BlockDevices` containing only this device.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `serde_json::Error` if the device cannot be serialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let json = r#"{"blockdevices": [{"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null]}]}"#;
+    /// let devices = parse_lsblk(json).unwrap();
+    /// let sda = devices.find_by_name("sda").unwrap();
+    /// let reparsed = parse_lsblk(&sda.single_device_json().unwrap()).unwrap();
+    /// assert_eq!(reparsed.find_by_name("sda"), Some(sda));
+    /// ```
+    pub fn single_device_json(&self) -> Result<String, serde_json::Error> {
+        #[derive(Serialize)]
+        struct Envelope<'a> {
+            blockdevices: [&'a BlockDevice; 1],
+        }
+
+        serde_json::to_string_pretty(&Envelope {
+            blockdevices: [self],
+        })
+    }
+}
+
+impl BlockDevices {
+    /// Returns the number of top-level block devices.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.blockdevices.len()
+    }
+
+    /// Returns `true` if there are no block devices.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.blockdevices.is_empty()
+    }
+
+    /// Returns an iterator over references to the block devices.
+    pub fn iter(&self) -> Iter<'_, BlockDevice> {
+        self.blockdevices.iter()
+    }
+
+    /// Returns a depth-first iterator over every device in the tree, parents before
+    /// their children.
+    ///
+    /// Devices that appear under several parents, such as a RAID array built from
+    /// partitions on different disks, are yielded once per occurrence.
+    pub fn iter_all(&self) -> IterAll<'_> {
+        IterAll {
+            stack: self.blockdevices.iter().rev().collect(),
+        }
+    }
+
+    /// Like [`iter_all`](Self::iter_all), but also yields each device's
+    /// immediate parent: `(None, device)` for a top-level device and
+    /// `(Some(parent), child)` for every nested one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "children": [
+    ///         {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": ["/"]}
+    ///      ]}
+    /// ]}"#).unwrap();
+    ///
+    /// let pairs: Vec<(Option<&str>, &str)> = devices
+    ///     .iter_pairs()
+    ///     .map(|(parent, d)| (parent.map(|p| p.name.as_str()), d.name.as_str()))
+    ///     .collect();
+    /// assert_eq!(pairs, vec![(None, "sda"), (Some("sda"), "sda1")]);
+    /// ```
+    pub fn iter_pairs(&self) -> impl Iterator<Item = (Option<&BlockDevice>, &BlockDevice)> {
+        let mut stack: Vec<(Option<&BlockDevice>, &BlockDevice)> =
+            self.blockdevices.iter().rev().map(|d| (None, d)).collect();
+        std::iter::from_fn(move || {
+            let (parent, device) = stack.pop()?;
+            stack.extend(
+                device
+                    .children
+                    .iter()
+                    .flatten()
+                    .rev()
+                    .map(|child| (Some(device), child)),
+            );
+            Some((parent, device))
+        })
+    }
+
+    /// Like [`iter_all`](Self::iter_all), but also yields each device's
+    /// ancestors from its top-level device down to its immediate parent,
+    /// which is empty for a top-level device.
+    pub fn iter_with_parent_path(&self) -> impl Iterator<Item = (Vec<&BlockDevice>, &BlockDevice)> {
+        let mut stack: Vec<(Vec<&BlockDevice>, &BlockDevice)> = self
+            .blockdevices
+            .iter()
+            .rev()
+            .map(|d| (Vec::new(), d))
+            .collect();
+        std::iter::from_fn(move || {
+            let (ancestors, device) = stack.pop()?;
+            for child in device.children.iter().flatten().rev() {
+                let mut path = ancestors.clone();
+                path.push(device);
+                stack.push((path, child));
+            }
+            Some((ancestors, device))
+        })
+    }
+
+    /// Returns a vector of references to `BlockDevice` entries that have a mountpoint
+    /// of `/` on them or on any of their recursive children.
+    ///
+    /// See [`BlockDevices::system_with`] for other notions of a system device.
+    #[must_use]
+    pub fn system(&self) -> Vec<&BlockDevice> {
+        self.system_with(&crate::SystemPolicy::default())
+    }
+
+    /// Returns a vector of references to `BlockDevice` entries that do not have a mountpoint
+    /// of `/` on them or on any of their recursive children.
+    ///
+    /// See [`BlockDevices::non_system_with`] for other notions of a system device.
+    #[must_use]
+    pub fn non_system(&self) -> Vec<&BlockDevice> {
+        self.non_system_with(&crate::SystemPolicy::default())
+    }
+
+    /// Returns the top-level devices that are at least `min` bytes in size.
+    #[must_use]
+    pub fn with_capacity_at_least(&self, min: u64) -> Vec<&BlockDevice> {
+        self.blockdevices.iter().filter(|d| d.size >= min).collect()
+    }
+
+    /// Returns the top-level devices whose size is between `min` and `max` bytes, inclusive.
+    #[must_use]
+    pub fn with_capacity_between(&self, min: u64, max: u64) -> Vec<&BlockDevice> {
+        self.blockdevices
+            .iter()
+            .filter(|d| (min..=max).contains(&d.size))
+            .collect()
+    }
+
+    /// Returns the largest top-level device.
+    ///
+    /// If several devices share the largest size, the first one is returned.
+    #[must_use]
+    pub fn largest_device(&self) -> Option<&BlockDevice> {
+        self.blockdevices
+            .iter()
+            .reduce(|best, d| if d.size > best.size { d } else { best })
+    }
+
+    /// Returns the smallest top-level device.
+    ///
+    /// If several devices share the smallest size, the first one is returned.
+    #[must_use]
+    pub fn smallest_device(&self) -> Option<&BlockDevice> {
+        self.blockdevices.iter().min_by_key(|d| d.size)
+    }
+
+    /// Finds a top-level block device by name.
+    ///
+    /// Returns `None` if no device with the given name exists.
+    #[must_use]
+    pub fn find_by_name(&self, name: &str) -> Option<&BlockDevice> {
+        self.blockdevices.iter().find(|d| d.name == name)
+    }
+
+    /// Merges the optional fields of `extended` into the matching devices of this tree.
+    ///
+    /// Devices are matched by name anywhere in the tree, and each match is updated
+    /// with [`BlockDevice::merge_extended`]. This is meant for combining a
+    /// `get_devices` snapshot with a `get_devices_extended` one.
+    pub fn merge_extended_into(&mut self, extended: &BlockDevices) {
+        fn merge_all(devices: &mut [BlockDevice], by_name: &HashMap<&str, &BlockDevice>) {
+            for device in devices {
+                if let Some(other) = by_name.get(device.name.as_str()) {
+                    device.merge_extended(other);
+                }
+                if let Some(children) = &mut device.children {
+                    merge_all(children, by_name);
+                }
+            }
+        }
+
+        let mut by_name = HashMap::new();
+        for device in extended.iter_all() {
+            by_name.entry(device.name.as_str()).or_insert(device);
+        }
+        merge_all(&mut self.blockdevices, &by_name);
+    }
+}
+
+/// A depth-first iterator over every device in a [`BlockDevices`] tree.
+///
+/// Created by [`BlockDevices::iter_all`].
+#[derive(Debug, Clone)]
+pub struct IterAll<'a> {
+    stack: Vec<&'a BlockDevice>,
+}
+
+impl<'a> IterAll<'a> {
+    /// Iterates over `device` and all of its descendants, depth-first.
+    pub(crate) fn subtree(device: &'a BlockDevice) -> Self {
+        IterAll {
+            stack: vec![device],
+        }
+    }
+}
+
+impl<'a> Iterator for IterAll<'a> {
+    type Item = &'a BlockDevice;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let device = self.stack.pop()?;
+        if let Some(children) = &device.children {
+            self.stack.extend(children.iter().rev());
+        }
+        Some(device)
+    }
+}
+
+impl IntoIterator for BlockDevices {
+    type Item = BlockDevice;
+    type IntoIter = IntoIter<BlockDevice>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.blockdevices.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a BlockDevices {
+    type Item = &'a BlockDevice;
+    type IntoIter = Iter<'a, BlockDevice>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.blockdevices.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_into_iterator() {
+        // Create dummy BlockDevice instances.
+        let device1 = BlockDevice {
+            name: "sda".to_string(),
+            maj_min: MajMin { major: 8, minor: 0 },
+            rm: false,
+            size: 536_870_912_000, // 500G in bytes
+            ro: false,
+            device_type: DeviceType::Disk,
+            mountpoints: vec![None],
+            children: None,
+            ..Default::default()
+        };
+
+        let device2 = BlockDevice {
+            name: "sdb".to_string(),
+            maj_min: MajMin {
+                major: 8,
+                minor: 16,
+            },
+            rm: false,
+            size: 536_870_912_000, // 500G in bytes
+            ro: false,
+            device_type: DeviceType::Disk,
+            mountpoints: vec![None],
+            children: None,
+            ..Default::default()
+        };
+
+        // Create a BlockDevices instance containing the two devices.
+        let devices = BlockDevices {
+            blockdevices: vec![device1, device2],
+            meta: None,
+            warnings: Vec::new(),
+        };
+
+        // Use the IntoIterator implementation to iterate over the devices.
+        let names: Vec<String> = devices.into_iter().map(|dev| dev.name).collect();
+        assert_eq!(names, vec!["sda".to_string(), "sdb".to_string()]);
+    }
+
+    #[test]
+    fn test_default_trait() {
+        let devices = BlockDevices::default();
+        assert!(devices.is_empty());
+        assert_eq!(devices.len(), 0);
+    }
+
+    #[test]
+    fn test_block_device_methods() {
+        let device = BlockDevice {
+            name: "sda".to_string(),
+            maj_min: MajMin { major: 8, minor: 0 },
+            rm: false,
+            size: 536_870_912_000, // 500G in bytes
+            ro: false,
+            device_type: DeviceType::Disk,
+            mountpoints: vec![Some("/mnt/data".to_string()), None],
+            children: Some(vec![BlockDevice {
+                name: "sda1".to_string(),
+                maj_min: MajMin { major: 8, minor: 1 },
+                rm: false,
+                size: 268_435_456_000, // 250G in bytes
+                ro: false,
+                device_type: DeviceType::Part,
+                mountpoints: vec![Some("/home".to_string())],
+                children: None,
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+
+        assert!(device.is_disk());
+        assert!(!device.is_partition());
+        assert!(device.has_children());
+        assert!(device.is_mounted());
+        assert_eq!(device.active_mountpoints(), vec!["/mnt/data"]);
+
+        let child = device.find_child("sda1").unwrap();
+        assert!(!child.is_disk());
+        assert!(child.is_partition());
+        assert!(!child.has_children());
+
+        assert!(device.find_child("nonexistent").is_none());
+    }
+
+    #[test]
+    fn test_children_iter() {
+        let device = BlockDevice {
+            name: "sda".to_string(),
+            maj_min: MajMin { major: 8, minor: 0 },
+            rm: false,
+            size: 536_870_912_000, // 500G in bytes
+            ro: false,
+            device_type: DeviceType::Disk,
+            mountpoints: vec![None],
+            children: Some(vec![
+                BlockDevice {
+                    name: "sda1".to_string(),
+                    maj_min: MajMin { major: 8, minor: 1 },
+                    rm: false,
+                    size: 268_435_456_000, // 250G in bytes
+                    ro: false,
+                    device_type: DeviceType::Part,
+                    mountpoints: vec![None],
+                    children: None,
+                    ..Default::default()
+                },
+                BlockDevice {
+                    name: "sda2".to_string(),
+                    maj_min: MajMin { major: 8, minor: 2 },
+                    rm: false,
+                    size: 268_435_456_000, // 250G in bytes
+                    ro: false,
+                    device_type: DeviceType::Part,
+                    mountpoints: vec![None],
+                    children: None,
+                    ..Default::default()
+                },
+            ]),
+            ..Default::default()
+        };
+
+        let names: Vec<&str> = device.children_iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["sda1", "sda2"]);
+
+        // Test empty children iterator
+        let device_no_children = BlockDevice {
+            name: "sdb".to_string(),
+            maj_min: MajMin {
+                major: 8,
+                minor: 16,
+            },
+            rm: false,
+            size: 536_870_912_000, // 500G in bytes
+            ro: false,
+            device_type: DeviceType::Disk,
+            mountpoints: vec![None],
+            children: None,
+            ..Default::default()
+        };
+        assert_eq!(device_no_children.children_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_borrowing_iterator() {
+        let devices = BlockDevices {
+            blockdevices: vec![
+                BlockDevice {
+                    name: "sda".to_string(),
+                    maj_min: MajMin { major: 8, minor: 0 },
+                    rm: false,
+                    size: 536_870_912_000, // 500G in bytes
+                    ro: false,
+                    device_type: DeviceType::Disk,
+                    mountpoints: vec![None],
+                    children: None,
+                    ..Default::default()
+                },
+                BlockDevice {
+                    name: "sdb".to_string(),
+                    maj_min: MajMin {
+                        major: 8,
+                        minor: 16,
+                    },
+                    rm: false,
+                    size: 536_870_912_000, // 500G in bytes
+                    ro: false,
+                    device_type: DeviceType::Disk,
+                    mountpoints: vec![None],
+                    children: None,
+                    ..Default::default()
+                },
+            ],
+            meta: None,
+            warnings: Vec::new(),
+        };
+
+        // Test borrowing iterator (doesn't consume)
+        let names: Vec<&str> = (&devices).into_iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["sda", "sdb"]);
+
+        // devices is still available
+        assert_eq!(devices.len(), 2);
+
+        // Test iter() method
+        let names2: Vec<&str> = devices.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names2, vec!["sda", "sdb"]);
+    }
+
+    #[test]
+    fn test_find_by_name() {
+        let devices = BlockDevices {
+            blockdevices: vec![
+                BlockDevice {
+                    name: "sda".to_string(),
+                    maj_min: MajMin { major: 8, minor: 0 },
+                    rm: false,
+                    size: 536_870_912_000, // 500G in bytes
+                    ro: false,
+                    device_type: DeviceType::Disk,
+                    mountpoints: vec![None],
+                    children: None,
+                    ..Default::default()
+                },
+                BlockDevice {
+                    name: "nvme0n1".to_string(),
+                    maj_min: MajMin {
+                        major: 259,
+                        minor: 0,
+                    },
+                    rm: false,
+                    size: 1_099_511_627_776, // 1T in bytes
+                    ro: false,
+                    device_type: DeviceType::Disk,
+                    mountpoints: vec![None],
+                    children: None,
+                    ..Default::default()
+                },
+            ],
+            meta: None,
+            warnings: Vec::new(),
+        };
+
+        assert!(devices.find_by_name("sda").is_some());
+        assert_eq!(devices.find_by_name("sda").unwrap().size, 536_870_912_000);
+        assert!(devices.find_by_name("nvme0n1").is_some());
+        assert!(devices.find_by_name("nonexistent").is_none());
+    }
+
+    #[test]
+    fn test_lsblk_repr() {
+        let device = BlockDevice {
+            name: "sda".to_string(),
+            maj_min: MajMin { major: 8, minor: 0 },
+            rm: false,
+            size: 536_870_912_000, // 500G in bytes
+            ro: false,
+            device_type: DeviceType::Disk,
+            mountpoints: vec![None],
+            children: Some(vec![BlockDevice {
+                name: "sda1".to_string(),
+                maj_min: MajMin { major: 8, minor: 1 },
+                rm: false,
+                size: 536_870_912_000, // 500G in bytes
+                ro: false,
+                device_type: DeviceType::Part,
+                mountpoints: vec![Some("/data".to_string())],
+                children: None,
+                ..Default::default()
+            }]),
+            ..Default::default()
+        };
+
+        let repr = device.lsblk_repr().expect("Failed to serialize device");
+        assert!(repr.trim_start().starts_with("{\n"));
+        assert!(!repr.contains("blockdevices"));
+        let value: serde_json::Value = serde_json::from_str(&repr).expect("Invalid JSON");
+        assert_eq!(value["name"], "sda");
+        assert_eq!(value["maj:min"], "8:0");
+        assert_eq!(value["children"][0]["mountpoints"][0], "/data");
+
+        let json = device
+            .single_device_json()
+            .expect("Failed to serialize device");
+        let reparsed = crate::parse_lsblk(&json).expect("Failed to parse JSON");
+        assert_eq!(reparsed.len(), 1);
+        assert_eq!(reparsed.blockdevices[0], device);
+    }
+
+    #[test]
+    fn test_iter_all() {
+        let devices =
+            crate::parse_lsblk(crate::fixtures::SAMPLE_JSON).expect("Failed to parse JSON");
+        // 10 disks, 28 partitions and 6 RAID array occurrences (md0-md2 under two disks each).
+        assert_eq!(devices.iter_all().count(), 44);
+
+        let names: Vec<&str> = devices
+            .iter_all()
+            .skip_while(|d| d.name != "nvme3n1")
+            .take(5)
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["nvme3n1", "nvme3n1p1", "nvme3n1p2", "nvme3n1p3", "md0"]
+        );
+        assert_eq!(BlockDevices::default().iter_all().count(), 0);
+    }
+
+    #[test]
+    fn test_iter_pairs() {
+        let devices = crate::parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": ["/boot"]},
+                    {"name": "sda2", "maj:min": "8:2", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": [null],
+                     "children": [
+                        {"name": "vg-root", "maj:min": "253:0", "rm": false, "size": "900G", "ro": false, "type": "lvm", "mountpoints": ["/"]},
+                        {"name": "vg-home", "maj:min": "253:1", "rm": false, "size": "100G", "ro": false, "type": "lvm", "mountpoints": ["/home"]}
+                     ]}
+                 ]},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+
+        let pairs: Vec<(Option<&str>, &str)> = devices
+            .iter_pairs()
+            .map(|(parent, d)| (parent.map(|p| p.name.as_str()), d.name.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                (None, "sda"),
+                (Some("sda"), "sda1"),
+                (Some("sda"), "sda2"),
+                (Some("sda2"), "vg-root"),
+                (Some("sda2"), "vg-home"),
+                (None, "sdb"),
+            ]
+        );
+
+        let paths: Vec<(Vec<&str>, &str)> = devices
+            .iter_with_parent_path()
+            .map(|(path, d)| {
+                (
+                    path.iter().map(|p| p.name.as_str()).collect(),
+                    d.name.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                (vec![], "sda"),
+                (vec!["sda"], "sda1"),
+                (vec!["sda"], "sda2"),
+                (vec!["sda", "sda2"], "vg-root"),
+                (vec!["sda", "sda2"], "vg-home"),
+                (vec![], "sdb"),
+            ]
+        );
+
+        // Both agree with iter_all, including for arrays under several members.
+        let devices =
+            crate::parse_lsblk(crate::fixtures::SAMPLE_JSON).expect("Failed to parse JSON");
+        assert!(devices.iter_pairs().map(|(_, d)| d).eq(devices.iter_all()));
+        for ((path, device), (parent, same)) in
+            devices.iter_with_parent_path().zip(devices.iter_pairs())
+        {
+            assert!(std::ptr::eq(device, same));
+            assert_eq!(path.last().map(|p| &p.name), parent.map(|p| &p.name));
+        }
+        assert_eq!(BlockDevices::default().iter_pairs().count(), 0);
+    }
+
+    #[test]
+    fn test_capacity_filters() {
+        const TIB: u64 = 1 << 40;
+        let devices =
+            crate::parse_lsblk(crate::fixtures::SAMPLE_JSON).expect("Failed to parse JSON");
+
+        assert_eq!(devices.largest_device().unwrap().name, "nvme1n1");
+        assert_eq!(devices.smallest_device().unwrap().name, "nvme3n1");
+
+        let large: Vec<&str> = devices
+            .with_capacity_at_least(TIB)
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(large.len(), 8);
+        assert!(!large.contains(&"nvme3n1"));
+        assert!(!large.contains(&"nvme2n1"));
+
+        let small = devices.with_capacity_between(0, TIB);
+        let names: Vec<&str> = small.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["nvme3n1", "nvme2n1"]);
+        assert!(devices.with_capacity_between(TIB, 0).is_empty());
+
+        let empty = BlockDevices::default();
+        assert!(empty.largest_device().is_none());
+        assert!(empty.smallest_device().is_none());
+    }
+
+    #[test]
+    fn test_as_column_map() {
+        let devices =
+            crate::parse_lsblk(crate::fixtures::SAMPLE_JSON).expect("Failed to parse JSON");
+        let md0 = devices
+            .find_by_name("nvme3n1")
+            .and_then(|d| d.find_child("nvme3n1p3"))
+            .and_then(|d| d.find_child("md0"))
+            .unwrap();
+        let columns = md0.as_column_map();
+        assert_eq!(columns["NAME"], "md0");
+        assert_eq!(columns["MAJ:MIN"], "9:0");
+        assert_eq!(columns["RM"], "false");
+        assert_eq!(columns["SIZE"], md0.size.to_string());
+        assert_eq!(columns["TYPE"], "raid1");
+        assert_eq!(columns["MOUNTPOINTS"], "/boot");
+        assert_eq!(columns.len(), 7);
+        for optional in [
+            "START", "LOG-SEC", "PHY-SEC", "UUID", "PARTTYPE", "FSTYPE", "FSVER", "PTTYPE", "ROTA",
+            "HOTPLUG", "TRAN", "FSSIZE", "FSUSED", "LABEL", "SERIAL", "WWN", "SCHED", "RQ-SIZE",
+            "VENDOR", "MODEL",
+        ] {
+            assert!(!columns.contains_key(optional), "{optional}");
+        }
+
+        let extended = BlockDevice {
+            mountpoints: vec![Some("/a".to_string()), None, Some("/b".to_string())],
+            start: Some(2048),
+            log_sec: Some(4096),
+            uuid: Some("1234-ABCD".to_string()),
+            ..Default::default()
+        };
+        let columns = extended.as_column_map();
+        assert_eq!(columns["MOUNTPOINTS"], "/a,/b");
+        assert_eq!(columns["START"], "2048");
+        assert_eq!(columns["LOG-SEC"], "4096");
+        assert_eq!(columns["UUID"], "1234-ABCD");
+        assert_eq!(columns["TYPE"], "other");
+    }
+
+    #[test]
+    fn test_partition_counts() {
+        let devices =
+            crate::parse_lsblk(crate::fixtures::SAMPLE_JSON).expect("Failed to parse JSON");
+        assert!(devices.iter().all(|d| d.is_partitioned()));
+        let nvme3n1 = devices.find_by_name("nvme3n1").unwrap();
+        assert_eq!(nvme3n1.partition_count(), 6);
+        let nvme0n1 = devices.find_by_name("nvme0n1").unwrap();
+        assert_eq!(nvme0n1.partition_count(), 2);
+
+        // md0 is a child of nvme3n1p3 but not a partition.
+        let nvme3n1p3 = nvme3n1.find_child("nvme3n1p3").unwrap();
+        assert!(!nvme3n1p3.is_partitioned());
+        assert_eq!(nvme3n1p3.partition_count(), 0);
+        assert!(!BlockDevice::default().is_partitioned());
+    }
+
+    #[test]
+    fn test_merge_extended() {
+        let mut basic = crate::parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null],
+                    "children": [
+                        {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "500G", "ro": false, "type": "part", "mountpoints": ["/data"]}
+                    ]
+                }
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+        let extended = crate::parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null], "log-sec": 512,
+                    "children": [
+                        {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "500G", "ro": false, "type": "part", "mountpoints": [null],
+                         "start": 2048, "log-sec": 512, "uuid": "0f3c2a9e-5b1d-4c7e-9a21-6d8e4f1b2c3d"}
+                    ]
+                }
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+        let original = basic.clone();
+        assert!(
+            basic.blockdevices[0]
+                .children_iter()
+                .all(|c| c.uuid.is_none())
+        );
+
+        basic.merge_extended_into(&extended);
+
+        let sda = basic.find_by_name("sda").unwrap();
+        let sda1 = sda.find_child("sda1").unwrap();
+        assert_eq!(
+            sda1.uuid.as_deref(),
+            Some("0f3c2a9e-5b1d-4c7e-9a21-6d8e4f1b2c3d")
+        );
+        assert_eq!(sda1.start, Some(2048));
+        assert_eq!(sda.log_sec, Some(512));
+        assert!(sda.uuid.is_none());
+
+        // Fields lsblk always reports keep their original values.
+        let original_sda1 = original.blockdevices[0].find_child("sda1").unwrap();
+        assert_eq!(sda1.mountpoints, original_sda1.mountpoints);
+        assert_eq!(sda1.size, original_sda1.size);
+        assert_eq!(sda1.name, original_sda1.name);
+    }
+
+    #[test]
+    fn test_merge_extended_keeps_existing_values() {
+        let mut device = BlockDevice {
+            name: "sda1".to_string(),
+            uuid: Some("existing".to_string()),
+            ..Default::default()
+        };
+        let extended = BlockDevice {
+            name: "sda1".to_string(),
+            uuid: Some("other".to_string()),
+            start: Some(2048),
+            ..Default::default()
+        };
+        device.merge_extended(&extended);
+        assert_eq!(device.uuid.as_deref(), Some("existing"));
+        assert_eq!(device.start, Some(2048));
+    }
+}
@@ -0,0 +1,340 @@
+use crate::{BlockDevError, BlockDevice, BlockDevices, MajMin};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// One line of `/proc/self/mountinfo`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MountEntry {
+    /// The unique ID of the mount. Lower IDs were mounted earlier.
+    pub mount_id: u32,
+    /// The ID of the parent mount.
+    pub parent_id: u32,
+    /// The device number of the mounted filesystem.
+    pub maj_min: MajMin,
+    /// The directory within the filesystem that forms the root of this mount
+    /// (`/` unless this is a bind mount of a subdirectory).
+    pub root: String,
+    /// The mount point, with octal escapes such as `\040` decoded.
+    pub mount_point: String,
+    /// Per-mount options such as `rw` or `noatime`.
+    pub mount_options: Vec<String>,
+    /// The filesystem type.
+    pub fs_type: String,
+    /// The mount source, such as `/dev/sda1`.
+    pub source: String,
+    /// Per-superblock options.
+    pub super_options: Vec<String>,
+}
+
+/// The parsed contents of a `mountinfo` file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct MountInfo {
+    /// The mounts in file order.
+    pub entries: Vec<MountEntry>,
+}
+
+/// Decodes the `\NNN` octal escapes the kernel uses for spaces, tabs, newlines
+/// and backslashes in mountinfo paths.
+fn unescape_octal(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 4)
+            .filter(|digits| bytes[i] == b'\\' && digits.iter().all(|b| (b'0'..=b'7').contains(b)))
+            .map(|d| {
+                u16::from(d[0] - b'0') * 64 + u16::from(d[1] - b'0') * 8 + u16::from(d[2] - b'0')
+            })
+            .and_then(|value| u8::try_from(value).ok());
+        match escaped {
+            Some(value) => {
+                out.push(value);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn parse_line(line: &str) -> Option<MountEntry> {
+    let (before, after) = line.split_once(" - ")?;
+    let mut fields = before.split(' ');
+    let mount_id = fields.next()?.parse().ok()?;
+    let parent_id = fields.next()?.parse().ok()?;
+    let (major, minor) = fields.next()?.split_once(':')?;
+    let maj_min = MajMin {
+        major: major.parse().ok()?,
+        minor: minor.parse().ok()?,
+    };
+    let root = unescape_octal(fields.next()?);
+    let mount_point = unescape_octal(fields.next()?);
+    let mount_options = fields.next()?.split(',').map(str::to_string).collect();
+
+    let mut fields = after.split(' ');
+    let fs_type = fields.next()?.to_string();
+    let source = unescape_octal(fields.next()?);
+    let super_options = fields
+        .next()
+        .map(|s| s.split(',').map(str::to_string).collect())
+        .unwrap_or_default();
+
+    Some(MountEntry {
+        mount_id,
+        parent_id,
+        maj_min,
+        root,
+        mount_point,
+        mount_options,
+        fs_type,
+        source,
+        super_options,
+    })
+}
+
+impl MountInfo {
+    /// Parses the contents of a `mountinfo` file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::InvalidInput`] naming the first line that is not
+    /// a valid mountinfo entry.
+    pub fn parse(contents: &str) -> Result<MountInfo, BlockDevError> {
+        let entries = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                parse_line(line).ok_or_else(|| {
+                    BlockDevError::InvalidInput(format!("invalid mountinfo line: '{line}'"))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(MountInfo { entries })
+    }
+
+    /// Reads and parses `/proc/self/mountinfo`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn from_proc() -> Result<MountInfo, BlockDevError> {
+        Self::from_path(Path::new("/proc/self/mountinfo"))
+    }
+
+    /// Reads and parses a mountinfo file at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn from_path(path: &Path) -> Result<MountInfo, BlockDevError> {
+        Self::parse(&std::fs::read_to_string(path).map_err(BlockDevError::IoError)?)
+    }
+
+    /// Returns the entries for the given device number, in file order.
+    pub fn entries_for(&self, maj_min: MajMin) -> impl Iterator<Item = &MountEntry> {
+        self.entries.iter().filter(move |e| e.maj_min == maj_min)
+    }
+}
+
+/// Whether a [`CanonicalMount`] was chosen from mountinfo or guessed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CanonicalSource {
+    /// The earliest mount (lowest mount ID) according to mountinfo.
+    MountInfo,
+    /// The shortest path, ties broken lexicographically, because no mountinfo
+    /// was attached to the device.
+    Heuristic,
+}
+
+/// The primary mountpoint of a device and the other paths it is bind-mounted at.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CanonicalMount {
+    /// The primary mountpoint.
+    pub path: String,
+    /// The remaining distinct mountpoints, treated as bind mounts.
+    pub binds: Vec<String>,
+    /// How `path` was chosen.
+    pub source: CanonicalSource,
+}
+
+impl BlockDevice {
+    /// Returns the distinct non-null mountpoints of this device, in lsblk order.
+    ///
+    /// Bind mounts can make lsblk list the same path more than once.
+    #[must_use]
+    pub fn unique_mountpoints(&self) -> Vec<&str> {
+        let mut unique: Vec<&str> = Vec::new();
+        for mountpoint in self.active_mountpoints() {
+            if !unique.contains(&mountpoint) {
+                unique.push(mountpoint);
+            }
+        }
+        unique
+    }
+
+    /// Returns the primary mountpoint of this device, treating the others as binds.
+    ///
+    /// When mountinfo has been attached (see [`BlockDevices::enrich_mountinfo`]), the
+    /// mountpoint that was mounted first (lowest mount ID) is primary. Otherwise
+    /// this falls back to a heuristic: the shortest path, ties broken
+    /// lexicographically. [`CanonicalMount::source`] records which rule applied.
+    ///
+    /// Returns `None` if the device is not mounted.
+    #[must_use]
+    pub fn canonical_mountpoint(&self) -> Option<CanonicalMount> {
+        let unique = self.unique_mountpoints();
+        if unique.is_empty() {
+            return None;
+        }
+
+        let from_mountinfo = self.mounts.as_ref().and_then(|mounts| {
+            mounts
+                .iter()
+                .filter(|m| unique.contains(&m.mount_point.as_str()))
+                .min_by_key(|m| m.mount_id)
+                .map(|m| m.mount_point.as_str())
+        });
+        let (path, source) = match from_mountinfo {
+            Some(path) => (path, CanonicalSource::MountInfo),
+            None => {
+                let path = unique
+                    .iter()
+                    .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+                    .copied()?;
+                (path, CanonicalSource::Heuristic)
+            }
+        };
+
+        Some(CanonicalMount {
+            path: path.to_string(),
+            binds: unique
+                .iter()
+                .filter(|m| **m != path)
+                .map(|m| (*m).to_string())
+                .collect(),
+            source,
+        })
+    }
+}
+
+impl BlockDevices {
+    /// Attaches the mountinfo entries of every device in the tree, matched by `maj:min`.
+    ///
+    /// Devices without entries get an empty list, so `mounts` is `Some` on every
+    /// device afterwards. Filesystems that report an anonymous device number,
+    /// such as btrfs subvolumes, cannot be matched this way.
+    pub fn enrich_mountinfo(&mut self, info: &MountInfo) {
+        fn enrich(devices: &mut [BlockDevice], info: &MountInfo) {
+            for device in devices {
+                device.mounts = Some(info.entries_for(device.maj_min).cloned().collect());
+                if let Some(children) = &mut device.children {
+                    enrich(children, info);
+                }
+            }
+        }
+        enrich(&mut self.blockdevices, info);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_lsblk;
+
+    const MOUNTINFO: &str = "\
+22 1 259:5 / / rw,relatime shared:1 - ext4 /dev/nvme0n1p2 rw
+25 22 259:4 / /boot/efi rw,relatime shared:2 - vfat /dev/nvme0n1p1 rw,fmask=0077
+31 22 8:17 / /var/lib/app rw,noatime shared:10 - xfs /dev/sdb1 rw,attr2
+48 22 8:17 /data /srv/data rw,noatime shared:10 - xfs /dev/sdb1 rw,attr2
+52 22 8:17 /app /opt/app\\040data ro,noatime shared:10 - xfs /dev/sdb1 rw,attr2
+";
+
+    const JSON: &str = r#"{
+        "blockdevices": [
+            {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                "children": [
+                    {"name": "sdb1", "maj:min": "8:17", "rm": false, "size": "1T", "ro": false, "type": "part",
+                     "mountpoints": ["/var/lib/app", "/srv/data", "/opt/app data", "/var/lib/app"]}
+                ]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_mountinfo() {
+        let info = MountInfo::parse(MOUNTINFO).expect("Failed to parse mountinfo");
+        assert_eq!(info.entries.len(), 5);
+        let efi = &info.entries[1];
+        assert_eq!(efi.mount_id, 25);
+        assert_eq!(efi.parent_id, 22);
+        assert_eq!(
+            efi.maj_min,
+            MajMin {
+                major: 259,
+                minor: 4
+            }
+        );
+        assert_eq!(efi.mount_point, "/boot/efi");
+        assert_eq!(efi.fs_type, "vfat");
+        assert_eq!(efi.source, "/dev/nvme0n1p1");
+        assert_eq!(efi.mount_options, vec!["rw", "relatime"]);
+        assert_eq!(efi.super_options, vec!["rw", "fmask=0077"]);
+        assert_eq!(info.entries[4].mount_point, "/opt/app data");
+        assert_eq!(
+            info.entries_for(MajMin {
+                major: 8,
+                minor: 17
+            })
+            .count(),
+            3
+        );
+
+        assert!(MountInfo::parse("garbage line").is_err());
+        assert_eq!(unescape_octal(r"a\040b\134c\999\0"), "a b\\c\\999\\0");
+    }
+
+    #[test]
+    fn test_unique_mountpoints() {
+        let devices = parse_lsblk(JSON).expect("Failed to parse JSON");
+        let sdb1 = devices.blockdevices[0].find_child("sdb1").unwrap();
+        assert_eq!(
+            sdb1.unique_mountpoints(),
+            vec!["/var/lib/app", "/srv/data", "/opt/app data"]
+        );
+    }
+
+    #[test]
+    fn test_canonical_mountpoint_with_mountinfo() {
+        let mut devices = parse_lsblk(JSON).expect("Failed to parse JSON");
+        devices.enrich_mountinfo(&MountInfo::parse(MOUNTINFO).unwrap());
+        let sdb = &devices.blockdevices[0];
+        assert_eq!(sdb.mounts.as_deref(), Some(&[][..]));
+        let canonical = sdb
+            .find_child("sdb1")
+            .unwrap()
+            .canonical_mountpoint()
+            .unwrap();
+        assert_eq!(canonical.path, "/var/lib/app");
+        assert_eq!(canonical.binds, vec!["/srv/data", "/opt/app data"]);
+        assert_eq!(canonical.source, CanonicalSource::MountInfo);
+    }
+
+    #[test]
+    fn test_canonical_mountpoint_heuristic() {
+        let devices = parse_lsblk(JSON).expect("Failed to parse JSON");
+        let sdb = &devices.blockdevices[0];
+        let canonical = sdb
+            .find_child("sdb1")
+            .unwrap()
+            .canonical_mountpoint()
+            .unwrap();
+        assert_eq!(canonical.path, "/srv/data");
+        assert_eq!(canonical.binds, vec!["/var/lib/app", "/opt/app data"]);
+        assert_eq!(canonical.source, CanonicalSource::Heuristic);
+        assert!(sdb.canonical_mountpoint().is_none());
+    }
+}
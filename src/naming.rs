@@ -0,0 +1,278 @@
+use crate::{BlockDevError, BlockDevice, BlockDevices, DeviceType};
+use std::path::PathBuf;
+
+/// Prefixes of drivers that append the partition number directly to the disk
+/// name (`sda1`, `vdb2`, `xvda1`).
+const DIRECT_SUFFIX_PREFIXES: &[&str] = &["sd", "hd", "vd", "xvd"];
+
+/// Prefixes of kernel names of devices that are not backed by a drive of
+/// their own: loop, RAM disk, device-mapper, zram, network block devices and
+/// KVM virtio disks. `md` is handled separately.
+const SYNTHETIC_PREFIXES: &[&str] = &["loop", "ram", "dm-", "zram", "nbd", "vd"];
+
+/// Strips the partition suffix from a kernel device name.
+///
+/// Returns `None` if the name does not look like a partition.
+pub(crate) fn partition_parent_name(name: &str) -> Option<&str> {
+    let base = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    if base.len() == name.len() || base.is_empty() {
+        return None;
+    }
+
+    // nvme0n1p1, mmcblk0p1, loop0p1, md0p1: the disk name ends in a digit, so
+    // the kernel separates the partition number with a `p`.
+    if let Some(disk) = base.strip_suffix('p') {
+        if disk.ends_with(|c: char| c.is_ascii_digit()) {
+            return Some(disk);
+        }
+    }
+
+    // sda1, vdb2, xvda1: the disk name ends in a letter.
+    if DIRECT_SUFFIX_PREFIXES.iter().any(|p| base.starts_with(p))
+        && base.ends_with(|c: char| c.is_ascii_alphabetic())
+    {
+        return Some(base);
+    }
+
+    None
+}
+
+/// Checks that `name` is a bare device name such as `sda`, so that joining it
+/// onto `/dev` cannot escape that directory.
+pub(crate) fn validate_device_name(name: &str) -> Result<(), BlockDevError> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\0']) {
+        return Err(BlockDevError::InvalidInput(format!(
+            "invalid device name '{name}': expected a bare name such as 'sda'"
+        )));
+    }
+    Ok(())
+}
+
+impl BlockDevice {
+    /// Returns the name of the disk this partition belongs to, derived from the
+    /// kernel naming scheme.
+    ///
+    /// Handles SCSI/SATA/virtio (`sda1` → `sda`), NVMe (`nvme0n1p1` → `nvme0n1`)
+    /// and MMC (`mmcblk0p1` → `mmcblk0`) names. Returns `None` for disks and for
+    /// names that do not follow a known partition pattern.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{BlockDevice, DeviceType};
+    ///
+    /// let part = BlockDevice {
+    ///     name: "nvme0n1p2".to_string(),
+    ///     device_type: DeviceType::Part,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(part.parent_device_name(), Some("nvme0n1"));
+    /// ```
+    #[must_use]
+    pub fn parent_device_name(&self) -> Option<&str> {
+        if self.device_type == DeviceType::Disk {
+            return None;
+        }
+        partition_parent_name(&self.name)
+    }
+
+    /// Returns `true` if this device's name marks it as a partition of `disk`.
+    #[must_use]
+    pub fn is_partition_of(&self, disk: &BlockDevice) -> bool {
+        self.parent_device_name() == Some(disk.name.as_str())
+    }
+
+    /// Returns the device node for this device.
+    ///
+    /// lsblk names device-mapper devices (LVM volumes and crypt mappings) by
+    /// their mapper name, so those map to `/dev/mapper/<name>`; everything else
+    /// maps to `/dev/<name>`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{BlockDevice, DeviceType};
+    /// use std::path::Path;
+    ///
+    /// let luks = BlockDevice {
+    ///     name: "luks-root".to_string(),
+    ///     device_type: DeviceType::Crypt,
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(luks.path(), Path::new("/dev/mapper/luks-root"));
+    /// ```
+    #[must_use]
+    pub fn path(&self) -> PathBuf {
+        match self.device_type {
+            DeviceType::Lvm | DeviceType::Crypt => PathBuf::from("/dev/mapper").join(&self.name),
+            _ => PathBuf::from("/dev").join(&self.name),
+        }
+    }
+
+    /// Returns `true` if this device is virtual rather than a drive, and so
+    /// should be left out of physical storage operations.
+    ///
+    /// Matches names starting with `loop`, `ram`, `dm-`, `zram`, `nbd` or `vd`
+    /// (KVM virtio), and `md` arrays unless `tran` reports a physical
+    /// transport. lsblk names device-mapper devices by their mapper name, so
+    /// loop, LVM and crypt devices are also recognised by their type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{BlockDevice, DeviceType};
+    ///
+    /// let loop0 = BlockDevice {
+    ///     name: "loop0".to_string(),
+    ///     device_type: DeviceType::Loop,
+    ///     ..Default::default()
+    /// };
+    /// assert!(loop0.is_synthetic());
+    /// ```
+    #[must_use]
+    pub fn is_synthetic(&self) -> bool {
+        matches!(
+            self.device_type,
+            DeviceType::Loop | DeviceType::Lvm | DeviceType::Crypt
+        ) || SYNTHETIC_PREFIXES.iter().any(|p| self.name.starts_with(p))
+            || (self.name.starts_with("md") && self.tran.is_none())
+    }
+}
+
+impl BlockDevices {
+    /// Returns the top-level devices that are not
+    /// [synthetic](BlockDevice::is_synthetic).
+    #[must_use]
+    pub fn physical_block_devices(&self) -> Vec<&BlockDevice> {
+        self.iter().filter(|d| !d.is_synthetic()).collect()
+    }
+
+    /// Returns every device in the tree that is not
+    /// [synthetic](BlockDevice::is_synthetic), in tree order.
+    ///
+    /// Partitions of physical disks are included. Stacked devices appear once
+    /// per parent, as in [`iter_all`](BlockDevices::iter_all).
+    #[must_use]
+    pub fn all_physical_block_devices(&self) -> Vec<&BlockDevice> {
+        self.iter_all().filter(|d| !d.is_synthetic()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    fn device(name: &str, device_type: DeviceType) -> BlockDevice {
+        BlockDevice {
+            name: name.to_string(),
+            device_type,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parent_device_name_schemes() {
+        let cases = [
+            ("sda1", Some("sda")),
+            ("sdab12", Some("sdab")),
+            ("vdb2", Some("vdb")),
+            ("xvda1", Some("xvda")),
+            ("nvme0n1p1", Some("nvme0n1")),
+            ("nvme12n3p15", Some("nvme12n3")),
+            ("mmcblk0p1", Some("mmcblk0")),
+            ("loop0p1", Some("loop0")),
+            ("md0", None),
+            ("dm-0", None),
+            ("sr0", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(
+                device(name, DeviceType::Part).parent_device_name(),
+                expected,
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parent_device_name_of_disks() {
+        for name in ["sda", "nvme0n1", "mmcblk0"] {
+            assert_eq!(device(name, DeviceType::Disk).parent_device_name(), None);
+            assert_eq!(partition_parent_name(name), None, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_is_partition_of() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let nvme3n1 = devices.find_by_name("nvme3n1").unwrap();
+        let nvme2n1 = devices.find_by_name("nvme2n1").unwrap();
+        for child in nvme3n1.children_iter() {
+            assert!(child.is_partition_of(nvme3n1), "{}", child.name);
+            assert!(!child.is_partition_of(nvme2n1), "{}", child.name);
+        }
+        assert!(!nvme3n1.is_partition_of(nvme3n1));
+    }
+
+    #[test]
+    fn test_path() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let md0 = devices.iter_all().find(|d| d.name == "md0").unwrap();
+        assert_eq!(md0.path(), PathBuf::from("/dev/md0"));
+        assert_eq!(
+            device("nvme0n1p1", DeviceType::Part).path(),
+            PathBuf::from("/dev/nvme0n1p1")
+        );
+        assert_eq!(
+            device("vg0-root", DeviceType::Lvm).path(),
+            PathBuf::from("/dev/mapper/vg0-root")
+        );
+    }
+
+    #[test]
+    fn test_is_synthetic() {
+        assert!(!device("nvme0n1", DeviceType::Disk).is_synthetic());
+        assert!(!device("sda1", DeviceType::Part).is_synthetic());
+        assert!(device("loop0", DeviceType::Loop).is_synthetic());
+        assert!(device("dm-0", DeviceType::Other).is_synthetic());
+        assert!(device("vg0-root", DeviceType::Lvm).is_synthetic());
+        for name in ["ram0", "zram0", "nbd0", "vda", "vda1", "md0", "md127p1"] {
+            assert!(device(name, DeviceType::Disk).is_synthetic(), "{name}");
+        }
+        let md = BlockDevice {
+            tran: Some("sas".to_string()),
+            ..device("md0", DeviceType::Raid1)
+        };
+        assert!(!md.is_synthetic());
+    }
+
+    #[test]
+    fn test_physical_block_devices() {
+        let mut devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let top = devices.blockdevices.len();
+        devices.blockdevices.push(device("loop0", DeviceType::Loop));
+        assert_eq!(devices.physical_block_devices().len(), top);
+
+        let all = devices.all_physical_block_devices();
+        assert!(all.iter().any(|d| d.name == "nvme3n1p5"));
+        assert!(all.iter().all(|d| !d.name.starts_with("md")));
+        assert!(all.iter().all(|d| d.name != "loop0"));
+    }
+
+    #[test]
+    fn test_validate_device_name() {
+        assert!(validate_device_name("sda").is_ok());
+        assert!(validate_device_name("nvme0n1p1").is_ok());
+        for bad in ["", ".", "..", "sda/../sdb", "../sda", "/dev/sda", "sda\0"] {
+            assert!(
+                matches!(
+                    validate_device_name(bad),
+                    Err(BlockDevError::InvalidInput(_))
+                ),
+                "'{bad}' should be rejected"
+            );
+        }
+    }
+}
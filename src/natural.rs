@@ -0,0 +1,66 @@
+use std::cmp::Ordering;
+
+/// Compares two device names in natural order, treating runs of ASCII digits
+/// as numbers so that `nvme2n1` sorts before `nvme10n1`.
+pub(crate) fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.as_bytes();
+    let mut b = b.as_bytes();
+    loop {
+        match (a.first(), b.first()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let a_len = a.iter().take_while(|c| c.is_ascii_digit()).count();
+                let b_len = b.iter().take_while(|c| c.is_ascii_digit()).count();
+                let (a_num, a_rest) = a.split_at(a_len);
+                let (b_num, b_rest) = b.split_at(b_len);
+                let a_trimmed = trim_leading_zeros(a_num);
+                let b_trimmed = trim_leading_zeros(b_num);
+                let ord = a_trimmed
+                    .len()
+                    .cmp(&b_trimmed.len())
+                    .then_with(|| a_trimmed.cmp(b_trimmed))
+                    .then_with(|| a_num.len().cmp(&b_num.len()));
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+                a = a_rest;
+                b = b_rest;
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(y);
+                }
+                a = &a[1..];
+                b = &b[1..];
+            }
+        }
+    }
+}
+
+fn trim_leading_zeros(digits: &[u8]) -> &[u8] {
+    let zeros = digits.iter().take_while(|&&c| c == b'0').count();
+    &digits[zeros..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names = vec![
+            "nvme10n1", "sdb", "nvme2n1", "sda10", "sda2", "nvme0n1", "sda",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            vec![
+                "nvme0n1", "nvme2n1", "nvme10n1", "sda", "sda2", "sda10", "sdb"
+            ]
+        );
+        assert_eq!(natural_cmp("md01", "md1"), Ordering::Greater);
+        assert_eq!(natural_cmp("md1", "md1"), Ordering::Equal);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{BlockDevice, BlockDevices};
+use std::collections::HashMap;
+
+/// Parses an NVMe namespace name `nvme<controller>n<namespace>`, such as
+/// `nvme0n1`, into its controller and namespace numbers.
+///
+/// Partitions (`nvme0n1p1`) and the per-path devices of multipath setups
+/// (`nvme0c1n1`) do not match.
+fn parse_nvme_name(name: &str) -> Option<(u32, u32)> {
+    let (controller, namespace) = name.strip_prefix("nvme")?.split_once('n')?;
+    let number = |digits: &str| {
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    };
+    Some((number(controller)?, number(namespace)?))
+}
+
+impl BlockDevice {
+    /// Returns the NVMe controller number of a namespace named
+    /// `nvme<controller>n<namespace>`, such as 0 for `nvme0n1`.
+    ///
+    /// Returns `None` for every other name, including partitions such as
+    /// `nvme0n1p1` and non-NVMe devices such as `sda`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::BlockDevice;
+    ///
+    /// let device = BlockDevice { name: "nvme3n2".to_string(), ..Default::default() };
+    /// assert_eq!(device.nvme_controller_id(), Some(3));
+    /// assert_eq!(device.nvme_namespace_id(), Some(2));
+    /// ```
+    #[must_use]
+    pub fn nvme_controller_id(&self) -> Option<u32> {
+        parse_nvme_name(&self.name).map(|(controller, _)| controller)
+    }
+
+    /// Returns the NVMe namespace number of a namespace named
+    /// `nvme<controller>n<namespace>`, such as 1 for `nvme0n1`.
+    ///
+    /// Returns `None` for the same names as
+    /// [`nvme_controller_id`](Self::nvme_controller_id).
+    #[must_use]
+    pub fn nvme_namespace_id(&self) -> Option<u32> {
+        parse_nvme_name(&self.name).map(|(_, namespace)| namespace)
+    }
+
+    /// Returns `true` if this is an NVMe device.
+    ///
+    /// The `tran` column is authoritative when it is set. Without it (it is only
+    /// requested by `get_devices_extended`, and lsblk leaves it empty for
+    /// partitions), the kernel name is used: NVMe namespaces and their
+    /// partitions are named `nvme*`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::BlockDevice;
+    ///
+    /// let by_name = BlockDevice { name: "nvme0n1".to_string(), ..Default::default() };
+    /// assert!(by_name.is_nvm_express());
+    ///
+    /// let over_fabric = BlockDevice {
+    ///     name: "sdb".to_string(),
+    ///     tran: Some("nvme".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// assert!(over_fabric.is_nvm_express());
+    /// ```
+    #[must_use]
+    pub fn is_nvm_express(&self) -> bool {
+        match self.tran.as_deref() {
+            Some(tran) if !tran.is_empty() => tran == "nvme",
+            _ => self.name.starts_with("nvme"),
+        }
+    }
+}
+
+impl BlockDevices {
+    /// Returns the top-level NVMe disks, in snapshot order.
+    #[must_use]
+    pub fn nvme_disks(&self) -> Vec<&BlockDevice> {
+        self.iter()
+            .filter(|d| d.is_disk() && d.is_nvm_express())
+            .collect()
+    }
+
+    /// Groups the top-level NVMe namespaces by
+    /// [controller](BlockDevice::nvme_controller_id).
+    ///
+    /// Within each group, namespaces keep the order in which they appear in
+    /// the snapshot.
+    #[must_use]
+    pub fn group_by_nvme_controller(&self) -> HashMap<u32, Vec<&BlockDevice>> {
+        let mut groups: HashMap<u32, Vec<&BlockDevice>> = HashMap::new();
+        for device in self.iter() {
+            if let Some(controller) = device.nvme_controller_id() {
+                groups.entry(controller).or_default().push(device);
+            }
+        }
+        groups
+    }
+
+    /// Returns the total size in bytes of the top-level NVMe disks.
+    #[must_use]
+    pub fn nvme_capacity_bytes(&self) -> u64 {
+        self.nvme_disks().iter().map(|d| d.size).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    #[test]
+    fn test_nvme_disks_sample() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        assert_eq!(devices.nvme_disks().len(), 10);
+
+        // Eight 3.5T and two 894.3G disks: roughly 29.7 TiB.
+        let tib = devices.nvme_capacity_bytes() as f64 / (1u64 << 40) as f64;
+        assert!((29.0..31.0).contains(&tib), "{tib}");
+        let md0 = devices.iter_all().find(|d| d.name == "md0").unwrap();
+        assert!(!md0.is_nvm_express());
+    }
+
+    #[test]
+    fn test_nvme_ids_sample() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let groups = devices.group_by_nvme_controller();
+        let mut controllers: Vec<u32> = groups.keys().copied().collect();
+        controllers.sort_unstable();
+        assert_eq!(controllers, (0..10).collect::<Vec<_>>());
+        for (controller, namespaces) in &groups {
+            assert_eq!(namespaces.len(), 1);
+            assert_eq!(namespaces[0].name, format!("nvme{controller}n1"));
+            assert_eq!(namespaces[0].nvme_namespace_id(), Some(1));
+        }
+        for partition in devices.iter_all().filter(|d| d.name.contains('p')) {
+            assert_eq!(partition.nvme_controller_id(), None, "{}", partition.name);
+        }
+    }
+
+    #[test]
+    fn test_parse_nvme_name() {
+        assert_eq!(parse_nvme_name("nvme0n1"), Some((0, 1)));
+        assert_eq!(parse_nvme_name("nvme12n3"), Some((12, 3)));
+        for name in [
+            "sda",
+            "nvme",
+            "nvme0",
+            "nvme0n",
+            "nvmen1",
+            "nvme0n1p1",
+            "nvme0c1n1",
+            "nvme+1n1",
+            "md0",
+        ] {
+            assert_eq!(parse_nvme_name(name), None, "{name}");
+        }
+        let sda = BlockDevice {
+            name: "sda".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(sda.nvme_controller_id(), None);
+        assert_eq!(sda.nvme_namespace_id(), None);
+
+        let devices = parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "nvme0n1", "maj:min": "259:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "nvme0n2", "maj:min": "259:1", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+        let groups = devices.group_by_nvme_controller();
+        assert_eq!(groups.len(), 1);
+        let names: Vec<&str> = groups[&0].iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["nvme0n1", "nvme0n2"]);
+    }
+
+    #[test]
+    fn test_is_nvm_express_transport_wins() {
+        let device = |name: &str, tran: Option<&str>| BlockDevice {
+            name: name.to_string(),
+            tran: tran.map(str::to_string),
+            ..Default::default()
+        };
+        assert!(device("nvme0n1", None).is_nvm_express());
+        assert!(device("nvme0n1p1", Some("")).is_nvm_express());
+        assert!(device("nvme0n1", Some("nvme")).is_nvm_express());
+        assert!(device("sda", Some("nvme")).is_nvm_express());
+        assert!(!device("nvme0n1", Some("sata")).is_nvm_express());
+        assert!(!device("sda", None).is_nvm_express());
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{BlockDevError, BlockDevice, BlockDevices};
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Extracts the message from a panic payload.
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+impl BlockDevices {
+    /// Runs `f` for every top-level device on at most `concurrency` threads and
+    /// returns the results in device order.
+    ///
+    /// This is meant for slow per-device work such as `lsblk` or `blkid` queries,
+    /// where running one device at a time is too slow but querying every device at
+    /// once would overload udev. A `concurrency` of zero is treated as one.
+    ///
+    /// If `f` panics for a device, the panic is caught and reported as
+    /// [`BlockDevError::WorkerPanicked`] for that device; the other devices are
+    /// still processed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null]},
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]}
+    /// ]}"#).unwrap();
+    ///
+    /// let names = devices.for_each_parallel(4, |device| Ok(device.name.to_uppercase()));
+    /// assert_eq!(names[0].as_ref().unwrap(), "SDA");
+    /// assert_eq!(names[1].as_ref().unwrap(), "SDB");
+    /// ```
+    pub fn for_each_parallel<F, T>(&self, concurrency: usize, f: F) -> Vec<Result<T, BlockDevError>>
+    where
+        F: Fn(&BlockDevice) -> Result<T, BlockDevError> + Sync,
+        T: Send,
+    {
+        let devices = &self.blockdevices;
+        let results: Vec<Mutex<Option<Result<T, BlockDevError>>>> =
+            devices.iter().map(|_| Mutex::new(None)).collect();
+        let next = AtomicUsize::new(0);
+        let workers = concurrency.clamp(1, devices.len().max(1));
+
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| {
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(device) = devices.get(index) else {
+                            break;
+                        };
+                        let result = panic::catch_unwind(AssertUnwindSafe(|| f(device)))
+                            .unwrap_or_else(|payload| {
+                                Err(BlockDevError::WorkerPanicked(format!(
+                                    "{}: {}",
+                                    device.name,
+                                    panic_message(payload.as_ref())
+                                )))
+                            });
+                        *results[index].lock().unwrap_or_else(|e| e.into_inner()) = Some(result);
+                    }
+                });
+            }
+        });
+
+        results
+            .into_iter()
+            .map(|slot| {
+                slot.into_inner()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .expect("every device is processed before the scope ends")
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+    use std::sync::Condvar;
+    use std::time::Duration;
+
+    #[test]
+    fn test_for_each_parallel_order_and_concurrency() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let active = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        // The first four devices wait until all four have started, which can
+        // only happen if four workers run at once. The timeout turns a
+        // missing worker into a failure rather than a hang.
+        let arrived = Mutex::new(0);
+        let all_arrived = Condvar::new();
+
+        let results = devices.for_each_parallel(4, |device| {
+            let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            let index = devices.iter().position(|d| d.name == device.name).unwrap();
+            if index < 4 {
+                let mut arrived = arrived.lock().unwrap();
+                *arrived += 1;
+                all_arrived.notify_all();
+                let (arrived, timeout) = all_arrived
+                    .wait_timeout_while(arrived, Duration::from_secs(30), |n| *n < 4)
+                    .unwrap();
+                assert!(!timeout.timed_out(), "only {} workers overlapped", *arrived);
+            }
+            active.fetch_sub(1, Ordering::SeqCst);
+            Ok(device.name.clone())
+        });
+
+        let names: Vec<String> = results.into_iter().map(Result::unwrap).collect();
+        let expected: Vec<String> = devices.iter().map(|d| d.name.clone()).collect();
+        assert_eq!(names, expected);
+        assert_eq!(peak.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_for_each_parallel_panic_is_isolated() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let results = devices.for_each_parallel(3, |device| {
+            if device.name == "nvme3n1" {
+                panic!("probe failed");
+            }
+            if device.name == "nvme2n1" {
+                return Err(BlockDevError::DeviceNotFound(device.name.clone()));
+            }
+            Ok(device.size)
+        });
+
+        assert_eq!(results.len(), devices.len());
+        for (device, result) in devices.iter().zip(&results) {
+            match device.name.as_str() {
+                "nvme3n1" => match result {
+                    Err(BlockDevError::WorkerPanicked(message)) => {
+                        assert_eq!(message, "nvme3n1: probe failed");
+                    }
+                    other => panic!("unexpected result {other:?}"),
+                },
+                "nvme2n1" => assert!(matches!(result, Err(BlockDevError::DeviceNotFound(_)))),
+                _ => assert_eq!(result.as_ref().unwrap(), &device.size),
+            }
+        }
+    }
+
+    #[test]
+    fn test_for_each_parallel_edge_cases() {
+        let empty = BlockDevices::default();
+        assert!(empty.for_each_parallel(8, |_| Ok(())).is_empty());
+
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let results = devices.for_each_parallel(0, |device| Ok(device.name.len()));
+        assert_eq!(results.len(), devices.len());
+        assert!(results.iter().all(Result::is_ok));
+    }
+}
@@ -0,0 +1,1016 @@
+use crate::{BlockDevError, BlockDevice, BlockDevices, MajMin};
+use serde::de::Error as DeError;
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serializer};
+use serde_json::Value;
+use std::str::FromStr;
+
+/// Parses a human-readable size string (e.g., "500G", "3.5T") into bytes.
+pub(crate) fn parse_size_string(s: &str) -> Option<u64> {
+    let s = s.trim();
+    if s.is_empty() {
+        return None;
+    }
+
+    // Find where the numeric part ends and the suffix begins
+    let (num_part, suffix) = {
+        let idx = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        (&s[..idx], s[idx..].trim())
+    };
+
+    let num: f64 = num_part.parse().ok()?;
+    let multiplier: u64 = match suffix.to_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1024,
+        "M" | "MB" | "MIB" => 1024 * 1024,
+        "G" | "GB" | "GIB" => 1024 * 1024 * 1024,
+        "T" | "TB" | "TIB" => 1024 * 1024 * 1024 * 1024,
+        "P" | "PB" | "PIB" => 1024 * 1024 * 1024 * 1024 * 1024,
+        _ => return None,
+    };
+
+    Some((num * multiplier as f64) as u64)
+}
+
+/// Custom deserializer that handles both numeric byte values and human-readable size strings.
+pub(crate) fn deserialize_size<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    match &value {
+        Value::Number(n) => n
+            .as_u64()
+            .or_else(|| n.as_f64().map(|f| f as u64))
+            .ok_or_else(|| DeError::custom("invalid numeric size")),
+        Value::String(s) => {
+            parse_size_string(s).ok_or_else(|| DeError::custom(format!("invalid size string: {s}")))
+        }
+        _ => Err(DeError::custom("size must be a number or string")),
+    }
+}
+
+/// Custom deserializer for optional size columns such as `FSSIZE`.
+///
+/// Accepts `null`, a byte count, or a human-readable size string, which lsblk
+/// writes when run without `--bytes`.
+pub(crate) fn deserialize_optional_size<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    match &value {
+        Value::Null => Ok(None),
+        Value::String(s) if s.trim().is_empty() => Ok(None),
+        _ => deserialize_size(value).map(Some).map_err(DeError::custom),
+    }
+}
+
+/// Custom deserializer for optional numeric columns.
+///
+/// Accepts `null`, a JSON number, or a string holding a decimal number, since
+/// older lsblk versions quote every value.
+pub(crate) fn deserialize_optional_u64<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    match &value {
+        Value::Null => Ok(None),
+        Value::Number(n) => n
+            .as_u64()
+            .map(Some)
+            .ok_or_else(|| DeError::custom(format!("invalid unsigned number: {n}"))),
+        Value::String(s) if s.trim().is_empty() => Ok(None),
+        Value::String(s) => s
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| DeError::custom(format!("invalid unsigned number: {s}"))),
+        _ => Err(DeError::custom(
+            "expected a number, a numeric string or null",
+        )),
+    }
+}
+
+/// Deserializes an optional boolean column such as `ROTA`.
+///
+/// Accepts JSON booleans, `null`, and the strings `"0"` and `"1"` that lsblk
+/// versions before util-linux 2.33 write.
+pub(crate) fn deserialize_optional_bool<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    match &value {
+        Value::Null => Ok(None),
+        Value::Bool(b) => Ok(Some(*b)),
+        Value::String(s) => match s.trim() {
+            "" => Ok(None),
+            "0" | "false" => Ok(Some(false)),
+            "1" | "true" => Ok(Some(true)),
+            other => Err(DeError::custom(format!("invalid boolean: {other}"))),
+        },
+        _ => Err(DeError::custom("expected a boolean, \"0\", \"1\" or null")),
+    }
+}
+
+/// Deserializes a required flag column, `RM` or `RO`.
+///
+/// Accepts everything [`deserialize_optional_bool`] does and reads `null`,
+/// which some patched lsblk builds write for device-mapper devices, as `false`.
+pub(crate) fn deserialize_flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(deserialize_optional_bool(deserializer)?.unwrap_or(false))
+}
+
+/// Custom deserializer for the device number of a flattened `BlockDevice`.
+///
+/// Uses the combined `"maj:min"` field when present and otherwise the separate
+/// `"maj"` and `"min"` fields, which may be numbers or numeric strings.
+pub(crate) fn deserialize_device_number<'de, D>(deserializer: D) -> Result<MajMin, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct DeviceNumberFields {
+        #[serde(rename = "maj:min")]
+        maj_min: Option<MajMin>,
+        #[serde(default, deserialize_with = "deserialize_optional_u64")]
+        maj: Option<u64>,
+        #[serde(default, deserialize_with = "deserialize_optional_u64")]
+        min: Option<u64>,
+    }
+
+    let fields = DeviceNumberFields::deserialize(deserializer)?;
+    if let Some(maj_min) = fields.maj_min {
+        return Ok(maj_min);
+    }
+    match (fields.maj, fields.min) {
+        (Some(major), Some(minor)) => Ok(MajMin {
+            major: u32::try_from(major).map_err(DeError::custom)?,
+            minor: u32::try_from(minor).map_err(DeError::custom)?,
+        }),
+        _ => Err(DeError::missing_field("maj:min")),
+    }
+}
+
+/// Serializes the device number of a flattened `BlockDevice` as the combined
+/// `"maj:min"` field.
+pub(crate) fn serialize_device_number<S>(maj_min: &MajMin, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry("maj:min", maj_min)?;
+    map.end()
+}
+
+/// Custom deserializer that supports both a single mountpoint (which may be null)
+/// and an array of mountpoints.
+///
+/// # Arguments
+///
+/// * `deserializer` - The deserializer instance.
+///
+/// # Returns
+///
+/// A vector of optional strings representing mountpoints.
+///
+/// # Errors
+///
+/// Returns an error quoting the offending JSON unless the value is null, a
+/// string, or an array of strings and nulls.
+///
+/// This function is used internally by Serde when deserializing block devices.
+/// For example, if the JSON value is `null`, it will be converted to `vec![None]`.
+pub(crate) fn deserialize_mountpoints<'de, D>(
+    deserializer: D,
+) -> Result<Vec<Option<String>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(vec![None]),
+        Value::String(mountpoint) => Ok(vec![Some(mountpoint)]),
+        Value::Array(entries) => {
+            if let Some(bad) = entries.iter().find(|e| !e.is_null() && !e.is_string()) {
+                return Err(DeError::custom(format!(
+                    "invalid mountpoints entry {bad} in {}: expected a string or null",
+                    Value::Array(entries.clone())
+                )));
+            }
+            Ok(entries
+                .into_iter()
+                .map(|entry| match entry {
+                    Value::String(mountpoint) => Some(mountpoint),
+                    _ => None,
+                })
+                .collect())
+        }
+        other => Err(DeError::custom(format!(
+            "invalid mountpoints {other}: expected null, a string or an array of strings and nulls"
+        ))),
+    }
+}
+
+/// Deserializes one device object, prefixing any error with the device's
+/// name when it has one.
+///
+/// The prefixes of nested devices add up, so an error in a partition reads
+/// like `device 'sda': device 'sda1': ...`.
+fn device_from_value<E: DeError>(value: Value) -> Result<BlockDevice, E> {
+    let name = value
+        .get("name")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    BlockDevice::deserialize(value).map_err(|error| match name {
+        Some(name) => E::custom(format!("device '{name}': {error}")),
+        None => E::custom(error),
+    })
+}
+
+/// Deserializes the `blockdevices` array with [`device_from_value`].
+pub(crate) fn deserialize_devices<'de, D>(deserializer: D) -> Result<Vec<BlockDevice>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<Value>::deserialize(deserializer)?
+        .into_iter()
+        .map(device_from_value)
+        .collect()
+}
+
+/// Deserializes a `children` array, which may be null, with [`device_from_value`].
+pub(crate) fn deserialize_children<'de, D>(
+    deserializer: D,
+) -> Result<Option<Vec<BlockDevice>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<Vec<Value>>::deserialize(deserializer)?
+        .map(|children| children.into_iter().map(device_from_value).collect())
+        .transpose()
+}
+
+/// Parses a JSON string (produced by `lsblk --json`)
+/// into a `BlockDevices` struct.
+///
+/// This function is useful when you already have JSON data from `lsblk`
+/// and want to parse it without running the command again.
+///
+/// # Arguments
+///
+/// * `json_data` - A string slice containing the JSON data.
+///
+/// # Errors
+///
+/// Returns a `serde_json::Error` if the JSON cannot be parsed.
+///
+/// # Leading warnings
+///
+/// Some lsblk builds print warnings such as `lsblk: /dev/sr0: unknown device`
+/// on stdout before the JSON. If the input does not start with a JSON object,
+/// the first `{` that begins a parseable document (within a bounded number of
+/// attempts) is used instead, and the skipped lines are recorded in
+/// [`BlockDevices::warnings`]. Anything after the document is still an error.
+///
+/// # Examples
+///
+/// ```
+/// use blockdev::parse_lsblk;
+///
+/// let json = r#"{"blockdevices": [{"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null]}]}"#;
+/// let devices = parse_lsblk(json).expect("Failed to parse JSON");
+/// assert_eq!(devices.len(), 1);
+/// ```
+pub fn parse_lsblk(json_data: &str) -> Result<BlockDevices, serde_json::Error> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("parse_lsblk", bytes = json_data.len()).entered();
+    let result = parse_lsblk_document(json_data);
+    #[cfg(feature = "tracing")]
+    match &result {
+        Ok(devices) => tracing::debug!(
+            device_count = devices.iter_all().count(),
+            warning_count = devices.warnings.len(),
+            "parsed lsblk output"
+        ),
+        Err(error) => tracing::debug!(%error, "lsblk output is not valid"),
+    }
+    result
+}
+
+/// Parses one lsblk document for [`parse_lsblk`], skipping leading warnings.
+fn parse_lsblk_document(json_data: &str) -> Result<BlockDevices, serde_json::Error> {
+    let error = match serde_json::from_str(json_data) {
+        Ok(devices) => return Ok(devices),
+        Err(error) => error,
+    };
+    if json_data.trim_start().starts_with('{') {
+        return Err(error);
+    }
+
+    for (start, _) in json_data.match_indices('{').take(MAX_JSON_START_ATTEMPTS) {
+        if let Ok(mut devices) = serde_json::from_str::<BlockDevices>(&json_data[start..]) {
+            devices.warnings = json_data[..start]
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect();
+            return Ok(devices);
+        }
+    }
+    Err(error)
+}
+
+/// How many `{` characters [`parse_lsblk`] tries as the start of the document
+/// when the output is prefixed with warnings.
+pub(crate) const MAX_JSON_START_ATTEMPTS: usize = 16;
+
+impl FromStr for BlockDevices {
+    type Err = serde_json::Error;
+
+    /// Parses `lsblk --json` output with [`parse_lsblk`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::BlockDevices;
+    ///
+    /// let devices: BlockDevices = r#"{"blockdevices": [{"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null]}]}"#
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(devices.len(), 1);
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_lsblk(s)
+    }
+}
+
+impl TryFrom<&str> for BlockDevices {
+    type Error = serde_json::Error;
+
+    /// Parses `lsblk --json` output with [`parse_lsblk`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::BlockDevices;
+    ///
+    /// let json = r#"{"blockdevices": [{"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null]}]}"#;
+    /// let devices = BlockDevices::try_from(json).unwrap();
+    /// assert!(devices.find_by_name("sda").is_some());
+    /// ```
+    fn try_from(json: &str) -> Result<Self, Self::Error> {
+        parse_lsblk(json)
+    }
+}
+
+impl TryFrom<Value> for BlockDevices {
+    type Error = serde_json::Error;
+
+    /// Converts an already parsed `lsblk --json` document, such as one nested in
+    /// a larger JSON structure, without serializing it back to a string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::BlockDevices;
+    /// use serde_json::json;
+    ///
+    /// let facts = json!({"host": "db-01", "lsblk": {"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null]}
+    /// ]}});
+    /// let devices = BlockDevices::try_from(facts["lsblk"].clone()).unwrap();
+    /// assert_eq!(devices.len(), 1);
+    /// ```
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value)
+    }
+}
+
+impl BlockDevices {
+    /// Converts an already parsed `lsblk --json` document, like the
+    /// `TryFrom<Value>` implementation but returning the crate's error type.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::JsonParse`] if `value` is not a valid lsblk document.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::BlockDevices;
+    /// use serde_json::json;
+    ///
+    /// let value = json!({"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null]}
+    /// ]});
+    /// let devices = BlockDevices::from_json_value(value).unwrap();
+    /// assert_eq!(devices.blockdevices[0].name, "sda");
+    /// ```
+    pub fn from_json_value(value: Value) -> Result<BlockDevices, BlockDevError> {
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl BlockDevice {
+    /// Converts a single already parsed device object, as found in the
+    /// `blockdevices` array of `lsblk --json` output, including its children.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::JsonParse`] if `value` is not a valid device object.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::BlockDevice;
+    /// use serde_json::json;
+    ///
+    /// let value = json!({"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null]});
+    /// let sda = BlockDevice::from_json_value(value).unwrap();
+    /// assert_eq!(sda.size, 500 * 1024 * 1024 * 1024);
+    /// ```
+    pub fn from_json_value(value: Value) -> Result<BlockDevice, BlockDevError> {
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl From<Vec<BlockDevice>> for BlockDevices {
+    /// Wraps a list of top-level devices, without metadata or warnings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{BlockDevice, BlockDevices};
+    ///
+    /// let sda = BlockDevice { name: "sda".to_string(), ..Default::default() };
+    /// let devices = BlockDevices::from(vec![sda]);
+    /// assert!(devices.meta.is_none());
+    /// assert_eq!(devices.len(), 1);
+    /// ```
+    fn from(blockdevices: Vec<BlockDevice>) -> Self {
+        BlockDevices {
+            blockdevices,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::{DeviceType, test_utils};
+
+    #[test]
+    fn test_parse_lsblk() {
+        let lsblk = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+
+        lsblk.assert_device_count(10);
+
+        // Verify that required fields are non-empty.
+        for device in &lsblk.blockdevices {
+            assert!(!device.name.is_empty(), "Device name should not be empty");
+        }
+
+        // Pick a device with nested children and validate details.
+        let nvme3n1 = lsblk
+            .blockdevices
+            .iter()
+            .find(|d| d.name == "nvme3n1")
+            .expect("Expected to find device nvme3n1");
+
+        // Its first mountpoint should be None.
+        assert!(
+            nvme3n1
+                .mountpoints
+                .first()
+                .and_then(|opt| opt.as_deref())
+                .is_none(),
+            "nvme3n1 effective mountpoint should be None"
+        );
+
+        // Verify that nvme3n1 has exactly 6 children.
+        let children = nvme3n1
+            .children
+            .as_ref()
+            .expect("nvme3n1 should have children");
+        assert_eq!(children.len(), 6, "nvme3n1 should have 6 children");
+
+        // Validate that child nvme3n1p2 has first mountpoint of "/boot/efi".
+        let nvme3n1p2 = children
+            .iter()
+            .find(|c| c.name == "nvme3n1p2")
+            .expect("Expected to find nvme3n1p2");
+        assert_eq!(
+            nvme3n1p2.mountpoints.first().and_then(|opt| opt.as_deref()),
+            Some("/boot/efi"),
+            "nvme3n1p2 first mountpoint should be '/boot/efi'"
+        );
+
+        // In nvme3n1p3, verify that its nested child md0 has an effective mountpoint of "/boot".
+        let nvme3n1p3 = children
+            .iter()
+            .find(|c| c.name == "nvme3n1p3")
+            .expect("Expected to find nvme3n1p3");
+        let nested_children = nvme3n1p3
+            .children
+            .as_ref()
+            .expect("nvme3n1p3 should have children");
+        let md0 = nested_children
+            .iter()
+            .find(|d| d.name == "md0")
+            .expect("Expected to find md0 under nvme3n1p3");
+        assert_eq!(
+            md0.mountpoints.first().and_then(|opt| opt.as_deref()),
+            Some("/boot"),
+            "md0 effective mountpoint should be '/boot'"
+        );
+
+        lsblk.assert_device_has_mountpoint("md2", "/");
+        lsblk.assert_device_has_mountpoint("md1", "[SWAP]");
+
+        // Test the non_system method.
+        // Since nvme3n1 has a descendant (md2) with effective mountpoint "/" it should be excluded.
+        lsblk.assert_non_system_count(8);
+        let non_system = lsblk.non_system();
+        assert!(
+            !non_system.iter().any(|d| d.name == "nvme3n1"),
+            "nvme3n1 should be excluded from non-system devices"
+        );
+        test_utils::assert_contains_no_system_devices(&non_system);
+    }
+
+    #[test]
+    fn test_non_system() {
+        // Create a JSON where one device is system (has "/" mountpoint in a child)
+        // and one is non-system.
+        let test_json = r#"
+        {
+            "blockdevices": [
+                {
+                    "name": "sda",
+                    "maj:min": "8:0",
+                    "rm": false,
+                    "size": "447.1G",
+                    "ro": false,
+                    "type": "disk",
+                    "mountpoints": [
+                        null
+                    ],
+                    "children": [
+                        {
+                        "name": "sda1",
+                        "maj:min": "8:1",
+                        "rm": false,
+                        "size": "512M",
+                        "ro": false,
+                        "type": "part",
+                        "mountpoints": [
+                            null
+                        ]
+                        },{
+                        "name": "sda2",
+                        "maj:min": "8:2",
+                        "rm": false,
+                        "size": "446.6G",
+                        "ro": false,
+                        "type": "part",
+                        "mountpoints": [
+                            null
+                        ],
+                        "children": [
+                            {
+                                "name": "md0",
+                                "maj:min": "9:0",
+                                "rm": false,
+                                "size": "446.6G",
+                                "ro": false,
+                                "type": "raid1",
+                                "mountpoints": [
+                                    "/"
+                                ]
+                            }
+                        ]
+                        }
+                    ]
+                },{
+                    "name": "sdb",
+                    "maj:min": "8:16",
+                    "rm": false,
+                    "size": "447.1G",
+                    "ro": false,
+                    "type": "disk",
+                    "mountpoints": [
+                        null
+                    ],
+                    "children": [
+                        {
+                        "name": "sdb1",
+                        "maj:min": "8:17",
+                        "rm": false,
+                        "size": "512M",
+                        "ro": false,
+                        "type": "part",
+                        "mountpoints": [
+                            "/boot/efi"
+                        ]
+                        },{
+                        "name": "sdb2",
+                        "maj:min": "8:18",
+                        "rm": false,
+                        "size": "446.6G",
+                        "ro": false,
+                        "type": "part",
+                        "mountpoints": [
+                            null
+                        ],
+                        "children": [
+                            {
+                                "name": "md0",
+                                "maj:min": "9:0",
+                                "rm": false,
+                                "size": "446.6G",
+                                "ro": false,
+                                "type": "raid1",
+                                "mountpoints": [
+                                    "/"
+                                ]
+                            }
+                        ]
+                        }
+                    ]
+                },{
+                    "name": "nvme0n1",
+                    "maj:min": "259:2",
+                    "rm": false,
+                    "size": "1.7T",
+                    "ro": false,
+                    "type": "disk",
+                    "mountpoints": [
+                        null
+                    ]
+                },{
+                    "name": "nvme1n1",
+                    "maj:min": "259:3",
+                    "rm": false,
+                    "size": "1.7T",
+                    "ro": false,
+                    "type": "disk",
+                    "mountpoints": [
+                        null
+                    ]
+                }
+            ]
+        }
+        "#;
+        let disks = parse_lsblk(test_json).unwrap();
+        disks.assert_device_count(4);
+        disks.assert_non_system_count(2);
+        test_utils::assert_device_names_eq(&disks.non_system(), &["nvme0n1", "nvme1n1"]);
+        disks.assert_device_has_mountpoint("sdb1", "/boot/efi");
+    }
+
+    #[test]
+    fn test_conversions() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        assert_eq!(SAMPLE_JSON.parse::<BlockDevices>().unwrap(), devices);
+        assert_eq!(BlockDevices::try_from(SAMPLE_JSON).unwrap(), devices);
+
+        let value: Value = serde_json::from_str(SAMPLE_JSON).unwrap();
+        assert_eq!(BlockDevices::try_from(value).unwrap(), devices);
+        assert_eq!(BlockDevices::from(devices.blockdevices.clone()), devices);
+    }
+
+    #[test]
+    fn test_from_json_value() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let value: Value = serde_json::from_str(SAMPLE_JSON).unwrap();
+        assert_eq!(
+            BlockDevices::from_json_value(value.clone()).unwrap(),
+            devices
+        );
+        assert_eq!(
+            BlockDevice::from_json_value(value["blockdevices"][0].clone()).unwrap(),
+            devices.blockdevices[0]
+        );
+
+        assert!(matches!(
+            BlockDevices::from_json_value(serde_json::json!({"devices": []})),
+            Err(BlockDevError::JsonParse(_))
+        ));
+        assert!(matches!(
+            BlockDevice::from_json_value(serde_json::json!({"name": "sda"})),
+            Err(BlockDevError::JsonParse(_))
+        ));
+    }
+
+    #[test]
+    fn test_conversion_errors() {
+        for malformed in ["", "{", r#"{"blockdevices": [{"name": "sda"}]}"#, "[]"] {
+            assert!(malformed.parse::<BlockDevices>().is_err(), "{malformed}");
+            assert!(BlockDevices::try_from(malformed).is_err(), "{malformed}");
+        }
+        for wrong_shape in [
+            serde_json::json!([]),
+            serde_json::json!({"devices": []}),
+            serde_json::json!({"blockdevices": {"name": "sda"}}),
+            serde_json::json!({"blockdevices": [{"name": "sda", "maj:min": "8:0", "size": true}]}),
+        ] {
+            assert!(
+                BlockDevices::try_from(wrong_shape.clone()).is_err(),
+                "{wrong_shape}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty_blockdevices() {
+        let json = r#"{"blockdevices": []}"#;
+        let devices = parse_lsblk(json).expect("Failed to parse empty JSON");
+        assert!(devices.is_empty());
+        assert_eq!(devices.len(), 0);
+        assert!(devices.non_system().is_empty());
+        assert!(devices.system().is_empty());
+        assert!(devices.find_by_name("sda").is_none());
+    }
+
+    #[test]
+    fn test_clone_trait() {
+        let json = r#"{"blockdevices": [{"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null]}]}"#;
+        let devices = parse_lsblk(json).expect("Failed to parse JSON");
+        let cloned = devices.clone();
+        assert_eq!(devices, cloned);
+        assert_eq!(cloned.len(), 1);
+    }
+
+    #[test]
+    fn test_serialization_roundtrip() {
+        let json = r#"{"blockdevices":[{"name":"sda","maj:min":"8:0","rm":false,"size":"500G","ro":false,"type":"disk","mountpoints":[null],"children":null}]}"#;
+        let devices = parse_lsblk(json).expect("Failed to parse JSON");
+        let serialized = serde_json::to_string(&devices).expect("Failed to serialize");
+        let deserialized: BlockDevices =
+            serde_json::from_str(&serialized).expect("Failed to deserialize");
+        assert_eq!(devices, deserialized);
+    }
+
+    #[test]
+    fn test_device_with_direct_root_mount() {
+        let json = r#"{
+            "blockdevices": [{
+                "name": "sda",
+                "maj:min": "8:0",
+                "rm": false,
+                "size": "500G",
+                "ro": false,
+                "type": "disk",
+                "mountpoints": ["/"]
+            }]
+        }"#;
+        let devices = parse_lsblk(json).expect("Failed to parse JSON");
+        let device = devices.find_by_name("sda").unwrap();
+        assert!(device.is_system());
+        assert!(device.is_mounted());
+        assert_eq!(device.active_mountpoints(), vec!["/"]);
+        assert_eq!(devices.system().len(), 1);
+        assert!(devices.non_system().is_empty());
+    }
+
+    #[test]
+    fn test_system_method() {
+        let json = r#"{
+            "blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": ["/"]},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": ["/home"]}
+            ]
+        }"#;
+        let devices = parse_lsblk(json).expect("Failed to parse JSON");
+        let system = devices.system();
+        assert_eq!(system.len(), 1);
+        assert_eq!(system[0].name, "sda");
+    }
+
+    #[test]
+    fn test_multiple_mountpoints() {
+        let json = r#"{
+            "blockdevices": [{
+                "name": "sda",
+                "maj:min": "8:0",
+                "rm": false,
+                "size": "500G",
+                "ro": false,
+                "type": "disk",
+                "mountpoints": ["/mnt/data", "/mnt/backup", null]
+            }]
+        }"#;
+        let devices = parse_lsblk(json).expect("Failed to parse JSON");
+        let device = devices.find_by_name("sda").unwrap();
+        assert!(device.is_mounted());
+        assert_eq!(
+            device.active_mountpoints(),
+            vec!["/mnt/data", "/mnt/backup"]
+        );
+    }
+
+    #[test]
+    fn test_removable_and_readonly() {
+        let json = r#"{
+            "blockdevices": [{
+                "name": "sr0",
+                "maj:min": "11:0",
+                "rm": true,
+                "size": "4.7G",
+                "ro": true,
+                "type": "rom",
+                "mountpoints": [null]
+            }]
+        }"#;
+        let devices = parse_lsblk(json).expect("Failed to parse JSON");
+        let device = devices.find_by_name("sr0").unwrap();
+        assert!(device.rm);
+        assert!(device.ro);
+        assert_eq!(device.device_type, DeviceType::Rom);
+    }
+
+    /// Output of a patched Alpine lsblk, which writes `null` flags for dm devices.
+    const NULL_FLAGS_JSON: &str = r#"{"blockdevices": [
+        {"name": "vda", "maj:min": "253:0", "rm": false, "size": "20G", "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "vda1", "maj:min": "253:1", "rm": false, "size": "20G", "ro": false, "type": "part", "mountpoints": [null],
+             "children": [
+                {"name": "vg0-root", "maj:min": "252:0", "rm": null, "size": "20G", "ro": null, "type": "lvm", "mountpoints": ["/"]}
+             ]}
+         ]},
+        {"name": "sr0", "maj:min": "11:0", "rm": "1", "size": "1024M", "ro": "0", "type": "rom", "mountpoints": [null]}
+    ]}"#;
+
+    #[test]
+    fn test_null_flags_read_as_false() {
+        let devices = parse_lsblk(NULL_FLAGS_JSON).expect("Failed to parse JSON");
+        let root = devices.iter_all().find(|d| d.name == "vg0-root").unwrap();
+        assert_eq!(root.active_mountpoints(), vec!["/"]);
+        assert!(!root.is_removable());
+        assert!(!root.is_read_only());
+        let sr0 = devices.find_by_name("sr0").unwrap();
+        assert!(sr0.is_removable());
+        assert!(!sr0.is_read_only());
+
+        for bad in [r#""rm": "yes""#, r#""rm": 1"#] {
+            let json = NULL_FLAGS_JSON.replacen(r#""rm": null"#, bad, 1);
+            assert!(parse_lsblk(&json).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_parse_size_zero_and_empty() {
+        assert_eq!(parse_size_string("0B"), Some(0));
+        assert_eq!(parse_size_string("0"), Some(0));
+        assert_eq!(parse_size_string(""), None);
+        assert_eq!(parse_size_string("  "), None);
+
+        // An empty card reader reports 0B, which is a valid size. An empty
+        // string is not, so it is rejected instead of read as zero.
+        let device = |size: &str| {
+            format!(
+                r#"{{"blockdevices": [{{"name": "sdf", "maj:min": "8:80", "rm": true, "size": "{size}", "ro": false, "type": "disk", "mountpoints": [null]}}]}}"#
+            )
+        };
+        let devices = parse_lsblk(&device("0B")).expect("Failed to parse JSON");
+        assert_eq!(devices.blockdevices[0].size, 0);
+        assert!(parse_lsblk(&device("")).is_err());
+    }
+
+    #[test]
+    fn test_parse_lsblk_skips_leading_warnings() {
+        let input = format!(
+            "lsblk: /dev/sr0: unknown device\nlsblk: {{locale}} not found\n\n{SAMPLE_JSON}"
+        );
+        let devices = parse_lsblk(&input).expect("Failed to parse JSON");
+        assert_eq!(devices.len(), 10);
+        assert_eq!(
+            devices.warnings,
+            vec![
+                "lsblk: /dev/sr0: unknown device",
+                "lsblk: {locale} not found"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_lsblk_clean_input_has_no_warnings() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        assert!(devices.warnings.is_empty());
+        let json = serde_json::to_string(&devices).expect("Failed to serialize");
+        assert!(!json.contains("_blockdev_warnings"));
+    }
+
+    #[test]
+    fn test_parse_lsblk_rejects_trailing_garbage() {
+        assert!(parse_lsblk(&format!("{SAMPLE_JSON}\nlsblk: done")).is_err());
+        assert!(parse_lsblk(&format!("warning\n{SAMPLE_JSON}\ntrailer")).is_err());
+        assert!(parse_lsblk("lsblk: no devices").is_err());
+    }
+
+    #[test]
+    fn test_parse_device_number_shapes() {
+        let shapes = [
+            r#"{"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": [null]}"#,
+            r#"{"name": "sda1", "maj": 8, "min": 1, "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": [null]}"#,
+            r#"{"name": "sda1", "maj": "8", "min": "1", "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": [null]}"#,
+            r#"{"name": "sda1", "maj:min": "8:1", "maj": 8, "min": 1, "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": [null]}"#,
+        ];
+        for shape in shapes {
+            let devices = parse_lsblk(&format!(r#"{{"blockdevices": [{shape}]}}"#))
+                .unwrap_or_else(|e| panic!("{shape}: {e}"));
+            let device = &devices.blockdevices[0];
+            assert_eq!(device.maj_min, MajMin { major: 8, minor: 1 }, "{shape}");
+            assert_eq!(device.device_number(), 0x801, "{shape}");
+
+            let json = serde_json::to_value(device).expect("Failed to serialize");
+            assert_eq!(json["maj:min"], "8:1");
+            assert!(json.get("maj").is_none());
+        }
+    }
+
+    #[test]
+    fn test_parse_device_number_missing() {
+        let json = r#"{"blockdevices": [{"name": "sda", "maj": 8, "rm": false, "size": "1G", "ro": false, "type": "disk", "mountpoints": [null]}]}"#;
+        let err = parse_lsblk(json).unwrap_err();
+        assert!(err.to_string().contains("maj:min"), "{err}");
+    }
+
+    #[test]
+    fn test_mountpoints_shapes() {
+        let shapes: [(&str, Vec<Option<&str>>); 5] = [
+            ("null", vec![None]),
+            (r#""/""#, vec![Some("/")]),
+            ("[null]", vec![None]),
+            (
+                r#"["/", null, "/srv"]"#,
+                vec![Some("/"), None, Some("/srv")],
+            ),
+            ("[]", vec![]),
+        ];
+        for (shape, expected) in shapes {
+            let json = format!(
+                r#"{{"blockdevices": [{{"name": "sda", "maj:min": "8:0", "rm": false, "size": "1G", "ro": false, "type": "disk", "mountpoints": {shape}}}]}}"#
+            );
+            let devices = parse_lsblk(&json).unwrap_or_else(|e| panic!("{shape}: {e}"));
+            let mountpoints: Vec<Option<&str>> = devices.blockdevices[0]
+                .mountpoints
+                .iter()
+                .map(Option::as_deref)
+                .collect();
+            assert_eq!(mountpoints, expected, "{shape}");
+        }
+    }
+
+    #[test]
+    fn test_mountpoints_rejects_invalid_shapes() {
+        let shapes = [
+            (r#"{"path": "/"}"#, r#"invalid mountpoints {"path":"/"}"#),
+            ("[42]", "invalid mountpoints entry 42 in [42]"),
+            (
+                r#"[["/"]]"#,
+                r#"invalid mountpoints entry ["/"] in [["/"]]"#,
+            ),
+            ("true", "invalid mountpoints true"),
+        ];
+        for (shape, fragment) in shapes {
+            let json = format!(
+                r#"{{"blockdevices": [{{"name": "sda", "maj:min": "8:0", "rm": false, "size": "1G", "ro": false, "type": "disk", "mountpoints": {shape}}}]}}"#
+            );
+            let err = parse_lsblk(&json).unwrap_err().to_string();
+            assert!(err.starts_with("device 'sda': "), "{err}");
+            assert!(err.contains(fragment), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_child_error_names_lineage() {
+        let json = r#"{"blockdevices": [
+            {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1G", "ro": false, "type": "disk", "mountpoints": [null],
+             "children": [
+                {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": [7]}
+             ]}
+        ]}"#;
+        let err = parse_lsblk(json).unwrap_err().to_string();
+        assert!(
+            err.starts_with("device 'sda': device 'sda1': invalid mountpoints entry 7"),
+            "{err}"
+        );
+
+        // Without a name there is nothing to prefix.
+        let json = r#"{"blockdevices": [{"maj:min": "8:0", "rm": false, "size": "1G", "ro": false, "type": "disk", "mountpoints": [null]}]}"#;
+        let err = parse_lsblk(json).unwrap_err().to_string();
+        assert!(err.starts_with("missing field `name`"), "{err}");
+    }
+}
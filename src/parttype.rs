@@ -0,0 +1,238 @@
+use crate::{BlockDevice, BlockDevices};
+
+/// The well-known purpose of a partition, decoded from its `PARTTYPE`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PartitionRole {
+    /// An EFI System Partition.
+    EfiSystem,
+    /// A Linux filesystem partition.
+    LinuxFilesystem,
+    /// A Linux swap partition.
+    LinuxSwap,
+    /// A Linux software RAID member.
+    LinuxRaid,
+    /// A Linux LVM physical volume.
+    LinuxLvm,
+    /// A BIOS boot partition used by GRUB on GPT disks.
+    BiosBoot,
+    /// A Microsoft basic data partition (NTFS, exFAT or FAT).
+    MicrosoftBasicData,
+    /// A Windows recovery environment partition.
+    WindowsRecovery,
+    /// Any other partition type, holding the raw `PARTTYPE` value.
+    Other(String),
+}
+
+/// GPT partition type GUIDs, in lowercase.
+const GPT_ROLES: &[(&str, PartitionRole)] = &[
+    (
+        "c12a7328-f81f-11d2-ba4b-00a0c93ec93b",
+        PartitionRole::EfiSystem,
+    ),
+    (
+        "0fc63daf-8483-4772-8e79-3d69d8477de4",
+        PartitionRole::LinuxFilesystem,
+    ),
+    (
+        "0657fd6d-a4ab-43c4-84e5-0933c84b4f4f",
+        PartitionRole::LinuxSwap,
+    ),
+    (
+        "a19d880f-05fc-4d3b-a006-743f0f84911e",
+        PartitionRole::LinuxRaid,
+    ),
+    (
+        "e6d6d379-f507-44c2-a23c-238f2a3df928",
+        PartitionRole::LinuxLvm,
+    ),
+    (
+        "21686148-6449-6e6f-744e-656564454649",
+        PartitionRole::BiosBoot,
+    ),
+    (
+        "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7",
+        PartitionRole::MicrosoftBasicData,
+    ),
+    (
+        "de94bba4-06d1-4d40-a16a-bfd50179d6ac",
+        PartitionRole::WindowsRecovery,
+    ),
+];
+
+/// MBR partition type codes.
+const MBR_ROLES: &[(u8, PartitionRole)] = &[
+    (0xef, PartitionRole::EfiSystem),
+    (0x83, PartitionRole::LinuxFilesystem),
+    (0x82, PartitionRole::LinuxSwap),
+    (0xfd, PartitionRole::LinuxRaid),
+    (0x8e, PartitionRole::LinuxLvm),
+    (0x07, PartitionRole::MicrosoftBasicData),
+    (0x0b, PartitionRole::MicrosoftBasicData),
+    (0x0c, PartitionRole::MicrosoftBasicData),
+    (0x27, PartitionRole::WindowsRecovery),
+];
+
+impl PartitionRole {
+    /// Decodes a `PARTTYPE` value: a GPT type GUID (in any case) or an MBR
+    /// type code written as lsblk prints it on `dos` tables, such as `0x83`.
+    #[must_use]
+    pub fn from_parttype(parttype: &str) -> PartitionRole {
+        let lowered = parttype.trim().to_ascii_lowercase();
+        let known = match lowered.strip_prefix("0x") {
+            Some(hex) => u8::from_str_radix(hex, 16).ok().and_then(|code| {
+                MBR_ROLES
+                    .iter()
+                    .find(|(c, _)| *c == code)
+                    .map(|(_, role)| role)
+            }),
+            None => GPT_ROLES
+                .iter()
+                .find(|(guid, _)| *guid == lowered)
+                .map(|(_, role)| role),
+        };
+        known
+            .cloned()
+            .unwrap_or_else(|| PartitionRole::Other(parttype.to_string()))
+    }
+}
+
+/// Mountpoints at which an EFI System Partition is conventionally mounted.
+const EFI_MOUNTPOINTS: &[&str] = &["/boot/efi", "/efi"];
+
+impl BlockDevice {
+    /// Returns the decoded partition type, or `None` if `parttype` is not set.
+    ///
+    /// `parttype` is only populated by `get_devices_extended` or JSON that
+    /// includes the `PARTTYPE` column.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{BlockDevice, PartitionRole};
+    ///
+    /// let esp = BlockDevice {
+    ///     parttype: Some("c12a7328-f81f-11d2-ba4b-00a0c93ec93b".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(esp.partition_role(), Some(PartitionRole::EfiSystem));
+    /// ```
+    #[must_use]
+    pub fn partition_role(&self) -> Option<PartitionRole> {
+        self.parttype.as_deref().map(PartitionRole::from_parttype)
+    }
+}
+
+impl BlockDevices {
+    /// Finds the EFI System Partition.
+    ///
+    /// A partition whose `parttype` decodes to [`PartitionRole::EfiSystem`] is
+    /// preferred. Without `parttype` information, this falls back to the first
+    /// partition mounted at `/boot/efi`, then `/efi`.
+    #[must_use]
+    pub fn efi_partition(&self) -> Option<&BlockDevice> {
+        let partitions = || self.iter_all().filter(|d| d.is_partition());
+        partitions()
+            .find(|d| d.partition_role() == Some(PartitionRole::EfiSystem))
+            .or_else(|| {
+                EFI_MOUNTPOINTS.iter().find_map(|mountpoint| {
+                    partitions().find(|d| d.active_mountpoints().contains(mountpoint))
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    #[test]
+    fn test_from_parttype() {
+        let cases = [
+            (
+                "c12a7328-f81f-11d2-ba4b-00a0c93ec93b",
+                PartitionRole::EfiSystem,
+            ),
+            (
+                "C12A7328-F81F-11D2-BA4B-00A0C93EC93B",
+                PartitionRole::EfiSystem,
+            ),
+            (
+                "0fc63daf-8483-4772-8e79-3d69d8477de4",
+                PartitionRole::LinuxFilesystem,
+            ),
+            (
+                "0657fd6d-a4ab-43c4-84e5-0933c84b4f4f",
+                PartitionRole::LinuxSwap,
+            ),
+            (
+                "a19d880f-05fc-4d3b-a006-743f0f84911e",
+                PartitionRole::LinuxRaid,
+            ),
+            (
+                "e6d6d379-f507-44c2-a23c-238f2a3df928",
+                PartitionRole::LinuxLvm,
+            ),
+            (
+                "21686148-6449-6e6f-744e-656564454649",
+                PartitionRole::BiosBoot,
+            ),
+            (
+                "ebd0a0a2-b9e5-4433-87c0-68b6b72699c7",
+                PartitionRole::MicrosoftBasicData,
+            ),
+            (
+                "de94bba4-06d1-4d40-a16a-bfd50179d6ac",
+                PartitionRole::WindowsRecovery,
+            ),
+            ("0xef", PartitionRole::EfiSystem),
+            ("0x83", PartitionRole::LinuxFilesystem),
+            ("0x82", PartitionRole::LinuxSwap),
+            ("0xfd", PartitionRole::LinuxRaid),
+            ("0x8e", PartitionRole::LinuxLvm),
+            ("0x7", PartitionRole::MicrosoftBasicData),
+            ("0x0c", PartitionRole::MicrosoftBasicData),
+            ("0x27", PartitionRole::WindowsRecovery),
+        ];
+        for (parttype, role) in cases {
+            assert_eq!(PartitionRole::from_parttype(parttype), role, "{parttype}");
+        }
+        for other in ["0x05", "0xzz", "6a898cc3-1dd2-11b2-99a6-080020736631", ""] {
+            assert_eq!(
+                PartitionRole::from_parttype(other),
+                PartitionRole::Other(other.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_efi_partition_fallback_to_mountpoint() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        assert!(devices.iter_all().all(|d| d.partition_role().is_none()));
+        assert_eq!(devices.efi_partition().unwrap().name, "nvme3n1p2");
+    }
+
+    #[test]
+    fn test_efi_partition_prefers_parttype() {
+        let json = r#"{
+            "blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "100G", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": ["/boot"],
+                     "parttype": "0fc63daf-8483-4772-8e79-3d69d8477de4"},
+                    {"name": "sda2", "maj:min": "8:2", "rm": false, "size": "512M", "ro": false, "type": "part", "mountpoints": [null],
+                     "parttype": "c12a7328-f81f-11d2-ba4b-00a0c93ec93b"}
+                 ]}
+            ]
+        }"#;
+        let devices = parse_lsblk(json).expect("Failed to parse JSON");
+        assert_eq!(devices.efi_partition().unwrap().name, "sda2");
+        let sda1 = devices
+            .find_by_name("sda")
+            .unwrap()
+            .find_child("sda1")
+            .unwrap();
+        assert_eq!(sda1.partition_role(), Some(PartitionRole::LinuxFilesystem));
+    }
+}
@@ -0,0 +1,441 @@
+use crate::{BlockDevice, BlockDevices};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Decodes the `\xNN` escapes that lsblk uses for spaces, newlines and other
+/// special bytes in mountpoints and labels.
+///
+/// Sequences that are not a backslash, `x` and two hex digits are kept as they
+/// are. Escaped bytes may together form multi-byte UTF-8 characters; if the
+/// decoded bytes are not valid UTF-8, the input is returned unchanged.
+pub(crate) fn unescape_hex(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i..i + 4)
+            .filter(|seq| seq[0] == b'\\' && seq[1] == b'x')
+            .and_then(|seq| std::str::from_utf8(&seq[2..]).ok())
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok());
+        match escaped {
+            Some(value) => {
+                out.push(value);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).unwrap_or_else(|_| s.to_string())
+}
+
+impl BlockDevice {
+    /// Returns the active mountpoints with lsblk's `\xNN` escapes decoded, so
+    /// `/mnt/my\x20disk` becomes `/mnt/my disk`.
+    ///
+    /// Depending on its version and output format, lsblk prints special
+    /// characters in paths either raw or as `\xNN` escapes. The raw form stays
+    /// available in [`BlockDevice::mountpoints`]; this normalized form is what
+    /// [`BlockDevices::device_for_path`] and [`BlockDevices::mounted_under`]
+    /// compare against. A path that genuinely contains a backslash followed by
+    /// `x` and two hex digits cannot be told apart from an escape.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::BlockDevice;
+    ///
+    /// let device = BlockDevice {
+    ///     mountpoints: vec![Some(r"/mnt/my\x20disk".to_string()), None],
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(device.mountpoints_unescaped(), vec!["/mnt/my disk"]);
+    /// ```
+    #[must_use]
+    pub fn mountpoints_unescaped(&self) -> Vec<String> {
+        self.active_mountpoints()
+            .into_iter()
+            .map(unescape_hex)
+            .collect()
+    }
+
+    /// Returns the filesystem label with lsblk's `\xNN` escapes decoded, or
+    /// `None` if `label` is not set.
+    ///
+    /// See [`BlockDevice::mountpoints_unescaped`] for the decoding rules.
+    #[must_use]
+    pub fn label_unescaped(&self) -> Option<String> {
+        self.label.as_deref().map(unescape_hex)
+    }
+}
+
+impl BlockDevices {
+    /// Finds the device whose filesystem contains `path`, i.e. the device with
+    /// the longest mountpoint that `path` lies under.
+    ///
+    /// Paths are compared component by component on the unescaped mountpoints
+    /// (see [`BlockDevice::mountpoints_unescaped`]), so `/data2` is not under
+    /// `/data`. Symlinks in `path` are not resolved. If several devices share the
+    /// mountpoint, the first in tree order is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    /// use std::path::Path;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/"]},
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/srv/my\\x20data"]}
+    /// ]}"#).unwrap();
+    /// let device = devices.device_for_path(Path::new("/srv/my data/file.txt")).unwrap();
+    /// assert_eq!(device.name, "sdb");
+    /// ```
+    #[must_use]
+    pub fn device_for_path(&self, path: &Path) -> Option<&BlockDevice> {
+        let mut best: Option<(&BlockDevice, usize)> = None;
+        for device in self.iter_all() {
+            for mountpoint in device.mountpoints_unescaped() {
+                let mountpoint = Path::new(&mountpoint);
+                if !path.starts_with(mountpoint) {
+                    continue;
+                }
+                let depth = mountpoint.components().count();
+                if best.is_none_or(|(_, best_depth)| depth > best_depth) {
+                    best = Some((device, depth));
+                }
+            }
+        }
+        best.map(|(device, _)| device)
+    }
+
+    /// Returns every device with a mountpoint at or below `prefix`, once each,
+    /// in tree order.
+    ///
+    /// Like [`BlockDevices::device_for_path`], this compares whole path
+    /// components of the unescaped mountpoints.
+    #[must_use]
+    pub fn mounted_under(&self, prefix: &Path) -> Vec<&BlockDevice> {
+        let mut seen = HashSet::new();
+        self.iter_all()
+            .filter(|device| {
+                device
+                    .mountpoints_unescaped()
+                    .iter()
+                    .any(|mountpoint| Path::new(mountpoint).starts_with(prefix))
+            })
+            .filter(|device| seen.insert(device.name.as_str()))
+            .collect()
+    }
+
+    /// Returns every device with a mountpoint that starts with `prefix`, once
+    /// each in tree order, together with the matching mountpoints.
+    ///
+    /// Unlike [`BlockDevices::mounted_under`], this is a plain string match on
+    /// the mountpoints as lsblk reports them, so `/var/lib/docker` also matches
+    /// `/var/lib/docker2`. Pass `"/"` to get every device mounted somewhere in
+    /// the filesystem tree, which leaves out swap.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/"]},
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/var/lib/docker/overlay2/3f1a/merged"]}
+    /// ]}"#).unwrap();
+    ///
+    /// let matches = devices.with_mountpoint_prefix("/var/lib/docker/");
+    /// assert_eq!(matches.len(), 1);
+    /// assert_eq!(matches[0].0.name, "sdb");
+    /// assert_eq!(matches[0].1, vec!["/var/lib/docker/overlay2/3f1a/merged"]);
+    /// ```
+    #[must_use]
+    pub fn with_mountpoint_prefix(&self, prefix: &str) -> Vec<(&BlockDevice, Vec<&str>)> {
+        self.with_mountpoints_matching(|mountpoint| mountpoint.starts_with(prefix))
+    }
+
+    /// Like [`BlockDevices::with_mountpoint_prefix`], but matches mountpoints
+    /// that end with `suffix`, such as every device mounted at some `.../data`.
+    #[must_use]
+    pub fn with_mountpoint_suffix(&self, suffix: &str) -> Vec<(&BlockDevice, Vec<&str>)> {
+        self.with_mountpoints_matching(|mountpoint| mountpoint.ends_with(suffix))
+    }
+
+    fn with_mountpoints_matching(
+        &self,
+        matches: impl Fn(&str) -> bool,
+    ) -> Vec<(&BlockDevice, Vec<&str>)> {
+        let mut seen = HashSet::new();
+        self.iter_all()
+            .filter(|device| seen.insert(device.name.as_str()))
+            .filter_map(|device| {
+                let mountpoints: Vec<&str> = device
+                    .active_mountpoints()
+                    .into_iter()
+                    .filter(|m| matches(m))
+                    .collect();
+                (!mountpoints.is_empty()).then_some((device, mountpoints))
+            })
+            .collect()
+    }
+
+    /// Returns every pair of distinct devices where a mountpoint of the first
+    /// is a proper ancestor of a mountpoint of the second, such as `/data` and
+    /// `/data/db`, together with the shared ancestor path.
+    ///
+    /// Nested mounts hide whatever the outer filesystem holds at that path and
+    /// often point to a misconfigured fstab. `/` is skipped because every other
+    /// mount is nested in it, and so are non-paths such as `[SWAP]`. Paths are
+    /// compared by whole components on the unescaped mountpoints, and each
+    /// device is considered once even if it appears under several parents.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/data"]},
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/data/db"]}
+    /// ]}"#).unwrap();
+    ///
+    /// let overlaps = devices.overlapping_mountpoints();
+    /// assert_eq!(overlaps.len(), 1);
+    /// assert_eq!((overlaps[0].0.name.as_str(), overlaps[0].1.name.as_str()), ("sda", "sdb"));
+    /// assert_eq!(overlaps[0].2, "/data");
+    /// ```
+    #[must_use]
+    pub fn overlapping_mountpoints(&self) -> Vec<(&BlockDevice, &BlockDevice, String)> {
+        let mut seen = HashSet::new();
+        let mounted: Vec<(&BlockDevice, Vec<String>)> = self
+            .iter_all()
+            .filter(|device| seen.insert(device.name.as_str()))
+            .map(|device| {
+                let mountpoints = device
+                    .mountpoints_unescaped()
+                    .into_iter()
+                    .filter(|m| m.starts_with('/') && m != "/")
+                    .collect();
+                (device, mountpoints)
+            })
+            .collect();
+
+        let mut overlaps = Vec::new();
+        for (outer, outer_mountpoints) in &mounted {
+            for (inner, inner_mountpoints) in &mounted {
+                if outer.name == inner.name {
+                    continue;
+                }
+                for ancestor in outer_mountpoints {
+                    let nested = inner_mountpoints.iter().any(|m| {
+                        Path::new(m) != Path::new(ancestor) && Path::new(m).starts_with(ancestor)
+                    });
+                    if nested {
+                        overlaps.push((*outer, *inner, ancestor.clone()));
+                    }
+                }
+            }
+        }
+        overlaps
+    }
+
+    /// Returns `true` if [`BlockDevices::overlapping_mountpoints`] finds any pair.
+    #[must_use]
+    pub fn has_overlapping_mountpoints(&self) -> bool {
+        !self.overlapping_mountpoints().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    #[test]
+    fn test_unescape_hex() {
+        let cases = [
+            (r"/mnt/my\x20disk", "/mnt/my disk"),
+            (r"/mnt/line\x0abreak", "/mnt/line\nbreak"),
+            (r"/mnt/back\x5cslash", "/mnt/back\\slash"),
+            (r"/mnt/\xe4\xb8\xad\xe6\x96\x87", "/mnt/中文"),
+            ("/mnt/中文 raw", "/mnt/中文 raw"),
+            (r"/mnt/\X20\x2", r"/mnt/\X20\x2"),
+            (r"/mnt/\xzz\x", r"/mnt/\xzz\x"),
+            (r"\x41\x", r"A\x"),
+            ("", ""),
+        ];
+        for (raw, expected) in cases {
+            assert_eq!(unescape_hex(raw), expected, "{raw}");
+        }
+        // A lone escaped byte that is not valid UTF-8 leaves the input untouched.
+        assert_eq!(unescape_hex(r"/mnt/\xff"), r"/mnt/\xff");
+    }
+
+    #[test]
+    fn test_unescape_hex_every_byte() {
+        for byte in 0x01..=0x7f_u8 {
+            let raw = format!("/a\\x{byte:02x}b");
+            assert_eq!(unescape_hex(&raw), format!("/a{}b", byte as char), "{raw}");
+            let upper = format!("/a\\x{byte:02X}b");
+            assert_eq!(
+                unescape_hex(&upper),
+                format!("/a{}b", byte as char),
+                "{upper}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_with_mountpoint_prefix_and_suffix() {
+        let json = r#"{
+            "blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": ["/boot"]},
+                    {"name": "sda2", "maj:min": "8:2", "rm": false, "size": "999G", "ro": false, "type": "part", "mountpoints": ["/", "/var/lib/docker/overlay2/3f1a/merged"]}
+                 ]},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/var/lib/docker/volumes/db/data", "/srv/data"]},
+                {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/var/lib/docker2", "[SWAP]"]},
+                {"name": "sdd", "maj:min": "8:48", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]}
+            ]
+        }"#;
+        let devices = parse_lsblk(json).expect("Failed to parse JSON");
+        let names = |matches: Vec<(&BlockDevice, Vec<&str>)>| -> Vec<String> {
+            matches.iter().map(|(d, _)| d.name.clone()).collect()
+        };
+
+        assert_eq!(
+            names(devices.with_mountpoint_prefix("/")),
+            vec!["sda1", "sda2", "sdb", "sdc"]
+        );
+
+        let docker = devices.with_mountpoint_prefix("/var/lib/docker/");
+        assert_eq!(docker.len(), 2);
+        assert_eq!(docker[0].0.name, "sda2");
+        assert_eq!(docker[0].1, vec!["/var/lib/docker/overlay2/3f1a/merged"]);
+        assert_eq!(docker[1].0.name, "sdb");
+        assert_eq!(docker[1].1, vec!["/var/lib/docker/volumes/db/data"]);
+        assert_eq!(
+            names(devices.with_mountpoint_prefix("/var/lib/docker")),
+            vec!["sda2", "sdb", "sdc"]
+        );
+        assert!(devices.with_mountpoint_prefix("/mnt").is_empty());
+
+        let data = devices.with_mountpoint_suffix("/data");
+        assert_eq!(data.len(), 1);
+        assert_eq!(
+            data[0].1,
+            vec!["/var/lib/docker/volumes/db/data", "/srv/data"]
+        );
+
+        // md0 appears under two disks in SAMPLE_JSON but is reported once.
+        let sample = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let boot = sample.with_mountpoint_suffix("/boot");
+        assert_eq!(names(boot), vec!["md0"]);
+    }
+
+    #[test]
+    fn test_overlapping_mountpoints() {
+        let json = r#"{
+            "blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": ["/boot/efi"]},
+                    {"name": "sda2", "maj:min": "8:2", "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": ["/boot"]},
+                    {"name": "sda3", "maj:min": "8:3", "rm": false, "size": "998G", "ro": false, "type": "part", "mountpoints": ["/"]}
+                 ]},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/data", "/data/db"]},
+                {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/data/db"]},
+                {"name": "sdd", "maj:min": "8:48", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/data2", "[SWAP]"]}
+            ]
+        }"#;
+        let devices = parse_lsblk(json).expect("Failed to parse JSON");
+        let overlaps: Vec<(&str, &str, String)> = devices
+            .overlapping_mountpoints()
+            .into_iter()
+            .map(|(a, b, prefix)| (a.name.as_str(), b.name.as_str(), prefix))
+            .collect();
+        assert_eq!(
+            overlaps,
+            vec![
+                ("sda2", "sda1", "/boot".to_string()),
+                ("sdb", "sdc", "/data".to_string()),
+            ]
+        );
+        assert!(devices.has_overlapping_mountpoints());
+
+        // The sample's ESP is mounted inside the RAID1 /boot; md0 is reported once.
+        let sample = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let overlaps = sample.overlapping_mountpoints();
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(overlaps[0].0.name, "md0");
+        assert_eq!(overlaps[0].1.name, "nvme3n1p2");
+    }
+
+    #[test]
+    fn test_device_for_path_and_mounted_under() {
+        let json = r#"{
+            "blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "100G", "ro": false, "type": "part", "mountpoints": ["/"]},
+                    {"name": "sda2", "maj:min": "8:2", "rm": false, "size": "900G", "ro": false, "type": "part",
+                     "mountpoints": ["/srv/my\\x20data"], "label": "my\\x20data"}
+                 ]},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk",
+                 "mountpoints": ["/srv/my data/写真"]},
+                {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk",
+                 "mountpoints": ["/srv/my datalake"]}
+            ]
+        }"#;
+        let devices = parse_lsblk(json).expect("Failed to parse JSON");
+        let owner = |path: &str| {
+            devices
+                .device_for_path(Path::new(path))
+                .map(|d| d.name.as_str())
+        };
+        assert_eq!(owner("/etc/fstab"), Some("sda1"));
+        assert_eq!(owner("/srv/my data"), Some("sda2"));
+        assert_eq!(owner("/srv/my data/a b.txt"), Some("sda2"));
+        assert_eq!(owner("/srv/my data/写真/IMG_0001.jpg"), Some("sdb"));
+        assert_eq!(owner("/srv/my datalake/x"), Some("sdc"));
+        assert_eq!(owner(r"/srv/my\x20data/x"), Some("sda1"));
+        assert_eq!(owner("relative/path"), None);
+
+        let sda2 = devices
+            .find_by_name("sda")
+            .unwrap()
+            .find_child("sda2")
+            .unwrap();
+        assert_eq!(sda2.active_mountpoints(), vec![r"/srv/my\x20data"]);
+        assert_eq!(sda2.label_unescaped().as_deref(), Some("my data"));
+
+        let under: Vec<&str> = devices
+            .mounted_under(Path::new("/srv/my data"))
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(under, vec!["sda2", "sdb"]);
+        assert_eq!(devices.mounted_under(Path::new("/")).len(), 4);
+    }
+
+    #[test]
+    fn test_device_for_path_sample() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let owner = |path: &str| {
+            devices
+                .device_for_path(Path::new(path))
+                .map(|d| d.name.as_str())
+        };
+        assert_eq!(owner("/boot/efi/EFI"), Some("nvme3n1p2"));
+        assert_eq!(owner("/boot/vmlinuz"), Some("md0"));
+        assert_eq!(owner("/var/log"), Some("md2"));
+    }
+}
@@ -0,0 +1,230 @@
+use crate::naming::partition_parent_name;
+use crate::{
+    BlockDevError, BlockDevice, BlockDevices, DeviceType, MajMin, SnapshotMeta, SourceKind,
+};
+
+/// `/proc/partitions` reports sizes in 1 KiB blocks.
+const PROC_PARTITIONS_BLOCK_SIZE: u64 = 1024;
+
+/// Guesses the device type from a kernel device name.
+pub(crate) fn device_type_from_name(name: &str) -> DeviceType {
+    if name.starts_with("loop") {
+        DeviceType::Loop
+    } else if name.starts_with("sr") {
+        DeviceType::Rom
+    } else if name.starts_with("md") || name.starts_with("dm-") || name.starts_with("zram") {
+        // The RAID level or dm target is not recorded in /proc/partitions.
+        DeviceType::Other
+    } else if partition_parent_name(name).is_some() {
+        DeviceType::Part
+    } else {
+        DeviceType::Disk
+    }
+}
+
+fn parse_line(line: &str) -> Option<BlockDevice> {
+    let mut fields = line.split_whitespace();
+    let major = fields.next()?.parse().ok()?;
+    let minor = fields.next()?.parse().ok()?;
+    let blocks: u64 = fields.next()?.parse().ok()?;
+    let name = fields.next()?;
+    if fields.next().is_some() {
+        return None;
+    }
+    Some(BlockDevice {
+        name: name.to_string(),
+        maj_min: MajMin { major, minor },
+        size: blocks.checked_mul(PROC_PARTITIONS_BLOCK_SIZE)?,
+        device_type: device_type_from_name(name),
+        mountpoints: vec![None],
+        children: None,
+        ..Default::default()
+    })
+}
+
+/// Parses the contents of `/proc/partitions` into a flat list of devices.
+fn parse_proc_partitions(contents: &str) -> Result<Vec<BlockDevice>, BlockDevError> {
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with("major"))
+        .map(|line| {
+            parse_line(line).ok_or_else(|| {
+                BlockDevError::InvalidInput(format!("invalid /proc/partitions line: '{line}'"))
+            })
+        })
+        .collect()
+}
+
+/// Moves every partition under the disk it belongs to, matching the disk by
+/// name (`sda1` under `sda`, `nvme0n1p2` under `nvme0n1`) and major number.
+///
+/// Partitions whose disk is not listed, or has a different major number
+/// because the kernel ran out of minors for it, stay at the top level.
+fn nest_partitions(devices: Vec<BlockDevice>) -> Vec<BlockDevice> {
+    let (partitions, mut top): (Vec<_>, Vec<_>) = devices
+        .into_iter()
+        .partition(|d| d.device_type == DeviceType::Part);
+    for partition in partitions {
+        let disk = top.iter_mut().find(|d| {
+            d.device_type == DeviceType::Disk
+                && d.maj_min.major == partition.maj_min.major
+                && partition.is_partition_of(d)
+        });
+        match disk {
+            Some(disk) => disk.children.get_or_insert_with(Vec::new).push(partition),
+            None => top.push(partition),
+        }
+    }
+    top
+}
+
+/// Reads `/proc/partitions` into a device tree with partitions nested under
+/// their disks, for rescue environments where neither `lsblk` nor a complete
+/// sysfs can be relied on.
+///
+/// Apart from the nesting, this has the same limitations as
+/// [`BlockDevices::from_proc_partitions`]: types are guessed from names, every
+/// device has `mountpoints: vec![None]`, `rm` and `ro` are `false`, and all
+/// optional columns are `None`. Partitions whose disk cannot be found stay at
+/// the top level.
+///
+/// # Errors
+///
+/// Returns [`BlockDevError::IoError`] if the file cannot be read and
+/// [`BlockDevError::InvalidInput`] if a line cannot be parsed.
+pub fn get_devices_proc() -> Result<BlockDevices, BlockDevError> {
+    let mut devices = BlockDevices::from_proc_partitions()?;
+    devices.blockdevices = nest_partitions(devices.blockdevices);
+    Ok(devices)
+}
+
+impl BlockDevices {
+    /// Reads `/proc/partitions` as a minimal fallback for systems without `lsblk`.
+    ///
+    /// This is much less detailed than `get_devices`:
+    ///
+    /// - The result is flat: partitions are listed next to their disks rather than
+    ///   as `children`. Use [`BlockDevice::is_partition_of`] to relate them.
+    /// - `device_type` is guessed from the name; RAID and device-mapper devices
+    ///   are reported as [`DeviceType::Other`].
+    /// - There are no mountpoints (every device has `vec![None]`), so
+    ///   [`BlockDevice::is_system`] is always `false`.
+    /// - `rm`, `ro` and all optional columns are not available.
+    ///
+    /// The snapshot's [`meta`](BlockDevices::meta) records [`SourceKind::ProcPartitions`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::IoError`] if the file cannot be read and
+    /// [`BlockDevError::InvalidInput`] if a line cannot be parsed.
+    pub fn from_proc_partitions() -> Result<BlockDevices, BlockDevError> {
+        let contents =
+            std::fs::read_to_string("/proc/partitions").map_err(BlockDevError::IoError)?;
+        Ok(BlockDevices {
+            blockdevices: parse_proc_partitions(&contents)?,
+            meta: Some(SnapshotMeta::new(SourceKind::ProcPartitions)),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::PROC_PARTITIONS;
+
+    #[test]
+    fn test_parse_proc_partitions() {
+        let devices = parse_proc_partitions(PROC_PARTITIONS).expect("Failed to parse");
+        assert_eq!(devices.len(), 11);
+
+        let nvme0n1p9 = &devices[2];
+        assert_eq!(nvme0n1p9.name, "nvme0n1p9");
+        assert_eq!(
+            nvme0n1p9.maj_min,
+            MajMin {
+                major: 259,
+                minor: 2
+            }
+        );
+        assert_eq!(nvme0n1p9.size, 8 << 20);
+        assert_eq!(nvme0n1p9.mountpoints, vec![None]);
+        assert!(nvme0n1p9.children.is_none());
+
+        let types: Vec<(&str, DeviceType)> = devices
+            .iter()
+            .map(|d| (d.name.as_str(), d.device_type.clone()))
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                ("nvme0n1", DeviceType::Disk),
+                ("nvme0n1p1", DeviceType::Part),
+                ("nvme0n1p9", DeviceType::Part),
+                ("sda", DeviceType::Disk),
+                ("sda1", DeviceType::Part),
+                ("mmcblk0", DeviceType::Disk),
+                ("mmcblk0p1", DeviceType::Part),
+                ("md0", DeviceType::Other),
+                ("loop0", DeviceType::Loop),
+                ("sr0", DeviceType::Rom),
+                ("dm-0", DeviceType::Other),
+            ]
+        );
+        assert!(devices[4].is_partition_of(&devices[3]));
+    }
+
+    #[test]
+    fn test_parse_proc_partitions_invalid() {
+        assert!(parse_proc_partitions("").unwrap().is_empty());
+        for bad in ["   8 0 sda", "x 0 100 sda", "8 0 100 sda extra"] {
+            assert!(
+                matches!(
+                    parse_proc_partitions(bad),
+                    Err(BlockDevError::InvalidInput(_))
+                ),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_nest_partitions() {
+        let devices = nest_partitions(parse_proc_partitions(PROC_PARTITIONS).unwrap());
+        let tree: Vec<(&str, Vec<&str>)> = devices
+            .iter()
+            .map(|d| {
+                (
+                    d.name.as_str(),
+                    d.children_iter().map(|c| c.name.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            tree,
+            vec![
+                ("nvme0n1", vec!["nvme0n1p1", "nvme0n1p9"]),
+                ("sda", vec!["sda1"]),
+                ("mmcblk0", vec!["mmcblk0p1"]),
+                ("md0", vec![]),
+                ("loop0", vec![]),
+                ("sr0", vec![]),
+                ("dm-0", vec![]),
+            ]
+        );
+        let sda1 = devices[1].find_child("sda1").unwrap();
+        assert_eq!(sda1.size, 976_761_560 * 1024);
+        assert!(!sda1.rm && !sda1.ro);
+        assert!(devices[6].children.is_none());
+
+        // sdq's 16th partition gets a minor from the extended range (major 259)
+        // and is left at the top level, as is a partition without a listed disk.
+        let devices = nest_partitions(
+            parse_proc_partitions("65 0 100 sdq\n65 1 50 sdq1\n259 20 40 sdq16\n8 17 10 sdb1\n")
+                .unwrap(),
+        );
+        let names: Vec<&str> = devices.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["sdq", "sdq16", "sdb1"]);
+        assert_eq!(devices[0].children.as_ref().unwrap().len(), 1);
+    }
+}
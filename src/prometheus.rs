@@ -0,0 +1,182 @@
+use crate::{BlockDevice, BlockDevices};
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// Escapes a label value as required by the Prometheus text exposition format.
+fn escape_label(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes the `# HELP` and `# TYPE` header of a gauge.
+fn write_header(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} gauge");
+}
+
+impl BlockDevices {
+    /// Renders the devices as metrics in the Prometheus text exposition format.
+    ///
+    /// Every device in the tree is exported once, using its first occurrence, so
+    /// RAID arrays that appear under several disks do not produce duplicate series.
+    /// The following gauges are emitted:
+    ///
+    /// - `blockdev_size_bytes{name, type}`: the size in bytes.
+    /// - `blockdev_is_mounted{name, mountpoint}`: `1` for each mountpoint, or `0`
+    ///   with an empty `mountpoint` label if the device is not mounted.
+    /// - `blockdev_is_system{name}`: `1` if the device or a descendant is mounted at `/`.
+    /// - `blockdev_is_removable{name}`: `1` if the device is removable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1K", "ro": false, "type": "disk", "mountpoints": ["/data"]}
+    /// ]}"#).unwrap();
+    /// let metrics = devices.to_prometheus_metrics();
+    /// assert!(metrics.contains("blockdev_size_bytes{name=\"sda\",type=\"disk\"} 1024\n"));
+    /// assert!(metrics.contains("blockdev_is_mounted{name=\"sda\",mountpoint=\"/data\"} 1\n"));
+    /// ```
+    #[must_use]
+    pub fn to_prometheus_metrics(&self) -> String {
+        let mut seen = HashSet::new();
+        let devices: Vec<&BlockDevice> = self
+            .iter_all()
+            .filter(|d| seen.insert(d.name.as_str()))
+            .collect();
+        let mut out = String::new();
+
+        write_header(
+            &mut out,
+            "blockdev_size_bytes",
+            "Size of the block device in bytes.",
+        );
+        for device in &devices {
+            let _ = writeln!(
+                out,
+                "blockdev_size_bytes{{name=\"{}\",type=\"{}\"}} {}",
+                escape_label(&device.name),
+                device.device_type,
+                device.size
+            );
+        }
+
+        write_header(
+            &mut out,
+            "blockdev_is_mounted",
+            "Whether the block device is mounted at the given mountpoint.",
+        );
+        for device in &devices {
+            let name = escape_label(&device.name);
+            let mountpoints = device.unique_mountpoints();
+            if mountpoints.is_empty() {
+                let _ = writeln!(
+                    out,
+                    "blockdev_is_mounted{{name=\"{name}\",mountpoint=\"\"}} 0"
+                );
+            }
+            for mountpoint in mountpoints {
+                let _ = writeln!(
+                    out,
+                    "blockdev_is_mounted{{name=\"{name}\",mountpoint=\"{}\"}} 1",
+                    escape_label(mountpoint)
+                );
+            }
+        }
+
+        write_header(
+            &mut out,
+            "blockdev_is_system",
+            "Whether the block device or one of its children is mounted at /.",
+        );
+        for device in &devices {
+            let _ = writeln!(
+                out,
+                "blockdev_is_system{{name=\"{}\"}} {}",
+                escape_label(&device.name),
+                u8::from(device.is_system())
+            );
+        }
+
+        write_header(
+            &mut out,
+            "blockdev_is_removable",
+            "Whether the block device is removable.",
+        );
+        for device in &devices {
+            let _ = writeln!(
+                out,
+                "blockdev_is_removable{{name=\"{}\"}} {}",
+                escape_label(&device.name),
+                u8::from(device.rm)
+            );
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    #[test]
+    fn test_prometheus_metrics_sample() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let metrics = devices.to_prometheus_metrics();
+
+        for name in [
+            "blockdev_size_bytes",
+            "blockdev_is_mounted",
+            "blockdev_is_system",
+            "blockdev_is_removable",
+        ] {
+            assert!(metrics.contains(&format!("# HELP {name} ")), "{name}");
+            assert!(
+                metrics.contains(&format!("# TYPE {name} gauge\n")),
+                "{name}"
+            );
+        }
+
+        // Every line is a comment or a `name{labels} value` sample.
+        for line in metrics.lines().filter(|l| !l.starts_with('#')) {
+            let (series, value) = line.rsplit_once(' ').expect("sample without value");
+            assert!(series.ends_with('}'), "{line}");
+            value.parse::<u64>().expect("non-numeric value");
+        }
+
+        let nvme0n1 = devices.find_by_name("nvme0n1").unwrap();
+        assert!(metrics.contains(&format!(
+            "blockdev_size_bytes{{name=\"nvme0n1\",type=\"disk\"}} {}\n",
+            nvme0n1.size
+        )));
+        assert!(metrics.contains("blockdev_is_mounted{name=\"md0\",mountpoint=\"/boot\"} 1\n"));
+        assert!(metrics.contains("blockdev_is_mounted{name=\"nvme0n1\",mountpoint=\"\"} 0\n"));
+        assert!(metrics.contains("blockdev_is_system{name=\"nvme3n1\"} 1\n"));
+        assert!(metrics.contains("blockdev_is_system{name=\"nvme0n1\"} 0\n"));
+        assert!(metrics.contains("blockdev_is_removable{name=\"nvme0n1\"} 0\n"));
+
+        // md0 appears under two disks but is exported once.
+        assert_eq!(
+            metrics.matches("blockdev_size_bytes{name=\"md0\"").count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_prometheus_label_escaping() {
+        assert_eq!(escape_label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -0,0 +1,565 @@
+use crate::naming::validate_device_name;
+use crate::{BlockDevError, BlockDevice, BlockDevices, DeviceType, SystemPolicy};
+use std::collections::{HashMap, HashSet};
+
+/// A software RAID array and the devices it is built from, as found by
+/// [`BlockDevices::detect_raid_arrays`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RaidArray<'a> {
+    /// The name of the md device, such as `md0`.
+    pub name: String,
+    /// The RAID level as lsblk reports it, such as `raid1`.
+    pub level: String,
+    /// The devices whose children include the md device, usually partitions,
+    /// in the order they appear in the snapshot.
+    pub members: Vec<&'a BlockDevice>,
+    /// The md device itself.
+    pub md_device: &'a BlockDevice,
+}
+
+/// The fraction of the raw member capacity that a RAID level makes usable.
+///
+/// Use [`RaidEfficiency::compute`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RaidEfficiency;
+
+impl RaidEfficiency {
+    /// Returns the usable fraction of `member_count` equal members at `level`,
+    /// or `None` for an unknown level or too few members to hold any data.
+    ///
+    /// `level` is a level as lsblk reports it (`raid1`) or as given to mdadm
+    /// (`1`). RAID 0 uses every member (1), RAID 1 mirrors onto all of them
+    /// (1/n), RAID 4 and 5 spend one member on parity ((n-1)/n), RAID 6 two
+    /// ((n-2)/n), and RAID 10 keeps two copies of everything (1/2, mdadm's
+    /// default layout).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::RaidEfficiency;
+    ///
+    /// assert_eq!(RaidEfficiency::compute("raid1", 2), Some(0.5));
+    /// assert_eq!(RaidEfficiency::compute("5", 4), Some(0.75));
+    /// assert_eq!(RaidEfficiency::compute("raid7", 4), None);
+    /// ```
+    #[must_use]
+    pub fn compute(level: &str, member_count: usize) -> Option<f64> {
+        let level = level.trim();
+        let level = level.strip_prefix("raid").unwrap_or(level);
+        let n = member_count as f64;
+        match (level, member_count) {
+            (_, 0) => None,
+            ("0", _) => Some(1.0),
+            ("1", _) => Some(1.0 / n),
+            ("4" | "5", 2..) => Some((n - 1.0) / n),
+            ("6", 3..) => Some((n - 2.0) / n),
+            ("10", 2..) => Some(0.5),
+            _ => None,
+        }
+    }
+}
+
+impl RaidArray<'_> {
+    /// Returns the capacity the array provides, computed from its members: the
+    /// smallest member's size times the member count times the
+    /// [`RaidEfficiency`] of the level.
+    ///
+    /// This does not rely on the size lsblk reports for the md device, so it
+    /// can cross-check it or plan an array. Returns `None` if the array has no
+    /// members in the snapshot or the level is unknown.
+    #[must_use]
+    pub fn virtual_size_bytes(&self) -> Option<u64> {
+        let member_size = self.members.iter().map(|m| m.size).min()?;
+        let efficiency = RaidEfficiency::compute(&self.level, self.members.len())?;
+        Some((efficiency * self.members.len() as f64 * member_size as f64).round() as u64)
+    }
+}
+
+/// Returns `true` for the `raid*` device types.
+pub(crate) fn is_raid_type(device_type: &DeviceType) -> bool {
+    matches!(
+        device_type,
+        DeviceType::Raid0
+            | DeviceType::Raid1
+            | DeviceType::Raid5
+            | DeviceType::Raid6
+            | DeviceType::Raid10
+    )
+}
+
+/// Minimum number of member devices `mdadm --create` accepts for each supported level.
+const MDADM_MIN_DEVICES: &[(u8, usize)] = &[(0, 2), (1, 2), (4, 3), (5, 3), (6, 4), (10, 2)];
+
+/// Checks that a name can be placed in a command line without quoting.
+fn validate_command_name(name: &str) -> Result<(), BlockDevError> {
+    validate_device_name(name)?;
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':' | '+' | '@'))
+    {
+        return Err(BlockDevError::InvalidInput(format!(
+            "name '{name}' contains characters that would need shell quoting"
+        )));
+    }
+    Ok(())
+}
+
+impl BlockDevice {
+    /// Returns `true` if this device is a member of a Linux software RAID (md)
+    /// array, i.e. its `fstype` is `linux_raid_member`.
+    ///
+    /// This needs the `fstype` column, which `get_devices_extended` requests.
+    /// Firmware RAID members such as `isw_raid_member` are not included.
+    #[must_use]
+    pub fn is_md_member(&self) -> bool {
+        self.fstype.as_deref() == Some("linux_raid_member")
+    }
+
+    /// Returns the logical size of this device in bytes.
+    ///
+    /// For a software RAID array this is [`RaidArray::virtual_size_bytes`],
+    /// computed from the members found in `ctx`, so two mirrored 3.5T drives
+    /// give 3.5T rather than 7T. Every other device, including LVM and other
+    /// device-mapper volumes, whose `size` lsblk already reports as the
+    /// logical size, returns `size`, and so does an array whose members or
+    /// level cannot be determined.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "3.5T", "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "children": [{"name": "md0", "maj:min": "9:0", "rm": false, "size": "3.5T", "ro": false, "type": "raid1", "mountpoints": ["/srv"]}]},
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "3.5T", "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "children": [{"name": "md0", "maj:min": "9:0", "rm": false, "size": "3.5T", "ro": false, "type": "raid1", "mountpoints": ["/srv"]}]}
+    /// ]}"#).unwrap();
+    ///
+    /// let md0 = devices.iter_all().find(|d| d.name == "md0").unwrap();
+    /// assert_eq!(md0.virtual_size_bytes(&devices), devices.blockdevices[0].size);
+    /// ```
+    #[must_use]
+    pub fn virtual_size_bytes(&self, ctx: &BlockDevices) -> u64 {
+        if !is_raid_type(&self.device_type) {
+            return self.size;
+        }
+        ctx.detect_raid_arrays()
+            .iter()
+            .find(|array| array.name == self.name)
+            .and_then(RaidArray::virtual_size_bytes)
+            .unwrap_or(self.size)
+    }
+}
+
+impl BlockDevices {
+    /// Returns every device in the tree that is an md RAID member (see
+    /// [`BlockDevice::is_md_member`]), usually partitions, in tree order.
+    #[must_use]
+    pub fn md_member_partitions(&self) -> Vec<&BlockDevice> {
+        let mut seen = HashSet::new();
+        self.iter_all()
+            .filter(|d| d.is_md_member() && seen.insert(d.name.as_str()))
+            .collect()
+    }
+
+    /// Groups non-system top-level disks by their size in bytes, with system
+    /// disks defined by [`SystemPolicy::strict`].
+    ///
+    /// Within each group, devices keep the order in which they appear in the snapshot.
+    #[must_use]
+    pub fn same_size_groups(&self) -> HashMap<u64, Vec<&BlockDevice>> {
+        self.same_size_groups_with(&SystemPolicy::strict())
+    }
+
+    /// Like [`same_size_groups`](Self::same_size_groups), with system disks
+    /// defined by `policy`.
+    #[must_use]
+    pub fn same_size_groups_with(&self, policy: &SystemPolicy) -> HashMap<u64, Vec<&BlockDevice>> {
+        let mut groups: HashMap<u64, Vec<&BlockDevice>> = HashMap::new();
+        for device in self
+            .iter()
+            .filter(|d| d.is_disk() && !d.is_system_with(policy))
+        {
+            groups.entry(device.size).or_default().push(device);
+        }
+        groups
+    }
+
+    /// Returns every unordered pair of non-system top-level disks that have the same size.
+    ///
+    /// This is useful for suggesting RAID 1 mirrors. Pairs are ordered by size,
+    /// largest first, and then by the order the devices appear in the snapshot.
+    /// System disks are defined by [`SystemPolicy::strict`].
+    #[must_use]
+    pub fn pairs_of_same_size(&self) -> Vec<(&BlockDevice, &BlockDevice)> {
+        self.pairs_of_same_size_with(&SystemPolicy::strict())
+    }
+
+    /// Like [`pairs_of_same_size`](Self::pairs_of_same_size), with system
+    /// disks defined by `policy`.
+    #[must_use]
+    pub fn pairs_of_same_size_with(
+        &self,
+        policy: &SystemPolicy,
+    ) -> Vec<(&BlockDevice, &BlockDevice)> {
+        let mut groups: Vec<(u64, Vec<&BlockDevice>)> =
+            self.same_size_groups_with(policy).into_iter().collect();
+        groups.sort_by_key(|(size, _)| std::cmp::Reverse(*size));
+
+        let mut pairs = Vec::new();
+        for (_, devices) in groups {
+            for (i, first) in devices.iter().enumerate() {
+                for second in &devices[i + 1..] {
+                    pairs.push((*first, *second));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Finds every software RAID array in the tree together with its members.
+    ///
+    /// lsblk lists an md device as a child of each of its members, so this needs
+    /// the full tree with children, as `get_devices` returns it; a flat snapshot
+    /// such as [`BlockDevices::from_proc_partitions`] finds no members. Each array
+    /// is reported once, in the order it first appears.
+    #[must_use]
+    pub fn detect_raid_arrays(&self) -> Vec<RaidArray<'_>> {
+        let mut seen = HashSet::new();
+        self.iter_all()
+            .filter(|d| is_raid_type(&d.device_type) && seen.insert(d.name.as_str()))
+            .map(|md_device| RaidArray {
+                name: md_device.name.clone(),
+                level: md_device.device_type.to_string(),
+                members: self
+                    .iter_all()
+                    .filter(|d| d.children_iter().any(|c| c.name == md_device.name))
+                    .collect(),
+                md_device,
+            })
+            .collect()
+    }
+
+    /// Builds an `mdadm --create` command line that assembles `devices` into a new
+    /// RAID array at `/dev/<array_name>`.
+    ///
+    /// This only generates the command string; it does not run `mdadm` or touch
+    /// any device. Review the command before running it, as it destroys the data
+    /// on every member.
+    ///
+    /// Supported levels are 0, 1, 4, 5, 6 and 10, which need at least 2, 2, 3, 3,
+    /// 4 and 2 devices respectively.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::InvalidInput`] if the level is unsupported, there
+    /// are too few devices, a device is listed twice, a device is a system device
+    /// under [`SystemPolicy::strict`] (so holding `/`, a boot filesystem or
+    /// swap), or a name is not a plain device name.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{BlockDevice, BlockDevices};
+    ///
+    /// let sda = BlockDevice { name: "sda".to_string(), ..Default::default() };
+    /// let sdb = BlockDevice { name: "sdb".to_string(), ..Default::default() };
+    /// let command = BlockDevices::generate_mdadm_create_command(&[&sda, &sdb], 1, "md0").unwrap();
+    /// assert_eq!(command, "mdadm --create /dev/md0 --level=1 --raid-devices=2 /dev/sda /dev/sdb");
+    /// ```
+    pub fn generate_mdadm_create_command(
+        devices: &[&BlockDevice],
+        level: u8,
+        array_name: &str,
+    ) -> Result<String, BlockDevError> {
+        Self::generate_mdadm_create_command_with(
+            devices,
+            level,
+            array_name,
+            &SystemPolicy::strict(),
+        )
+    }
+
+    /// Like [`generate_mdadm_create_command`](Self::generate_mdadm_create_command),
+    /// refusing the system devices defined by `policy`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of
+    /// [`generate_mdadm_create_command`](Self::generate_mdadm_create_command).
+    pub fn generate_mdadm_create_command_with(
+        devices: &[&BlockDevice],
+        level: u8,
+        array_name: &str,
+        policy: &SystemPolicy,
+    ) -> Result<String, BlockDevError> {
+        let min_devices = MDADM_MIN_DEVICES
+            .iter()
+            .find(|(l, _)| *l == level)
+            .map(|(_, min)| *min)
+            .ok_or_else(|| {
+                BlockDevError::InvalidInput(format!("unsupported RAID level {level}"))
+            })?;
+        if devices.len() < min_devices {
+            return Err(BlockDevError::InvalidInput(format!(
+                "RAID {level} needs at least {min_devices} devices, got {}",
+                devices.len()
+            )));
+        }
+        validate_command_name(array_name)?;
+
+        let mut command = format!(
+            "mdadm --create /dev/{array_name} --level={level} --raid-devices={}",
+            devices.len()
+        );
+        for (i, device) in devices.iter().enumerate() {
+            validate_command_name(&device.name)?;
+            if device.is_system_with(policy) {
+                return Err(BlockDevError::InvalidInput(format!(
+                    "{} is a system device",
+                    device.name
+                )));
+            }
+            if devices[..i].iter().any(|d| d.name == device.name) {
+                return Err(BlockDevError::InvalidInput(format!(
+                    "{} is listed more than once",
+                    device.name
+                )));
+            }
+            command.push_str(" /dev/");
+            command.push_str(&device.name);
+        }
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::{BlockDevError, BlockDevices, RaidEfficiency, parse_lsblk};
+
+    /// Two disks whose first partitions form a RAID1, plus a whole-disk member.
+    const MD_MEMBERS_JSON: &str = r#"{"blockdevices": [
+        {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "512G", "ro": false, "type": "part", "mountpoints": [null],
+             "fstype": "linux_raid_member",
+             "children": [
+                {"name": "md0", "maj:min": "9:0", "rm": false, "size": "512G", "ro": false, "type": "raid1", "mountpoints": ["/srv"],
+                 "fstype": "ext4"}
+             ]},
+            {"name": "sda2", "maj:min": "8:2", "rm": false, "size": "512G", "ro": false, "type": "part", "mountpoints": [null],
+             "fstype": "xfs"}
+         ]},
+        {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "sdb1", "maj:min": "8:17", "rm": false, "size": "512G", "ro": false, "type": "part", "mountpoints": [null],
+             "fstype": "linux_raid_member",
+             "children": [
+                {"name": "md0", "maj:min": "9:0", "rm": false, "size": "512G", "ro": false, "type": "raid1", "mountpoints": ["/srv"],
+                 "fstype": "ext4"}
+             ]}
+         ]},
+        {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "fstype": "linux_raid_member"},
+        {"name": "sdd", "maj:min": "8:48", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "fstype": "isw_raid_member"}
+    ]}"#;
+
+    #[test]
+    fn test_md_member_partitions() {
+        let devices = parse_lsblk(MD_MEMBERS_JSON).expect("Failed to parse JSON");
+        let names: Vec<&str> = devices
+            .md_member_partitions()
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["sda1", "sdb1", "sdc"]);
+        assert!(!devices.find_by_name("sdd").unwrap().is_md_member());
+
+        // Without the FSTYPE column, nothing is recognized.
+        let sample = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        assert!(sample.md_member_partitions().is_empty());
+    }
+
+    #[test]
+    fn test_same_size_groups() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let groups = devices.same_size_groups();
+
+        // The two 894.3G disks carry the root filesystem, so only the 3.5T group remains.
+        assert_eq!(groups.len(), 1);
+        let big = groups
+            .get(&devices.find_by_name("nvme0n1").unwrap().size)
+            .expect("Expected a 3.5T group");
+        assert_eq!(big.len(), 8);
+        assert!(
+            !groups.contains_key(&devices.find_by_name("nvme3n1").unwrap().size),
+            "system disks must not be grouped"
+        );
+    }
+
+    #[test]
+    fn test_pairs_of_same_size() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let pairs = devices.pairs_of_same_size();
+
+        // C(8, 2) pairs from the eight non-system 3.5T disks.
+        assert_eq!(pairs.len(), 28);
+        assert_eq!(
+            (pairs[0].0.name.as_str(), pairs[0].1.name.as_str()),
+            ("nvme1n1", "nvme7n1")
+        );
+        for (a, b) in &pairs {
+            assert_ne!(a.name, b.name);
+            assert_eq!(a.size, b.size);
+        }
+    }
+
+    #[test]
+    fn test_single_disk_forms_no_pairs() {
+        let json = r#"{
+            "blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "894.3G", "ro": false, "type": "disk", "mountpoints": [null]}
+            ]
+        }"#;
+        let devices = parse_lsblk(json).expect("Failed to parse JSON");
+        let pairs = devices.pairs_of_same_size();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].0.name, "sda");
+        assert_eq!(pairs[0].1.name, "sdb");
+        assert_eq!(devices.same_size_groups().len(), 2);
+    }
+
+    #[test]
+    fn test_detect_raid_arrays() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let arrays = devices.detect_raid_arrays();
+        let summary: Vec<(&str, &str, Vec<&str>)> = arrays
+            .iter()
+            .map(|a| {
+                (
+                    a.name.as_str(),
+                    a.level.as_str(),
+                    a.members.iter().map(|m| m.name.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("md0", "raid1", vec!["nvme3n1p3", "nvme2n1p3"]),
+                ("md1", "raid1", vec!["nvme3n1p4", "nvme2n1p4"]),
+                ("md2", "raid1", vec!["nvme3n1p5", "nvme2n1p5"]),
+            ]
+        );
+        assert_eq!(arrays[2].md_device.active_mountpoints(), vec!["/"]);
+        assert!(BlockDevices::default().detect_raid_arrays().is_empty());
+    }
+
+    #[test]
+    fn test_raid_efficiency() {
+        let cases = [
+            ("raid0", 3, Some(1.0)),
+            ("raid1", 2, Some(0.5)),
+            ("raid1", 3, Some(1.0 / 3.0)),
+            ("1", 1, Some(1.0)),
+            ("raid4", 3, Some(2.0 / 3.0)),
+            ("raid5", 4, Some(0.75)),
+            ("raid5", 1, None),
+            ("raid6", 4, Some(0.5)),
+            ("raid6", 2, None),
+            ("raid10", 4, Some(0.5)),
+            ("raid1", 0, None),
+            ("linear", 2, None),
+            ("", 2, None),
+        ];
+        for (level, members, expected) in cases {
+            assert_eq!(
+                RaidEfficiency::compute(level, members),
+                expected,
+                "{level} x{members}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_virtual_size_bytes() {
+        let tera = 1024_u64.pow(4);
+        let mirror = |md_size: &str| {
+            format!(
+                r#"{{"blockdevices": [
+                    {{"name": "sda", "maj:min": "8:0", "rm": false, "size": "3.5T", "ro": false, "type": "disk", "mountpoints": [null],
+                      "children": [{{"name": "md0", "maj:min": "9:0", "rm": false, "size": "{md_size}", "ro": false, "type": "raid1", "mountpoints": ["/srv"]}}]}},
+                    {{"name": "sdb", "maj:min": "8:16", "rm": false, "size": "3.5T", "ro": false, "type": "disk", "mountpoints": [null],
+                      "children": [{{"name": "md0", "maj:min": "9:0", "rm": false, "size": "{md_size}", "ro": false, "type": "raid1", "mountpoints": ["/srv"]}}]}}
+                ]}}"#
+            )
+        };
+        // Computed from the members even if the reported array size is wrong.
+        let devices = parse_lsblk(&mirror("7T")).expect("Failed to parse JSON");
+        let md0 = devices.iter_all().find(|d| d.name == "md0").unwrap();
+        assert_eq!(md0.virtual_size_bytes(&devices), 7 * tera / 2);
+        assert_eq!(
+            devices.detect_raid_arrays()[0].virtual_size_bytes(),
+            Some(7 * tera / 2)
+        );
+        assert_eq!(
+            devices.blockdevices[0].virtual_size_bytes(&devices),
+            7 * tera / 2
+        );
+
+        // Without its members in the snapshot, the array's own size is used.
+        let flat = BlockDevices::from(vec![md0.clone()]);
+        assert_eq!(md0.virtual_size_bytes(&flat), 7 * tera);
+
+        let sample = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        for array in sample.detect_raid_arrays() {
+            let members_min = array.members.iter().map(|m| m.size).min().unwrap();
+            assert_eq!(array.virtual_size_bytes(), Some(members_min));
+        }
+    }
+
+    #[test]
+    fn test_generate_mdadm_create_command() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let nvme0n1 = devices.find_by_name("nvme0n1").unwrap();
+        let nvme1n1 = devices.find_by_name("nvme1n1").unwrap();
+
+        let command =
+            BlockDevices::generate_mdadm_create_command(&[nvme0n1, nvme1n1], 1, "md3").unwrap();
+        assert_eq!(
+            command,
+            "mdadm --create /dev/md3 --level=1 --raid-devices=2 /dev/nvme0n1 /dev/nvme1n1"
+        );
+    }
+
+    #[test]
+    fn test_generate_mdadm_create_command_invalid() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let nvme0n1 = devices.find_by_name("nvme0n1").unwrap();
+        let nvme1n1 = devices.find_by_name("nvme1n1").unwrap();
+        let nvme3n1 = devices.find_by_name("nvme3n1").unwrap();
+
+        let cases: [(&[_], u8, &str); 6] = [
+            (&[nvme0n1, nvme3n1], 1, "md3"),
+            (&[nvme0n1, nvme1n1], 5, "md3"),
+            (&[nvme0n1, nvme1n1], 3, "md3"),
+            (&[nvme0n1, nvme0n1], 1, "md3"),
+            (&[nvme0n1, nvme1n1], 1, "../sda"),
+            (&[nvme0n1, nvme1n1], 1, "md3; reboot"),
+        ];
+        for (members, level, name) in cases {
+            assert!(
+                matches!(
+                    BlockDevices::generate_mdadm_create_command(members, level, name),
+                    Err(BlockDevError::InvalidInput(_))
+                ),
+                "level {level}, name '{name}'"
+            );
+        }
+    }
+}
@@ -0,0 +1,336 @@
+use crate::{BlockDevice, BlockDevices};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// An I/O scheduler suggestion returned by [`BlockDevice::recommended_scheduler`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SchedulerRecommendation {
+    /// The scheduler name as written to `/sys/block/<name>/queue/scheduler`,
+    /// such as `none`, `mq-deadline` or `bfq`.
+    pub recommended: String,
+    /// A short explanation of why this scheduler was chosen.
+    pub reason: String,
+}
+
+impl SchedulerRecommendation {
+    fn new(recommended: &str, reason: &str) -> Self {
+        SchedulerRecommendation {
+            recommended: recommended.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl BlockDevice {
+    /// Suggests an I/O scheduler for this device.
+    ///
+    /// NVMe devices (see [`BlockDevice::is_nvm_express`]) get `none`, since
+    /// their hardware queues make host-side scheduling pure overhead. Otherwise
+    /// the `rota` column decides: `mq-deadline` for SSDs and `bfq` for
+    /// rotational disks. Without `rota` (it is only set by
+    /// `get_devices_extended` or JSON that includes the `ROTA` column), the
+    /// `tran` column and then the name are used: MMC and eMMC flash gets
+    /// `mq-deadline`, virtio and Xen disks get `none` because the host
+    /// schedules their I/O, and anything else falls back to `mq-deadline`.
+    ///
+    /// The scheduler is a property of the whole disk; partitions share their disk's.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::BlockDevice;
+    ///
+    /// let hdd = BlockDevice {
+    ///     name: "sda".to_string(),
+    ///     rota: Some(true),
+    ///     ..Default::default()
+    /// };
+    /// assert_eq!(hdd.recommended_scheduler().recommended, "bfq");
+    /// ```
+    #[must_use]
+    pub fn recommended_scheduler(&self) -> SchedulerRecommendation {
+        let name = self.name.as_str();
+        let tran = self.tran.as_deref();
+        if self.is_nvm_express() {
+            return SchedulerRecommendation::new(
+                "none",
+                "NVMe devices have deep hardware queues that do their own scheduling",
+            );
+        }
+        match self.rota {
+            Some(false) => {
+                return SchedulerRecommendation::new(
+                    "mq-deadline",
+                    "non-rotational (SSD) devices gain nothing from reordering, only from deadlines",
+                );
+            }
+            Some(true) => {
+                return SchedulerRecommendation::new(
+                    "bfq",
+                    "rotational (HDD) devices benefit from bfq's seek-aware fair queueing",
+                );
+            }
+            None => {}
+        }
+
+        if tran == Some("mmc") || name.starts_with("mmcblk") {
+            SchedulerRecommendation::new(
+                "mq-deadline",
+                "MMC and eMMC flash storage is non-rotational",
+            )
+        } else if tran == Some("virtio") || name.starts_with("vd") || name.starts_with("xvd") {
+            SchedulerRecommendation::new(
+                "none",
+                "virtual disks are scheduled by the host, so guest-side scheduling is overhead",
+            )
+        } else {
+            SchedulerRecommendation::new(
+                "mq-deadline",
+                "rotational status is unknown; mq-deadline is a safe default for both SSDs and HDDs",
+            )
+        }
+    }
+}
+
+/// Which I/O schedulers [`BlockDevices::scheduler_report_with`] flags, by
+/// whether the disk is rotational (the `ROTA` column).
+///
+/// The default flags rotational disks using `none`, which leaves seeks
+/// unordered, and SSDs using `bfq`, whose per-request overhead buys nothing
+/// without seeks.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SchedulerRules {
+    /// Schedulers that should not be used on rotational disks.
+    pub rotational_avoid: Vec<String>,
+    /// Schedulers that should not be used on non-rotational disks.
+    pub non_rotational_avoid: Vec<String>,
+}
+
+impl Default for SchedulerRules {
+    fn default() -> Self {
+        SchedulerRules {
+            rotational_avoid: vec!["none".to_string()],
+            non_rotational_avoid: vec!["bfq".to_string()],
+        }
+    }
+}
+
+/// A disk whose scheduler is ruled out by [`SchedulerRules`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SchedulerFinding {
+    /// The kernel name of the disk, such as `sda`.
+    pub device: String,
+    /// The scheduler the disk uses.
+    pub scheduler: String,
+    /// Whether the disk is rotational.
+    pub rotational: bool,
+}
+
+impl fmt::Display for SchedulerFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.rotational {
+            "rotational"
+        } else {
+            "non-rotational"
+        };
+        write!(f, "{} is {kind} but uses {}", self.device, self.scheduler)
+    }
+}
+
+/// The result of [`BlockDevices::scheduler_report`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SchedulerReport {
+    /// The names of the top-level disks using each scheduler, in tree order.
+    pub by_scheduler: BTreeMap<String, Vec<String>>,
+    /// The disks whose scheduler is ruled out, in tree order.
+    pub findings: Vec<SchedulerFinding>,
+}
+
+impl BlockDevices {
+    /// Audits the schedulers of the top-level disks with the default
+    /// [`SchedulerRules`].
+    #[must_use]
+    pub fn scheduler_report(&self) -> SchedulerReport {
+        self.scheduler_report_with(&SchedulerRules::default())
+    }
+
+    /// Groups the top-level disks by scheduler and flags those whose scheduler
+    /// `rules` avoids for their kind of disk.
+    ///
+    /// Needs the `SCHED` column, and `ROTA` for findings; `get_devices_extended`
+    /// requests both. Disks without `sched` are left out, and disks without
+    /// `rota` are grouped but never flagged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "16T", "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "rota": true, "sched": "none"}
+    /// ]}"#).unwrap();
+    ///
+    /// let report = devices.scheduler_report();
+    /// assert_eq!(report.findings[0].to_string(), "sda is rotational but uses none");
+    /// ```
+    #[must_use]
+    pub fn scheduler_report_with(&self, rules: &SchedulerRules) -> SchedulerReport {
+        let mut report = SchedulerReport::default();
+        for disk in self.iter().filter(|d| d.is_disk()) {
+            let Some(scheduler) = disk.sched.as_deref() else {
+                continue;
+            };
+            report
+                .by_scheduler
+                .entry(scheduler.to_string())
+                .or_default()
+                .push(disk.name.clone());
+            let Some(rotational) = disk.rota else {
+                continue;
+            };
+            let avoid = if rotational {
+                &rules.rotational_avoid
+            } else {
+                &rules.non_rotational_avoid
+            };
+            if avoid.iter().any(|s| s == scheduler) {
+                report.findings.push(SchedulerFinding {
+                    device: disk.name.clone(),
+                    scheduler: scheduler.to_string(),
+                    rotational,
+                });
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_lsblk;
+
+    #[test]
+    fn test_recommended_scheduler() {
+        let json = r#"{
+            "blockdevices": [
+                {"name": "nvme0n1", "maj:min": "259:0", "rm": false, "size": "3.5T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "rota": false, "tran": "nvme"},
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "960G", "ro": false, "type": "disk", "mountpoints": [null],
+                 "rota": false, "tran": "sata"},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "16T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "rota": "1", "tran": "sata"},
+                {"name": "mmcblk0", "maj:min": "179:0", "rm": false, "size": "32G", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "vda", "maj:min": "252:0", "rm": false, "size": "40G", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]}
+            ]
+        }"#;
+        let devices = parse_lsblk(json).expect("Failed to parse JSON");
+        let schedulers: Vec<(&str, String)> = devices
+            .iter()
+            .map(|d| (d.name.as_str(), d.recommended_scheduler().recommended))
+            .collect();
+        assert_eq!(
+            schedulers,
+            vec![
+                ("nvme0n1", "none".to_string()),
+                ("sda", "mq-deadline".to_string()),
+                ("sdb", "bfq".to_string()),
+                ("mmcblk0", "mq-deadline".to_string()),
+                ("vda", "none".to_string()),
+                ("sdc", "mq-deadline".to_string()),
+            ]
+        );
+        assert_eq!(devices.find_by_name("sdb").unwrap().rota, Some(true));
+        assert!(
+            devices
+                .find_by_name("sdb")
+                .unwrap()
+                .recommended_scheduler()
+                .reason
+                .contains("HDD")
+        );
+    }
+
+    const SCHEDULERS_JSON: &str = r#"{"blockdevices": [
+        {"name": "nvme0n1", "maj:min": "259:0", "rm": false, "size": "3.5T", "ro": false, "type": "disk", "mountpoints": [null],
+         "rota": false, "sched": "none", "rq-size": 1023},
+        {"name": "nvme1n1", "maj:min": "259:1", "rm": false, "size": "3.5T", "ro": false, "type": "disk", "mountpoints": [null],
+         "rota": false, "sched": "bfq", "rq-size": "1023"},
+        {"name": "sda", "maj:min": "8:0", "rm": false, "size": "16T", "ro": false, "type": "disk", "mountpoints": [null],
+         "rota": true, "sched": "mq-deadline", "rq-size": 64,
+         "children": [
+            {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "16T", "ro": false, "type": "part", "mountpoints": ["/srv"],
+             "rota": true, "sched": "none"}
+         ]},
+        {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "16T", "ro": false, "type": "disk", "mountpoints": [null],
+         "rota": true, "sched": "none", "rq-size": 64},
+        {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "16T", "ro": false, "type": "disk", "mountpoints": [null],
+         "sched": "none"},
+        {"name": "sdd", "maj:min": "8:48", "rm": false, "size": "960G", "ro": false, "type": "disk", "mountpoints": [null],
+         "rota": false, "sched": "mq-deadline"},
+        {"name": "loop0", "maj:min": "7:0", "rm": false, "size": "64M", "ro": true, "type": "loop", "mountpoints": ["/snap/core"],
+         "rota": false, "sched": "bfq"}
+    ]}"#;
+
+    #[test]
+    fn test_scheduler_report() {
+        let devices = parse_lsblk(SCHEDULERS_JSON).expect("Failed to parse JSON");
+        assert_eq!(devices.blockdevices[1].rq_size, Some(1023));
+
+        let report = devices.scheduler_report();
+        let groups: Vec<(&str, Vec<&str>)> = report
+            .by_scheduler
+            .iter()
+            .map(|(s, names)| (s.as_str(), names.iter().map(String::as_str).collect()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                ("bfq", vec!["nvme1n1"]),
+                ("mq-deadline", vec!["sda", "sdd"]),
+                ("none", vec!["nvme0n1", "sdb", "sdc"]),
+            ]
+        );
+        let findings: Vec<String> = report.findings.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            findings,
+            vec![
+                "nvme1n1 is non-rotational but uses bfq",
+                "sdb is rotational but uses none",
+            ]
+        );
+
+        // Only accept mq-deadline on HDDs and none on SSDs.
+        let rules = SchedulerRules {
+            rotational_avoid: vec!["none".to_string(), "bfq".to_string(), "kyber".to_string()],
+            non_rotational_avoid: vec!["mq-deadline".to_string(), "bfq".to_string()],
+        };
+        let report = devices.scheduler_report_with(&rules);
+        assert_eq!(
+            report.findings,
+            vec![
+                SchedulerFinding {
+                    device: "nvme1n1".to_string(),
+                    scheduler: "bfq".to_string(),
+                    rotational: false,
+                },
+                SchedulerFinding {
+                    device: "sdb".to_string(),
+                    scheduler: "none".to_string(),
+                    rotational: true,
+                },
+                SchedulerFinding {
+                    device: "sdd".to_string(),
+                    scheduler: "mq-deadline".to_string(),
+                    rotational: false,
+                },
+            ]
+        );
+        assert_eq!(
+            BlockDevices::default().scheduler_report(),
+            SchedulerReport::default()
+        );
+    }
+}
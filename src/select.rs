@@ -0,0 +1,215 @@
+use crate::natural::natural_cmp;
+use crate::{BlockDevice, BlockDevices, SystemPolicy};
+
+/// The order in which [`BlockDevices::best_candidate`] prefers qualifying disks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SelectionOrder {
+    /// Prefer the largest qualifying disk.
+    #[default]
+    LargestFirst,
+    /// Prefer the smallest disk that still meets the minimum size.
+    SmallestSufficient,
+}
+
+/// Criteria used by [`BlockDevices::best_candidate`] to pick a disk for new data.
+///
+/// System devices (under [`system`](Self::system)), read-only devices and
+/// disks with any mounted descendant are never selected, and neither are
+/// removable drives with no media inserted (see
+/// [`BlockDevice::media_present`]). The default policy accepts disks of any
+/// size, rejects removable disks, accepts disks that are partitioned but
+/// entirely unmounted, prefers the largest disk, and treats devices as system
+/// devices under [`SystemPolicy::strict`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SelectionPolicy {
+    /// The minimum size in bytes a disk must have to qualify.
+    pub min_size: u64,
+    /// Whether removable disks qualify.
+    pub allow_removable: bool,
+    /// Whether disks that already have partitions (all unmounted) qualify.
+    pub allow_partitioned: bool,
+    /// Which qualifying disk to prefer.
+    pub order: SelectionOrder,
+    /// Which mountpoints make a disk a system disk, and so never selected.
+    pub system: SystemPolicy,
+}
+
+impl Default for SelectionPolicy {
+    fn default() -> Self {
+        SelectionPolicy {
+            min_size: 0,
+            allow_removable: false,
+            allow_partitioned: true,
+            order: SelectionOrder::LargestFirst,
+            system: SystemPolicy::strict(),
+        }
+    }
+}
+
+/// Returns `true` if the device or any of its recursive children is mounted.
+pub(crate) fn is_mounted_recursive(device: &BlockDevice) -> bool {
+    device.is_mounted() || device.children_iter().any(is_mounted_recursive)
+}
+
+impl SelectionPolicy {
+    /// Returns `true` if the top-level device qualifies under this policy.
+    fn accepts(&self, device: &BlockDevice) -> bool {
+        device.is_disk()
+            && !device.ro
+            && !device.is_system_with(&self.system)
+            && !is_mounted_recursive(device)
+            && device.size >= self.min_size
+            && (self.allow_removable || !device.rm)
+            && device.media_present() != Some(false)
+            && (self.allow_partitioned || !device.has_children())
+    }
+}
+
+impl BlockDevices {
+    /// Picks the best top-level disk for new data according to `policy`.
+    ///
+    /// Ties in size are broken by natural name order (`nvme2n1` before
+    /// `nvme10n1`), so the result is deterministic for a given snapshot.
+    ///
+    /// Returns `None` if no disk qualifies.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{SelectionPolicy, parse_lsblk};
+    ///
+    /// let json = r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": ["/"]},
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]}
+    /// ]}"#;
+    /// let devices = parse_lsblk(json).unwrap();
+    /// let best = devices.best_candidate(&SelectionPolicy::default()).unwrap();
+    /// assert_eq!(best.name, "sdb");
+    /// ```
+    #[must_use]
+    pub fn best_candidate(&self, policy: &SelectionPolicy) -> Option<&BlockDevice> {
+        self.iter()
+            .filter(|device| policy.accepts(device))
+            .min_by(|a, b| {
+                let by_size = match policy.order {
+                    SelectionOrder::LargestFirst => b.size.cmp(&a.size),
+                    SelectionOrder::SmallestSufficient => a.size.cmp(&b.size),
+                };
+                by_size.then_with(|| natural_cmp(&a.name, &b.name))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{CARD_READER_JSON, SAMPLE_JSON};
+    use crate::parse_lsblk;
+
+    const MIXED_JSON: &str = r#"{
+        "blockdevices": [
+            {"name": "sda", "maj:min": "8:0", "rm": false, "size": "2T", "ro": false, "type": "disk", "mountpoints": [null]},
+            {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null]},
+            {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+            {"name": "sdd", "maj:min": "8:48", "rm": true, "size": "4T", "ro": false, "type": "disk", "mountpoints": [null]},
+            {"name": "sde", "maj:min": "8:64", "rm": false, "size": "8T", "ro": true, "type": "disk", "mountpoints": [null]},
+            {"name": "sdf", "maj:min": "8:80", "rm": false, "size": "8T", "ro": false, "type": "disk", "mountpoints": [null],
+                "children": [
+                    {"name": "sdf1", "maj:min": "8:81", "rm": false, "size": "8T", "ro": false, "type": "part", "mountpoints": ["/srv"]}
+                ]
+            },
+            {"name": "loop0", "maj:min": "7:0", "rm": false, "size": "16T", "ro": false, "type": "loop", "mountpoints": [null]}
+        ]
+    }"#;
+
+    #[test]
+    fn test_best_candidate_sample() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let best = devices
+            .best_candidate(&SelectionPolicy::default())
+            .expect("Expected a candidate");
+        assert_eq!(best.name, "nvme0n1");
+
+        let smallest = devices
+            .best_candidate(&SelectionPolicy {
+                order: SelectionOrder::SmallestSufficient,
+                ..SelectionPolicy::default()
+            })
+            .expect("Expected a candidate");
+        assert_eq!(smallest.name, "nvme0n1");
+    }
+
+    #[test]
+    fn test_best_candidate_never_picks_system_disks() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        for order in [
+            SelectionOrder::LargestFirst,
+            SelectionOrder::SmallestSufficient,
+        ] {
+            for min_size in [0, 1 << 30, 1 << 40] {
+                let policy = SelectionPolicy {
+                    min_size,
+                    order,
+                    ..SelectionPolicy::default()
+                };
+                let best = devices
+                    .best_candidate(&policy)
+                    .expect("Expected a candidate");
+                assert!(best.name != "nvme2n1" && best.name != "nvme3n1");
+            }
+        }
+    }
+
+    #[test]
+    fn test_best_candidate_rejects_partitioned() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let policy = SelectionPolicy {
+            allow_partitioned: false,
+            ..SelectionPolicy::default()
+        };
+        assert!(devices.best_candidate(&policy).is_none());
+    }
+
+    #[test]
+    fn test_best_candidate_skips_empty_readers() {
+        let devices = parse_lsblk(CARD_READER_JSON).expect("Failed to parse JSON");
+        let policy = SelectionPolicy {
+            allow_removable: true,
+            order: SelectionOrder::SmallestSufficient,
+            ..SelectionPolicy::default()
+        };
+        // sdf is an empty card reader and sdg has a mounted partition.
+        assert!(devices.best_candidate(&policy).is_none());
+    }
+
+    #[test]
+    fn test_best_candidate_policy() {
+        let devices = parse_lsblk(MIXED_JSON).expect("Failed to parse JSON");
+
+        // sde is read-only, sdf has a mounted partition, sdd is removable and loop0 is not a disk.
+        let best = devices.best_candidate(&SelectionPolicy::default()).unwrap();
+        assert_eq!(best.name, "sda");
+
+        let with_removable = SelectionPolicy {
+            allow_removable: true,
+            ..SelectionPolicy::default()
+        };
+        assert_eq!(devices.best_candidate(&with_removable).unwrap().name, "sdd");
+
+        let smallest_sufficient = SelectionPolicy {
+            min_size: 600 * 1024 * 1024 * 1024,
+            order: SelectionOrder::SmallestSufficient,
+            ..SelectionPolicy::default()
+        };
+        assert_eq!(
+            devices.best_candidate(&smallest_sufficient).unwrap().name,
+            "sdc"
+        );
+
+        let too_large = SelectionPolicy {
+            min_size: 3 * 1024 * 1024 * 1024 * 1024,
+            ..SelectionPolicy::default()
+        };
+        assert!(devices.best_candidate(&too_large).is_none());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{BlockDevice, BlockDevices, BlockDevicesRef};
+use std::collections::HashMap;
+
+/// Replaces every character that is not safe in a file name on common
+/// filesystems with `_`.
+fn sanitize_stem(stem: &str) -> String {
+    stem.trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Returns the serial, the WWN or the name of the device, whichever is set first.
+fn preferred_stem(device: &BlockDevice) -> String {
+    [device.serial.as_deref(), device.wwn.as_deref()]
+        .into_iter()
+        .flatten()
+        .map(sanitize_stem)
+        .find(|stem| !stem.is_empty() && stem.bytes().any(|b| b != b'.'))
+        .unwrap_or_else(|| sanitize_stem(&device.name))
+}
+
+impl BlockDevices {
+    /// Splits the snapshot into one JSON document per top-level device, each
+    /// paired with a suggested file name stem.
+    ///
+    /// The stem is the device's `serial`, else its `wwn`, else its name, with
+    /// characters other than ASCII letters, digits, `-`, `_` and `.` replaced
+    /// by `_`. If several devices end up with the same stem, each of them gets
+    /// a `-<major>-<minor>` suffix. `serial` and `wwn` are only set by
+    /// `get_devices_extended` or JSON that includes the `SERIAL` and `WWN`
+    /// columns.
+    ///
+    /// Every document is a complete `{"blockdevices": [...]}` snapshot that
+    /// [`parse_lsblk`](crate::parse_lsblk) accepts, carrying this snapshot's
+    /// [`meta`](BlockDevices::meta) but not its warnings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if [`SnapshotMeta::taken_at`](crate::SnapshotMeta::taken_at)
+    /// is before the Unix epoch, since such a timestamp cannot be serialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "serial": "S5GXNF0R123456"},
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]}
+    /// ]}"#).unwrap();
+    ///
+    /// let documents = devices.split().unwrap();
+    /// assert_eq!(documents[0].0, "S5GXNF0R123456");
+    /// assert_eq!(documents[1].0, "sdb");
+    /// assert_eq!(parse_lsblk(&documents[1].1).unwrap().blockdevices[0].name, "sdb");
+    /// ```
+    pub fn split(&self) -> serde_json::Result<Vec<(String, String)>> {
+        let stems: Vec<String> = self.iter().map(preferred_stem).collect();
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for stem in &stems {
+            *counts.entry(stem.as_str()).or_default() += 1;
+        }
+
+        self.iter()
+            .zip(&stems)
+            .map(|(device, stem)| {
+                let stem = if counts[stem.as_str()] > 1 {
+                    format!("{stem}-{}-{}", device.maj_min.major, device.maj_min.minor)
+                } else {
+                    stem.clone()
+                };
+                let document = BlockDevicesRef {
+                    blockdevices: vec![device.as_ref_tree()],
+                    meta: self.meta.as_ref(),
+                    warnings: &[],
+                };
+                serde_json::to_string(&document).map(|json| (stem, json))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::{SnapshotMeta, SourceKind, parse_lsblk};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_split_stem_fallback_order() {
+        let devices = parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "serial": "WD-WX12A3456789", "wwn": "0x50014ee2b1c2d3e4"},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "wwn": "0x5000c500a1b2c3d4"},
+                {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "serial": "  "},
+                {"name": "sdd", "maj:min": "8:48", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "serial": "QEMU HARDDISK/0"},
+                {"name": "sde", "maj:min": "8:64", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "serial": "DUP123"},
+                {"name": "sdf", "maj:min": "8:80", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "serial": "DUP123"}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+        let stems: Vec<String> = devices
+            .split()
+            .unwrap()
+            .into_iter()
+            .map(|(stem, _)| stem)
+            .collect();
+        assert_eq!(
+            stems,
+            vec![
+                "WD-WX12A3456789",
+                "0x5000c500a1b2c3d4",
+                "sdc",
+                "QEMU_HARDDISK_0",
+                "DUP123-8-64",
+                "DUP123-8-80",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_documents_reparse() {
+        let mut devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        devices.meta = Some(SnapshotMeta::new(SourceKind::Lsblk));
+        devices.warnings = vec!["lsblk: /dev/sr0: unknown device".to_string()];
+
+        let documents = devices.split().unwrap();
+        assert_eq!(documents.len(), devices.len());
+        for ((stem, json), device) in documents.iter().zip(devices.iter()) {
+            assert_eq!(stem, &device.name);
+            let single = parse_lsblk(json).expect("Failed to parse split document");
+            assert_eq!(single.blockdevices, vec![device.clone()]);
+            assert_eq!(single.meta, devices.meta);
+            assert!(single.warnings.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_split_rejects_time_before_epoch() {
+        let mut devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let mut meta = SnapshotMeta::new(SourceKind::Manual);
+        meta.taken_at = SystemTime::UNIX_EPOCH - Duration::from_secs(1);
+        devices.meta = Some(meta);
+        assert!(devices.split().is_err());
+    }
+}
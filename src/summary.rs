@@ -0,0 +1,346 @@
+use crate::select::is_mounted_recursive;
+use crate::{BlockDevice, BlockDevices, DeviceType, IterAll, SystemPolicy};
+use std::collections::HashSet;
+use std::fmt::{self, Write};
+
+/// Formats a byte count the way lsblk does without `--bytes`, e.g. `894.3G` or `8M`.
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 7] = ["B", "K", "M", "G", "T", "P", "E"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    let rounded = format!("{value:.1}");
+    let rounded = rounded.strip_suffix(".0").unwrap_or(&rounded);
+    format!("{rounded}{}", UNITS[unit])
+}
+
+/// Appends `count noun` to `out`, adding an `s` unless `count` is one.
+fn write_count(out: &mut String, count: usize, noun: &str) {
+    let plural = if count == 1 { "" } else { "s" };
+    let _ = write!(out, "{count} {noun}{plural}");
+}
+
+impl BlockDevices {
+    /// Returns a one-line, human-readable overview of the snapshot for log messages.
+    ///
+    /// The line lists the number of top-level disks, how many of them have nothing
+    /// mounted and how many are system disks, their total capacity, the number of
+    /// RAID, crypt and LVM devices, and the total swap size. Categories with a
+    /// count of zero are left out, and a device that appears under several
+    /// parents is counted once.
+    ///
+    /// [`BlockDevices`] also implements [`Display`](std::fmt::Display) using this summary.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/"]},
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "512G", "ro": false, "type": "disk", "mountpoints": [null]}
+    /// ]}"#).unwrap();
+    /// assert_eq!(devices.summary(), "2 disks (1 empty, 1 system), 1.5T total");
+    /// ```
+    #[must_use]
+    pub fn summary(&self) -> String {
+        self.summary_with(&SystemPolicy::default())
+    }
+
+    /// Like [`summary`](Self::summary), counting the system disks defined by
+    /// `policy`.
+    #[must_use]
+    pub fn summary_with(&self, policy: &SystemPolicy) -> String {
+        let disks: Vec<&BlockDevice> = self.iter().filter(|d| d.is_disk()).collect();
+        let empty = disks.iter().filter(|d| !is_mounted_recursive(d)).count();
+        let system = disks.iter().filter(|d| d.is_system_with(policy)).count();
+        let total: u64 = disks.iter().map(|d| d.size).sum();
+        let others = self.len() - disks.len();
+
+        let mut seen = HashSet::new();
+        let (mut raid, mut crypt, mut lvm, mut swap) = (0, 0, 0, 0u64);
+        for device in self.iter_all().filter(|d| seen.insert(d.name.as_str())) {
+            match device.device_type {
+                DeviceType::Raid0
+                | DeviceType::Raid1
+                | DeviceType::Raid5
+                | DeviceType::Raid6
+                | DeviceType::Raid10 => raid += 1,
+                DeviceType::Crypt => crypt += 1,
+                DeviceType::Lvm => lvm += 1,
+                _ => {}
+            }
+            if device.active_mountpoints().contains(&"[SWAP]") {
+                swap += device.size;
+            }
+        }
+
+        let mut out = String::new();
+        write_count(&mut out, disks.len(), "disk");
+        let _ = write!(out, " ({empty} empty, {system} system)");
+        if others > 0 {
+            out.push_str(", ");
+            write_count(&mut out, others, "other device");
+        }
+        let _ = write!(out, ", {} total", format_size(total));
+        for (count, noun) in [
+            (raid, "RAID array"),
+            (crypt, "crypt device"),
+            (lvm, "LVM volume"),
+        ] {
+            if count > 0 {
+                out.push_str(", ");
+                write_count(&mut out, count, noun);
+            }
+        }
+        if swap > 0 {
+            let _ = write!(out, ", swap {}", format_size(swap));
+        }
+        out
+    }
+}
+
+impl BlockDevices {
+    /// Counts the devices of each type and sums their sizes, as
+    /// `(type name, count, total bytes)`.
+    ///
+    /// Every device in the tree is included, but a device that appears under
+    /// several parents (such as a RAID array) is counted once. Type names are
+    /// lsblk's `TYPE` strings, such as `part` or `raid1`. Entries are sorted by
+    /// count, most frequent first, and then by name.
+    #[must_use]
+    pub fn summarize_by_type(&self) -> Vec<(String, usize, u64)> {
+        let mut seen = HashSet::new();
+        let mut totals: Vec<(String, usize, u64)> = Vec::new();
+        for device in self.iter_all().filter(|d| seen.insert(d.name.as_str())) {
+            let name = device.device_type.to_string();
+            match totals.iter_mut().find(|(n, _, _)| *n == name) {
+                Some((_, count, size)) => {
+                    *count += 1;
+                    *size += device.size;
+                }
+                None => totals.push((name, 1, device.size)),
+            }
+        }
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        totals
+    }
+
+    /// Formats [`BlockDevices::summarize_by_type`] as a table with aligned columns.
+    fn type_summary_table(&self) -> String {
+        let rows: Vec<(String, String, String)> = self
+            .summarize_by_type()
+            .into_iter()
+            .map(|(name, count, size)| (name, count.to_string(), format_size(size)))
+            .collect();
+        let header = ("TYPE", "COUNT", "SIZE");
+        let width = |column: fn(&(String, String, String)) -> &String, title: &str| {
+            rows.iter()
+                .map(|r| column(r).len())
+                .fold(title.len(), usize::max)
+        };
+        let (type_width, count_width, size_width) = (
+            width(|r| &r.0, header.0),
+            width(|r| &r.1, header.1),
+            width(|r| &r.2, header.2),
+        );
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{:<type_width$}  {:>count_width$}  {:>size_width$}",
+            header.0, header.1, header.2
+        );
+        for (name, count, size) in &rows {
+            let _ = writeln!(
+                out,
+                "{name:<type_width$}  {count:>count_width$}  {size:>size_width$}"
+            );
+        }
+        out
+    }
+
+    /// Prints [`BlockDevices::summarize_by_type`] to stdout as a table with a
+    /// `TYPE`, `COUNT` and `SIZE` column.
+    pub fn print_type_summary(&self) {
+        print!("{}", self.type_summary_table());
+    }
+}
+
+impl BlockDevice {
+    /// Returns a one-line list of what is mounted on this device and beneath
+    /// it, such as `/boot/efi, /boot (raid1), / (raid1), swap`, for display
+    /// next to the device's name.
+    ///
+    /// Mountpoints are listed in tree order. Those of a descendant that is not
+    /// a partition are followed by its type in parentheses. Swap is not a
+    /// path, so any `[SWAP]` entries are shown as a single `swap` at the end.
+    /// A device that appears under several parents is listed once. Returns
+    /// `unmounted` if nothing is mounted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "children": [
+    ///         {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": ["/boot"]},
+    ///         {"name": "sda2", "maj:min": "8:2", "rm": false, "size": "8G", "ro": false, "type": "part", "mountpoints": ["[SWAP]"]}
+    ///      ]},
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]}
+    /// ]}"#).unwrap();
+    ///
+    /// assert_eq!(devices.blockdevices[0].mount_summary(), "/boot, swap");
+    /// assert_eq!(devices.blockdevices[1].mount_summary(), "unmounted");
+    /// ```
+    #[must_use]
+    pub fn mount_summary(&self) -> String {
+        let mut seen = HashSet::new();
+        let mut entries = Vec::new();
+        let mut swap = false;
+        for device in IterAll::subtree(self).filter(|d| seen.insert(d.name.as_str())) {
+            let annotate = !std::ptr::eq(device, self) && device.device_type != DeviceType::Part;
+            for mountpoint in device.active_mountpoints() {
+                if mountpoint == "[SWAP]" {
+                    swap = true;
+                } else if annotate {
+                    entries.push(format!("{mountpoint} ({})", device.device_type));
+                } else {
+                    entries.push(mountpoint.to_string());
+                }
+            }
+        }
+        if swap {
+            entries.push("swap".to_string());
+        }
+        if entries.is_empty() {
+            "unmounted".to_string()
+        } else {
+            entries.join(", ")
+        }
+    }
+}
+
+impl fmt::Display for BlockDevices {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.summary())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0B");
+        assert_eq!(format_size(512), "512B");
+        assert_eq!(format_size(8 << 20), "8M");
+        assert_eq!(format_size(960_197_124_096), "894.3G");
+        assert_eq!(format_size(3 << 40 | 1 << 39), "3.5T");
+    }
+
+    #[test]
+    fn test_summary_sample() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let summary = "10 disks (8 empty, 2 system), 29.7T total, 3 RAID arrays, swap 7.6G";
+        assert_eq!(devices.summary(), summary);
+        assert_eq!(devices.to_string(), summary);
+    }
+
+    #[test]
+    fn test_summarize_by_type() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let by_type = devices.summarize_by_type();
+        let names: Vec<(&str, usize)> = by_type.iter().map(|(n, c, _)| (n.as_str(), *c)).collect();
+        assert_eq!(names, vec![("part", 28), ("disk", 10), ("raid1", 3)]);
+
+        for (name, _, size) in &by_type {
+            let mut seen = HashSet::new();
+            let expected: u64 = devices
+                .iter_all()
+                .filter(|d| d.device_type.to_string() == *name && seen.insert(d.name.as_str()))
+                .map(|d| d.size)
+                .sum();
+            assert_eq!(*size, expected, "{name}");
+        }
+        let disks: u64 = devices.iter().map(|d| d.size).sum();
+        assert_eq!(by_type[1].2, disks);
+
+        let table = devices.type_summary_table();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "TYPE   COUNT   SIZE",
+                "part      28  29.7T",
+                "disk      10  29.7T",
+                "raid1      3  27.2G",
+            ]
+        );
+        assert_eq!(
+            BlockDevices::default().type_summary_table(),
+            "TYPE  COUNT  SIZE\n"
+        );
+    }
+
+    #[test]
+    fn test_mount_summary_sample() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let summary = |name: &str| devices.find_by_name(name).unwrap().mount_summary();
+        assert_eq!(
+            summary("nvme3n1"),
+            "/boot/efi, /boot (raid1), / (raid1), swap"
+        );
+        assert_eq!(summary("nvme2n1"), "/boot (raid1), / (raid1), swap");
+        assert_eq!(summary("nvme0n1"), "unmounted");
+        assert_eq!(summary("nvme6n1"), "unmounted");
+    }
+
+    #[test]
+    fn test_summary_other_layers() {
+        let json = r#"{
+            "blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "100G", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "100G", "ro": false, "type": "part", "mountpoints": [null],
+                     "children": [
+                        {"name": "luks", "maj:min": "253:0", "rm": false, "size": "100G", "ro": false, "type": "crypt", "mountpoints": [null],
+                         "children": [
+                            {"name": "vg-root", "maj:min": "253:1", "rm": false, "size": "90G", "ro": false, "type": "lvm", "mountpoints": ["/"]},
+                            {"name": "vg-swap", "maj:min": "253:2", "rm": false, "size": "10G", "ro": false, "type": "lvm", "mountpoints": ["[SWAP]"]}
+                         ]}
+                     ]}
+                 ]},
+                {"name": "sr0", "maj:min": "11:0", "rm": true, "size": "1024M", "ro": false, "type": "rom", "mountpoints": [null]}
+            ]
+        }"#;
+        let devices = parse_lsblk(json).expect("Failed to parse JSON");
+        assert_eq!(
+            devices.summary(),
+            "1 disk (0 empty, 1 system), 1 other device, 100G total, 1 crypt device, 2 LVM volumes, swap 10G"
+        );
+        assert_eq!(devices.blockdevices[0].mount_summary(), "/ (lvm), swap");
+        assert_eq!(devices.blockdevices[1].mount_summary(), "unmounted");
+
+        let whole = parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "4T", "ro": false, "type": "disk", "mountpoints": ["/srv", "/srv/backup"]}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+        assert_eq!(whole.blockdevices[0].mount_summary(), "/srv, /srv/backup");
+
+        assert_eq!(
+            BlockDevices::default().summary(),
+            "0 disks (0 empty, 0 system), 0B total"
+        );
+    }
+}
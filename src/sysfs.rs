@@ -0,0 +1,1411 @@
+use crate::naming::validate_device_name;
+use crate::natural::natural_cmp;
+use crate::proc_partitions::device_type_from_name;
+use crate::{
+    BlockDevError, BlockDevice, BlockDevices, DeviceType, IterAll, MajMin, SnapshotMeta, SourceKind,
+};
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// The directory under which the kernel exposes one entry per whole disk.
+const SYS_BLOCK: &str = "/sys/block";
+
+/// Rejects an attribute path that could leave the device directory: one that
+/// is absolute or has an empty, `.` or `..` component.
+fn validate_attr_path(attribute: &str) -> Result<(), BlockDevError> {
+    if attribute.contains('\0')
+        || attribute
+            .split('/')
+            .any(|component| matches!(component, "" | "." | ".."))
+    {
+        return Err(BlockDevError::InvalidInput(format!(
+            "invalid sysfs attribute path '{attribute}'"
+        )));
+    }
+    Ok(())
+}
+
+fn read_sysfs_attr_in(root: &Path, name: &str, attribute: &str) -> Result<String, BlockDevError> {
+    validate_device_name(name)?;
+    validate_attr_path(attribute)?;
+    std::fs::read_to_string(root.join(name).join(attribute))
+        .map(|contents| contents.trim().to_string())
+        .map_err(BlockDevError::IoError)
+}
+
+fn read_sysfs_u64_in(root: &Path, name: &str, attribute: &str) -> Result<u64, BlockDevError> {
+    let contents = read_sysfs_attr_in(root, name, attribute)?;
+    contents.parse().map_err(|_| {
+        BlockDevError::InvalidInput(format!("invalid {name}/{attribute} '{contents}'"))
+    })
+}
+
+fn read_sysfs_bool_in(root: &Path, name: &str, attribute: &str) -> Result<bool, BlockDevError> {
+    match read_sysfs_attr_in(root, name, attribute)?.as_str() {
+        "1" | "Y" => Ok(true),
+        "0" | "N" => Ok(false),
+        other => Err(BlockDevError::InvalidInput(format!(
+            "invalid {name}/{attribute} '{other}'"
+        ))),
+    }
+}
+
+/// Turns a missing device or attribute into `Ok(None)`.
+fn optional<T>(result: Result<T, BlockDevError>) -> Result<Option<T>, BlockDevError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(BlockDevError::IoError(err)) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Reads the sysfs attribute `<root>/<name>/<attribute>`, trimmed.
+///
+/// Returns `Ok(None)` if the device or the attribute does not exist.
+pub(crate) fn read_attribute(
+    root: &Path,
+    name: &str,
+    attribute: &str,
+) -> Result<Option<String>, BlockDevError> {
+    optional(read_sysfs_attr_in(root, name, attribute))
+}
+
+/// Reads `/sys/block/<device_name>/<attr_path>` with surrounding whitespace trimmed.
+///
+/// `attr_path` may name a nested attribute, such as `queue/scheduler` or
+/// `device/model`. This is Linux-only.
+///
+/// # Errors
+///
+/// Returns [`BlockDevError::InvalidInput`] if `device_name` is not a bare device
+/// name or `attr_path` is absolute or has an empty, `.` or `..` component, and
+/// [`BlockDevError::IoError`] if the attribute cannot be read, including when
+/// it does not exist.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use blockdev::read_sysfs_attr;
+/// let scheduler = read_sysfs_attr("sda", "queue/scheduler").expect("Failed to read scheduler");
+/// ```
+pub fn read_sysfs_attr(device_name: &str, attr_path: &str) -> Result<String, BlockDevError> {
+    read_sysfs_attr_in(Path::new(SYS_BLOCK), device_name, attr_path)
+}
+
+/// Reads a numeric attribute such as `size` or `queue/read_ahead_kb` with
+/// [`read_sysfs_attr`].
+///
+/// # Errors
+///
+/// Returns the errors of [`read_sysfs_attr`], and [`BlockDevError::InvalidInput`]
+/// if the contents are not an unsigned integer.
+pub fn read_sysfs_u64(device_name: &str, attr_path: &str) -> Result<u64, BlockDevError> {
+    read_sysfs_u64_in(Path::new(SYS_BLOCK), device_name, attr_path)
+}
+
+/// Reads a flag attribute such as `ro` or `queue/rotational` with
+/// [`read_sysfs_attr`]. `1` and `Y` are `true`, `0` and `N` are `false`.
+///
+/// # Errors
+///
+/// Returns the errors of [`read_sysfs_attr`], and [`BlockDevError::InvalidInput`]
+/// for any other contents.
+pub fn read_sysfs_bool(device_name: &str, attr_path: &str) -> Result<bool, BlockDevError> {
+    read_sysfs_bool_in(Path::new(SYS_BLOCK), device_name, attr_path)
+}
+
+/// Parses a `queue/scheduler` line such as `mq-deadline kyber [bfq] none`, in
+/// which the active scheduler is bracketed.
+fn parse_schedulers(contents: &str) -> Vec<String> {
+    contents
+        .split_whitespace()
+        .map(|name| {
+            name.trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string()
+        })
+        .collect()
+}
+
+fn available_schedulers_in(root: &Path, device_name: &str) -> Result<Vec<String>, BlockDevError> {
+    read_sysfs_attr_in(root, device_name, "queue/scheduler")
+        .map(|contents| parse_schedulers(&contents))
+}
+
+/// Lists the I/O schedulers the kernel offers for a disk, from
+/// `/sys/block/<device_name>/queue/scheduler`, including the active one.
+///
+/// Devices without a request queue of their own, such as device-mapper
+/// targets, usually only offer `none`. This is Linux-only.
+///
+/// # Errors
+///
+/// Returns the errors of [`read_sysfs_attr`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # use blockdev::available_schedulers;
+/// let schedulers = available_schedulers("sda").expect("Failed to read schedulers");
+/// assert!(schedulers.iter().any(|s| s == "none"));
+/// ```
+pub fn available_schedulers(device_name: &str) -> Result<Vec<String>, BlockDevError> {
+    available_schedulers_in(Path::new(SYS_BLOCK), device_name)
+}
+
+/// A queue attribute that [`write_sysfs_attr`] sets, with its new value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SysfsWritableAttr {
+    /// The I/O scheduler; must be one of [`available_schedulers`].
+    Scheduler(String),
+    /// The read-ahead window in KiB; must be a power of two, or 0 to turn
+    /// read-ahead off.
+    ReadAheadKb(u64),
+    /// The number of requests the queue may hold; must not be 0.
+    NrRequests(u32),
+}
+
+impl SysfsWritableAttr {
+    /// The attribute's path relative to `/sys/block/<name>`.
+    #[must_use]
+    pub fn attr_path(&self) -> &'static str {
+        match self {
+            SysfsWritableAttr::Scheduler(_) => "queue/scheduler",
+            SysfsWritableAttr::ReadAheadKb(_) => "queue/read_ahead_kb",
+            SysfsWritableAttr::NrRequests(_) => "queue/nr_requests",
+        }
+    }
+
+    #[cfg(any(test, target_os = "linux"))]
+    fn value(&self) -> String {
+        match self {
+            SysfsWritableAttr::Scheduler(name) => name.clone(),
+            SysfsWritableAttr::ReadAheadKb(kb) => kb.to_string(),
+            SysfsWritableAttr::NrRequests(requests) => requests.to_string(),
+        }
+    }
+
+    #[cfg(any(test, target_os = "linux"))]
+    fn validate(&self, root: &Path, device_name: &str) -> Result<(), BlockDevError> {
+        match self {
+            SysfsWritableAttr::Scheduler(name) => {
+                let available = available_schedulers_in(root, device_name)?;
+                if !available.contains(name) {
+                    return Err(BlockDevError::InvalidInput(format!(
+                        "scheduler '{name}' is not available for {device_name} (available: {})",
+                        available.join(", ")
+                    )));
+                }
+            }
+            SysfsWritableAttr::ReadAheadKb(kb) => {
+                if *kb != 0 && !kb.is_power_of_two() {
+                    return Err(BlockDevError::InvalidInput(format!(
+                        "read-ahead of {kb} KiB is not a power of two"
+                    )));
+                }
+            }
+            SysfsWritableAttr::NrRequests(requests) => {
+                if *requests == 0 {
+                    return Err(BlockDevError::InvalidInput(
+                        "nr_requests must not be 0".to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(test, target_os = "linux"))]
+fn write_sysfs_attr_in(
+    root: &Path,
+    device_name: &str,
+    attr: &SysfsWritableAttr,
+) -> Result<(), BlockDevError> {
+    validate_device_name(device_name)?;
+    attr.validate(root, device_name)?;
+    std::fs::write(root.join(device_name).join(attr.attr_path()), attr.value())
+        .map_err(BlockDevError::IoError)
+}
+
+/// Sets a queue attribute of a disk by writing it to
+/// `/sys/block/<device_name>/<attr_path>`, after validating the value.
+///
+/// The kernel forgets the setting when the device goes away or the system
+/// reboots. Writing the attribute needs root. Linux only.
+///
+/// # Errors
+///
+/// Returns [`BlockDevError::InvalidInput`] if `device_name` is not a bare
+/// device name or the value is rejected as described on [`SysfsWritableAttr`],
+/// and [`BlockDevError::IoError`] if the attribute cannot be read or written.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use blockdev::{SysfsWritableAttr, write_sysfs_attr};
+/// write_sysfs_attr("sda", SysfsWritableAttr::Scheduler("mq-deadline".to_string()))
+///     .expect("Failed to set scheduler");
+/// write_sysfs_attr("sda", SysfsWritableAttr::ReadAheadKb(4096)).expect("Failed to set read-ahead");
+/// ```
+#[cfg(target_os = "linux")]
+pub fn write_sysfs_attr(device_name: &str, attr: SysfsWritableAttr) -> Result<(), BlockDevError> {
+    write_sysfs_attr_in(Path::new(SYS_BLOCK), device_name, &attr)
+}
+
+/// sysfs reports sizes in 512-byte sectors regardless of the device's sector size.
+const SYSFS_SECTOR_SIZE: u64 = 512;
+
+/// Parses `KEY=value` lines from a `uevent` file.
+fn parse_uevent(contents: &str) -> HashMap<&str, &str> {
+    contents
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect()
+}
+
+fn from_sysfs_uevent_in(root: &Path, device_name: &str) -> Result<BlockDevice, BlockDevError> {
+    let uevent = read_attribute(root, device_name, "uevent")?
+        .ok_or_else(|| BlockDevError::DeviceNotFound(device_name.to_string()))?;
+    let fields = parse_uevent(&uevent);
+    let number = |key: &str| {
+        let value = fields.get(key).ok_or_else(|| {
+            BlockDevError::InvalidInput(format!("{device_name}/uevent has no {key}"))
+        })?;
+        value.parse().map_err(|_| {
+            BlockDevError::InvalidInput(format!("invalid {key} '{value}' in {device_name}/uevent"))
+        })
+    };
+    let maj_min = MajMin {
+        major: number("MAJOR")?,
+        minor: number("MINOR")?,
+    };
+
+    let name = fields.get("DEVNAME").copied().unwrap_or(device_name);
+    let device_type = match fields.get("DEVTYPE").copied() {
+        Some("partition") => DeviceType::Part,
+        _ => device_type_from_name(name),
+    };
+    let sectors = optional(read_sysfs_u64_in(root, device_name, "size"))?.unwrap_or(0);
+    Ok(BlockDevice {
+        name: name.to_string(),
+        maj_min,
+        rm: optional(read_sysfs_bool_in(root, device_name, "removable"))?.unwrap_or(false),
+        size: sectors.saturating_mul(SYSFS_SECTOR_SIZE),
+        ro: optional(read_sysfs_bool_in(root, device_name, "ro"))?.unwrap_or(false),
+        device_type,
+        mountpoints: vec![None],
+        children: None,
+        ..Default::default()
+    })
+}
+
+impl BlockDevice {
+    /// Builds a device from `/sys/block/<name>/uevent` without running lsblk.
+    ///
+    /// `MAJOR`, `MINOR`, `DEVNAME` and `DEVTYPE` come from the `uevent` file, the
+    /// size from the `size` attribute (in 512-byte sectors), and `rm` and `ro`
+    /// from the `removable` and `ro` attributes. As with
+    /// [`BlockDevices::from_proc_partitions`], `device_type` is guessed from the
+    /// name for whole disks, there are no mountpoints or children, and all
+    /// optional columns are `None`. This is Linux-only.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::DeviceNotFound`] if the device has no `uevent`
+    /// file, [`BlockDevError::InvalidInput`] if `device_name` is not a bare device
+    /// name or `MAJOR`/`MINOR` are missing or invalid, and
+    /// [`BlockDevError::IoError`] if a file cannot be read.
+    pub fn from_sysfs_uevent(device_name: &str) -> Result<BlockDevice, BlockDevError> {
+        from_sysfs_uevent_in(Path::new(SYS_BLOCK), device_name)
+    }
+}
+
+fn from_sysfs_in(root: &Path) -> Result<BlockDevices, BlockDevError> {
+    let mut names = Vec::new();
+    for entry in std::fs::read_dir(root).map_err(BlockDevError::IoError)? {
+        names.push(
+            entry
+                .map_err(BlockDevError::IoError)?
+                .file_name()
+                .to_string_lossy()
+                .into_owned(),
+        );
+    }
+    names.sort_by(|a, b| natural_cmp(a, b));
+    Ok(BlockDevices {
+        blockdevices: names
+            .iter()
+            .map(|name| from_sysfs_uevent_in(root, name))
+            .collect::<Result<_, _>>()?,
+        meta: Some(SnapshotMeta::new(SourceKind::Sysfs)),
+        ..Default::default()
+    })
+}
+
+fn last_modified_time_in(root: &Path, device_name: &str) -> Result<SystemTime, BlockDevError> {
+    validate_device_name(device_name)?;
+    std::fs::metadata(root.join(device_name).join("uevent"))
+        .and_then(|metadata| metadata.modified())
+        .map_err(BlockDevError::IoError)
+}
+
+/// Returns the modification time of `/sys/block/<device_name>/uevent`.
+///
+/// sysfs creates the file when the kernel registers the device, so this is
+/// usually when the device was added, although a `change` event written to
+/// `uevent` updates it. This is Linux-only.
+///
+/// # Errors
+///
+/// Returns [`BlockDevError::InvalidInput`] if `device_name` is not a bare device
+/// name, and [`BlockDevError::IoError`] if the file does not exist or its
+/// modification time is not available.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use blockdev::last_modified_time;
+/// let added = last_modified_time("sda").expect("Failed to read sda's uevent");
+/// ```
+pub fn last_modified_time(device_name: &str) -> Result<SystemTime, BlockDevError> {
+    last_modified_time_in(Path::new(SYS_BLOCK), device_name)
+}
+
+/// Parses the contents of an `events` or `events_async` attribute, such as
+/// `"media_change eject_request\n"`, into event names.
+fn parse_events(contents: &str) -> Vec<String> {
+    contents.split_whitespace().map(str::to_string).collect()
+}
+
+fn kernel_events_in(root: &Path, device_name: &str) -> Result<Vec<String>, BlockDevError> {
+    let mut events = parse_events(&read_sysfs_attr_in(root, device_name, "events")?);
+    let asynchronous = read_attribute(root, device_name, "events_async")?.unwrap_or_default();
+    for event in parse_events(&asynchronous) {
+        if !events.contains(&event) {
+            events.push(event);
+        }
+    }
+    Ok(events)
+}
+
+/// Returns the disk events the kernel reports for a device, such as
+/// `media_change` and `eject_request`, from `/sys/block/<name>/events` and
+/// `/sys/block/<name>/events_async`.
+///
+/// Events are listed once each, in the order they appear. Devices without
+/// removable media usually report none. `events_poll_msecs` holds the polling
+/// interval rather than event names; read it with [`read_sysfs_attr`] if needed.
+/// This is Linux-only.
+///
+/// # Errors
+///
+/// Returns [`BlockDevError::InvalidInput`] if `device_name` is not a bare device
+/// name, and [`BlockDevError::IoError`] if `events` cannot be read, including
+/// when it does not exist, as for partitions.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use blockdev::kernel_events;
+/// let events = kernel_events("sr0").expect("Failed to read events");
+/// println!("sr0 reports: {}", events.join(", "));
+/// ```
+pub fn kernel_events(device_name: &str) -> Result<Vec<String>, BlockDevError> {
+    kernel_events_in(Path::new(SYS_BLOCK), device_name)
+}
+
+/// Parses the contents of a `queue_depth` attribute, such as `"32\n"`.
+fn parse_queue_depth(contents: &str) -> Result<u32, BlockDevError> {
+    contents.trim().parse().map_err(|_| {
+        BlockDevError::InvalidInput(format!("invalid queue_depth '{}'", contents.trim()))
+    })
+}
+
+fn queue_depth_in(root: &Path, device_name: &str) -> Result<Option<u32>, BlockDevError> {
+    read_attribute(root, device_name, "device/queue_depth")?
+        .map(|contents| parse_queue_depth(&contents))
+        .transpose()
+}
+
+/// Reads the command queue depth of a disk from `/sys/block/<name>/device/queue_depth`.
+///
+/// SATA drives typically report 32. Devices without this attribute, such as
+/// NVMe drives, partitions and virtual devices, return `Ok(None)`.
+///
+/// # Errors
+///
+/// Returns [`BlockDevError::InvalidInput`] if `device_name` is not a bare device
+/// name or the attribute is not a number, and [`BlockDevError::IoError`] if it
+/// exists but cannot be read.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use blockdev::queue_depth;
+/// if let Some(depth) = queue_depth("sda").expect("Failed to read queue depth") {
+///     println!("sda queue depth: {depth}");
+/// }
+/// ```
+pub fn queue_depth(device_name: &str) -> Result<Option<u32>, BlockDevError> {
+    queue_depth_in(Path::new(SYS_BLOCK), device_name)
+}
+
+/// How the kernel queues requests for a disk, as returned by [`queue_type`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum QueueType {
+    /// The multi-queue block layer (blk-mq), with a submission queue per CPU
+    /// or hardware queue.
+    MultiQueue,
+    /// A single request queue, as used by legacy drivers before Linux 5.0, or
+    /// no request queue at all, as for device-mapper and md devices.
+    SingleQueue,
+}
+
+fn queue_type_in(root: &Path, device_name: &str) -> Result<QueueType, BlockDevError> {
+    validate_device_name(device_name)?;
+    let device = root.join(device_name);
+    std::fs::metadata(&device).map_err(BlockDevError::IoError)?;
+    if device.join("mq").is_dir() {
+        Ok(QueueType::MultiQueue)
+    } else {
+        Ok(QueueType::SingleQueue)
+    }
+}
+
+/// Reports whether a disk uses the multi-queue block layer, which the kernel
+/// shows by creating `/sys/block/<device_name>/mq/`.
+///
+/// Multi-queue generally performs better on multi-core systems, since CPUs
+/// submit I/O without contending for one queue lock; NVMe drives with
+/// several hardware queues gain the most. This is Linux-only.
+///
+/// # Errors
+///
+/// Returns [`BlockDevError::InvalidInput`] if `device_name` is not a bare device
+/// name, and [`BlockDevError::IoError`] if `/sys/block/<device_name>` does not
+/// exist, as for partitions.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use blockdev::{QueueType, queue_type};
+/// if queue_type("nvme0n1").expect("Failed to read sysfs") == QueueType::MultiQueue {
+///     println!("nvme0n1 uses blk-mq");
+/// }
+/// ```
+pub fn queue_type(device_name: &str) -> Result<QueueType, BlockDevError> {
+    queue_type_in(Path::new(SYS_BLOCK), device_name)
+}
+
+fn size_bytes_from_sysfs_in(root: &Path, device_name: &str) -> Result<u64, BlockDevError> {
+    let sectors = read_sysfs_u64_in(root, device_name, "size")?;
+    sectors.checked_mul(SYSFS_SECTOR_SIZE).ok_or_else(|| {
+        BlockDevError::InvalidInput(format!("{device_name}/size of {sectors} sectors overflows"))
+    })
+}
+
+/// Reads the size of a disk from `/sys/block/<device_name>/size`, in 512-byte
+/// sectors regardless of the device's logical sector size.
+///
+/// Only whole disks and virtual devices have an entry in `/sys/block`, so this
+/// fails for partitions. This is Linux-only.
+///
+/// # Errors
+///
+/// Returns the errors of [`read_sysfs_u64`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # use blockdev::read_sector_count;
+/// let sectors = read_sector_count("sda").expect("Failed to read sector count");
+/// ```
+pub fn read_sector_count(device_name: &str) -> Result<u64, BlockDevError> {
+    read_sysfs_u64_in(Path::new(SYS_BLOCK), device_name, "size")
+}
+
+/// Reads the size of a disk in bytes: [`read_sector_count`] times 512.
+///
+/// # Errors
+///
+/// Returns the errors of [`read_sector_count`], and
+/// [`BlockDevError::InvalidInput`] if the size in bytes does not fit a `u64`.
+pub fn size_bytes_from_sysfs(device_name: &str) -> Result<u64, BlockDevError> {
+    size_bytes_from_sysfs_in(Path::new(SYS_BLOCK), device_name)
+}
+
+/// Returns `true` if two sizes differ by at most 1% of the larger one.
+fn sizes_agree(a: u64, b: u64) -> bool {
+    u128::from(a.abs_diff(b)) * 100 <= u128::from(a.max(b))
+}
+
+/// Parses the `in_flight` field of a `/sys/block/<name>/stat` line: the number
+/// of I/O requests issued to the driver that have not completed yet.
+fn parse_ios_in_progress(stat: &str) -> Result<u64, BlockDevError> {
+    stat.split_whitespace()
+        .nth(8)
+        .and_then(|field| field.parse().ok())
+        .ok_or_else(|| BlockDevError::InvalidInput(format!("invalid stat '{}'", stat.trim())))
+}
+
+/// Takes a disk offline by writing `offline` to `/sys/block/<name>/device/state`,
+/// after checking [`BlockDevices::power_off_safe`] against a fresh
+/// [`get_devices`](crate::get_devices) snapshot.
+///
+/// Once offline, the kernel fails all I/O to the disk, so it can be unplugged
+/// or powered down. Writing the attribute needs root. Linux only; requires the
+/// `exec` feature.
+///
+/// # Errors
+///
+/// Returns [`BlockDevError::DeviceNotFound`] if `device_name` is not a
+/// top-level device, [`BlockDevError::DeviceBusy`] if anything on it is
+/// mounted or I/O is in flight, and [`BlockDevError::IoError`] if the state
+/// cannot be written, as for devices without a `device/state` attribute.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use blockdev::power_off;
+/// power_off("sdb").expect("Failed to take sdb offline");
+/// ```
+#[cfg(all(feature = "exec", target_os = "linux"))]
+pub fn power_off(device_name: &str) -> Result<(), BlockDevError> {
+    crate::get_devices()?.power_off_in(Path::new(SYS_BLOCK), device_name)
+}
+
+impl BlockDevice {
+    /// Reads this device's command queue depth from sysfs.
+    ///
+    /// See [`queue_depth`](crate::queue_depth) for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sysfs attribute exists but cannot be read or parsed.
+    pub fn queue_depth(&self) -> Result<Option<u32>, BlockDevError> {
+        queue_depth(&self.name)
+    }
+
+    /// Reports whether this device uses the multi-queue block layer.
+    ///
+    /// See [`queue_type`](crate::queue_type) for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `/sys/block/<name>` does not exist, as for partitions.
+    pub fn queue_type(&self) -> Result<QueueType, BlockDevError> {
+        queue_type(&self.name)
+    }
+
+    /// Returns when this device's sysfs entry was last modified.
+    ///
+    /// See [`last_modified_time`](crate::last_modified_time) for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `/sys/block/<name>/uevent` does not exist, as for
+    /// partitions, or its modification time cannot be read.
+    pub fn last_modified_time(&self) -> Result<SystemTime, BlockDevError> {
+        last_modified_time(&self.name)
+    }
+
+    /// Reads the disk events the kernel reports for this device.
+    ///
+    /// See [`kernel_events`](crate::kernel_events) for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `/sys/block/<name>/events` cannot be read.
+    pub fn kernel_events(&self) -> Result<Vec<String>, BlockDevError> {
+        kernel_events(&self.name)
+    }
+
+    /// Reads this device's size in bytes from sysfs.
+    ///
+    /// See [`size_bytes_from_sysfs`](crate::size_bytes_from_sysfs) for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `/sys/block/<name>/size` cannot be read or parsed,
+    /// as for partitions.
+    pub fn size_bytes_sysfs(&self) -> Result<u64, BlockDevError> {
+        size_bytes_from_sysfs(&self.name)
+    }
+
+    /// Checks that the size lsblk reported agrees with sysfs within 1%.
+    ///
+    /// The tolerance covers sizes parsed from lsblk's rounded human-readable
+    /// form, such as `894.3G`; with `--bytes` the two should be equal.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`size_bytes_sysfs`](Self::size_bytes_sysfs).
+    pub fn size_bytes_verify(&self) -> Result<bool, BlockDevError> {
+        self.size_bytes_verify_in(Path::new(SYS_BLOCK))
+    }
+
+    fn size_bytes_verify_in(&self, root: &Path) -> Result<bool, BlockDevError> {
+        Ok(sizes_agree(
+            self.size,
+            size_bytes_from_sysfs_in(root, &self.name)?,
+        ))
+    }
+
+    /// Returns `true` if the kernel reports `media_change` events for this
+    /// device, as it does for optical drives and card readers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `/sys/block/<name>/events` cannot be read.
+    pub fn supports_media_change_event(&self) -> Result<bool, BlockDevError> {
+        Ok(self.kernel_events()?.iter().any(|e| e == "media_change"))
+    }
+}
+
+impl BlockDevices {
+    /// Builds a flat snapshot of every entry in `/sys/block` with
+    /// [`BlockDevice::from_sysfs_uevent`], in natural name order.
+    ///
+    /// `/sys/block` lists whole disks and virtual devices (loop, md, dm, ...) but
+    /// not partitions. The snapshot's [`meta`](BlockDevices::meta) records
+    /// [`SourceKind::Sysfs`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::IoError`] if `/sys/block` cannot be listed, or the
+    /// first error from [`BlockDevice::from_sysfs_uevent`].
+    pub fn from_sysfs() -> Result<BlockDevices, BlockDevError> {
+        from_sysfs_in(Path::new(SYS_BLOCK))
+    }
+
+    /// Returns the top-level device with the highest queue depth, together with that depth.
+    ///
+    /// Devices whose queue depth is unavailable or unreadable are skipped. If several
+    /// devices share the highest depth, the first one is returned.
+    #[must_use]
+    pub fn max_queue_depth_device(&self) -> Option<(&BlockDevice, u32)> {
+        self.max_queue_depth_device_in(Path::new(SYS_BLOCK))
+    }
+
+    /// Returns the top-level devices that use the multi-queue block layer,
+    /// according to [`BlockDevice::queue_type`].
+    ///
+    /// Devices whose queue type cannot be read are skipped.
+    #[must_use]
+    pub fn multi_queue_devices(&self) -> Vec<&BlockDevice> {
+        self.multi_queue_devices_in(Path::new(SYS_BLOCK))
+    }
+
+    fn multi_queue_devices_in(&self, root: &Path) -> Vec<&BlockDevice> {
+        self.iter()
+            .filter(|d| matches!(queue_type_in(root, &d.name), Ok(QueueType::MultiQueue)))
+            .collect()
+    }
+
+    /// Returns the top-level device whose `/sys/block/<name>/uevent` was modified
+    /// most recently, which is usually the one added last.
+    ///
+    /// Returns `Ok(None)` if there are no devices. If several devices share the
+    /// latest time, the first one is returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from [`BlockDevice::last_modified_time`].
+    pub fn most_recently_added_device(&self) -> Result<Option<&BlockDevice>, BlockDevError> {
+        self.most_recently_added_device_in(Path::new(SYS_BLOCK))
+    }
+
+    /// Returns `true` if the top-level device `device_name` can be powered off:
+    /// neither it nor anything stacked on it is mounted (swap included), and
+    /// `/sys/block/<name>/stat` shows no I/O in flight.
+    ///
+    /// If the device has no `stat` attribute, only the mountpoints are checked.
+    /// The mountpoints come from this snapshot, so it should be fresh.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::DeviceNotFound`] if `device_name` is not a
+    /// top-level device in this snapshot, and [`BlockDevError::InvalidInput`] or
+    /// [`BlockDevError::IoError`] if `stat` exists but cannot be read or parsed.
+    pub fn power_off_safe(&self, device_name: &str) -> Result<bool, BlockDevError> {
+        self.power_off_safe_in(Path::new(SYS_BLOCK), device_name)
+    }
+
+    fn power_off_safe_in(&self, root: &Path, device_name: &str) -> Result<bool, BlockDevError> {
+        let device = self
+            .find_by_name(device_name)
+            .ok_or_else(|| BlockDevError::DeviceNotFound(device_name.to_string()))?;
+        if IterAll::subtree(device).any(|d| !d.active_mountpoints().is_empty()) {
+            return Ok(false);
+        }
+        let in_progress = read_attribute(root, device_name, "stat")?
+            .map(|stat| parse_ios_in_progress(&stat))
+            .transpose()?;
+        Ok(in_progress.is_none_or(|ios| ios == 0))
+    }
+
+    #[cfg(any(test, all(feature = "exec", target_os = "linux")))]
+    fn power_off_in(&self, root: &Path, device_name: &str) -> Result<(), BlockDevError> {
+        if !self.power_off_safe_in(root, device_name)? {
+            return Err(BlockDevError::DeviceBusy(device_name.to_string()));
+        }
+        std::fs::write(root.join(device_name).join("device/state"), "offline")
+            .map_err(BlockDevError::IoError)
+    }
+
+    fn most_recently_added_device_in(
+        &self,
+        root: &Path,
+    ) -> Result<Option<&BlockDevice>, BlockDevError> {
+        let mut latest: Option<(&BlockDevice, SystemTime)> = None;
+        for device in self.iter() {
+            let modified = last_modified_time_in(root, &device.name)?;
+            if latest.is_none_or(|(_, time)| modified > time) {
+                latest = Some((device, modified));
+            }
+        }
+        Ok(latest.map(|(device, _)| device))
+    }
+
+    /// Returns the combined size of the top-level disks in 512-byte sectors,
+    /// read from sysfs with [`read_sector_count`].
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from [`read_sector_count`].
+    pub fn total_sectors(&self) -> Result<u64, BlockDevError> {
+        self.total_sectors_in(Path::new(SYS_BLOCK))
+    }
+
+    fn total_sectors_in(&self, root: &Path) -> Result<u64, BlockDevError> {
+        self.iter()
+            .filter(|d| d.is_disk())
+            .map(|d| read_sysfs_u64_in(root, &d.name, "size"))
+            .sum()
+    }
+
+    fn max_queue_depth_device_in(&self, root: &Path) -> Option<(&BlockDevice, u32)> {
+        self.iter()
+            .filter_map(|device| {
+                queue_depth_in(root, &device.name)
+                    .ok()
+                    .flatten()
+                    .map(|depth| (device, depth))
+            })
+            .reduce(|best, candidate| {
+                if candidate.1 > best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_lsblk;
+    use std::path::PathBuf;
+
+    /// Creates a fake `/sys/block` tree with the given `queue_depth` contents.
+    fn fake_sys_block(test: &str, depths: &[(&str, &str)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("blockdev-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        for (name, contents) in depths {
+            let device = root.join(name).join("device");
+            std::fs::create_dir_all(&device).expect("Failed to create fake sysfs");
+            std::fs::write(device.join("queue_depth"), contents)
+                .expect("Failed to write fake sysfs");
+        }
+        root
+    }
+
+    /// Writes `<root>/<name>/<attribute>` for a fake sysfs tree.
+    fn write_attribute(root: &Path, name: &str, attribute: &str, contents: &str) {
+        let path = root.join(name).join(attribute);
+        std::fs::create_dir_all(path.parent().unwrap()).expect("Failed to create fake sysfs");
+        std::fs::write(path, contents).expect("Failed to write fake sysfs");
+    }
+
+    #[test]
+    fn test_read_sysfs_attr() {
+        let root = fake_sys_block("read-attr", &[("sda", "32\n")]);
+        write_attribute(&root, "sda", "ro", "0\n");
+        write_attribute(&root, "sda", "removable", "Y\n");
+        write_attribute(&root, "sda", "size", "  1953525168\n");
+        write_attribute(&root, "sda", "model", " Samsung SSD 870 \n");
+
+        assert_eq!(
+            read_sysfs_attr_in(&root, "sda", "model").unwrap(),
+            "Samsung SSD 870"
+        );
+        assert_eq!(
+            read_sysfs_attr_in(&root, "sda", "device/queue_depth").unwrap(),
+            "32"
+        );
+        assert_eq!(
+            read_sysfs_u64_in(&root, "sda", "size").unwrap(),
+            1_953_525_168
+        );
+        assert!(!read_sysfs_bool_in(&root, "sda", "ro").unwrap());
+        assert!(read_sysfs_bool_in(&root, "sda", "removable").unwrap());
+
+        for (name, attribute) in [("sdz", "size"), ("sda", "queue/rotational")] {
+            match read_sysfs_attr_in(&root, name, attribute) {
+                Err(BlockDevError::IoError(err)) => {
+                    assert_eq!(err.kind(), io::ErrorKind::NotFound, "{name}/{attribute}");
+                }
+                other => panic!("{name}/{attribute}: {other:?}"),
+            }
+        }
+        assert!(matches!(
+            read_sysfs_attr_in(&root, "../sda", "size"),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+        // The attribute path must not leave the device directory either.
+        for attribute in [
+            "/etc/shadow",
+            "../sdb/size",
+            "device/../../sdb/size",
+            "./size",
+            "queue//rotational",
+            "",
+        ] {
+            assert!(
+                matches!(
+                    read_sysfs_attr_in(&root, "sda", attribute),
+                    Err(BlockDevError::InvalidInput(_))
+                ),
+                "{attribute}"
+            );
+        }
+        assert!(matches!(
+            read_sysfs_u64_in(&root, "sda", "model"),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            read_sysfs_bool_in(&root, "sda", "size"),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+        assert_eq!(
+            read_attribute(&root, "sda", "queue/rotational").unwrap(),
+            None
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_from_sysfs_uevent() {
+        let root = fake_sys_block("uevent", &[]);
+        write_attribute(
+            &root,
+            "nvme0n1",
+            "uevent",
+            "MAJOR=259\nMINOR=0\nDEVNAME=nvme0n1\nDEVTYPE=disk\nDISKSEQ=1\n",
+        );
+        write_attribute(&root, "nvme0n1", "size", "7501476528\n");
+        write_attribute(&root, "nvme0n1", "removable", "0\n");
+        write_attribute(&root, "nvme0n1", "ro", "0\n");
+        write_attribute(
+            &root,
+            "sr0",
+            "uevent",
+            "MAJOR=11\nMINOR=0\nDEVNAME=sr0\nDEVTYPE=disk\n",
+        );
+        write_attribute(&root, "sr0", "size", "2097151\n");
+        write_attribute(&root, "sr0", "removable", "1\n");
+        write_attribute(&root, "sr0", "ro", "1\n");
+        write_attribute(
+            &root,
+            "loop10",
+            "uevent",
+            "MAJOR=7\nMINOR=10\nDEVNAME=loop10\n",
+        );
+        write_attribute(
+            &root,
+            "loop2",
+            "uevent",
+            "MAJOR=7\nMINOR=2\nDEVNAME=loop2\n",
+        );
+
+        let nvme0n1 = from_sysfs_uevent_in(&root, "nvme0n1").unwrap();
+        assert_eq!(nvme0n1.name, "nvme0n1");
+        assert_eq!(
+            nvme0n1.maj_min,
+            MajMin {
+                major: 259,
+                minor: 0
+            }
+        );
+        assert_eq!(nvme0n1.size, 7_501_476_528 * 512);
+        assert_eq!(nvme0n1.device_type, DeviceType::Disk);
+        assert!(!nvme0n1.rm && !nvme0n1.ro);
+        assert_eq!(nvme0n1.mountpoints, vec![None]);
+        assert!(nvme0n1.uuid.is_none() && nvme0n1.children.is_none());
+
+        let sr0 = from_sysfs_uevent_in(&root, "sr0").unwrap();
+        assert_eq!(sr0.device_type, DeviceType::Rom);
+        assert!(sr0.rm && sr0.ro);
+
+        // Missing size, removable and ro attributes fall back to zero.
+        let loop2 = from_sysfs_uevent_in(&root, "loop2").unwrap();
+        assert_eq!(loop2.device_type, DeviceType::Loop);
+        assert_eq!(loop2.size, 0);
+
+        let devices = from_sysfs_in(&root).unwrap();
+        let names: Vec<&str> = devices.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["loop2", "loop10", "nvme0n1", "sr0"]);
+        assert_eq!(devices.meta.unwrap().source, SourceKind::Sysfs);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_last_modified_time() {
+        let root = fake_sys_block("last-modified", &[]);
+        let epoch = SystemTime::UNIX_EPOCH;
+        for (name, secs) in [("sda", 1_000), ("sdb", 3_000), ("sdc", 2_000)] {
+            write_attribute(&root, name, "uevent", "MAJOR=8\n");
+            let file = std::fs::File::options()
+                .write(true)
+                .open(root.join(name).join("uevent"))
+                .unwrap();
+            file.set_modified(epoch + std::time::Duration::from_secs(secs))
+                .unwrap();
+        }
+
+        let sdb = last_modified_time_in(&root, "sdb").unwrap();
+        assert_eq!(sdb.duration_since(epoch).unwrap().as_secs(), 3_000);
+        assert!(matches!(
+            last_modified_time_in(&root, "sdz"),
+            Err(BlockDevError::IoError(_))
+        ));
+        assert!(matches!(
+            last_modified_time_in(&root, "../sda"),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+
+        let devices = parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+        let latest = devices.most_recently_added_device_in(&root).unwrap();
+        assert_eq!(latest.unwrap().name, "sdb");
+        assert!(
+            BlockDevices::default()
+                .most_recently_added_device_in(&root)
+                .unwrap()
+                .is_none()
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_from_sysfs_uevent_invalid() {
+        let root = fake_sys_block("uevent-invalid", &[]);
+        write_attribute(&root, "sda", "uevent", "MINOR=0\nDEVNAME=sda\n");
+        write_attribute(&root, "sdb", "uevent", "MAJOR=8\nMINOR=x\n");
+        write_attribute(&root, "sdc", "uevent", "MAJOR=8\nMINOR=32\n");
+        write_attribute(&root, "sdc", "size", "lots\n");
+
+        for name in ["sda", "sdb", "sdc", "../sda"] {
+            assert!(
+                matches!(
+                    from_sysfs_uevent_in(&root, name),
+                    Err(BlockDevError::InvalidInput(_))
+                ),
+                "{name}"
+            );
+        }
+        assert!(matches!(
+            from_sysfs_uevent_in(&root, "sdz"),
+            Err(BlockDevError::DeviceNotFound(_))
+        ));
+        assert!(from_sysfs_in(&root).is_err());
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_parse_queue_depth() {
+        assert_eq!(parse_queue_depth("32\n").unwrap(), 32);
+        assert_eq!(parse_queue_depth("1023").unwrap(), 1023);
+        assert!(matches!(
+            parse_queue_depth("deep\n"),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_queue_depth_from_sysfs() {
+        let root = fake_sys_block("queue-depth", &[("sda", "32\n"), ("sdb", "garbage\n")]);
+        assert_eq!(queue_depth_in(&root, "sda").unwrap(), Some(32));
+        assert_eq!(queue_depth_in(&root, "nvme0n1").unwrap(), None);
+        assert!(queue_depth_in(&root, "sdb").is_err());
+        assert!(matches!(
+            queue_depth_in(&root, "../sda"),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_queue_type() {
+        let root = fake_sys_block("queue-type", &[("sda", "32\n")]);
+        std::fs::create_dir_all(root.join("nvme0n1/mq/0")).unwrap();
+        std::fs::create_dir_all(root.join("dm-0")).unwrap();
+        // A file named `mq` is not the blk-mq directory.
+        write_attribute(&root, "md0", "mq", "");
+
+        assert_eq!(
+            queue_type_in(&root, "nvme0n1").unwrap(),
+            QueueType::MultiQueue
+        );
+        assert_eq!(queue_type_in(&root, "sda").unwrap(), QueueType::SingleQueue);
+        assert_eq!(
+            queue_type_in(&root, "dm-0").unwrap(),
+            QueueType::SingleQueue
+        );
+        assert_eq!(queue_type_in(&root, "md0").unwrap(), QueueType::SingleQueue);
+        match queue_type_in(&root, "nvme0n1p1") {
+            Err(BlockDevError::IoError(err)) => assert_eq!(err.kind(), io::ErrorKind::NotFound),
+            other => panic!("{other:?}"),
+        }
+        assert!(matches!(
+            queue_type_in(&root, "../nvme0n1"),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+
+        let devices = parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "nvme0n1", "maj:min": "259:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "nvme1n1", "maj:min": "259:1", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+        let names: Vec<&str> = devices
+            .multi_queue_devices_in(&root)
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["nvme0n1"]);
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_parse_events() {
+        assert_eq!(parse_events("media_change\n"), vec!["media_change"]);
+        assert_eq!(
+            parse_events("media_change eject_request"),
+            vec!["media_change", "eject_request"]
+        );
+        assert!(parse_events("").is_empty());
+        assert!(parse_events("\n").is_empty());
+    }
+
+    #[test]
+    fn test_kernel_events_from_sysfs() {
+        let root = fake_sys_block("kernel-events", &[]);
+        write_attribute(&root, "sr0", "events", "media_change eject_request\n");
+        write_attribute(&root, "sr0", "events_async", "media_change\n");
+        write_attribute(&root, "sr0", "events_poll_msecs", "2000\n");
+        write_attribute(&root, "sdb", "events", "\n");
+        write_attribute(&root, "sdb", "events_async", "media_change\n");
+        write_attribute(&root, "sda", "events", "\n");
+
+        assert_eq!(
+            kernel_events_in(&root, "sr0").unwrap(),
+            vec!["media_change", "eject_request"]
+        );
+        assert_eq!(
+            kernel_events_in(&root, "sdb").unwrap(),
+            vec!["media_change"]
+        );
+        assert!(kernel_events_in(&root, "sda").unwrap().is_empty());
+        assert!(matches!(
+            kernel_events_in(&root, "sda1"),
+            Err(BlockDevError::IoError(_))
+        ));
+        assert!(matches!(
+            kernel_events_in(&root, "../sr0"),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_max_queue_depth_device() {
+        let root = fake_sys_block(
+            "max-queue-depth",
+            &[
+                ("sda", "32\n"),
+                ("sdb", "254\n"),
+                ("sdc", "bad"),
+                ("sdd", "254\n"),
+            ],
+        );
+        let devices = parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "sdd", "maj:min": "8:48", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "nvme0n1", "maj:min": "259:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null]}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+
+        let (device, depth) = devices.max_queue_depth_device_in(&root).unwrap();
+        assert_eq!(device.name, "sdb");
+        assert_eq!(depth, 254);
+        assert!(
+            BlockDevices::default()
+                .max_queue_depth_device_in(&root)
+                .is_none()
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_parse_ios_in_progress() {
+        let idle = "  1114   0  81456   2110    27    3   224   37    0   2148   2148    0    0    0    0\n";
+        assert_eq!(parse_ios_in_progress(idle).unwrap(), 0);
+        let busy = "5 0 40 1 7 0 56 2 3 4 5";
+        assert_eq!(parse_ios_in_progress(busy).unwrap(), 3);
+        for bad in ["", "1 2 3", "1 2 3 4 5 6 7 8 x 10 11"] {
+            assert!(
+                matches!(
+                    parse_ios_in_progress(bad),
+                    Err(BlockDevError::InvalidInput(_))
+                ),
+                "{bad:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_power_off_safe() {
+        let devices = parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sdb", "maj:min": "8:16", "rm": true, "size": "32G", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "sdb1", "maj:min": "8:17", "rm": true, "size": "32G", "ro": false, "type": "part", "mountpoints": ["/media/usb"]}
+                 ]},
+                {"name": "sdc", "maj:min": "8:32", "rm": true, "size": "64G", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "sdc1", "maj:min": "8:33", "rm": true, "size": "64G", "ro": false, "type": "part", "mountpoints": [null]}
+                 ]},
+                {"name": "sdd", "maj:min": "8:48", "rm": true, "size": "64G", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "sde", "maj:min": "8:64", "rm": true, "size": "64G", "ro": false, "type": "disk", "mountpoints": [null]}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+        let root = fake_sys_block("power-off", &[("sdb", "1"), ("sdc", "1"), ("sdd", "1")]);
+        let idle = "1114 0 81456 2110 27 3 224 37 0 2148 2148\n";
+        write_attribute(&root, "sdb", "stat", idle);
+        write_attribute(&root, "sdc", "stat", idle);
+        write_attribute(
+            &root,
+            "sdd",
+            "stat",
+            "1114 0 81456 2110 27 3 224 37 2 2148 2148\n",
+        );
+        for name in ["sdb", "sdc", "sdd"] {
+            write_attribute(&root, name, "device/state", "running\n");
+        }
+
+        // sdb1 is mounted, sdd has I/O in flight, sde has no stat attribute.
+        let safe: Vec<(&str, bool)> = ["sdb", "sdc", "sdd", "sde"]
+            .into_iter()
+            .map(|name| (name, devices.power_off_safe_in(&root, name).unwrap()))
+            .collect();
+        assert_eq!(
+            safe,
+            vec![("sdb", false), ("sdc", true), ("sdd", false), ("sde", true)]
+        );
+        assert!(matches!(
+            devices.power_off_safe_in(&root, "sdc1"),
+            Err(BlockDevError::DeviceNotFound(_))
+        ));
+
+        devices.power_off_in(&root, "sdc").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(root.join("sdc/device/state")).unwrap(),
+            "offline"
+        );
+        for name in ["sdb", "sdd"] {
+            assert!(
+                matches!(
+                    devices.power_off_in(&root, name),
+                    Err(BlockDevError::DeviceBusy(_))
+                ),
+                "{name}"
+            );
+            assert_eq!(
+                std::fs::read_to_string(root.join(name).join("device/state")).unwrap(),
+                "running\n"
+            );
+        }
+        assert!(matches!(
+            devices.power_off_in(&root, "sde"),
+            Err(BlockDevError::IoError(_))
+        ));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_parse_schedulers() {
+        assert_eq!(
+            parse_schedulers("mq-deadline kyber [bfq] none\n"),
+            vec!["mq-deadline", "kyber", "bfq", "none"]
+        );
+        assert_eq!(parse_schedulers("[none]"), vec!["none"]);
+        assert!(parse_schedulers("").is_empty());
+    }
+
+    #[test]
+    fn test_write_sysfs_attr() {
+        let root = fake_sys_block("write-attr", &[]);
+        write_attribute(
+            &root,
+            "sda",
+            "queue/scheduler",
+            "[mq-deadline] kyber bfq none\n",
+        );
+        write_attribute(&root, "sda", "queue/read_ahead_kb", "128\n");
+        write_attribute(&root, "sda", "queue/nr_requests", "64\n");
+        write_attribute(&root, "dm-0", "queue/scheduler", "none\n");
+        assert_eq!(
+            available_schedulers_in(&root, "sda").unwrap(),
+            vec!["mq-deadline", "kyber", "bfq", "none"]
+        );
+
+        let read =
+            |attribute: &str| std::fs::read_to_string(root.join("sda").join(attribute)).unwrap();
+        let write = |name: &str, attr: SysfsWritableAttr| write_sysfs_attr_in(&root, name, &attr);
+        write("sda", SysfsWritableAttr::Scheduler("bfq".to_string())).unwrap();
+        assert_eq!(read("queue/scheduler"), "bfq");
+        write("sda", SysfsWritableAttr::ReadAheadKb(4096)).unwrap();
+        assert_eq!(read("queue/read_ahead_kb"), "4096");
+        write("sda", SysfsWritableAttr::ReadAheadKb(0)).unwrap();
+        assert_eq!(read("queue/read_ahead_kb"), "0");
+        write("sda", SysfsWritableAttr::NrRequests(256)).unwrap();
+        assert_eq!(read("queue/nr_requests"), "256");
+        write("dm-0", SysfsWritableAttr::Scheduler("none".to_string())).unwrap();
+
+        // A real scheduler file lists the choices again after a write.
+        write_attribute(
+            &root,
+            "sda",
+            "queue/scheduler",
+            "mq-deadline kyber [bfq] none\n",
+        );
+        let err = write("sda", SysfsWritableAttr::Scheduler("cfq".to_string())).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid input: scheduler 'cfq' is not available for sda (available: mq-deadline, kyber, bfq, none)"
+        );
+        for attr in [
+            SysfsWritableAttr::Scheduler("mq-deadline".to_string()),
+            SysfsWritableAttr::ReadAheadKb(1000),
+            SysfsWritableAttr::NrRequests(0),
+        ] {
+            assert!(
+                matches!(
+                    write("dm-0", attr.clone()),
+                    Err(BlockDevError::InvalidInput(_))
+                ),
+                "{attr:?}"
+            );
+        }
+        assert_eq!(read("queue/read_ahead_kb"), "0");
+        assert_eq!(read("queue/nr_requests"), "256");
+
+        assert!(matches!(
+            write("../sda", SysfsWritableAttr::NrRequests(1)),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            write("sdz", SysfsWritableAttr::Scheduler("none".to_string())),
+            Err(BlockDevError::IoError(_))
+        ));
+        assert!(matches!(
+            write("sdz", SysfsWritableAttr::NrRequests(1)),
+            Err(BlockDevError::IoError(_))
+        ));
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_sector_counts() {
+        let root = fake_sys_block("sector-counts", &[]);
+        write_attribute(&root, "sda", "size", "7814037168\n");
+        write_attribute(&root, "sdb", "size", "1953525168\n");
+        write_attribute(&root, "sdc", "size", "36028797018963968\n");
+        assert_eq!(
+            read_sysfs_u64_in(&root, "sda", "size").unwrap(),
+            7_814_037_168
+        );
+        assert_eq!(
+            size_bytes_from_sysfs_in(&root, "sda").unwrap(),
+            7_814_037_168 * 512
+        );
+        assert!(matches!(
+            size_bytes_from_sysfs_in(&root, "sdc"),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+
+        // lsblk without --bytes prints sdb as 931.5G, which is within 1%.
+        let devices = parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": 4000787030016, "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "sda1", "maj:min": "8:1", "rm": false, "size": 4000785104896, "ro": false, "type": "part", "mountpoints": ["/srv"]}
+                 ]},
+                {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "931.5G", "ro": false, "type": "disk", "mountpoints": [null]},
+                {"name": "loop0", "maj:min": "7:0", "rm": false, "size": "64M", "ro": true, "type": "loop", "mountpoints": ["/snap/core"]}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+        let sda = devices.find_by_name("sda").unwrap();
+        let sdb = devices.find_by_name("sdb").unwrap();
+        assert!(sda.size_bytes_verify_in(&root).unwrap());
+        assert!(sdb.size_bytes_verify_in(&root).unwrap());
+        let shrunk = BlockDevice {
+            size: sda.size / 100 * 98,
+            ..sda.clone()
+        };
+        assert!(!shrunk.size_bytes_verify_in(&root).unwrap());
+        assert!(matches!(
+            sda.children_iter()
+                .next()
+                .unwrap()
+                .size_bytes_verify_in(&root),
+            Err(BlockDevError::IoError(_))
+        ));
+
+        assert_eq!(
+            devices.total_sectors_in(&root).unwrap(),
+            7_814_037_168 + 1_953_525_168
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_sizes_agree() {
+        assert!(sizes_agree(0, 0));
+        assert!(sizes_agree(100, 99));
+        assert!(sizes_agree(99, 100));
+        assert!(!sizes_agree(100, 98));
+        assert!(!sizes_agree(0, 1));
+        assert!(sizes_agree(u64::MAX, u64::MAX - u64::MAX / 100));
+    }
+}
@@ -0,0 +1,231 @@
+use crate::{BlockDevice, BlockDevices, IterAll};
+use std::path::Path;
+
+/// Which mountpoints make a device part of the running system.
+///
+/// [`BlockDevice::is_system`], [`BlockDevices::system`],
+/// [`BlockDevices::non_system`], [`BlockDevices::summary`] and
+/// [`BlockDevices::health_check`] each have a `_with` variant that takes a
+/// policy, and use [`SystemPolicy::default`] without one. The helpers that
+/// pick devices to overwrite use [`SystemPolicy::strict`] without one:
+/// [`BlockDevices::non_system_disk_paths`], [`BlockDevices::same_size_groups`],
+/// [`BlockDevices::pairs_of_same_size`],
+/// [`BlockDevices::generate_mdadm_create_command`] and
+/// [`BlockDevice::is_safe_for_data_use`], each with a `_with` variant, and
+/// [`BlockDevices::best_candidate`] through
+/// [`SelectionPolicy::system`](crate::SelectionPolicy::system).
+///
+/// A device is a system device if it or anything stacked on it has a
+/// mountpoint that is listed in `root_mounts` or `boot_mounts`, is `[SWAP]` while
+/// `treat_swap_as_system` is set, or lies at or below one of
+/// `extra_protected_prefixes`. Prefixes are compared by whole path components,
+/// so `/srv` protects `/srv/data` but not `/srv2`.
+///
+/// # Examples
+///
+/// ```
+/// use blockdev::{SystemPolicy, parse_lsblk};
+///
+/// let devices = parse_lsblk(r#"{"blockdevices": [
+///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/var/lib/ceph/osd/ceph-0"]}
+/// ]}"#).unwrap();
+///
+/// let policy = SystemPolicy {
+///     extra_protected_prefixes: vec!["/var/lib/ceph".to_string()],
+///     ..SystemPolicy::strict()
+/// };
+/// assert!(!devices.blockdevices[0].is_system());
+/// assert!(devices.blockdevices[0].is_system_with(&policy));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SystemPolicy {
+    /// Exact mountpoints of the root filesystem.
+    pub root_mounts: Vec<String>,
+    /// Exact mountpoints of boot filesystems.
+    pub boot_mounts: Vec<String>,
+    /// Whether swap (`[SWAP]`) makes a device a system device.
+    pub treat_swap_as_system: bool,
+    /// Paths under which every mountpoint is protected, compared by whole components.
+    pub extra_protected_prefixes: Vec<String>,
+}
+
+impl Default for SystemPolicy {
+    /// Only `/` makes a device a system device, which is what
+    /// [`BlockDevice::is_system`] has always checked.
+    fn default() -> Self {
+        SystemPolicy {
+            root_mounts: vec!["/".to_string()],
+            boot_mounts: Vec::new(),
+            treat_swap_as_system: false,
+            extra_protected_prefixes: Vec::new(),
+        }
+    }
+}
+
+impl SystemPolicy {
+    /// Protects `/`, the boot filesystems at `/boot`, `/boot/efi` and `/efi`,
+    /// and swap.
+    #[must_use]
+    pub fn strict() -> Self {
+        SystemPolicy {
+            boot_mounts: ["/boot", "/boot/efi", "/efi"]
+                .iter()
+                .map(|m| (*m).to_string())
+                .collect(),
+            treat_swap_as_system: true,
+            ..SystemPolicy::default()
+        }
+    }
+
+    /// Returns `true` if a single mountpoint is protected by this policy.
+    #[must_use]
+    pub fn protects(&self, mountpoint: &str) -> bool {
+        self.root_mounts.iter().any(|m| m == mountpoint)
+            || self.boot_mounts.iter().any(|m| m == mountpoint)
+            || (self.treat_swap_as_system && mountpoint == "[SWAP]")
+            || self
+                .extra_protected_prefixes
+                .iter()
+                .any(|prefix| Path::new(mountpoint).starts_with(prefix))
+    }
+}
+
+impl BlockDevice {
+    /// Returns `true` if this device or any of its recursive children has a
+    /// mountpoint protected by `policy`.
+    #[must_use]
+    pub fn is_system_with(&self, policy: &SystemPolicy) -> bool {
+        IterAll::subtree(self).any(|d| d.active_mountpoints().iter().any(|m| policy.protects(m)))
+    }
+}
+
+impl BlockDevices {
+    /// Returns the top-level devices that are system devices under `policy`.
+    #[must_use]
+    pub fn system_with(&self, policy: &SystemPolicy) -> Vec<&BlockDevice> {
+        self.iter().filter(|d| d.is_system_with(policy)).collect()
+    }
+
+    /// Returns the top-level devices that are not system devices under `policy`.
+    #[must_use]
+    pub fn non_system_with(&self, policy: &SystemPolicy) -> Vec<&BlockDevice> {
+        self.iter().filter(|d| !d.is_system_with(policy)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::{SelectionPolicy, parse_lsblk};
+    use std::path::PathBuf;
+
+    const SERVER_JSON: &str = r#"{"blockdevices": [
+        {"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1G", "ro": false, "type": "part", "mountpoints": ["/boot"]},
+            {"name": "sda2", "maj:min": "8:2", "rm": false, "size": "499G", "ro": false, "type": "part", "mountpoints": ["/"]}
+         ]},
+        {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "sdb1", "maj:min": "8:17", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": ["/srv/www"]}
+         ]},
+        {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "2T", "ro": false, "type": "disk", "mountpoints": ["[SWAP]"]},
+        {"name": "sdd", "maj:min": "8:48", "rm": false, "size": "4T", "ro": false, "type": "disk", "mountpoints": ["/srv2"]}
+    ]}"#;
+
+    fn names(devices: Vec<&BlockDevice>) -> Vec<&str> {
+        devices.iter().map(|d| d.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_default_and_strict() {
+        let devices = parse_lsblk(SERVER_JSON).expect("Failed to parse JSON");
+        assert_eq!(
+            names(devices.non_system_with(&SystemPolicy::default())),
+            names(devices.non_system())
+        );
+        assert_eq!(names(devices.non_system()), vec!["sdb", "sdc", "sdd"]);
+        assert_eq!(
+            names(devices.non_system_with(&SystemPolicy::strict())),
+            vec!["sdb", "sdd"]
+        );
+
+        let sample = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        for device in sample.iter_all() {
+            assert_eq!(
+                device.is_system(),
+                device.is_system_with(&SystemPolicy::default()),
+                "{}",
+                device.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_custom_policy() {
+        let devices = parse_lsblk(SERVER_JSON).expect("Failed to parse JSON");
+        let policy = SystemPolicy {
+            extra_protected_prefixes: vec!["/srv".to_string()],
+            treat_swap_as_system: false,
+            ..SystemPolicy::strict()
+        };
+        assert!(policy.protects("/srv"));
+        assert!(policy.protects("/srv/www/html"));
+        assert!(!policy.protects("/srv2"));
+        assert!(!policy.protects("[SWAP]"));
+
+        assert_eq!(names(devices.non_system_with(&policy)), vec!["sdc", "sdd"]);
+        assert_eq!(
+            devices.non_system_disk_paths_with(&policy),
+            vec![PathBuf::from("/dev/sdc"), PathBuf::from("/dev/sdd")]
+        );
+        assert_eq!(
+            devices.non_system_disk_paths(),
+            vec![PathBuf::from("/dev/sdb"), PathBuf::from("/dev/sdd")]
+        );
+
+        assert_eq!(names(devices.system_with(&policy)), vec!["sda", "sdb"]);
+
+        // The other helpers follow the policy too: sdb1 under /srv is
+        // protected and the swap disk sdc is not.
+        let sdb = devices.find_by_name("sdb").unwrap();
+        let sdc = devices.find_by_name("sdc").unwrap();
+        assert_eq!(
+            devices.summary(),
+            "4 disks (0 empty, 1 system), 7.5T total, swap 2T"
+        );
+        assert_eq!(
+            devices.summary_with(&policy),
+            "4 disks (0 empty, 2 system), 7.5T total, swap 2T"
+        );
+        assert!(sdb.is_safe_for_data_use_with(&SystemPolicy::default()));
+        assert!(!sdb.is_safe_for_data_use_with(&policy));
+        assert!(
+            BlockDevices::generate_mdadm_create_command_with(&[sdb, sdc], 1, "md0", &policy)
+                .is_err()
+        );
+        assert!(
+            BlockDevices::generate_mdadm_create_command_with(
+                &[sdc, sdb],
+                1,
+                "md0",
+                &SystemPolicy::default()
+            )
+            .is_ok()
+        );
+        assert!(BlockDevices::generate_mdadm_create_command(&[sdc, sdb], 1, "md0").is_err());
+        let groups = devices.same_size_groups_with(&policy);
+        assert_eq!(groups.values().flatten().count(), 2);
+        assert!(!groups.values().flatten().any(|d| d.name == "sdb"));
+
+        // Every disk here is mounted, so best_candidate cannot pick one
+        // whichever system policy it is given; it defaults to the strict one.
+        assert_eq!(SelectionPolicy::default().system, SystemPolicy::strict());
+        let selection = SelectionPolicy {
+            system: policy.clone(),
+            ..SelectionPolicy::default()
+        };
+        assert_eq!(devices.best_candidate(&selection), None);
+    }
+}
@@ -0,0 +1,331 @@
+//! Assertion helpers for tests that exercise block device filtering.
+//!
+//! These helpers are intended for test code only: they panic with a descriptive
+//! message instead of returning an error. They are compiled for this crate's own
+//! tests and, for downstream crates, when the `test-utils` feature is enabled:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! blockdev = { version = "0.3", features = ["test-utils"] }
+//! ```
+
+use crate::parse::parse_size_string;
+use crate::{BlockDevice, BlockDevices, DeviceType};
+use std::collections::HashSet;
+
+/// Asserts that none of the given devices is a system device.
+///
+/// A device is a system device when it, or any of its recursive children, is
+/// mounted at `/` (see [`BlockDevice::is_system`]).
+///
+/// # Panics
+///
+/// Panics if any device is a system device, listing the names of every
+/// offending device.
+///
+/// # Examples
+///
+/// ```
+/// use blockdev::parse_lsblk;
+/// use blockdev::test_utils::assert_contains_no_system_devices;
+///
+/// let json = r#"{"blockdevices": [
+///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": ["/"]},
+///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null]}
+/// ]}"#;
+/// let devices = parse_lsblk(json).unwrap();
+/// assert_contains_no_system_devices(&devices.non_system());
+/// ```
+#[track_caller]
+pub fn assert_contains_no_system_devices(devices: &[&BlockDevice]) {
+    let system: Vec<&str> = devices
+        .iter()
+        .filter(|d| d.is_system())
+        .map(|d| d.name.as_str())
+        .collect();
+    assert!(
+        system.is_empty(),
+        "expected no system devices, but found {} system device(s): {:?}",
+        system.len(),
+        system
+    );
+}
+
+/// Asserts that the given devices have exactly the expected names, in order.
+///
+/// # Panics
+///
+/// Panics if the names differ, showing both the expected and actual names.
+#[track_caller]
+pub fn assert_device_names_eq(devices: &[&BlockDevice], expected: &[&str]) {
+    let actual: Vec<&str> = devices.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(
+        actual, expected,
+        "device names do not match: expected {expected:?}, got {actual:?}"
+    );
+}
+
+/// Asserts that exactly `expected` devices are present.
+///
+/// # Panics
+///
+/// Panics if the count differs, listing the names of the devices that were found.
+#[track_caller]
+pub fn assert_device_count(devices: &[&BlockDevice], expected: usize) {
+    let names: Vec<&str> = devices.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(
+        devices.len(),
+        expected,
+        "expected {expected} device(s), got {}: {names:?}",
+        devices.len()
+    );
+}
+
+/// Builds a disk without children for test fixtures, without writing lsblk JSON.
+///
+/// `size` is a human-readable size as lsblk prints it, such as `500G` or
+/// `3.5T`. The other fields are those of a fixed, writable disk with device
+/// number `0:0`.
+///
+/// # Panics
+///
+/// Panics if `size` is not a valid size.
+///
+/// # Examples
+///
+/// ```
+/// use blockdev::BlockDevices;
+/// use blockdev::test_utils::make_simple_disk;
+///
+/// let devices = BlockDevices::from(vec![
+///     make_simple_disk("sda", "500G", Some("/")),
+///     make_simple_disk("sdb", "1T", None),
+/// ]);
+/// devices.assert_non_system_count(1);
+/// devices.assert_device_has_mountpoint("sda", "/");
+/// ```
+#[track_caller]
+#[must_use]
+pub fn make_simple_disk(name: &str, size: &str, mountpoint: Option<&str>) -> BlockDevice {
+    BlockDevice {
+        name: name.to_string(),
+        size: parse_size_string(size).unwrap_or_else(|| panic!("invalid size {size:?}")),
+        device_type: DeviceType::Disk,
+        mountpoints: vec![mountpoint.map(str::to_string)],
+        ..BlockDevice::default()
+    }
+}
+
+/// Returns the name of every device in the tree once, in tree order.
+fn all_names(devices: &BlockDevices) -> Vec<&str> {
+    let mut seen = HashSet::new();
+    devices
+        .iter_all()
+        .map(|d| d.name.as_str())
+        .filter(|name| seen.insert(*name))
+        .collect()
+}
+
+impl BlockDevices {
+    /// Asserts that a device named `name` is somewhere in the tree.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such device, listing the names that are present.
+    #[track_caller]
+    pub fn assert_device_exists(&self, name: &str) {
+        assert!(
+            self.iter_all().any(|d| d.name == name),
+            "expected device {name:?}, but it was not found among {:?}",
+            all_names(self)
+        );
+    }
+
+    /// Asserts that there are exactly `expected` top-level devices.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the count differs, listing the names of the top-level devices.
+    #[track_caller]
+    pub fn assert_device_count(&self, expected: usize) {
+        let devices: Vec<&BlockDevice> = self.iter().collect();
+        assert_device_count(&devices, expected);
+    }
+
+    /// Asserts that exactly `expected` top-level devices are not system
+    /// devices, as returned by [`BlockDevices::non_system`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the count differs, listing the names of the non-system devices.
+    #[track_caller]
+    pub fn assert_non_system_count(&self, expected: usize) {
+        assert_device_count(&self.non_system(), expected);
+    }
+
+    /// Asserts that the device named `name` is mounted at `mountpoint`.
+    ///
+    /// A device that appears under several parents passes if any occurrence
+    /// has the mountpoint.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is no such device, or if it is not mounted at
+    /// `mountpoint`, showing its mountpoints.
+    #[track_caller]
+    pub fn assert_device_has_mountpoint(&self, name: &str, mountpoint: &str) {
+        self.assert_device_exists(name);
+        let mountpoints: Vec<&str> = self
+            .iter_all()
+            .filter(|d| d.name == name)
+            .flat_map(BlockDevice::active_mountpoints)
+            .collect();
+        assert!(
+            mountpoints.contains(&mountpoint),
+            "expected {name} to be mounted at {mountpoint:?}, but its mountpoints are {mountpoints:?}"
+        );
+    }
+
+    /// Asserts that none of the top-level devices is a system device.
+    ///
+    /// This is the same check as [`assert_all_non_system`](Self::assert_all_non_system).
+    ///
+    /// # Panics
+    ///
+    /// Panics if any top-level device is a system device, listing the names of
+    /// every offending device.
+    #[track_caller]
+    pub fn assert_no_system_devices(&self) {
+        self.assert_all_non_system();
+    }
+
+    /// Asserts that none of the top-level devices is a system device.
+    ///
+    /// Intended for test code; see [`assert_contains_no_system_devices`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if any top-level device is a system device, listing the names of
+    /// every offending device.
+    #[track_caller]
+    pub fn assert_all_non_system(&self) {
+        let devices: Vec<&BlockDevice> = self.iter().collect();
+        assert_contains_no_system_devices(&devices);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_lsblk;
+
+    const JSON: &str = r#"{
+        "blockdevices": [
+            {"name": "sda", "maj:min": "8:0", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null],
+                "children": [
+                    {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "500G", "ro": false, "type": "part", "mountpoints": ["/"]}
+                ]
+            },
+            {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": [null]},
+            {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "500G", "ro": false, "type": "disk", "mountpoints": ["/data"]}
+        ]
+    }"#;
+
+    #[test]
+    fn test_assertions_pass_on_non_system_devices() {
+        let devices = parse_lsblk(JSON).expect("Failed to parse JSON");
+        let non_system = devices.non_system();
+        assert_contains_no_system_devices(&non_system);
+        assert_device_names_eq(&non_system, &["sdb", "sdc"]);
+        assert_device_count(&non_system, 2);
+        assert_device_count(&devices.system(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "found 1 system device(s): [\"sda\"]")]
+    fn test_assert_contains_no_system_devices_panics() {
+        let devices = parse_lsblk(JSON).expect("Failed to parse JSON");
+        let all: Vec<&BlockDevice> = devices.iter().collect();
+        assert_contains_no_system_devices(&all);
+    }
+
+    #[test]
+    #[should_panic(expected = "found 1 system device(s): [\"sda\"]")]
+    fn test_assert_all_non_system_panics() {
+        let devices = parse_lsblk(JSON).expect("Failed to parse JSON");
+        devices.assert_all_non_system();
+    }
+
+    #[test]
+    #[should_panic(expected = "device names do not match")]
+    fn test_assert_device_names_eq_panics() {
+        let devices = parse_lsblk(JSON).expect("Failed to parse JSON");
+        assert_device_names_eq(&devices.non_system(), &["sdc", "sdb"]);
+    }
+
+    #[test]
+    fn test_block_devices_assertions() {
+        let devices = parse_lsblk(JSON).expect("Failed to parse JSON");
+        devices.assert_device_count(3);
+        devices.assert_non_system_count(2);
+        devices.assert_device_exists("sda1");
+        devices.assert_device_has_mountpoint("sda1", "/");
+        devices.assert_device_has_mountpoint("sdc", "/data");
+
+        let built = BlockDevices::from(vec![
+            make_simple_disk("sdb", "500G", None),
+            make_simple_disk("sdc", "500G", Some("/data")),
+        ]);
+        built.assert_no_system_devices();
+        built.assert_device_has_mountpoint("sdc", "/data");
+        assert_eq!(built.blockdevices[0].size, 500 << 30);
+        assert_eq!(built.blockdevices[0].mountpoints, vec![None]);
+        assert!(built.blockdevices[1].is_disk());
+        assert_eq!(make_simple_disk("sdd", "3.5T", None).size, 7 << 39);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected device \"sdz\", but it was not found among [\"sda\", \"sda1\", \"sdb\", \"sdc\"]"
+    )]
+    fn test_assert_device_exists_panics() {
+        let devices = parse_lsblk(JSON).expect("Failed to parse JSON");
+        devices.assert_device_exists("sdz");
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "expected sda1 to be mounted at \"/data\", but its mountpoints are [\"/\"]"
+    )]
+    fn test_assert_device_has_mountpoint_panics() {
+        let devices = parse_lsblk(JSON).expect("Failed to parse JSON");
+        devices.assert_device_has_mountpoint("sda1", "/data");
+    }
+
+    #[test]
+    #[should_panic(expected = "found 1 system device(s): [\"sda\"]")]
+    fn test_assert_no_system_devices_panics() {
+        let devices = parse_lsblk(JSON).expect("Failed to parse JSON");
+        devices.assert_no_system_devices();
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 1 device(s), got 2: [\"sdb\", \"sdc\"]")]
+    fn test_assert_non_system_count_panics() {
+        let devices = parse_lsblk(JSON).expect("Failed to parse JSON");
+        devices.assert_non_system_count(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid size \"lots\"")]
+    fn test_make_simple_disk_panics() {
+        let _ = make_simple_disk("sda", "lots", None);
+    }
+
+    #[test]
+    #[should_panic(expected = "expected 3 device(s), got 2")]
+    fn test_assert_device_count_panics() {
+        let devices = parse_lsblk(JSON).expect("Failed to parse JSON");
+        assert_device_count(&devices.non_system(), 3);
+    }
+}
@@ -0,0 +1,364 @@
+use crate::naming::validate_device_name;
+use crate::sysfs::read_attribute;
+use crate::{BlockDevError, BlockDevice, BlockDevices};
+use std::collections::HashSet;
+use std::io;
+use std::path::Path;
+
+/// The directory under which the kernel exposes every block device, including partitions.
+const SYS_CLASS_BLOCK: &str = "/sys/class/block";
+
+/// Finds the entry under `root` for a device as lsblk names it. Device-mapper
+/// devices appear in sysfs only as `dm-N`, so a name with no entry of its own is
+/// looked up through each `dm-N/dm/name`. Returns `Ok(None)` if nothing matches.
+fn sysfs_name(root: &Path, name: &str) -> Result<Option<String>, BlockDevError> {
+    if root.join(name).exists() {
+        return Ok(Some(name.to_string()));
+    }
+    let entries = match std::fs::read_dir(root) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(BlockDevError::IoError(err)),
+    };
+    for entry in entries {
+        let kernel_name = entry
+            .map_err(BlockDevError::IoError)?
+            .file_name()
+            .to_string_lossy()
+            .into_owned();
+        if !kernel_name.starts_with("dm-") {
+            continue;
+        }
+        let mapper_name = read_attribute(root, &kernel_name, "dm/name")?;
+        if mapper_name.as_deref() == Some(name) {
+            return Ok(Some(kernel_name));
+        }
+    }
+    Ok(None)
+}
+
+/// Lists `<root>/<name>/<relation>`, resolving device-mapper kernel names such as
+/// `dm-0` to the mapper names lsblk shows, in both directions. Returns `Ok(None)`
+/// if the device is not present in sysfs.
+fn related_devices(
+    root: &Path,
+    name: &str,
+    relation: &str,
+) -> Result<Option<Vec<String>>, BlockDevError> {
+    validate_device_name(name)?;
+    let Some(sysfs_name) = sysfs_name(root, name)? else {
+        return Ok(None);
+    };
+    let entries = match std::fs::read_dir(root.join(sysfs_name).join(relation)) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Some(Vec::new())),
+        Err(err) => return Err(BlockDevError::IoError(err)),
+    };
+
+    let mut names = Vec::new();
+    for entry in entries {
+        let kernel_name = entry
+            .map_err(BlockDevError::IoError)?
+            .file_name()
+            .to_string_lossy()
+            .into_owned();
+        let mapper_name = read_attribute(root, &kernel_name, "dm/name")?
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+        names.push(mapper_name.unwrap_or(kernel_name));
+    }
+    names.sort();
+    Ok(Some(names))
+}
+
+fn required_related_devices(
+    root: &Path,
+    name: &str,
+    relation: &str,
+) -> Result<Vec<String>, BlockDevError> {
+    related_devices(root, name, relation)?
+        .ok_or_else(|| BlockDevError::DeviceNotFound(name.to_string()))
+}
+
+/// A disagreement between a parsed device tree and the kernel's view in sysfs,
+/// reported by [`BlockDevices::verify_topology`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TopologyIssue {
+    /// The device has no entry in `/sys/class/block`, usually because it was removed.
+    MissingInSysfs {
+        /// The device name.
+        name: String,
+    },
+    /// The device's sysfs entry exists but could not be read.
+    Unreadable {
+        /// The device name.
+        name: String,
+        /// The error that occurred.
+        error: String,
+    },
+    /// The tree shows `child` on top of `parent`, but sysfs does not list it as a holder.
+    EdgeNotInSysfs {
+        /// The lower device.
+        parent: String,
+        /// The device stacked on top of it.
+        child: String,
+    },
+    /// Sysfs lists `child` as a holder of `parent`, but the tree does not.
+    EdgeNotInTree {
+        /// The lower device.
+        parent: String,
+        /// The device stacked on top of it.
+        child: String,
+    },
+}
+
+/// Collects every parent-to-child edge of the tree in depth-first order, skipping
+/// partitions, which sysfs does not list as holders.
+fn tree_edges<'a>(devices: &'a [BlockDevice], edges: &mut Vec<(&'a str, &'a str)>) {
+    for device in devices {
+        for child in device.children_iter() {
+            let edge = (device.name.as_str(), child.name.as_str());
+            if !child.is_partition() && !edges.contains(&edge) {
+                edges.push(edge);
+            }
+        }
+        if let Some(children) = &device.children {
+            tree_edges(children, edges);
+        }
+    }
+}
+
+impl BlockDevice {
+    /// Lists the devices stacked directly on top of this one, such as the RAID
+    /// array or dm-crypt mapping that uses it, from `/sys/class/block/<name>/holders`.
+    ///
+    /// Device-mapper devices are looked up, and reported as holders, by their
+    /// mapper name (as lsblk shows them) rather than as `dm-N`. This is Linux-only.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::DeviceNotFound`] if the device is not in sysfs and
+    /// [`BlockDevError::IoError`] if the directory cannot be read.
+    pub fn holders(&self) -> Result<Vec<String>, BlockDevError> {
+        required_related_devices(Path::new(SYS_CLASS_BLOCK), &self.name, "holders")
+    }
+
+    /// Lists the devices this one is built from, from `/sys/class/block/<name>/slaves`.
+    ///
+    /// See [`BlockDevice::holders`] for details.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::DeviceNotFound`] if the device is not in sysfs and
+    /// [`BlockDevError::IoError`] if the directory cannot be read.
+    pub fn slaves(&self) -> Result<Vec<String>, BlockDevError> {
+        required_related_devices(Path::new(SYS_CLASS_BLOCK), &self.name, "slaves")
+    }
+}
+
+impl BlockDevices {
+    /// Cross-checks the stacking edges of this tree (RAID, LVM, dm-crypt and so on)
+    /// against the `holders` directories in sysfs.
+    ///
+    /// This reveals devices that were assembled or torn down after the snapshot
+    /// was taken. Partition edges are not checked. Every problem is reported in
+    /// the returned list rather than as an error; an empty list means the tree
+    /// matches the kernel's view.
+    #[must_use]
+    pub fn verify_topology(&self) -> Vec<TopologyIssue> {
+        self.verify_topology_in(Path::new(SYS_CLASS_BLOCK))
+    }
+
+    fn verify_topology_in(&self, root: &Path) -> Vec<TopologyIssue> {
+        let mut edges = Vec::new();
+        tree_edges(&self.blockdevices, &mut edges);
+        let tree: HashSet<(&str, &str)> = edges.iter().copied().collect();
+
+        let mut issues = Vec::new();
+        let mut seen = HashSet::new();
+        for device in self.iter_all().filter(|d| seen.insert(d.name.as_str())) {
+            let name = device.name.as_str();
+            let holders = match related_devices(root, name, "holders") {
+                Ok(Some(holders)) => holders,
+                Ok(None) => {
+                    issues.push(TopologyIssue::MissingInSysfs {
+                        name: name.to_string(),
+                    });
+                    continue;
+                }
+                Err(err) => {
+                    issues.push(TopologyIssue::Unreadable {
+                        name: name.to_string(),
+                        error: err.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            for &(parent, child) in edges.iter().filter(|(parent, _)| *parent == name) {
+                if !holders.iter().any(|h| h == child) {
+                    issues.push(TopologyIssue::EdgeNotInSysfs {
+                        parent: parent.to_string(),
+                        child: child.to_string(),
+                    });
+                }
+            }
+            for holder in holders {
+                if !tree.contains(&(name, holder.as_str())) {
+                    issues.push(TopologyIssue::EdgeNotInTree {
+                        parent: name.to_string(),
+                        child: holder,
+                    });
+                }
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DeviceType;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    /// Builds a fake `/sys/class/block` matching a tree's stacking, with crypt and
+    /// LVM devices under `dm-N` names as the kernel shows them.
+    fn fake_sys_class_block(test: &str, devices: &BlockDevices) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("blockdev-{test}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let mut kernel_names = HashMap::new();
+        let mut dm_count = 0;
+        for device in devices.iter_all() {
+            if kernel_names.contains_key(device.name.as_str()) {
+                continue;
+            }
+            let kernel_name = match device.device_type {
+                DeviceType::Crypt | DeviceType::Lvm => {
+                    let kernel_name = format!("dm-{dm_count}");
+                    dm_count += 1;
+                    std::fs::create_dir_all(root.join(&kernel_name).join("dm")).unwrap();
+                    std::fs::write(root.join(&kernel_name).join("dm/name"), &device.name).unwrap();
+                    kernel_name
+                }
+                _ => {
+                    std::fs::create_dir_all(root.join(&device.name)).unwrap();
+                    device.name.clone()
+                }
+            };
+            kernel_names.insert(device.name.as_str(), kernel_name);
+        }
+        let mut edges = Vec::new();
+        tree_edges(&devices.blockdevices, &mut edges);
+        for (parent, child) in edges {
+            let (parent, child) = (&kernel_names[parent], &kernel_names[child]);
+            std::fs::create_dir_all(root.join(parent).join("holders").join(child)).unwrap();
+            std::fs::create_dir_all(root.join(child).join("slaves").join(parent)).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn test_related_devices() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let root = fake_sys_class_block("related-devices", &devices);
+
+        let slaves = required_related_devices(&root, "md0", "slaves").unwrap();
+        assert_eq!(slaves, vec!["nvme2n1p3", "nvme3n1p3"]);
+        let holders = required_related_devices(&root, "nvme3n1p5", "holders").unwrap();
+        assert_eq!(holders, vec!["md2"]);
+        assert!(
+            required_related_devices(&root, "nvme0n1", "holders")
+                .unwrap()
+                .is_empty()
+        );
+        assert!(matches!(
+            required_related_devices(&root, "sdz", "holders"),
+            Err(BlockDevError::DeviceNotFound(_))
+        ));
+
+        // dm-N holders are reported by their mapper name.
+        std::fs::create_dir_all(root.join("nvme0n1p1/holders/dm-0")).unwrap();
+        std::fs::create_dir_all(root.join("dm-0/dm")).unwrap();
+        std::fs::write(root.join("dm-0/dm/name"), "luks-data\n").unwrap();
+        let holders = required_related_devices(&root, "nvme0n1p1", "holders").unwrap();
+        assert_eq!(holders, vec!["luks-data"]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_verify_topology() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let root = fake_sys_class_block("verify-topology", &devices);
+        assert!(devices.verify_topology_in(&root).is_empty());
+
+        // md1 lost a member, a new array appeared on nvme0n1p1, and nvme9n1p9 is gone.
+        std::fs::remove_dir_all(root.join("nvme2n1p4/holders/md1")).unwrap();
+        std::fs::create_dir_all(root.join("nvme0n1p1/holders/md5")).unwrap();
+        std::fs::remove_dir_all(root.join("nvme9n1p9")).unwrap();
+
+        let issues = devices.verify_topology_in(&root);
+        assert_eq!(
+            issues,
+            vec![
+                TopologyIssue::MissingInSysfs {
+                    name: "nvme9n1p9".to_string(),
+                },
+                TopologyIssue::EdgeNotInTree {
+                    parent: "nvme0n1p1".to_string(),
+                    child: "md5".to_string(),
+                },
+                TopologyIssue::EdgeNotInSysfs {
+                    parent: "nvme2n1p4".to_string(),
+                    child: "md1".to_string(),
+                },
+            ]
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_verify_topology_device_mapper() {
+        let devices = parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": [null],
+                     "children": [
+                        {"name": "luks-data", "maj:min": "253:0", "rm": false, "size": "1T", "ro": false, "type": "crypt", "mountpoints": ["/srv"]}
+                     ]}
+                 ]}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+        let root = fake_sys_class_block("verify-topology-dm", &devices);
+        assert!(root.join("dm-0/slaves/sda1").exists());
+        assert!(devices.verify_topology_in(&root).is_empty());
+
+        let slaves = required_related_devices(&root, "luks-data", "slaves").unwrap();
+        assert_eq!(slaves, vec!["sda1"]);
+        let holders = required_related_devices(&root, "sda1", "holders").unwrap();
+        assert_eq!(holders, vec!["luks-data"]);
+
+        // Closing the mapping removes dm-0 and its holder link.
+        std::fs::remove_dir_all(root.join("dm-0")).unwrap();
+        std::fs::remove_dir_all(root.join("sda1/holders/dm-0")).unwrap();
+        assert_eq!(
+            devices.verify_topology_in(&root),
+            vec![
+                TopologyIssue::EdgeNotInSysfs {
+                    parent: "sda1".to_string(),
+                    child: "luks-data".to_string(),
+                },
+                TopologyIssue::MissingInSysfs {
+                    name: "luks-data".to_string(),
+                },
+            ]
+        );
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
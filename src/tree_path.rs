@@ -0,0 +1,216 @@
+use crate::{BlockDevError, BlockDevice, BlockDevices};
+
+/// How [`BlockDevices::path_of_with`] and [`BlockDevices::by_path_with`] spell
+/// a device's lineage.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TreePathOptions {
+    /// The string placed between components, `/` by default.
+    pub separator: String,
+    /// Use `major:minor` numbers instead of names as components, such as
+    /// `259:3/259:8/9:2`.
+    pub use_maj_min: bool,
+}
+
+impl Default for TreePathOptions {
+    fn default() -> Self {
+        TreePathOptions {
+            separator: "/".to_string(),
+            use_maj_min: false,
+        }
+    }
+}
+
+impl TreePathOptions {
+    fn component(&self, device: &BlockDevice) -> String {
+        if self.use_maj_min {
+            device.maj_min.to_string()
+        } else {
+            device.name.clone()
+        }
+    }
+}
+
+impl BlockDevices {
+    /// Returns the lineage of the first occurrence of `name` in tree order,
+    /// such as `nvme3n1/nvme3n1p5/md2`, or `None` if it is not in the tree.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "children": [
+    ///         {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": ["/"]}
+    ///      ]}
+    /// ]}"#).unwrap();
+    ///
+    /// assert_eq!(devices.path_of("sda1").as_deref(), Some("sda/sda1"));
+    /// assert_eq!(devices.by_path("sda/sda1").unwrap().name, "sda1");
+    /// ```
+    #[must_use]
+    pub fn path_of(&self, name: &str) -> Option<String> {
+        self.path_of_with(name, &TreePathOptions::default())
+    }
+
+    /// Like [`path_of`](Self::path_of), spelled according to `options`.
+    #[must_use]
+    pub fn path_of_with(&self, name: &str, options: &TreePathOptions) -> Option<String> {
+        self.paths_of_with(name, options).into_iter().next()
+    }
+
+    /// Returns the lineage of every occurrence of `name`, in tree order.
+    ///
+    /// A RAID array or other stacked device appears under each of its members,
+    /// so it has one path per member.
+    #[must_use]
+    pub fn paths_of(&self, name: &str) -> Vec<String> {
+        self.paths_of_with(name, &TreePathOptions::default())
+    }
+
+    /// Like [`paths_of`](Self::paths_of), spelled according to `options`.
+    #[must_use]
+    pub fn paths_of_with(&self, name: &str, options: &TreePathOptions) -> Vec<String> {
+        self.iter_with_parent_path()
+            .filter(|(_, device)| device.name == name)
+            .map(|(ancestors, device)| {
+                let components: Vec<String> = ancestors
+                    .iter()
+                    .chain([&device])
+                    .map(|d| options.component(d))
+                    .collect();
+                components.join(&options.separator)
+            })
+            .collect()
+    }
+
+    /// Resolves a lineage produced by [`path_of`](Self::path_of) back to its device.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::DeviceNotFound`] naming the first component
+    /// that is not a child of the one before it, or not a top-level device if
+    /// it is the first.
+    pub fn by_path(&self, path: &str) -> Result<&BlockDevice, BlockDevError> {
+        self.by_path_with(path, &TreePathOptions::default())
+    }
+
+    /// Like [`by_path`](Self::by_path), for a path spelled according to `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::InvalidInput`] if `path` or the separator is
+    /// empty, and [`BlockDevError::DeviceNotFound`] as for [`by_path`](Self::by_path).
+    pub fn by_path_with(
+        &self,
+        path: &str,
+        options: &TreePathOptions,
+    ) -> Result<&BlockDevice, BlockDevError> {
+        if path.is_empty() || options.separator.is_empty() {
+            return Err(BlockDevError::InvalidInput(
+                "device path and separator must not be empty".to_string(),
+            ));
+        }
+        let mut level = self.blockdevices.as_slice();
+        let mut resolved: Option<&BlockDevice> = None;
+        for component in path.split(options.separator.as_str()) {
+            let device = level
+                .iter()
+                .find(|d| options.component(d) == component)
+                .ok_or_else(|| {
+                    BlockDevError::DeviceNotFound(match resolved {
+                        Some(parent) => format!("'{component}' under '{}' in {path}", parent.name),
+                        None => format!("'{component}' at the top level in {path}"),
+                    })
+                })?;
+            level = device.children.as_deref().unwrap_or_default();
+            resolved = Some(device);
+        }
+        resolved.ok_or_else(|| BlockDevError::DeviceNotFound(path.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::SAMPLE_JSON;
+    use crate::parse_lsblk;
+
+    #[test]
+    fn test_path_of() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        assert_eq!(devices.path_of("nvme3n1").as_deref(), Some("nvme3n1"));
+        assert_eq!(
+            devices.paths_of("md2"),
+            vec!["nvme3n1/nvme3n1p5/md2", "nvme2n1/nvme2n1p5/md2"]
+        );
+        assert_eq!(
+            devices.path_of("md2").as_deref(),
+            Some("nvme3n1/nvme3n1p5/md2")
+        );
+        assert_eq!(devices.path_of("sdz"), None);
+        assert!(devices.paths_of("sdz").is_empty());
+
+        let options = TreePathOptions {
+            separator: " > ".to_string(),
+            use_maj_min: true,
+        };
+        let md2 = devices.path_of_with("md2", &options).unwrap();
+        assert_eq!(md2, "259:19 > 259:27 > 9:2");
+        assert_eq!(devices.by_path_with(&md2, &options).unwrap().name, "md2");
+    }
+
+    #[test]
+    fn test_by_path_round_trip() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let maj_min = TreePathOptions {
+            use_maj_min: true,
+            ..TreePathOptions::default()
+        };
+        for device in devices.iter_all() {
+            let paths = devices.paths_of(&device.name);
+            assert!(!paths.is_empty(), "{}", device.name);
+            for path in paths {
+                assert_eq!(devices.by_path(&path).unwrap().name, device.name, "{path}");
+            }
+            let path = devices.path_of_with(&device.name, &maj_min).unwrap();
+            assert_eq!(
+                devices.by_path_with(&path, &maj_min).unwrap().name,
+                device.name,
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_by_path_errors() {
+        let devices = parse_lsblk(SAMPLE_JSON).expect("Failed to parse JSON");
+        let err = devices.by_path("nvme3n1/nvme3n1p9/md2").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "device not found: 'nvme3n1p9' under 'nvme3n1' in nvme3n1/nvme3n1p9/md2"
+        );
+        let err = devices.by_path("sdz/sdz1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "device not found: 'sdz' at the top level in sdz/sdz1"
+        );
+        assert!(matches!(
+            devices.by_path("nvme3n1p5"),
+            Err(BlockDevError::DeviceNotFound(_))
+        ));
+        assert!(matches!(
+            devices.by_path(""),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+        let no_separator = TreePathOptions {
+            separator: String::new(),
+            ..TreePathOptions::default()
+        };
+        assert!(matches!(
+            devices.by_path_with("nvme3n1", &no_separator),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+    }
+}
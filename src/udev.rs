@@ -0,0 +1,343 @@
+use crate::exec::tool_command;
+use crate::naming::validate_device_name;
+use crate::{BlockDevError, BlockDevice, BlockDevices};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// The udev properties that [`BlockDevices::enrich_udev`] keeps on each device.
+pub const UDEV_ENRICH_KEYS: &[&str] = &["ID_MODEL", "ID_SERIAL_SHORT", "ID_BUS"];
+
+/// Splits `line` into name and value if it starts a `KEY=value` property.
+fn property_key(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.split_once('=')?;
+    let valid = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+    valid.then_some((key, value))
+}
+
+/// Parses the output of `udevadm info --query=property` into a map.
+///
+/// Both the bare `KEY=value` form and the `E: KEY=value` records of a full
+/// `udevadm info` dump are accepted; the other records of a full dump (`P:`,
+/// `N:`, `S:`, ...) are skipped. A line that does not start a new property
+/// continues the value of the previous one, joined with a newline. If a
+/// property appears more than once, the last value wins.
+///
+/// # Examples
+///
+/// ```
+/// use blockdev::parse_udev_properties;
+///
+/// let properties = parse_udev_properties("E: ID_BUS=usb\nE: ID_SERIAL_SHORT=4C530001\n");
+/// assert_eq!(properties["ID_BUS"], "usb");
+/// ```
+#[must_use]
+pub fn parse_udev_properties(output: &str) -> BTreeMap<String, String> {
+    let mut properties = BTreeMap::new();
+    let mut last: Option<String> = None;
+    for line in output.lines() {
+        let record = match line.split_once(": ") {
+            Some(("E", property)) => property,
+            Some((tag, _)) if tag.len() == 1 && tag.chars().all(|c| c.is_ascii_uppercase()) => {
+                last = None;
+                continue;
+            }
+            _ => line,
+        };
+        if let Some((key, value)) = property_key(record) {
+            properties.insert(key.to_string(), value.to_string());
+            last = Some(key.to_string());
+        } else if let Some(key) = &last {
+            if let Some(value) = properties.get_mut(key) {
+                value.push('\n');
+                value.push_str(line);
+            }
+        }
+    }
+    properties
+}
+
+/// Runs `udevadm info --query=property --name=<path>` and parses its output.
+///
+/// `udevadm` is resolved and run like lsblk: with the `secure-exec` feature,
+/// from a trusted system directory and with only `LC_ALL=C` set.
+fn query_properties(path: &Path) -> Result<BTreeMap<String, String>, BlockDevError> {
+    let output = tool_command("udevadm")
+        .arg("info")
+        .arg("--query=property")
+        .arg(format!("--name={}", path.display()))
+        .output()?;
+    if !output.status.success() {
+        return Err(BlockDevError::UdevadmError(
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ));
+    }
+    Ok(parse_udev_properties(&String::from_utf8(output.stdout)?))
+}
+
+/// Returns every udev property of `/dev/<device_name>`, by running
+/// `udevadm info --query=property --name=/dev/<device_name>`.
+///
+/// This needs the `udevadm` binary, which ships with systemd and eudev, so it
+/// only works on Linux. Requires the `udev-props` feature.
+///
+/// # Errors
+///
+/// Returns the errors of [`BlockDevice::udev_properties`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # use blockdev::udev_properties;
+/// let properties = udev_properties("sda").expect("Failed to query udev");
+/// println!("{:?}", properties.get("ID_SERIAL"));
+/// ```
+pub fn udev_properties(device_name: &str) -> Result<BTreeMap<String, String>, BlockDevError> {
+    validate_device_name(device_name)?;
+    query_properties(&Path::new("/dev").join(device_name))
+}
+
+impl BlockDevice {
+    /// Returns every udev property of this device, by running
+    /// `udevadm info --query=property --name=<path>` on its [`path`](Self::path).
+    ///
+    /// udev often knows more than lsblk: `ID_SERIAL_SHORT` is set for some
+    /// devices whose `SERIAL` column is empty, and `ID_BUS` tells USB from ATA
+    /// devices. This needs the `udevadm` binary. Requires the `udev-props` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BlockDevError::InvalidInput`] if the name is not a plain device
+    /// name, [`BlockDevError::CommandFailed`] if `udevadm` cannot be run,
+    /// [`BlockDevError::UdevadmError`] if it exits with an error, and
+    /// [`BlockDevError::InvalidUtf8`] if its output is not UTF-8.
+    pub fn udev_properties(&self) -> Result<BTreeMap<String, String>, BlockDevError> {
+        validate_device_name(&self.name)?;
+        query_properties(&self.path())
+    }
+
+    /// Returns the udev property `key` of this device, such as `ID_VENDOR`, or
+    /// `None` if udev does not set it.
+    ///
+    /// Each call runs `udevadm`; use [`udev_properties`](Self::udev_properties)
+    /// to look up several keys. Requires the `udev-props` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`udev_properties`](Self::udev_properties).
+    pub fn udev_property(&self, key: &str) -> Result<Option<String>, BlockDevError> {
+        self.udev_property_with(key, BlockDevice::udev_properties)
+    }
+
+    fn udev_property_with<F>(
+        &self,
+        key: &str,
+        properties: F,
+    ) -> Result<Option<String>, BlockDevError>
+    where
+        F: FnOnce(&BlockDevice) -> Result<BTreeMap<String, String>, BlockDevError>,
+    {
+        Ok(properties(self)?.remove(key))
+    }
+}
+
+impl BlockDevices {
+    /// Attaches the [`UDEV_ENRICH_KEYS`] properties of every device in the tree
+    /// to its [`udev`](BlockDevice::udev) field, using
+    /// [`BlockDevice::udev_properties`].
+    ///
+    /// Devices without any of the keys get an empty map. Requires the
+    /// `udev-props` feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from [`BlockDevice::udev_properties`]; devices
+    /// enriched before it keep their properties.
+    pub fn enrich_udev(&mut self) -> Result<(), BlockDevError> {
+        self.enrich_udev_with(BlockDevice::udev_properties)
+    }
+
+    fn enrich_udev_with<F>(&mut self, mut properties: F) -> Result<(), BlockDevError>
+    where
+        F: FnMut(&BlockDevice) -> Result<BTreeMap<String, String>, BlockDevError>,
+    {
+        fn enrich<F>(devices: &mut [BlockDevice], properties: &mut F) -> Result<(), BlockDevError>
+        where
+            F: FnMut(&BlockDevice) -> Result<BTreeMap<String, String>, BlockDevError>,
+        {
+            for device in devices {
+                let mut all = properties(device)?;
+                device.udev = Some(
+                    UDEV_ENRICH_KEYS
+                        .iter()
+                        .filter_map(|key| all.remove_entry(*key))
+                        .collect(),
+                );
+                if let Some(children) = &mut device.children {
+                    enrich(children, properties)?;
+                }
+            }
+            Ok(())
+        }
+        enrich(&mut self.blockdevices, &mut properties)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_lsblk;
+
+    /// `udevadm info --query=property --name=/dev/sdb` for a USB stick.
+    const QUERY_PROPERTY: &str = "\
+DEVPATH=/devices/pci0000:00/0000:00:14.0/usb2/2-1/2-1:1.0/host6/target6:0:0/6:0:0:0/block/sdb
+DEVNAME=/dev/sdb
+DEVTYPE=disk
+MAJOR=8
+MINOR=16
+SUBSYSTEM=block
+ID_VENDOR=SanDisk
+ID_MODEL=Ultra_Fit
+ID_SERIAL=SanDisk_Ultra_Fit_4C530001230518108283-0:0
+ID_SERIAL_SHORT=4C530001230518108283
+ID_BUS=usb
+ID_PATH=pci-0000:00:14.0-usb-0:1:1.0-scsi-0:0:0:0
+DEVLINKS=/dev/disk/by-id/usb-SanDisk_Ultra_Fit_4C530001230518108283-0:0 /dev/disk/by-path/pci-0000:00:14.0-usb-0:1:1.0-scsi-0:0:0:0
+";
+
+    /// `udevadm info --name=/dev/sda`, with a value that spans two lines.
+    const FULL_INFO: &str = "\
+P: /devices/pci0000:00/0000:00:17.0/ata1/host0/target0:0:0/0:0:0:0/block/sda
+M: sda
+U: block
+T: disk
+N: sda
+L: 0
+S: disk/by-id/ata-Samsung_SSD_870_EVO_1TB_S6PTNM0T123456A
+E: DEVPATH=/devices/pci0000:00/0000:00:17.0/ata1/host0/target0:0:0/0:0:0:0/block/sda
+E: DEVNAME=/dev/sda
+E: ID_MODEL=Samsung_SSD_870_EVO_1TB
+E: ID_SERIAL_SHORT=S6PTNM0T123456A
+E: ID_BUS=ata
+E: ID_NOTE=first line
+second line
+E: ID_ATA=1
+";
+
+    #[test]
+    fn test_parse_query_property() {
+        let properties = parse_udev_properties(QUERY_PROPERTY);
+        assert_eq!(properties.len(), 13);
+        assert_eq!(properties["ID_MODEL"], "Ultra_Fit");
+        assert_eq!(properties["ID_SERIAL_SHORT"], "4C530001230518108283");
+        assert_eq!(properties["ID_BUS"], "usb");
+        assert_eq!(
+            properties["ID_PATH"],
+            "pci-0000:00:14.0-usb-0:1:1.0-scsi-0:0:0:0"
+        );
+    }
+
+    #[test]
+    fn test_parse_full_info() {
+        let properties = parse_udev_properties(FULL_INFO);
+        assert_eq!(
+            properties.keys().collect::<Vec<_>>(),
+            vec![
+                "DEVNAME",
+                "DEVPATH",
+                "ID_ATA",
+                "ID_BUS",
+                "ID_MODEL",
+                "ID_NOTE",
+                "ID_SERIAL_SHORT"
+            ]
+        );
+        assert_eq!(properties["ID_BUS"], "ata");
+        assert_eq!(properties["ID_NOTE"], "first line\nsecond line");
+        assert_eq!(properties["ID_ATA"], "1");
+        assert!(parse_udev_properties("").is_empty());
+        assert!(parse_udev_properties("continuation without key\n").is_empty());
+    }
+
+    #[test]
+    fn test_udev_property_with() {
+        let device = BlockDevice {
+            name: "sdb".to_string(),
+            ..Default::default()
+        };
+        let query = |_: &BlockDevice| Ok(parse_udev_properties(QUERY_PROPERTY));
+        for (key, value) in [
+            ("ID_SERIAL", "SanDisk_Ultra_Fit_4C530001230518108283-0:0"),
+            ("ID_VENDOR", "SanDisk"),
+            ("ID_MODEL", "Ultra_Fit"),
+        ] {
+            assert_eq!(
+                device.udev_property_with(key, query).unwrap().as_deref(),
+                Some(value)
+            );
+        }
+        assert_eq!(device.udev_property_with("ID_WWN", query).unwrap(), None);
+
+        let failed = device.udev_property_with("ID_MODEL", |_| {
+            Err(BlockDevError::UdevadmError("no such device".to_string()))
+        });
+        assert!(matches!(failed, Err(BlockDevError::UdevadmError(_))));
+
+        // Names are checked before udevadm is run.
+        assert!(matches!(
+            udev_properties("../sda"),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+        let bad = BlockDevice {
+            name: "sda/../../etc".to_string(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            bad.udev_property("ID_MODEL"),
+            Err(BlockDevError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_enrich_udev_with() {
+        let mut devices = parse_lsblk(
+            r#"{"blockdevices": [
+                {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+                 "children": [
+                    {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": ["/"]}
+                 ]},
+                {"name": "sdb", "maj:min": "8:16", "rm": true, "size": "64G", "ro": false, "type": "disk", "mountpoints": [null]}
+            ]}"#,
+        )
+        .expect("Failed to parse JSON");
+        devices
+            .enrich_udev_with(|device| {
+                Ok(match device.name.as_str() {
+                    "sda" => parse_udev_properties(FULL_INFO),
+                    "sdb" => parse_udev_properties(QUERY_PROPERTY),
+                    _ => BTreeMap::new(),
+                })
+            })
+            .unwrap();
+
+        let sda = &devices.blockdevices[0];
+        let udev = sda.udev.as_ref().unwrap();
+        assert_eq!(udev.len(), 3);
+        assert_eq!(udev["ID_BUS"], "ata");
+        assert_eq!(sda.find_child("sda1").unwrap().udev, Some(BTreeMap::new()));
+        assert_eq!(
+            devices.blockdevices[1].udev.as_ref().unwrap()["ID_SERIAL_SHORT"],
+            "4C530001230518108283"
+        );
+
+        let json = serde_json::to_string(&devices).unwrap();
+        assert!(json.contains(r#""_blockdev_udev":{"ID_BUS":"ata""#));
+        assert_eq!(parse_lsblk(&json).unwrap(), devices);
+
+        let failed = devices
+            .enrich_udev_with(|_| Err(BlockDevError::UdevadmError("no such device".to_string())));
+        assert!(matches!(failed, Err(BlockDevError::UdevadmError(_))));
+    }
+}
@@ -0,0 +1,420 @@
+use crate::{BlockDevice, BlockDevices, IterAll};
+use std::collections::{HashMap, HashSet};
+
+/// How [`BlockDevice::rollup_usage`] attributes a filesystem that spans several
+/// top-level disks, such as one on a RAID array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UsageAttribution {
+    /// Split the filesystem's size and usage evenly across the disks it spans,
+    /// so the per-disk figures add up to the real totals. A RAID1 filesystem of
+    /// 100G on two disks counts 50G towards each.
+    Fractional,
+    /// Count the whole filesystem towards every disk it spans. A RAID1
+    /// filesystem of 100G on two disks counts 100G towards each.
+    Full,
+}
+
+/// Filesystem space on one disk, summed over the mounted filesystems on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct UsageRollup {
+    /// The bytes used, from `FSUSED`.
+    pub used: u64,
+    /// The total filesystem size in bytes, from `FSSIZE`.
+    pub size: u64,
+}
+
+impl UsageRollup {
+    /// Returns the used fraction between 0.0 and 1.0, or `None` if `size` is zero.
+    #[must_use]
+    pub fn used_fraction(&self) -> Option<f64> {
+        (self.size > 0).then(|| self.used as f64 / self.size as f64)
+    }
+}
+
+/// Allocated and used space of one partition, returned by
+/// [`BlockDevices::partition_usage_map`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct PartitionUsage {
+    /// The partition size in bytes.
+    pub total_bytes: u64,
+    /// The free bytes in the mounted filesystem.
+    pub free_bytes: u64,
+    /// The used bytes in the mounted filesystem.
+    pub used_bytes: u64,
+    /// The used share of the filesystem, from 0.0 to 100.0.
+    pub use_percent: f64,
+}
+
+impl PartitionUsage {
+    /// Returns `true` if [`use_percent`](PartitionUsage::use_percent) is above
+    /// `threshold`, a percentage such as `90.0`.
+    #[must_use]
+    pub fn is_critical(&self, threshold: f64) -> bool {
+        self.use_percent > threshold
+    }
+}
+
+/// Returns the free and used bytes that `statvfs` reported for a mounted
+/// filesystem, as carried in the `FSSIZE` and `FSUSED` columns.
+fn filesystem_space(device: &BlockDevice) -> Option<(u64, u64)> {
+    let (size, used) = (device.fssize?, device.fsused?);
+    Some((size.saturating_sub(used), used))
+}
+
+/// Identifies the filesystem on a device: its UUID when known, so the members
+/// of a multi-device filesystem such as btrfs RAID1 compare equal, and
+/// otherwise the device name.
+fn filesystem_key(device: &BlockDevice) -> &str {
+    device
+        .uuid
+        .as_deref()
+        .map(str::trim)
+        .filter(|uuid| !uuid.is_empty())
+        .unwrap_or(&device.name)
+}
+
+impl BlockDevice {
+    /// Sums `fsused` and `fssize` over every mounted filesystem on this device
+    /// and the layers stacked on it (partitions, RAID, LVM, crypt, ...).
+    ///
+    /// `ctx` is the snapshot this device belongs to. It is used to count how many
+    /// top-level disks each filesystem spans, which `attribution` then uses to
+    /// split or duplicate shared filesystems. A filesystem that appears several
+    /// times below this device is counted once. Fractional shares are rounded down.
+    ///
+    /// Filesystems are told apart by UUID when the `UUID` column is present, so
+    /// a multi-device filesystem such as btrfs RAID1, which lsblk reports on
+    /// every member, is treated like a RAID array (see
+    /// [`BlockDevices::filesystem_groups`]).
+    ///
+    /// Returns `None` if no mounted descendant has both `fssize` and `fsused`,
+    /// which are only set by `get_devices_extended` or JSON that includes the
+    /// `FSSIZE` and `FSUSED` columns.
+    #[must_use]
+    pub fn rollup_usage(
+        &self,
+        ctx: &BlockDevices,
+        attribution: UsageAttribution,
+    ) -> Option<UsageRollup> {
+        let mut seen = HashSet::new();
+        let mut rollup: Option<UsageRollup> = None;
+        for device in IterAll::subtree(self) {
+            let (Some(size), Some(used)) = (device.fssize, device.fsused) else {
+                continue;
+            };
+            if !device.is_mounted() || !seen.insert(filesystem_key(device)) {
+                continue;
+            }
+            let key = filesystem_key(device);
+            let shares = match attribution {
+                UsageAttribution::Full => 1,
+                UsageAttribution::Fractional => ctx
+                    .iter()
+                    .filter(|disk| IterAll::subtree(disk).any(|d| filesystem_key(d) == key))
+                    .count()
+                    .max(1) as u64,
+            };
+            let total = rollup.get_or_insert_with(UsageRollup::default);
+            total.used += used / shares;
+            total.size += size / shares;
+        }
+        rollup
+    }
+}
+
+impl BlockDevices {
+    /// Returns [`BlockDevice::rollup_usage`] for every top-level disk, in snapshot order.
+    ///
+    /// Disks without any mounted filesystem data are included with `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{parse_lsblk, UsageAttribution};
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/"],
+    ///      "fssize": "1T", "fsused": "256G"}
+    /// ]}"#).unwrap();
+    ///
+    /// let usage = devices.usage_by_disk(UsageAttribution::Fractional);
+    /// assert_eq!(usage[0].1.unwrap().used_fraction(), Some(0.25));
+    /// ```
+    #[must_use]
+    pub fn usage_by_disk(
+        &self,
+        attribution: UsageAttribution,
+    ) -> Vec<(&BlockDevice, Option<UsageRollup>)> {
+        self.iter()
+            .filter(|d| d.is_disk())
+            .map(|disk| (disk, disk.rollup_usage(self, attribution)))
+            .collect()
+    }
+
+    /// Sums `fsused` and `fssize` over every mounted filesystem in the tree.
+    ///
+    /// A device that appears under several parents, such as an md array, is
+    /// counted once. With `count_groups_once`, devices that share a filesystem
+    /// UUID are also counted once, so a btrfs RAID1 across two disks adds its
+    /// size a single time (see [`BlockDevices::filesystem_groups`]); without
+    /// it, every member adds the full filesystem size.
+    ///
+    /// Returns `None` if no mounted device has both `fssize` and `fsused`.
+    #[must_use]
+    pub fn total_usage(&self, count_groups_once: bool) -> Option<UsageRollup> {
+        let mut seen = HashSet::new();
+        let mut rollup: Option<UsageRollup> = None;
+        for device in self.iter_all() {
+            let (Some(size), Some(used)) = (device.fssize, device.fsused) else {
+                continue;
+            };
+            let key = if count_groups_once {
+                filesystem_key(device)
+            } else {
+                device.name.as_str()
+            };
+            if !device.is_mounted() || !seen.insert(key) {
+                continue;
+            }
+            let total = rollup.get_or_insert_with(UsageRollup::default);
+            total.used += used;
+            total.size += size;
+        }
+        rollup
+    }
+
+    /// Returns the allocated and used space of every partition in the tree, by name.
+    ///
+    /// `total_bytes` is the partition size. The free and used bytes come from
+    /// the `FSSIZE` and `FSUSED` columns, which lsblk fills in by calling
+    /// `statvfs` on the mountpoint, so they are only set by
+    /// `get_devices_extended` or JSON that includes those columns. Unmounted
+    /// partitions, and mounted ones without those columns, have zero free and
+    /// used bytes and a `use_percent` of 0.0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::parse_lsblk;
+    ///
+    /// let devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sda", "maj:min": "8:0", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+    ///      "children": [
+    ///         {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": ["/data"],
+    ///          "fssize": "1000G", "fsused": "950G"}
+    ///      ]}
+    /// ]}"#).unwrap();
+    ///
+    /// let usage = devices.partition_usage_map();
+    /// assert_eq!(usage["sda1"].use_percent, 95.0);
+    /// assert!(usage["sda1"].is_critical(90.0));
+    /// ```
+    #[must_use]
+    pub fn partition_usage_map(&self) -> HashMap<String, PartitionUsage> {
+        self.partition_usage_map_with(filesystem_space)
+    }
+
+    /// Builds [`BlockDevices::partition_usage_map`] with `statvfs` returning the
+    /// free and used bytes of a mounted partition.
+    fn partition_usage_map_with(
+        &self,
+        statvfs: impl Fn(&BlockDevice) -> Option<(u64, u64)>,
+    ) -> HashMap<String, PartitionUsage> {
+        self.iter_all()
+            .filter(|d| d.is_partition())
+            .map(|partition| {
+                let mut usage = PartitionUsage {
+                    total_bytes: partition.size,
+                    ..Default::default()
+                };
+                if partition.is_mounted() {
+                    if let Some((free, used)) = statvfs(partition) {
+                        usage.free_bytes = free;
+                        usage.used_bytes = used;
+                        let size = free + used;
+                        if size > 0 {
+                            usage.use_percent = used as f64 / size as f64 * 100.0;
+                        }
+                    }
+                }
+                (partition.name.clone(), usage)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::BTRFS_RAID1_JSON;
+    use crate::parse_lsblk;
+
+    const GIB: u64 = 1 << 30;
+
+    /// Two disks with a RAID1 root filesystem and one unmirrored data partition.
+    const RAID1_USAGE_JSON: &str = r#"{
+        "blockdevices": [
+            {"name": "sda", "maj:min": "8:0", "rm": false, "size": "200G", "ro": false, "type": "disk", "mountpoints": [null],
+             "children": [
+                {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "100G", "ro": false, "type": "part", "mountpoints": [null],
+                 "children": [
+                    {"name": "md0", "maj:min": "9:0", "rm": false, "size": "100G", "ro": false, "type": "raid1", "mountpoints": ["/"],
+                     "fssize": "100G", "fsused": "40G"}
+                 ]},
+                {"name": "sda2", "maj:min": "8:2", "rm": false, "size": "100G", "ro": false, "type": "part", "mountpoints": ["/data"],
+                 "fssize": "50G", "fsused": "10G"}
+             ]},
+            {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "200G", "ro": false, "type": "disk", "mountpoints": [null],
+             "children": [
+                {"name": "sdb1", "maj:min": "8:17", "rm": false, "size": "100G", "ro": false, "type": "part", "mountpoints": [null],
+                 "children": [
+                    {"name": "md0", "maj:min": "9:0", "rm": false, "size": "100G", "ro": false, "type": "raid1", "mountpoints": ["/"],
+                     "fssize": "100G", "fsused": "40G"}
+                 ]}
+             ]},
+            {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "200G", "ro": false, "type": "disk", "mountpoints": [null]}
+        ]
+    }"#;
+
+    fn usage(
+        devices: &BlockDevices,
+        attribution: UsageAttribution,
+    ) -> Vec<(&str, Option<(u64, u64)>)> {
+        devices
+            .usage_by_disk(attribution)
+            .into_iter()
+            .map(|(disk, rollup)| {
+                (
+                    disk.name.as_str(),
+                    rollup.map(|r| (r.used / GIB, r.size / GIB)),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_usage_by_disk_attribution() {
+        let devices = parse_lsblk(RAID1_USAGE_JSON).expect("Failed to parse JSON");
+        assert_eq!(
+            usage(&devices, UsageAttribution::Full),
+            vec![
+                ("sda", Some((50, 150))),
+                ("sdb", Some((40, 100))),
+                ("sdc", None)
+            ]
+        );
+        assert_eq!(
+            usage(&devices, UsageAttribution::Fractional),
+            vec![
+                ("sda", Some((30, 100))),
+                ("sdb", Some((20, 50))),
+                ("sdc", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rollup_usage_ignores_unmounted_and_missing_columns() {
+        let mut devices = parse_lsblk(RAID1_USAGE_JSON).expect("Failed to parse JSON");
+        let sda2 = &mut devices.blockdevices[0].children.as_mut().unwrap()[1];
+        sda2.mountpoints = vec![None];
+        let sda = devices.find_by_name("sda").unwrap();
+        let rollup = sda.rollup_usage(&devices, UsageAttribution::Full).unwrap();
+        assert_eq!((rollup.used, rollup.size), (40 * GIB, 100 * GIB));
+        assert_eq!(rollup.used_fraction(), Some(0.4));
+
+        let sample = parse_lsblk(crate::fixtures::SAMPLE_JSON).expect("Failed to parse JSON");
+        assert!(
+            sample
+                .usage_by_disk(UsageAttribution::Fractional)
+                .iter()
+                .all(|(_, rollup)| rollup.is_none())
+        );
+        assert_eq!(UsageRollup::default().used_fraction(), None);
+    }
+
+    #[test]
+    fn test_multi_device_filesystem_usage() {
+        let devices = parse_lsblk(BTRFS_RAID1_JSON).expect("Failed to parse JSON");
+        let total = |once| {
+            devices
+                .total_usage(once)
+                .map(|r| (r.used / GIB, r.size / GIB))
+        };
+        assert_eq!(total(false), Some((820, 2146)));
+        assert_eq!(total(true), Some((420, 1122)));
+
+        // The btrfs RAID1 is split across its two disks like an md RAID1.
+        assert_eq!(
+            usage(&devices, UsageAttribution::Fractional),
+            vec![
+                ("sda", Some((20, 98))),
+                ("sdb", Some((200, 512))),
+                ("sdc", Some((200, 512))),
+                ("sdd", None),
+            ]
+        );
+        assert_eq!(
+            usage(&devices, UsageAttribution::Full)[2],
+            ("sdc", Some((400, 1024)))
+        );
+
+        let raid1 = parse_lsblk(RAID1_USAGE_JSON).expect("Failed to parse JSON");
+        assert_eq!(
+            raid1
+                .total_usage(true)
+                .map(|r| (r.used / GIB, r.size / GIB)),
+            Some((50, 150))
+        );
+    }
+
+    #[test]
+    fn test_partition_usage_map() {
+        let devices = parse_lsblk(RAID1_USAGE_JSON).expect("Failed to parse JSON");
+        let usage = devices.partition_usage_map();
+        assert_eq!(usage.len(), 3);
+        assert_eq!(
+            usage["sda2"],
+            PartitionUsage {
+                total_bytes: 100 * GIB,
+                free_bytes: 40 * GIB,
+                used_bytes: 10 * GIB,
+                use_percent: 20.0,
+            }
+        );
+        // sda1 and sdb1 carry md0 but are not mounted themselves.
+        assert_eq!(
+            usage["sda1"],
+            PartitionUsage {
+                total_bytes: 100 * GIB,
+                ..Default::default()
+            }
+        );
+        assert_eq!(usage["sdb1"].use_percent, 0.0);
+    }
+
+    #[test]
+    fn test_partition_usage_map_with_statvfs() {
+        let mut devices = parse_lsblk(RAID1_USAGE_JSON).expect("Failed to parse JSON");
+        devices.blockdevices[1].children.as_mut().unwrap()[0].mountpoints =
+            vec![Some("/scratch".to_string())];
+        let statvfs = |device: &BlockDevice| match device.name.as_str() {
+            "sda2" => Some((GIB, 3 * GIB)),
+            "sdb1" => Some((0, 0)),
+            _ => None,
+        };
+        let usage = devices.partition_usage_map_with(statvfs);
+
+        let sda2 = usage["sda2"];
+        assert_eq!((sda2.free_bytes, sda2.used_bytes), (GIB, 3 * GIB));
+        assert_eq!(sda2.use_percent, 75.0);
+        assert!(sda2.is_critical(70.0));
+        assert!(!sda2.is_critical(75.0));
+
+        // An empty filesystem does not divide by zero.
+        assert_eq!(usage["sdb1"].use_percent, 0.0);
+        assert!(!usage["sdb1"].is_critical(0.0));
+        // statvfs is not consulted for unmounted partitions.
+        assert_eq!(usage["sda1"].free_bytes, 0);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{BlockDevice, BlockDevices, IterAll, MountEntry};
+
+/// Whether data can be written through a device, taking both the block layer
+/// and the mounted filesystems into account. Returned by
+/// [`BlockDevice::effective_writability`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Writability {
+    /// The device, or a device it is stacked on, has the `ro` flag set.
+    DeviceReadOnly,
+    /// The device is writable, but every filesystem on it or its descendants is
+    /// mounted read-only.
+    MountedReadOnly,
+    /// At least one filesystem on the device or its descendants is mounted read-write.
+    Writable,
+    /// The device is writable and nothing on it is mounted.
+    NotMounted,
+}
+
+/// Returns `true` if the mount or its superblock is read-only.
+fn is_read_only_mount(entry: &MountEntry) -> bool {
+    entry.mount_options.iter().any(|o| o == "ro") || entry.super_options.iter().any(|o| o == "ro")
+}
+
+/// Walks `device` and its descendants, recording whether anything is mounted
+/// and returning `true` if any mount is writable.
+fn scan(device: &BlockDevice, read_only_below: bool, mounted: &mut bool) -> bool {
+    let read_only = read_only_below || device.ro;
+    let mut writable = match device.mounts.as_deref() {
+        Some(entries) if !entries.is_empty() => {
+            *mounted = true;
+            !read_only && entries.iter().any(|e| !is_read_only_mount(e))
+        }
+        _ if device.is_mounted() => {
+            *mounted = true;
+            !read_only
+        }
+        _ => false,
+    };
+    for child in device.children_iter() {
+        writable |= scan(child, read_only, mounted);
+    }
+    writable
+}
+
+impl BlockDevice {
+    /// Returns whether data can be written through this device, separating a
+    /// read-only device from a read-only mount.
+    ///
+    /// `ctx` is the snapshot this device belongs to. A device stacked on a
+    /// read-only device, such as a partition of a write-protected disk, is
+    /// reported as [`Writability::DeviceReadOnly`] even if its own `ro` flag is
+    /// clear. For container devices such as disks and RAID members, the mounts
+    /// of every descendant are considered: one read-write mount anywhere below
+    /// makes the device [`Writability::Writable`].
+    ///
+    /// Mount options come from the mountinfo attached by
+    /// [`BlockDevices::enrich_mountinfo`]; a mount counts as read-only if
+    /// either its per-mount or its superblock options contain `ro`. A device
+    /// that lsblk shows as mounted but that has no mountinfo entries is
+    /// assumed to be mounted read-write.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use blockdev::{parse_lsblk, MountInfo, Writability};
+    ///
+    /// let mut devices = parse_lsblk(r#"{"blockdevices": [
+    ///     {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": ["/data"]}
+    /// ]}"#).unwrap();
+    /// devices.enrich_mountinfo(
+    ///     &MountInfo::parse("31 22 8:16 / /data ro,relatime shared:10 - xfs /dev/sdb rw,attr2\n").unwrap(),
+    /// );
+    ///
+    /// let sdb = &devices.blockdevices[0];
+    /// assert_eq!(sdb.effective_writability(&devices), Writability::MountedReadOnly);
+    /// ```
+    #[must_use]
+    pub fn effective_writability(&self, ctx: &BlockDevices) -> Writability {
+        let stacked_on_read_only = ctx.iter_all().any(|lower| {
+            lower.ro
+                && lower.name != self.name
+                && IterAll::subtree(lower).any(|d| d.name == self.name)
+        });
+        if self.ro || stacked_on_read_only {
+            return Writability::DeviceReadOnly;
+        }
+
+        let mut mounted = false;
+        let writable = scan(self, false, &mut mounted);
+        match (mounted, writable) {
+            (false, _) => Writability::NotMounted,
+            (true, true) => Writability::Writable,
+            (true, false) => Writability::MountedReadOnly,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MountInfo, parse_lsblk};
+
+    /// A write-protected SD card whose partition does not carry the flag itself.
+    const DEVICE_READ_ONLY_JSON: &str = r#"{"blockdevices": [
+        {"name": "mmcblk0", "maj:min": "179:0", "rm": true, "size": "32G", "ro": true, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "mmcblk0p1", "maj:min": "179:1", "rm": true, "size": "32G", "ro": false, "type": "part", "mountpoints": ["/media/card"]}
+         ]}
+    ]}"#;
+    const DEVICE_READ_ONLY_MOUNTINFO: &str =
+        "40 22 179:1 / /media/card rw,nosuid shared:20 - vfat /dev/mmcblk0p1 rw\n";
+
+    /// A writable disk whose only filesystem is mounted read-only, once directly
+    /// and once through a bind mount.
+    const MOUNTED_READ_ONLY_JSON: &str = r#"{"blockdevices": [
+        {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "sdb1", "maj:min": "8:17", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": ["/data", "/srv/data"]}
+         ]}
+    ]}"#;
+    const MOUNTED_READ_ONLY_MOUNTINFO: &str = "\
+31 22 8:17 / /data ro,relatime shared:10 - xfs /dev/sdb1 rw,attr2
+48 22 8:17 / /srv/data rw,relatime shared:10 - xfs /dev/sdb1 ro,attr2
+";
+
+    /// A RAID1 array mounted read-write, plus a read-only bind mount of it.
+    const WRITABLE_JSON: &str = r#"{"blockdevices": [
+        {"name": "sda", "maj:min": "8:0", "rm": false, "size": "100G", "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "sda1", "maj:min": "8:1", "rm": false, "size": "100G", "ro": false, "type": "part", "mountpoints": [null],
+             "children": [
+                {"name": "md0", "maj:min": "9:0", "rm": false, "size": "100G", "ro": false, "type": "raid1", "mountpoints": ["/", "/mnt/ro"]}
+             ]}
+         ]},
+        {"name": "sdb", "maj:min": "8:16", "rm": false, "size": "100G", "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "sdb1", "maj:min": "8:17", "rm": false, "size": "100G", "ro": false, "type": "part", "mountpoints": [null],
+             "children": [
+                {"name": "md0", "maj:min": "9:0", "rm": false, "size": "100G", "ro": false, "type": "raid1", "mountpoints": ["/", "/mnt/ro"]}
+             ]}
+         ]}
+    ]}"#;
+    const WRITABLE_MOUNTINFO: &str = "\
+22 1 9:0 / / rw,relatime shared:1 - ext4 /dev/md0 rw
+60 22 9:0 / /mnt/ro ro,relatime shared:1 - ext4 /dev/md0 rw
+";
+
+    /// A writable disk with an unmounted partition.
+    const NOT_MOUNTED_JSON: &str = r#"{"blockdevices": [
+        {"name": "sdc", "maj:min": "8:32", "rm": false, "size": "1T", "ro": false, "type": "disk", "mountpoints": [null],
+         "children": [
+            {"name": "sdc1", "maj:min": "8:33", "rm": false, "size": "1T", "ro": false, "type": "part", "mountpoints": [null]}
+         ]}
+    ]}"#;
+
+    fn writability(json: &str, mountinfo: &str) -> Vec<(String, Writability)> {
+        let mut devices = parse_lsblk(json).expect("Failed to parse JSON");
+        devices.enrich_mountinfo(&MountInfo::parse(mountinfo).expect("Failed to parse mountinfo"));
+        devices
+            .iter_all()
+            .map(|d| (d.name.clone(), d.effective_writability(&devices)))
+            .collect()
+    }
+
+    fn expected(pairs: &[(&str, Writability)]) -> Vec<(String, Writability)> {
+        pairs
+            .iter()
+            .map(|(name, w)| ((*name).to_string(), *w))
+            .collect()
+    }
+
+    #[test]
+    fn test_device_read_only() {
+        assert_eq!(
+            writability(DEVICE_READ_ONLY_JSON, DEVICE_READ_ONLY_MOUNTINFO),
+            expected(&[
+                ("mmcblk0", Writability::DeviceReadOnly),
+                ("mmcblk0p1", Writability::DeviceReadOnly),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_mounted_read_only() {
+        assert_eq!(
+            writability(MOUNTED_READ_ONLY_JSON, MOUNTED_READ_ONLY_MOUNTINFO),
+            expected(&[
+                ("sdb", Writability::MountedReadOnly),
+                ("sdb1", Writability::MountedReadOnly),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_writable() {
+        assert_eq!(
+            writability(WRITABLE_JSON, WRITABLE_MOUNTINFO),
+            expected(&[
+                ("sda", Writability::Writable),
+                ("sda1", Writability::Writable),
+                ("md0", Writability::Writable),
+                ("sdb", Writability::Writable),
+                ("sdb1", Writability::Writable),
+                ("md0", Writability::Writable),
+            ])
+        );
+
+        // Without mountinfo, a mounted device is assumed to be writable.
+        let devices = parse_lsblk(WRITABLE_JSON).expect("Failed to parse JSON");
+        assert_eq!(
+            devices.blockdevices[0].effective_writability(&devices),
+            Writability::Writable
+        );
+    }
+
+    #[test]
+    fn test_not_mounted() {
+        assert_eq!(
+            writability(NOT_MOUNTED_JSON, ""),
+            expected(&[
+                ("sdc", Writability::NotMounted),
+                ("sdc1", Writability::NotMounted),
+            ])
+        );
+    }
+}